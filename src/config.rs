@@ -1,15 +1,33 @@
-use std::{fs, path::PathBuf};
+use std::{
+    collections::HashSet,
+    fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+};
 
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     model::{
-        sample_remote_targets, AppSettings, AuthMethod, Language, RemoteTarget, SyncRule, TargetId,
+        sample_remote_targets, AppSettings, AuthMethod, Language, PostSyncHook, RemoteCommands,
+        RemoteTarget, SessionSort, SshAlgorithms, SyncRule, TargetColor, TargetId, TargetSort,
+        DEFAULT_MAX_CONNECTIONS,
     },
     secrets::{self, SecretSlot},
+    sync::TargetSnapshot,
 };
 
 const CONFIG_FILE_NAME: &str = "config.json";
+const PROFILE_FILE_NAME: &str = "profile";
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Profile whose config, snapshots, journals and secrets are in use. Read once
+/// from the `profile` file so the choice survives restarts.
+static ACTIVE_PROFILE: Lazy<RwLock<String>> = Lazy::new(|| RwLock::new(read_active_profile()));
+const EXPORT_FORMAT_VERSION: u32 = 1;
 
 #[derive(Serialize, Deserialize, Default)]
 struct PersistedState {
@@ -19,12 +37,44 @@ struct PersistedState {
     auto_connect: bool,
     #[serde(default = "default_true")]
     watch_local_changes: bool,
+    #[serde(default = "default_watch_poll_secs")]
+    watch_poll_secs: u64,
     #[serde(default = "default_true")]
     confirm_destructive: bool,
+    #[serde(default = "default_mass_delete_count")]
+    mass_delete_count: usize,
+    #[serde(default = "default_mass_delete_percent")]
+    mass_delete_percent: u8,
     #[serde(default)]
     limit_bandwidth: bool,
     #[serde(default = "default_bandwidth")]
     bandwidth_mbps: u32,
+    #[serde(default = "default_true")]
+    stability_check: bool,
+    #[serde(default = "default_stability_window")]
+    stability_window_ms: u64,
+    #[serde(default = "default_keepalive")]
+    keepalive_secs: u32,
+    #[serde(default)]
+    staging_dir: Option<PathBuf>,
+    #[serde(default)]
+    target_sort: TargetSort,
+    #[serde(default)]
+    session_sort: SessionSort,
+    #[serde(default)]
+    log_problems_only: bool,
+    #[serde(default)]
+    log_active_target_only: bool,
+    #[serde(default)]
+    log_to_file: bool,
+    #[serde(default = "default_true")]
+    backup_conflicts: bool,
+    #[serde(default)]
+    review_deletions: bool,
+    #[serde(default = "default_health_check_mins")]
+    health_check_mins: u32,
+    #[serde(default)]
+    last_target: Option<TargetId>,
     #[serde(default)]
     remote_targets: Vec<PersistedRemoteTarget>,
 }
@@ -35,6 +85,23 @@ struct LegacySettings {
     language: String,
 }
 
+#[derive(Serialize, Deserialize)]
+struct ExportedConfig {
+    #[serde(default = "default_export_version")]
+    version: u32,
+    #[serde(flatten)]
+    state: PersistedState,
+}
+
+pub struct ImportSummary {
+    pub added: usize,
+    pub skipped: usize,
+}
+
+fn default_export_version() -> u32 {
+    EXPORT_FORMAT_VERSION
+}
+
 fn default_language_code() -> String {
     "en".to_string()
 }
@@ -47,118 +114,554 @@ fn default_bandwidth() -> u32 {
     200
 }
 
+fn default_watch_poll_secs() -> u64 {
+    2
+}
+
+fn default_stability_window() -> u64 {
+    500
+}
+
+fn default_keepalive() -> u32 {
+    30
+}
+
+fn default_max_connections() -> usize {
+    DEFAULT_MAX_CONNECTIONS
+}
+
+fn default_mass_delete_count() -> usize {
+    1000
+}
+
+fn default_mass_delete_percent() -> u8 {
+    50
+}
+
+fn default_health_check_mins() -> u32 {
+    5
+}
+
+/// No config file was saved yet, so the app has never run with this profile.
+pub fn is_first_run() -> bool {
+    config_path().is_some_and(|path| !path.exists())
+}
+
 pub fn load_state() -> (AppSettings, Vec<RemoteTarget>) {
-    let mut settings = AppSettings::default();
-    settings.language = detect_system_language();
+    let mut settings = AppSettings {
+        language: detect_system_language(),
+        ..AppSettings::default()
+    };
     let mut remote_targets = sample_remote_targets();
 
-    if let Some(path) = config_path() {
-        if let Ok(contents) = fs::read_to_string(&path) {
-            if let Ok(serialized) = serde_json::from_str::<PersistedState>(&contents) {
-                settings.language = language_from_code(&serialized.language);
-                settings.auto_connect = serialized.auto_connect;
-                settings.watch_local_changes = serialized.watch_local_changes;
-                settings.confirm_destructive = serialized.confirm_destructive;
-                settings.limit_bandwidth = serialized.limit_bandwidth;
-                settings.bandwidth_mbps = serialized.bandwidth_mbps;
-
-                if !serialized.remote_targets.is_empty() {
-                    remote_targets = serialized
-                        .remote_targets
-                        .into_iter()
-                        .map(PersistedRemoteTarget::into_runtime)
-                        .collect();
-                }
+    if let Some(path) = config_path()
+        && let Ok(contents) = fs::read_to_string(&path)
+    {
+        if let Ok(serialized) = serde_json::from_str::<PersistedState>(&contents) {
+            apply_persisted_settings(&mut settings, &serialized);
 
-                return (settings, remote_targets);
-            } else if let Ok(legacy) = serde_json::from_str::<LegacySettings>(&contents) {
-                settings.language = language_from_code(&legacy.language);
-                return (settings, remote_targets);
+            if !serialized.remote_targets.is_empty() {
+                remote_targets = serialized
+                    .remote_targets
+                    .into_iter()
+                    .map(PersistedRemoteTarget::into_runtime)
+                    .collect();
             }
+
+            return (settings, remote_targets);
+        } else if let Ok(legacy) = serde_json::from_str::<LegacySettings>(&contents) {
+            settings.language = language_from_code(&legacy.language);
+            return (settings, remote_targets);
         }
     }
 
     (settings, remote_targets)
 }
 
-pub fn save_state(settings: &AppSettings, remote_targets: &[RemoteTarget]) {
+fn apply_persisted_settings(settings: &mut AppSettings, serialized: &PersistedState) {
+    settings.language = language_from_code(&serialized.language);
+    settings.auto_connect = serialized.auto_connect;
+    settings.watch_local_changes = serialized.watch_local_changes;
+    settings.watch_poll_secs = serialized.watch_poll_secs.max(1);
+    settings.confirm_destructive = serialized.confirm_destructive;
+    settings.mass_delete_count = serialized.mass_delete_count;
+    settings.mass_delete_percent = serialized.mass_delete_percent;
+    settings.limit_bandwidth = serialized.limit_bandwidth;
+    settings.bandwidth_mbps = serialized.bandwidth_mbps;
+    settings.stability_check = serialized.stability_check;
+    settings.stability_window_ms = serialized.stability_window_ms;
+    settings.keepalive_secs = serialized.keepalive_secs;
+    settings.staging_dir = serialized.staging_dir.clone();
+    settings.target_sort = serialized.target_sort;
+    settings.session_sort = serialized.session_sort;
+    settings.log_problems_only = serialized.log_problems_only;
+    settings.log_active_target_only = serialized.log_active_target_only;
+    settings.log_to_file = serialized.log_to_file;
+    settings.backup_conflicts = serialized.backup_conflicts;
+    settings.review_deletions = serialized.review_deletions;
+    settings.health_check_mins = serialized.health_check_mins;
+    settings.last_target = serialized.last_target;
+}
+
+fn persisted_state(
+    settings: &AppSettings,
+    remote_targets: Vec<PersistedRemoteTarget>,
+) -> PersistedState {
+    PersistedState {
+        language: language_to_code(settings.language).to_string(),
+        auto_connect: settings.auto_connect,
+        watch_local_changes: settings.watch_local_changes,
+        watch_poll_secs: settings.watch_poll_secs,
+        confirm_destructive: settings.confirm_destructive,
+        mass_delete_count: settings.mass_delete_count,
+        mass_delete_percent: settings.mass_delete_percent,
+        limit_bandwidth: settings.limit_bandwidth,
+        bandwidth_mbps: settings.bandwidth_mbps,
+        stability_check: settings.stability_check,
+        stability_window_ms: settings.stability_window_ms,
+        keepalive_secs: settings.keepalive_secs,
+        staging_dir: settings.staging_dir.clone(),
+        target_sort: settings.target_sort,
+        session_sort: settings.session_sort,
+        log_problems_only: settings.log_problems_only,
+        log_active_target_only: settings.log_active_target_only,
+        log_to_file: settings.log_to_file,
+        backup_conflicts: settings.backup_conflicts,
+        review_deletions: settings.review_deletions,
+        health_check_mins: settings.health_check_mins,
+        last_target: settings.last_target,
+        remote_targets,
+    }
+}
+
+/// A secret the keychain refused to store. It stays usable until the app
+/// exits, and its target is saved as needing credentials.
+pub struct UnsavedCredential {
+    pub target_id: TargetId,
+    pub slot: SecretSlot,
+    pub error: String,
+}
+
+/// Writes settings and targets, moving secrets into the keychain. Returns the
+/// secrets that could not be stored.
+pub fn save_state(
+    settings: &AppSettings,
+    remote_targets: &[RemoteTarget],
+) -> Vec<UnsavedCredential> {
+    let mut unsaved = Vec::new();
     if let Some(path) = config_path() {
-        let data = PersistedState {
-            language: language_to_code(settings.language).to_string(),
-            auto_connect: settings.auto_connect,
-            watch_local_changes: settings.watch_local_changes,
-            confirm_destructive: settings.confirm_destructive,
-            limit_bandwidth: settings.limit_bandwidth,
-            bandwidth_mbps: settings.bandwidth_mbps,
-            remote_targets: persist_remote_targets(remote_targets),
-        };
+        let targets = persist_remote_targets(remote_targets, &mut unsaved);
+        let data = persisted_state(settings, targets);
 
         if let Some(parent) = path.parent() {
             let _ = fs::create_dir_all(parent);
         }
 
         if let Ok(contents) = serde_json::to_string_pretty(&data) {
-            let _ = fs::write(path, contents);
+            let _ = write_atomic(&path, contents);
+        }
+    }
+    unsaved
+}
+
+/// Writes settings and targets to `path` without any credentials. Every
+/// exported target is marked as needing its secret re-entered on import.
+pub fn export_state(
+    settings: &AppSettings,
+    remote_targets: &[RemoteTarget],
+    path: &Path,
+) -> Result<()> {
+    let targets = remote_targets
+        .iter()
+        .map(|target| {
+            PersistedRemoteTarget::from_runtime(
+                target,
+                export_auth(&target.auth),
+                target.fallback_auth.as_ref().map(export_auth),
+            )
+        })
+        .collect();
+    let data = ExportedConfig {
+        version: EXPORT_FORMAT_VERSION,
+        state: persisted_state(settings, targets),
+    };
+
+    let contents =
+        serde_json::to_string_pretty(&data).context("failed to serialize configuration")?;
+    fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Reads an exported configuration and merges it into the current state.
+/// Imported settings replace the current ones; targets are appended with
+/// fresh ids unless an equivalent target (same name, host and user) exists.
+pub fn import_state(
+    path: &Path,
+    settings: &mut AppSettings,
+    remote_targets: &mut Vec<RemoteTarget>,
+) -> Result<ImportSummary> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let exported: ExportedConfig =
+        serde_json::from_str(&contents).context("file is not a valid SFTP-SYNC export")?;
+    if exported.version > EXPORT_FORMAT_VERSION {
+        return Err(anyhow!(
+            "export format version {} is newer than supported version {}",
+            exported.version,
+            EXPORT_FORMAT_VERSION
+        ));
+    }
+
+    for target in &exported.state.remote_targets {
+        validate_imported_target(target)?;
+    }
+
+    apply_persisted_settings(settings, &exported.state);
+
+    let mut existing: HashSet<(String, String, String)> = remote_targets
+        .iter()
+        .map(|target| {
+            (
+                target.name.clone(),
+                target.host.clone(),
+                target.username.clone(),
+            )
+        })
+        .collect();
+    let mut next_id = remote_targets
+        .iter()
+        .map(|target| target.id)
+        .max()
+        .unwrap_or(0);
+    let mut summary = ImportSummary {
+        added: 0,
+        skipped: 0,
+    };
+
+    for persisted in exported.state.remote_targets {
+        let key = (
+            persisted.name.clone(),
+            persisted.host.clone(),
+            persisted.username.clone(),
+        );
+        if !existing.insert(key) {
+            summary.skipped += 1;
+            continue;
         }
+
+        next_id = next_id.saturating_add(1);
+        remote_targets.push(RemoteTarget {
+            id: next_id,
+            name: persisted.name,
+            host: persisted.host,
+            username: persisted.username,
+            base_path: persisted.base_path,
+            local_base: persisted.local_base,
+            rules: persisted.rules,
+            auth: blank_auth(persisted.auth),
+            fallback_auth: persisted.fallback_auth.map(blank_auth),
+            enabled: persisted.enabled,
+            // Session-only secrets are asked for at connect time anyway.
+            credentials_pending: persisted.remember_credentials,
+            remember_credentials: persisted.remember_credentials,
+            use_ssh_config: persisted.use_ssh_config,
+            correct_clock_skew: persisted.correct_clock_skew,
+            stat_fallback: persisted.stat_fallback,
+            remote_read_only: persisted.remote_read_only,
+            monitor_health: persisted.monitor_health,
+            algorithms: persisted.algorithms,
+            color: persisted.color,
+            tags: persisted.tags,
+            max_connections: persisted.max_connections.max(1),
+            source_address: persisted.source_address,
+            socket_buffer_kib: persisted.socket_buffer_kib,
+            // A shared file must not be able to run commands on this machine.
+            post_sync_hook: PostSyncHook {
+                enabled: false,
+                ..persisted.post_sync_hook
+            },
+            remote_commands: RemoteCommands {
+                enabled: false,
+                ..persisted.remote_commands
+            },
+        });
+        summary.added += 1;
+    }
+
+    Ok(summary)
+}
+
+fn validate_imported_target(target: &PersistedRemoteTarget) -> Result<()> {
+    if target.name.trim().is_empty() {
+        return Err(anyhow!("imported target {} has no name", target.id));
+    }
+    if target.host.trim().is_empty() || target.username.trim().is_empty() {
+        return Err(anyhow!(
+            "imported target {} is missing a host or username",
+            target.name
+        ));
     }
+    if target.rules.is_empty() {
+        return Err(anyhow!("imported target {} has no sync rules", target.name));
+    }
+    Ok(())
 }
 
-fn persist_remote_targets(remote_targets: &[RemoteTarget]) -> Vec<PersistedRemoteTarget> {
+fn persist_remote_targets(
+    remote_targets: &[RemoteTarget],
+    unsaved: &mut Vec<UnsavedCredential>,
+) -> Vec<PersistedRemoteTarget> {
     remote_targets
         .iter()
         .map(|target| {
-            let auth = match &target.auth {
-                AuthMethod::Password { secret, .. } => {
-                    let slot = SecretSlot::Password(target.id);
-                    let stored = if secret.is_empty() {
-                        secrets::delete(slot).ok();
-                        false
-                    } else {
-                        secrets::store(slot, secret).ok();
-                        true
-                    };
-                    PersistedAuth::Password { stored }
-                }
-                AuthMethod::SshKey {
-                    private_key,
-                    passphrase,
-                    ..
-                } => {
-                    let slot = SecretSlot::KeyPassphrase(target.id);
-                    let stored = if let Some(secret) = passphrase {
-                        if secret.is_empty() {
-                            secrets::delete(slot).ok();
-                            false
-                        } else {
-                            secrets::store(slot, secret).ok();
-                            true
-                        }
-                    } else {
-                        secrets::delete(slot).ok();
-                        false
-                    };
-                    PersistedAuth::SshKey {
-                        private_key: private_key.clone(),
-                        passphrase_stored: stored,
-                    }
+            let failures = unsaved.len();
+            let remember = target.remember_credentials;
+            let auth = persist_auth(target.id, &target.auth, remember, unsaved);
+            let fallback_auth = target
+                .fallback_auth
+                .as_ref()
+                .map(|auth| persist_auth(target.id, auth, remember, unsaved));
+            let mut persisted = PersistedRemoteTarget::from_runtime(target, auth, fallback_auth);
+            // The secret is gone after a restart, so ask for it again then.
+            persisted.credentials_pending |= unsaved.len() > failures;
+            persisted
+        })
+        .collect()
+}
+
+/// Stores a secret, recording a refusal instead of pretending it was saved.
+fn store_secret(
+    target_id: TargetId,
+    slot: SecretSlot,
+    secret: &str,
+    unsaved: &mut Vec<UnsavedCredential>,
+) -> bool {
+    match secrets::store(slot, secret) {
+        Ok(()) => true,
+        Err(err) => {
+            unsaved.push(UnsavedCredential {
+                target_id,
+                slot,
+                error: format!("{err:#}"),
+            });
+            false
+        }
+    }
+}
+
+/// Moves the method's secret into the keychain and returns what goes on disk.
+/// Without `remember` any keychain copy is removed and the secret stays in memory.
+fn persist_auth(
+    target_id: TargetId,
+    auth: &AuthMethod,
+    remember: bool,
+    unsaved: &mut Vec<UnsavedCredential>,
+) -> PersistedAuth {
+    match auth {
+        AuthMethod::Password { secret, .. } => {
+            let slot = SecretSlot::Password(target_id);
+            let stored = if !remember || secret.is_empty() {
+                secrets::delete(slot).ok();
+                false
+            } else {
+                store_secret(target_id, slot, secret, unsaved)
+            };
+            PersistedAuth::Password { stored }
+        }
+        AuthMethod::SshKey {
+            private_key,
+            passphrase,
+            ..
+        } => {
+            let slot = SecretSlot::KeyPassphrase(target_id);
+            let stored = if let Some(secret) = passphrase.as_ref().filter(|_| remember) {
+                if secret.is_empty() {
+                    secrets::delete(slot).ok();
+                    false
+                } else {
+                    store_secret(target_id, slot, secret, unsaved)
                 }
+            } else {
+                secrets::delete(slot).ok();
+                false
             };
+            PersistedAuth::SshKey {
+                private_key: private_key.clone(),
+                passphrase_stored: stored,
+            }
+        }
+    }
+}
 
-            PersistedRemoteTarget {
-                id: target.id,
-                name: target.name.clone(),
-                host: target.host.clone(),
-                username: target.username.clone(),
-                base_path: target.base_path.clone(),
-                rules: target.rules.clone(),
-                auth,
+fn export_auth(auth: &AuthMethod) -> PersistedAuth {
+    match auth {
+        AuthMethod::Password { .. } => PersistedAuth::Password { stored: false },
+        AuthMethod::SshKey { private_key, .. } => PersistedAuth::SshKey {
+            private_key: private_key.clone(),
+            passphrase_stored: false,
+        },
+    }
+}
+
+fn blank_auth(auth: PersistedAuth) -> AuthMethod {
+    match auth {
+        PersistedAuth::Password { .. } => AuthMethod::password(String::new()),
+        PersistedAuth::SshKey { private_key, .. } => AuthMethod::ssh_key(private_key),
+    }
+}
+
+fn restore_auth(target_id: TargetId, auth: PersistedAuth, remember: bool) -> AuthMethod {
+    if !remember {
+        return blank_auth(auth);
+    }
+    match auth {
+        PersistedAuth::Password { stored } => {
+            let secret = secrets::load(SecretSlot::Password(target_id))
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            AuthMethod::Password {
+                secret,
+                stored,
+            }
+        }
+        PersistedAuth::SshKey {
+            private_key,
+            passphrase_stored,
+        } => {
+            let passphrase = secrets::load(SecretSlot::KeyPassphrase(target_id))
+                .ok()
+                .flatten();
+            AuthMethod::SshKey {
+                private_key,
+                passphrase,
+                passphrase_stored,
             }
+        }
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("SFTP-SYNC"))
+}
+
+/// Writes `data` next to `path` and renames it into place, so an exit
+/// mid-write leaves the previous file intact instead of a truncated one.
+pub fn write_atomic(path: &Path, data: impl AsRef<[u8]>) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, data).with_context(|| format!("failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("failed to replace {}", path.display()))
+}
+
+fn read_active_profile() -> String {
+    config_dir()
+        .and_then(|dir| fs::read_to_string(dir.join(PROFILE_FILE_NAME)).ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| is_valid_profile_name(name))
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+pub fn active_profile() -> String {
+    ACTIVE_PROFILE.read().clone()
+}
+
+/// Makes `name` the active profile, now and on the next launch. The caller
+/// reloads its state with `load_state`.
+pub fn set_active_profile(name: &str) -> Result<()> {
+    if !is_valid_profile_name(name) {
+        return Err(anyhow!("invalid profile name \"{name}\""));
+    }
+    let dir = config_dir().ok_or_else(|| anyhow!("no config directory"))?;
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = dir.join(PROFILE_FILE_NAME);
+    fs::write(&path, name).with_context(|| format!("failed to write {}", path.display()))?;
+    *ACTIVE_PROFILE.write() = name.to_string();
+    Ok(())
+}
+
+/// Letters, digits, `-` and `_`, so a name is safe in file names and keyring keys.
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 32
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+}
+
+/// The default profile, then every `config.<profile>.json` in the config
+/// folder, plus the active profile even before its first save.
+pub fn list_profiles() -> Vec<String> {
+    let mut profiles: Vec<String> = config_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let profile = name.strip_prefix("config.")?.strip_suffix(".json")?;
+            is_valid_profile_name(profile).then(|| profile.to_string())
         })
-        .collect()
+        .collect();
+    profiles.push(active_profile());
+    profiles.retain(|profile| profile != DEFAULT_PROFILE);
+    profiles.sort();
+    profiles.dedup();
+    profiles.insert(0, DEFAULT_PROFILE.to_string());
+    profiles
+}
+
+/// Where the active profile keeps snapshots and journals. The default profile
+/// uses the config folder itself, as before profiles existed.
+fn profile_dir() -> Option<PathBuf> {
+    let profile = active_profile();
+    config_dir().map(|dir| {
+        if profile == DEFAULT_PROFILE {
+            dir
+        } else {
+            dir.join("profiles").join(profile)
+        }
+    })
+}
+
+fn snapshot_path(target_id: TargetId) -> Option<PathBuf> {
+    profile_dir().map(|dir| dir.join("snapshots").join(format!("{target_id}.json")))
+}
+
+pub fn load_snapshot(target_id: TargetId) -> Option<TargetSnapshot> {
+    let contents = fs::read_to_string(snapshot_path(target_id)?).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+pub fn save_snapshot(target_id: TargetId, snapshot: &TargetSnapshot) -> Result<()> {
+    let path = snapshot_path(target_id).ok_or_else(|| anyhow!("no config directory"))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let data = serde_json::to_vec(snapshot).context("failed to serialize snapshot")?;
+    write_atomic(&path, data)
+}
+
+/// Journals of executions in progress, one file per session.
+pub fn journal_dir() -> Option<PathBuf> {
+    profile_dir().map(|dir| dir.join("journal"))
+}
+
+/// Activity log files, when logging to disk is on.
+pub fn log_dir() -> Option<PathBuf> {
+    profile_dir().map(|dir| dir.join("logs"))
 }
 
 fn config_path() -> Option<PathBuf> {
-    dirs::config_dir().map(|dir| dir.join("SFTP-SYNC").join(CONFIG_FILE_NAME))
+    let profile = active_profile();
+    config_dir().map(|dir| {
+        if profile == DEFAULT_PROFILE {
+            dir.join(CONFIG_FILE_NAME)
+        } else {
+            dir.join(format!("config.{profile}.json"))
+        }
+    })
 }
 
 fn language_from_code(code: &str) -> Language {
@@ -190,9 +693,45 @@ struct PersistedRemoteTarget {
     host: String,
     username: String,
     base_path: PathBuf,
+    #[serde(default)]
+    local_base: PathBuf,
     rules: Vec<SyncRule>,
     #[serde(default)]
     auth: PersistedAuth,
+    #[serde(default)]
+    fallback_auth: Option<PersistedAuth>,
+    #[serde(default = "default_true")]
+    enabled: bool,
+    #[serde(default)]
+    credentials_pending: bool,
+    #[serde(default = "default_true")]
+    remember_credentials: bool,
+    #[serde(default)]
+    use_ssh_config: bool,
+    #[serde(default)]
+    correct_clock_skew: bool,
+    #[serde(default = "default_true")]
+    stat_fallback: bool,
+    #[serde(default)]
+    remote_read_only: bool,
+    #[serde(default)]
+    monitor_health: bool,
+    #[serde(default)]
+    algorithms: SshAlgorithms,
+    #[serde(default)]
+    color: TargetColor,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default = "default_max_connections")]
+    max_connections: usize,
+    #[serde(default)]
+    source_address: Option<IpAddr>,
+    #[serde(default)]
+    socket_buffer_kib: u32,
+    #[serde(default)]
+    post_sync_hook: PostSyncHook,
+    #[serde(default)]
+    remote_commands: RemoteCommands,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -213,41 +752,69 @@ impl Default for PersistedAuth {
 }
 
 impl PersistedRemoteTarget {
-    fn into_runtime(self) -> RemoteTarget {
-        let auth = match self.auth {
-            PersistedAuth::Password { stored } => {
-                let secret = secrets::load(SecretSlot::Password(self.id))
-                    .ok()
-                    .flatten()
-                    .unwrap_or_default();
-                AuthMethod::Password {
-                    secret,
-                    stored,
-                }
-            }
-            PersistedAuth::SshKey {
-                private_key,
-                passphrase_stored,
-            } => {
-                let passphrase = secrets::load(SecretSlot::KeyPassphrase(self.id))
-                    .ok()
-                    .flatten();
-                AuthMethod::SshKey {
-                    private_key,
-                    passphrase,
-                    passphrase_stored,
-                }
-            }
-        };
+    fn from_runtime(
+        target: &RemoteTarget,
+        auth: PersistedAuth,
+        fallback_auth: Option<PersistedAuth>,
+    ) -> Self {
+        Self {
+            id: target.id,
+            name: target.name.clone(),
+            host: target.host.clone(),
+            username: target.username.clone(),
+            base_path: target.base_path.clone(),
+            local_base: target.local_base.clone(),
+            rules: target.rules.clone(),
+            auth,
+            fallback_auth,
+            enabled: target.enabled,
+            credentials_pending: target.credentials_pending,
+            remember_credentials: target.remember_credentials,
+            use_ssh_config: target.use_ssh_config,
+            correct_clock_skew: target.correct_clock_skew,
+            stat_fallback: target.stat_fallback,
+            remote_read_only: target.remote_read_only,
+            monitor_health: target.monitor_health,
+            algorithms: target.algorithms.clone(),
+            color: target.color,
+            tags: target.tags.clone(),
+            max_connections: target.max_connections,
+            source_address: target.source_address,
+            socket_buffer_kib: target.socket_buffer_kib,
+            post_sync_hook: target.post_sync_hook.clone(),
+            remote_commands: target.remote_commands.clone(),
+        }
+    }
 
+    fn into_runtime(self) -> RemoteTarget {
         RemoteTarget {
             id: self.id,
             name: self.name,
             host: self.host,
             username: self.username,
             base_path: self.base_path,
+            local_base: self.local_base,
             rules: self.rules,
-            auth,
+            auth: restore_auth(self.id, self.auth, self.remember_credentials),
+            fallback_auth: self
+                .fallback_auth
+                .map(|auth| restore_auth(self.id, auth, self.remember_credentials)),
+            enabled: self.enabled,
+            credentials_pending: self.credentials_pending,
+            remember_credentials: self.remember_credentials,
+            use_ssh_config: self.use_ssh_config,
+            correct_clock_skew: self.correct_clock_skew,
+            stat_fallback: self.stat_fallback,
+            remote_read_only: self.remote_read_only,
+            monitor_health: self.monitor_health,
+            algorithms: self.algorithms,
+            color: self.color,
+            tags: self.tags,
+            max_connections: self.max_connections.max(1),
+            source_address: self.source_address,
+            socket_buffer_kib: self.socket_buffer_kib,
+            post_sync_hook: self.post_sync_hook,
+            remote_commands: self.remote_commands,
         }
     }
 }
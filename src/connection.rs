@@ -1,31 +1,287 @@
 use std::{
-    net::{TcpStream, ToSocketAddrs},
-    time::Duration,
+    collections::HashMap,
+    fs,
+    io::{self, Read, Write},
+    net::{IpAddr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use anyhow::{anyhow, Context, Result};
-use ssh2::Session;
+use anyhow::{anyhow, bail, Context, Result};
+use once_cell::sync::Lazy;
+use parking_lot::{Condvar, Mutex};
+use socket2::{Domain, Protocol, Socket, Type};
+use ssh2::{Channel, ErrorCode, MethodType, Session, Sftp};
 
 use crate::{
-    model::{AuthMethod, RemoteTarget},
+    model::{AuthMethod, RemoteTarget, SshAlgorithms},
+    pool,
     security::{self, HostCheck},
 };
 
 const DEFAULT_SSH_PORT: u16 = 22;
-const CONNECT_TIMEOUT_SECS: u64 = 5;
+pub const CONNECT_TIMEOUT_SECS: u64 = 5;
+/// Longest one name lookup may take before it counts as failed.
+const DNS_TIMEOUT: Duration = Duration::from_secs(5);
+/// Lookups per connect; a flaky resolver often answers the second query.
+const DNS_ATTEMPTS: u32 = 2;
+const CHANNEL_OPEN_ATTEMPTS: u32 = 5;
+const CHANNEL_RETRY_DELAY: Duration = Duration::from_millis(250);
+// What libssh2 reports when the server refuses a channel, e.g. sshd's `MaxSessions`.
+const LIBSSH2_ERROR_CHANNEL_FAILURE: i32 = -21;
+/// Pause of the ProxyJump relay when neither side had data.
+const RELAY_IDLE: Duration = Duration::from_millis(2);
 
-pub fn test_connection(target: &RemoteTarget) -> Result<()> {
-    let _ = establish_session(target)?;
-    Ok(())
+/// Open connections per `host:port`, shared by every worker thread.
+static HOST_SLOTS: Lazy<(Mutex<HashMap<String, usize>>, Condvar)> =
+    Lazy::new(|| (Mutex::new(HashMap::new()), Condvar::new()));
+
+// Method strings accepted by libssh2 `method_pref`; unsupported names are
+// dropped by libssh2 as long as at least one entry is usable.
+//
+// Host key (`MethodType::HostKey`):
+//   ssh-ed25519, ecdsa-sha2-nistp{256,384,521}, rsa-sha2-{512,256}  secure
+//   ssh-rsa (RSA/SHA-1), ssh-dss (DSA)                             legacy
+// Key exchange (`MethodType::Kex`):
+//   curve25519-sha256, ecdh-sha2-nistp*, diffie-hellman-group{14-sha256,16-sha512,18-sha512},
+//   diffie-hellman-group-exchange-sha256                            secure
+//   diffie-hellman-group14-sha1, diffie-hellman-group-exchange-sha1,
+//   diffie-hellman-group1-sha1                                      legacy
+const SECURE_HOST_KEYS: &str = "ssh-ed25519,ecdsa-sha2-nistp256,ecdsa-sha2-nistp384,\
+ecdsa-sha2-nistp521,rsa-sha2-512,rsa-sha2-256";
+const LEGACY_HOST_KEYS: &str = "ssh-rsa,ssh-dss";
+const SECURE_KEX: &str = "curve25519-sha256,curve25519-sha256@libssh.org,ecdh-sha2-nistp256,\
+ecdh-sha2-nistp384,ecdh-sha2-nistp521,diffie-hellman-group-exchange-sha256,\
+diffie-hellman-group16-sha512,diffie-hellman-group18-sha512,diffie-hellman-group14-sha256";
+const LEGACY_KEX: &str =
+    "diffie-hellman-group14-sha1,diffie-hellman-group-exchange-sha1,diffie-hellman-group1-sha1";
+
+/// Holds one of a host's connection slots until dropped.
+pub struct HostSlot {
+    key: String,
 }
 
-pub fn establish_session(target: &RemoteTarget) -> Result<Session> {
-    let (host, port) = split_host_port(&target.host);
+impl HostSlot {
+    /// Blocks until fewer than `limit` connections to the host are open, so
+    /// excess work queues instead of tripping the server's own limits.
+    pub fn acquire(host: &str, limit: usize) -> Self {
+        let key = slot_key(host);
+        let (slots, freed) = &*HOST_SLOTS;
+        let mut open = slots.lock();
+        while open.get(&key).copied().unwrap_or(0) >= limit.max(1) {
+            freed.wait(&mut open);
+        }
+        *open.entry(key.clone()).or_default() += 1;
+        Self { key }
+    }
+
+    /// A slot on `target`'s host. When the host is full, its pooled sessions
+    /// are taken out of the pool first, so no login waits on an idle one.
+    pub fn for_target(target: &RemoteTarget) -> Self {
+        pool::make_room(target);
+        Self::acquire(&target.host, target.max_connections)
+    }
+
+    /// Whether `acquire` would have to wait.
+    pub fn is_full(host: &str, limit: usize) -> bool {
+        let open = HOST_SLOTS.0.lock();
+        open.get(&slot_key(host)).copied().unwrap_or(0) >= limit.max(1)
+    }
+
+    pub fn is_for(&self, host: &str) -> bool {
+        self.key == slot_key(host)
+    }
+}
+
+fn slot_key(host: &str) -> String {
+    let (name, port) = split_host_port(host);
+    format!("{}:{port}", name.to_ascii_lowercase())
+}
+
+impl Drop for HostSlot {
+    fn drop(&mut self) {
+        let (slots, freed) = &*HOST_SLOTS;
+        let mut open = slots.lock();
+        if let Some(count) = open.get_mut(&self.key) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                open.remove(&self.key);
+            }
+        }
+        freed.notify_all();
+    }
+}
+
+pub struct ConnectionCheck {
+    /// Label of the authentication method that was accepted.
+    pub method: &'static str,
+    /// Seconds the server clock runs ahead of ours; `None` when the login
+    /// directory is not writable or SFTP is unavailable.
+    pub clock_skew: Option<i64>,
+}
+
+pub fn test_connection(target: &RemoteTarget) -> Result<ConnectionCheck> {
+    let _slot = HostSlot::for_target(target);
+    let (session, method) = connect(target)?;
+    let clock_skew = open_channel(|| session.sftp()).ok().and_then(|sftp| {
+        let dir = sftp.realpath(Path::new(".")).ok()?;
+        measure_clock_skew(&sftp, &dir).ok()
+    });
+    Ok(ConnectionCheck { method, clock_skew })
+}
+
+/// Seconds the server clock runs ahead of ours, read off the modification
+/// time of a probe file created in `dir`. The probe file is removed again.
+pub fn measure_clock_skew(sftp: &Sftp, dir: &Path) -> Result<i64> {
+    let probe = dir.join(format!(".sftp-sync-clock-{}", std::process::id()));
+    let before = SystemTime::now();
+    sftp.create(&probe)
+        .with_context(|| format!("cannot create files in {}", dir.display()))?;
+    let after = SystemTime::now();
+    let stat = sftp.stat(&probe);
+    let _ = sftp.unlink(&probe);
+    let mtime = stat
+        .with_context(|| format!("failed to stat {}", probe.display()))?
+        .mtime
+        .ok_or_else(|| anyhow!("server did not report a modification time"))?;
+    Ok(skew_seconds(before, after, mtime))
+}
+
+/// Remote mtimes are whole seconds, so the file was created somewhere in the
+/// second after `mtime`; compare its middle with the middle of our window.
+fn skew_seconds(before: SystemTime, after: SystemTime, mtime: u64) -> i64 {
+    let millis = |time: SystemTime| {
+        time.duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as i64)
+    };
+    let local = (millis(before) + millis(after)) / 2;
+    let remote = mtime as i64 * 1000 + 500;
+    ((remote - local) as f64 / 1000.0).round() as i64
+}
+
+/// The session counts against the target's connection limit for as long as
+/// the returned slot is alive.
+pub fn establish_session(target: &RemoteTarget) -> Result<(Session, HostSlot)> {
+    let slot = HostSlot::for_target(target);
+    connect(target).map(|(session, _)| (session, slot))
+}
+
+/// Retries `open` with a growing delay while the server refuses new channels,
+/// instead of failing the action on the first rejection.
+pub fn open_channel<T>(mut open: impl FnMut() -> Result<T, ssh2::Error>) -> Result<T, ssh2::Error> {
+    let mut delay = CHANNEL_RETRY_DELAY;
+    for _ in 1..CHANNEL_OPEN_ATTEMPTS {
+        match open() {
+            Err(err) if is_channel_refusal(&err) => {
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    open()
+}
+
+fn is_channel_refusal(err: &ssh2::Error) -> bool {
+    err.code() == ErrorCode::Session(LIBSSH2_ERROR_CHANNEL_FAILURE)
+}
+
+fn connect(target: &RemoteTarget) -> Result<(Session, &'static str)> {
+    if !target.use_ssh_config {
+        let stream = open_tcp(&target.host, target.source_address, target.socket_buffer_kib)?;
+        return start_session(stream, target);
+    }
+    let (resolved, proxy_jump) = apply_ssh_config(target, &read_ssh_config());
+    let stream = match proxy_jump {
+        Some(jump) => open_jump_stream(&jump, &resolved)?,
+        None => open_tcp(
+            &resolved.host,
+            resolved.source_address,
+            resolved.socket_buffer_kib,
+        )?,
+    };
+    start_session(stream, &resolved)
+}
+
+/// `buffer_kib` sizes both socket buffers; 0 keeps the OS default.
+fn open_tcp(host: &str, source: Option<IpAddr>, buffer_kib: u32) -> Result<TcpStream> {
+    let (host, port) = split_host_port(host);
     let addr = format!("{host}:{port}");
-    let socket_addr = resolve_addr(&addr)?.ok_or_else(|| anyhow!("unable to resolve {host}"))?;
+    let mut candidates = resolve_addrs(&addr)?;
+    if let Some(source) = source {
+        // A socket bound to an IPv4 address can only reach IPv4 peers, and vice versa.
+        candidates.retain(|candidate| candidate.is_ipv4() == source.is_ipv4());
+        if candidates.is_empty() {
+            let family = if source.is_ipv4() { "IPv4" } else { "IPv6" };
+            bail!("{host} has no {family} address to reach from source address {source}");
+        }
+    }
+    let timeout = Duration::from_secs(CONNECT_TIMEOUT_SECS);
+    connect_any(&candidates, source, timeout, buffer_kib)
+        .with_context(|| format!("failed to connect to {addr}"))
+}
+
+/// Tries each resolved address in order and returns the first connection;
+/// the error is the last address's.
+fn connect_any(
+    candidates: &[SocketAddr],
+    source: Option<IpAddr>,
+    timeout: Duration,
+    buffer_kib: u32,
+) -> Result<TcpStream> {
+    let mut last_err = anyhow!("no address to connect to");
+    for &candidate in candidates {
+        match connect_socket(source, candidate, timeout, buffer_kib) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_err = err,
+        }
+    }
+    Err(last_err)
+}
 
-    let stream = TcpStream::connect_timeout(&socket_addr, Duration::from_secs(CONNECT_TIMEOUT_SECS))
-        .with_context(|| format!("failed to connect to {addr}"))?;
+/// Connects to `remote`, from `source` when given so the traffic leaves
+/// through the interface that owns that address. Nagle's algorithm is off:
+/// SFTP waits for the reply to each small request, and holding one back for
+/// the previous ACK costs a round trip per file. Buffers are sized before
+/// connecting, while the TCP window scale can still follow them.
+fn connect_socket(
+    source: Option<IpAddr>,
+    remote: SocketAddr,
+    timeout: Duration,
+    buffer_kib: u32,
+) -> Result<TcpStream> {
+    let domain = Domain::for_address(remote);
+    let socket = Socket::new(domain, Type::STREAM, Some(Protocol::TCP))
+        .context("failed to create socket")?;
+    if buffer_kib > 0 {
+        let bytes = buffer_kib as usize * 1024;
+        socket
+            .set_send_buffer_size(bytes)
+            .and_then(|_| socket.set_recv_buffer_size(bytes))
+            .with_context(|| format!("failed to set socket buffers to {buffer_kib} KiB"))?;
+    }
+    socket.set_tcp_nodelay(true).context("failed to disable Nagle's algorithm")?;
+    if let Some(source) = source {
+        socket
+            .bind(&SocketAddr::new(source, 0).into())
+            .with_context(|| {
+                format!(
+                    "failed to bind to source address {source}; is it assigned to this machine?"
+                )
+            })?;
+    }
+    socket.connect_timeout(&remote.into(), timeout).with_context(|| match source {
+        Some(source) => format!("failed to connect to {remote} from {source}"),
+        None => format!("failed to connect to {remote}"),
+    })?;
+    Ok(socket.into())
+}
+
+/// Handshakes over `stream`, checks the host key and authenticates.
+fn start_session(stream: TcpStream, target: &RemoteTarget) -> Result<(Session, &'static str)> {
+    let (host, _) = split_host_port(&target.host);
     stream
         .set_read_timeout(Some(Duration::from_secs(CONNECT_TIMEOUT_SECS)))
         .ok();
@@ -35,6 +291,7 @@ pub fn establish_session(target: &RemoteTarget) -> Result<Session> {
 
     let mut session = Session::new().context("failed to create SSH session")?;
     session.set_tcp_stream(stream);
+    apply_algorithm_prefs(&session, &target.algorithms)?;
     session.handshake().context("SSH handshake failed")?;
 
     if let Some((raw_key, _)) = session.host_key() {
@@ -49,42 +306,407 @@ pub fn establish_session(target: &RemoteTarget) -> Result<Session> {
         }
     }
 
-    match &target.auth {
+    // libssh2 keeps the session usable after a rejected attempt, so each
+    // configured method is tried in turn on the same connection.
+    let mut failures = Vec::new();
+    for method in target.auth_methods() {
+        let result = authenticate(&session, &target.username, method).and_then(|_| {
+            if session.authenticated() {
+                Ok(())
+            } else {
+                Err(anyhow!("authentication rejected"))
+            }
+        });
+        match result {
+            Ok(()) => return Ok((session, method.label())),
+            Err(err) if target.fallback_auth.is_none() => return Err(err),
+            Err(err) => failures.push(format!("{}: {err:#}", method.label())),
+        }
+    }
+
+    Err(anyhow!(
+        "all authentication methods failed ({})",
+        failures.join("; ")
+    ))
+}
+
+/// The subset of one host's `ssh_config` settings used to connect.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SshHostConfig {
+    pub host_name: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub identity_file: Option<PathBuf>,
+    pub proxy_jump: Option<String>,
+}
+
+fn read_ssh_config() -> String {
+    dirs::home_dir()
+        .and_then(|home| fs::read_to_string(home.join(".ssh").join("config")).ok())
+        .unwrap_or_default()
+}
+
+/// Settings for `alias` from ssh_config text. As in OpenSSH the first value
+/// found for a keyword wins; `Match` blocks and `Include` are not supported.
+pub fn parse_ssh_config(text: &str, alias: &str) -> SshHostConfig {
+    let mut config = SshHostConfig::default();
+    let mut active = true;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let separator = |c: char| c.is_whitespace() || c == '=';
+        let Some((keyword, value)) = line.split_once(separator) else {
+            continue;
+        };
+        let value = value.trim_start_matches(separator).trim_matches('"');
+        match keyword.to_ascii_lowercase().as_str() {
+            "host" => active = host_matches(value, alias),
+            "match" => active = false,
+            _ if !active => {}
+            "hostname" => {
+                let host_name = value.replace("%h", alias);
+                config.host_name.get_or_insert(host_name);
+            }
+            "port" => {
+                if let Ok(port) = value.parse() {
+                    config.port.get_or_insert(port);
+                }
+            }
+            "user" => {
+                config.user.get_or_insert_with(|| value.to_string());
+            }
+            "identityfile" => {
+                config
+                    .identity_file
+                    .get_or_insert_with(|| expand_home(value));
+            }
+            "proxyjump" => {
+                config.proxy_jump.get_or_insert_with(|| value.to_string());
+            }
+            _ => {}
+        }
+    }
+    config.proxy_jump = config
+        .proxy_jump
+        .filter(|jump| !jump.eq_ignore_ascii_case("none"));
+    config
+}
+
+/// A `Host` line matches when any pattern does and no `!pattern` does.
+fn host_matches(patterns: &str, alias: &str) -> bool {
+    let alias = alias.to_ascii_lowercase();
+    let mut matched = false;
+    for pattern in patterns.split_whitespace() {
+        let pattern = pattern.to_ascii_lowercase();
+        match pattern.strip_prefix('!') {
+            Some(negated) if glob_matches(negated.as_bytes(), alias.as_bytes()) => return false,
+            Some(_) => {}
+            None => matched |= glob_matches(pattern.as_bytes(), alias.as_bytes()),
+        }
+    }
+    matched
+}
+
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    match (pattern.split_first(), text.split_first()) {
+        (None, None) => true,
+        (Some((b'*', rest)), _) => {
+            glob_matches(rest, text) || (!text.is_empty() && glob_matches(pattern, &text[1..]))
+        }
+        (Some((b'?', rest)), Some((_, text_rest))) => glob_matches(rest, text_rest),
+        (Some((p, rest)), Some((t, text_rest))) if p == t => glob_matches(rest, text_rest),
+        _ => false,
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    let home = dirs::home_dir().unwrap_or_default();
+    match Path::new(path).strip_prefix("~") {
+        Ok(rest) => home.join(rest),
+        Err(_) => PathBuf::from(path.replace("%d", &home.to_string_lossy())),
+    }
+}
+
+/// Fills what the target leaves open from its `ssh_config` entry: the alias
+/// becomes `HostName`, an empty user or key path takes `User` and
+/// `IdentityFile`, and a port typed into the host wins over `Port`. Returns
+/// the `ProxyJump` hop, if any.
+fn apply_ssh_config(target: &RemoteTarget, config_text: &str) -> (RemoteTarget, Option<String>) {
+    let (alias, port) = split_host_port(&target.host);
+    let explicit_port = alias != target.host;
+    let config = parse_ssh_config(config_text, &alias);
+
+    let mut resolved = target.clone();
+    let host = config.host_name.unwrap_or(alias);
+    let port = match config.port {
+        Some(config_port) if !explicit_port => config_port,
+        _ => port,
+    };
+    resolved.host = join_host_port(&host, port);
+    if resolved.username.trim().is_empty() {
+        resolved.username = config.user.unwrap_or_default();
+    }
+    if let Some(identity) = config.identity_file {
+        let auths = std::iter::once(&mut resolved.auth).chain(resolved.fallback_auth.as_mut());
+        for auth in auths {
+            match auth {
+                AuthMethod::SshKey { private_key, .. } if private_key.as_os_str().is_empty() => {
+                    *private_key = identity.clone();
+                }
+                _ => {}
+            }
+        }
+    }
+    (resolved, config.proxy_jump)
+}
+
+/// Where the port a host field connects on comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortOrigin {
+    /// Typed into the field as `host:port`.
+    Typed,
+    /// The `Port` of the host's `ssh_config` entry.
+    SshConfig,
+    /// Nothing named one, so the SSH default of 22.
+    Default,
+}
+
+/// The `host:port` a host field connects to, after the `ssh_config` lookup
+/// when `use_ssh_config` is on, and where its port came from.
+pub fn effective_endpoint(host: &str, use_ssh_config: bool) -> (String, PortOrigin) {
+    let config_text = if use_ssh_config {
+        read_ssh_config()
+    } else {
+        String::new()
+    };
+    endpoint_from_config(host, &config_text)
+}
+
+fn endpoint_from_config(host: &str, config_text: &str) -> (String, PortOrigin) {
+    let (alias, port) = split_host_port(host);
+    let config = parse_ssh_config(config_text, &alias);
+    let (port, origin) = match config.port {
+        _ if alias != host => (port, PortOrigin::Typed),
+        Some(config_port) => (config_port, PortOrigin::SshConfig),
+        None => (port, PortOrigin::Default),
+    };
+    let host = config.host_name.unwrap_or(alias);
+    (join_host_port(&host, port), origin)
+}
+
+fn join_host_port(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    }
+}
+
+/// Connects to the jump host and returns a local stream relayed through a
+/// `direct-tcpip` channel to `target`. The jump host authenticates with its
+/// own `IdentityFile` when it has one, otherwise with the target's methods.
+fn open_jump_stream(jump: &str, target: &RemoteTarget) -> Result<TcpStream> {
+    if jump.contains(',') {
+        bail!("ProxyJump with more than one hop is not supported");
+    }
+    let (jump_user, jump_host) = match jump.split_once('@') {
+        Some((user, host)) => (Some(user.to_string()), host),
+        None => (None, jump),
+    };
+    let config_text = read_ssh_config();
+    let hop = RemoteTarget {
+        host: jump_host.to_string(),
+        username: jump_user.unwrap_or_default(),
+        ..target.clone()
+    };
+    let (mut hop, nested) = apply_ssh_config(&hop, &config_text);
+    if nested.is_some() {
+        bail!("ProxyJump with more than one hop is not supported");
+    }
+    if hop.username.is_empty() {
+        hop.username = target.username.clone();
+    }
+    let (jump_alias, _) = split_host_port(jump_host);
+    if let Some(identity) = parse_ssh_config(&config_text, &jump_alias).identity_file {
+        hop.auth = AuthMethod::ssh_key(identity);
+        hop.fallback_auth = None;
+    }
+
+    let stream = open_tcp(&hop.host, hop.source_address, hop.socket_buffer_kib)?;
+    let (session, _) = start_session(stream, &hop)
+        .with_context(|| format!("failed to connect through jump host {jump_host}"))?;
+    let (host, port) = split_host_port(&target.host);
+    let channel = session
+        .channel_direct_tcpip(&host, port, None)
+        .with_context(|| format!("jump host {jump_host} could not reach {host}:{port}"))?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").context("failed to open ProxyJump relay")?;
+    let local = TcpStream::connect(listener.local_addr()?)?;
+    let (peer, peer_addr) = listener.accept()?;
+    if peer_addr != local.local_addr()? {
+        bail!("unexpected connection to the ProxyJump relay");
+    }
+    thread::Builder::new()
+        .name("proxy-jump-relay".into())
+        .spawn(move || relay(session, channel, peer))
+        .context("failed to start ProxyJump relay")?;
+    Ok(local)
+}
+
+/// Copies bytes between the local socket and the jump channel until either
+/// side closes. The session runs non-blocking so one thread serves both ways.
+fn relay(session: Session, mut channel: Channel, mut peer: TcpStream) {
+    session.set_blocking(false);
+    if peer.set_nonblocking(true).is_err() {
+        return;
+    }
+    let mut buf = vec![0; 32 * 1024];
+    loop {
+        let mut idle = true;
+        match peer.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => {
+                if write_fully(&mut channel, &buf[..read]).is_err() {
+                    break;
+                }
+                idle = false;
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+        match channel.read(&mut buf) {
+            Ok(0) if channel.eof() => break,
+            Ok(0) => {}
+            Ok(read) => {
+                if write_fully(&mut peer, &buf[..read]).is_err() {
+                    break;
+                }
+                idle = false;
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+        if idle {
+            thread::sleep(RELAY_IDLE);
+        }
+    }
+    let _ = channel.close();
+}
+
+fn write_fully(writer: &mut impl Write, mut data: &[u8]) -> io::Result<()> {
+    while !data.is_empty() {
+        match writer.write(data) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(written) => data = &data[written..],
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => thread::sleep(RELAY_IDLE),
+            Err(err) => return Err(err),
+        }
+    }
+    writer.flush()
+}
+
+fn authenticate(session: &Session, username: &str, method: &AuthMethod) -> Result<()> {
+    match method {
+        AuthMethod::Password { secret, .. } if secret.is_empty() => {
+            Err(anyhow!("no password available; enter it to connect"))
+        }
         AuthMethod::Password { secret, .. } => session
-            .userauth_password(&target.username, secret.as_str())
-            .context("authentication failed")?,
+            .userauth_password(username, secret.as_str())
+            .context("authentication failed"),
         AuthMethod::SshKey {
             private_key,
             passphrase,
             ..
         } => session
-            .userauth_pubkey_file(
-                &target.username,
-                None,
-                private_key,
-                passphrase.as_deref(),
-            )
-            .context("public key authentication failed")?,
-    };
+            .userauth_pubkey_file(username, None, private_key, passphrase.as_deref())
+            .context("public key authentication failed"),
+    }
+}
+
+fn apply_algorithm_prefs(session: &Session, algorithms: &SshAlgorithms) -> Result<()> {
+    let allow_legacy = algorithms.allow_legacy;
+    let host_key = method_preference(
+        &algorithms.host_key,
+        SECURE_HOST_KEYS,
+        LEGACY_HOST_KEYS,
+        allow_legacy,
+    );
+    let kex = method_preference(&algorithms.kex, SECURE_KEX, LEGACY_KEX, allow_legacy);
 
-    if !session.authenticated() {
-        return Err(anyhow!("authentication rejected"));
+    if let Some(prefs) = host_key {
+        session
+            .method_pref(MethodType::HostKey, &prefs)
+            .with_context(|| format!("unsupported host key algorithms \"{prefs}\""))?;
     }
+    if let Some(prefs) = kex {
+        session
+            .method_pref(MethodType::Kex, &prefs)
+            .with_context(|| format!("unsupported key exchange algorithms \"{prefs}\""))?;
+    }
+    Ok(())
+}
 
-    Ok(session)
+/// Custom list (or the secure defaults when opting into legacy) with legacy
+/// algorithms appended last, so they are only negotiated as a fallback.
+fn method_preference(
+    custom: &str,
+    secure: &str,
+    legacy: &str,
+    allow_legacy: bool,
+) -> Option<String> {
+    let custom = custom
+        .split([',', ' ', '\n'])
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect::<Vec<_>>()
+        .join(",");
+    match (custom.is_empty(), allow_legacy) {
+        (true, false) => None,
+        (true, true) => Some(format!("{secure},{legacy}")),
+        (false, false) => Some(custom),
+        (false, true) => Some(format!("{custom},{legacy}")),
+    }
 }
 
-fn resolve_addr(addr: &str) -> Result<Option<std::net::SocketAddr>> {
-    let mut addrs = addr.to_socket_addrs()?;
-    Ok(addrs.next())
+/// Resolves `addr` on a helper thread, giving up after `DNS_TIMEOUT` per
+/// attempt, so a stalled resolver fails the connect instead of hanging it.
+/// A lookup that hangs keeps its thread until the resolver gives up.
+fn resolve_addrs(addr: &str) -> Result<Vec<SocketAddr>> {
+    let mut last_err = anyhow!("failed to resolve {addr}");
+    for _ in 0..DNS_ATTEMPTS {
+        let (tx, rx) = mpsc::channel();
+        let query = addr.to_string();
+        thread::Builder::new()
+            .name("dns-resolve".into())
+            .spawn(move || {
+                let _ = tx.send(query.to_socket_addrs().map(Iterator::collect::<Vec<_>>));
+            })
+            .context("failed to start name resolution")?;
+        match rx.recv_timeout(DNS_TIMEOUT) {
+            Ok(Ok(addrs)) if !addrs.is_empty() => return Ok(addrs),
+            Ok(Ok(_)) => last_err = anyhow!("{addr} did not resolve to any address"),
+            Ok(Err(err)) => {
+                last_err = anyhow!(err).context(format!("failed to resolve {addr}"));
+            }
+            Err(_) => {
+                last_err = anyhow!(
+                    "resolving {addr} timed out after {} seconds; check the DNS settings of this network",
+                    DNS_TIMEOUT.as_secs()
+                );
+            }
+        }
+    }
+    Err(last_err)
 }
 
-fn split_host_port(host: &str) -> (String, u16) {
+pub fn split_host_port(host: &str) -> (String, u16) {
     if let Some(rest) = host.strip_prefix('[') {
-        if let Some((addr, port)) = rest.split_once("]:") {
-            if let Ok(port) = port.parse::<u16>() {
-                return (addr.to_string(), port);
-            }
+        if let Some((addr, port)) = rest.split_once("]:")
+            && let Ok(port) = port.parse::<u16>()
+        {
+            return (addr.to_string(), port);
         }
         return (host.to_string(), DEFAULT_SSH_PORT);
     }
@@ -93,10 +715,175 @@ fn split_host_port(host: &str) -> (String, u16) {
         return (host.to_string(), DEFAULT_SSH_PORT);
     }
 
-    if let Some((name, port_str)) = host.rsplit_once(':') {
-        if let Ok(port) = port_str.parse::<u16>() {
-            return (name.to_string(), port);
-        }
+    if let Some((name, port_str)) = host.rsplit_once(':')
+        && let Ok(port) = port_str.parse::<u16>()
+    {
+        return (name.to_string(), port);
     }
     (host.to_string(), DEFAULT_SSH_PORT)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    #[test]
+    fn source_address_is_bound_before_connecting() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let remote = listener.local_addr().unwrap();
+        let timeout = Duration::from_secs(CONNECT_TIMEOUT_SECS);
+
+        let source = Some("127.0.0.1".parse().unwrap());
+        let stream = connect_socket(source, remote, timeout, 0).unwrap();
+        let (_, peer) = listener.accept().unwrap();
+        assert_eq!(peer, stream.local_addr().unwrap());
+
+        // TEST-NET-1 is never assigned to a local interface.
+        let source = Some("192.0.2.1".parse().unwrap());
+        let err = connect_socket(source, remote, timeout, 0).unwrap_err();
+        assert!(format!("{err}").contains("failed to bind to source address 192.0.2.1"));
+        let err = open_tcp(&remote.to_string(), Some("::1".parse().unwrap()), 0).unwrap_err();
+        assert!(err.to_string().contains("no IPv6 address"));
+    }
+
+    #[test]
+    fn connect_falls_back_to_later_addresses() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let open = listener.local_addr().unwrap();
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let timeout = Duration::from_secs(CONNECT_TIMEOUT_SECS);
+
+        let stream = connect_any(&[closed, open], None, timeout, 0).unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), open);
+        let err = connect_any(&[closed], None, timeout, 0).unwrap_err();
+        assert!(format!("{err}").contains(&closed.to_string()));
+
+        assert_eq!(resolve_addrs(&open.to_string()).unwrap(), [open]);
+    }
+
+    #[test]
+    fn sockets_skip_nagle_and_take_the_requested_buffers() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let remote = listener.local_addr().unwrap();
+        let timeout = Duration::from_secs(CONNECT_TIMEOUT_SECS);
+
+        let stream = connect_socket(None, remote, timeout, 0).unwrap();
+        assert!(stream.nodelay().unwrap());
+
+        let stream = connect_socket(None, remote, timeout, 1024).unwrap();
+        let socket = socket2::SockRef::from(&stream);
+        // Linux doubles the value for bookkeeping; others may round it.
+        assert!(socket.send_buffer_size().unwrap() >= 512 * 1024);
+        assert!(socket.recv_buffer_size().unwrap() >= 512 * 1024);
+    }
+
+    #[test]
+    fn clock_skew_compares_the_middle_of_both_clocks() {
+        let at = |millis: u64| UNIX_EPOCH + Duration::from_millis(millis);
+        assert_eq!(skew_seconds(at(1_000_200), at(1_000_600), 1_000), 0);
+        assert_eq!(skew_seconds(at(1_000_200), at(1_000_600), 1_090), 90);
+        assert_eq!(skew_seconds(at(1_000_200), at(1_000_600), 997), -3);
+        assert_eq!(skew_seconds(at(1_000_900), at(1_001_900), 1_001), 0);
+    }
+
+    #[test]
+    fn host_slots_queue_connections_beyond_the_limit() {
+        let first = HostSlot::acquire("limit.example.com", 2);
+        let _second = HostSlot::acquire("LIMIT.example.com:22", 2);
+        assert!(HostSlot::is_full("limit.example.com:22", 2));
+        assert!(first.is_for("Limit.example.com"));
+
+        let (tx, rx) = mpsc::channel();
+        let waiter = thread::spawn(move || {
+            let _third = HostSlot::acquire("limit.example.com:22", 2);
+            tx.send(()).unwrap();
+        });
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+        drop(first);
+        assert!(rx.recv_timeout(Duration::from_secs(2)).is_ok());
+        waiter.join().unwrap();
+    }
+
+    const SSH_CONFIG: &str = "\
+# personal boxes
+Host web !web-old
+    HostName web.example.com
+    Port 2222
+    IdentityFile=/keys/web
+
+Match user root
+    User nobody
+
+Host web* *.internal
+    User deploy
+    Port 22
+    ProxyJump bastion
+
+Host *
+    ProxyJump none
+";
+
+    #[test]
+    fn ssh_config_takes_the_first_value_from_matching_hosts() {
+        let web = parse_ssh_config(SSH_CONFIG, "web");
+        assert_eq!(web.host_name.as_deref(), Some("web.example.com"));
+        assert_eq!(web.port, Some(2222));
+        assert_eq!(web.user.as_deref(), Some("deploy"));
+        assert_eq!(web.identity_file, Some(PathBuf::from("/keys/web")));
+        assert_eq!(web.proxy_jump.as_deref(), Some("bastion"));
+
+        let old = parse_ssh_config(SSH_CONFIG, "web-old");
+        assert_eq!(old.host_name, None);
+        assert_eq!(old.user.as_deref(), Some("deploy"));
+
+        let other = parse_ssh_config(SSH_CONFIG, "db");
+        assert_eq!(other, SshHostConfig::default());
+    }
+
+    #[test]
+    fn values_on_the_target_override_the_ssh_config() {
+        let mut target = crate::model::sample_remote_targets().remove(0);
+        target.host = "web".into();
+        target.username = String::new();
+        target.auth = AuthMethod::ssh_key(PathBuf::new());
+        let (resolved, jump) = apply_ssh_config(&target, SSH_CONFIG);
+        assert_eq!(resolved.host, "web.example.com:2222");
+        assert_eq!(resolved.username, "deploy");
+        assert!(matches!(
+            &resolved.auth,
+            AuthMethod::SshKey { private_key, .. } if private_key == Path::new("/keys/web")
+        ));
+        assert_eq!(jump.as_deref(), Some("bastion"));
+
+        target.host = "web:2200".into();
+        target.username = "admin".into();
+        let (resolved, _) = apply_ssh_config(&target, SSH_CONFIG);
+        assert_eq!(resolved.host, "web.example.com:2200");
+        assert_eq!(resolved.username, "admin");
+    }
+
+    #[test]
+    fn effective_endpoints_tell_where_the_port_came_from() {
+        let endpoint = |host| endpoint_from_config(host, SSH_CONFIG);
+        assert_eq!(
+            endpoint("web"),
+            ("web.example.com:2222".into(), PortOrigin::SshConfig)
+        );
+        assert_eq!(
+            endpoint("web:2200"),
+            ("web.example.com:2200".into(), PortOrigin::Typed)
+        );
+        assert_eq!(endpoint("db"), ("db:22".into(), PortOrigin::Default));
+        assert_eq!(
+            endpoint("[::1]:2022"),
+            ("[::1]:2022".into(), PortOrigin::Typed)
+        );
+        assert_eq!(endpoint("fe80::1"), ("[fe80::1]:22".into(), PortOrigin::Default));
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt::Write;
+
+use crate::{
+    connection,
+    model::{AppState, AuthMethod, RemoteTarget},
+};
+
+const RECENT_LOG_LINES: usize = 20;
+
+/// Builds a plain-text report for bug reports. Passwords and passphrases of
+/// every configured target are scrubbed from all free-form text.
+pub fn build_bundle(
+    state: &AppState,
+    target: Option<&RemoteTarget>,
+    error: Option<&str>,
+) -> String {
+    let scrub = |text: &str| state.redact(text);
+
+    let mut out = String::new();
+    let _ = writeln!(out, "SFTP-SYNC {} diagnostics", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        out,
+        "os: {} ({})",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+
+    if let Some(target) = target {
+        let (host, port) = connection::split_host_port(&target.host);
+        let _ = writeln!(out, "\ntarget: {}", scrub(&target.name));
+        let _ = writeln!(out, "host: {host}");
+        let _ = writeln!(out, "port: {port}");
+        let _ = writeln!(out, "username: {}", target.username);
+        let _ = writeln!(out, "auth: {}", describe_auth(&target.auth));
+        if let Some(fallback) = &target.fallback_auth {
+            let _ = writeln!(out, "fallback auth: {}", describe_auth(fallback));
+        }
+        let _ = writeln!(
+            out,
+            "connect timeout: {}s",
+            connection::CONNECT_TIMEOUT_SECS
+        );
+        let _ = writeln!(out, "rules: {}", target.rules.len());
+    }
+
+    if let Some(error) = error {
+        let _ = writeln!(out, "\nerror:\n{}", scrub(error));
+    }
+
+    let skip = state.logs.len().saturating_sub(RECENT_LOG_LINES);
+    if skip < state.logs.len() {
+        let _ = writeln!(out, "\nrecent log:");
+        for log in &state.logs[skip..] {
+            let _ = writeln!(out, "[{}] {}", log.level.as_str(), scrub(&log.message));
+        }
+    }
+
+    out
+}
+
+fn describe_auth(auth: &AuthMethod) -> String {
+    match auth {
+        AuthMethod::Password { secret, stored } => format!(
+            "password (set: {}, stored in keychain: {stored})",
+            !secret.is_empty()
+        ),
+        AuthMethod::SshKey {
+            private_key,
+            passphrase,
+            ..
+        } => format!(
+            "ssh key {} (passphrase set: {})",
+            private_key.display(),
+            passphrase.is_some()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{sample_remote_targets, AppSettings, LogLevel};
+
+    #[test]
+    fn bundle_never_contains_secrets() {
+        let mut targets = sample_remote_targets();
+        targets[0].auth = AuthMethod::password("hunter2-secret");
+        let mut state = AppState::new(AppSettings::default(), targets);
+        state.log_event(LogLevel::Error, "login hunter2-secret rejected");
+
+        let target = state.remote_targets[0].clone();
+        let bundle = build_bundle(
+            &state,
+            Some(&target),
+            Some("authentication failed for hunter2-secret"),
+        );
+
+        assert!(!bundle.contains("hunter2-secret"));
+        assert!(bundle.contains(crate::redact::MASK));
+        assert!(bundle.contains("authentication failed"));
+    }
+}
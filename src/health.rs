@@ -0,0 +1,19 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::{model::RemoteTarget, pool};
+
+/// Checks that `target` is reachable on its pooled session: a keepalive,
+/// then an SFTP request whose reply proves the server is still answering
+/// rather than just accepting writes. A session that went stale is replaced
+/// by one fresh login, whose result decides.
+pub fn check(target: &RemoteTarget) -> Result<()> {
+    let (session, sftp, _slot) = pool::borrow(target)?;
+    session
+        .keepalive_send()
+        .context("failed to send keepalive")?;
+    sftp.realpath(Path::new("."))
+        .context("server did not answer")?;
+    Ok(())
+}
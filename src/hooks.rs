@@ -0,0 +1,133 @@
+use std::{
+    io::Read,
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+
+use crate::{model::RemoteTarget, sync::ExecutionSummary};
+
+/// Longest a post-sync hook may run before it is killed.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(300);
+const HOOK_POLL: Duration = Duration::from_millis(50);
+/// Output kept for the log; the rest is dropped.
+const OUTPUT_LIMIT: usize = 4096;
+
+/// How a post-sync hook ended.
+pub struct HookOutcome {
+    /// `None` when the command was killed, by a signal or by the timeout.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    /// Trimmed stdout, cut to `OUTPUT_LIMIT` bytes.
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Runs the target's post-sync hook if it is enabled and the sync outcome
+/// allows it. `None` means no hook was due.
+pub fn run_post_sync(
+    target: &RemoteTarget,
+    summary: &ExecutionSummary,
+) -> Option<Result<HookOutcome>> {
+    let hook = &target.post_sync_hook;
+    let failures = summary.failures.len() + usize::from(summary.remote_command_failure().is_some());
+    if !hook.due(failures) {
+        return None;
+    }
+    let mut command = shell(hook.command.trim());
+    command
+        .env("SFTP_SYNC_TARGET", &target.name)
+        .env("SFTP_SYNC_HOST", &target.host)
+        .env("SFTP_SYNC_APPLIED", summary.applied.to_string())
+        .env("SFTP_SYNC_FAILED", failures.to_string());
+    Some(run(command, HOOK_TIMEOUT))
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
+
+fn run(mut command: Command, timeout: Duration) -> Result<HookOutcome> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to start post-sync hook")?;
+    let stdout = capture(child.stdout.take());
+    let stderr = capture(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let (status, timed_out) = loop {
+        if let Some(status) = child
+            .try_wait()
+            .context("failed to wait for post-sync hook")?
+        {
+            break (Some(status), false);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            break (None, true);
+        }
+        thread::sleep(HOOK_POLL);
+    };
+
+    // Background children of the hook may keep the pipes open; don't wait on them.
+    let collect = |output: mpsc::Receiver<String>| {
+        output
+            .recv_timeout(Duration::from_secs(1))
+            .unwrap_or_default()
+    };
+    Ok(HookOutcome {
+        exit_code: status.and_then(|status| status.code()),
+        timed_out,
+        stdout: collect(stdout),
+        stderr: collect(stderr),
+    })
+}
+
+fn capture(pipe: Option<impl Read + Send + 'static>) -> mpsc::Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    if let Some(mut pipe) = pipe {
+        thread::spawn(move || {
+            let mut bytes = Vec::new();
+            let _ = pipe.read_to_end(&mut bytes);
+            bytes.truncate(OUTPUT_LIMIT);
+            let _ = tx.send(String::from_utf8_lossy(&bytes).trim().to_string());
+        });
+    }
+    rx
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hook_reports_output_and_exit_code() {
+        let outcome = run(shell("echo deployed; echo oops >&2; exit 3"), HOOK_TIMEOUT).unwrap();
+        assert_eq!(outcome.exit_code, Some(3));
+        assert_eq!(
+            (outcome.stdout.as_str(), outcome.stderr.as_str()),
+            ("deployed", "oops")
+        );
+
+        let outcome = run(shell("sleep 5"), Duration::from_millis(100)).unwrap();
+        assert!(outcome.timed_out);
+        assert_eq!(outcome.exit_code, None);
+    }
+}
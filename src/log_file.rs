@@ -0,0 +1,131 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::config;
+
+const FILE_NAME: &str = "activity.log";
+/// The live file is rotated before it would grow past this.
+pub const MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// Files kept, the live one included; rotation deletes the oldest.
+pub const MAX_FILES: usize = 5;
+
+/// One writer for every thread that logs, so lines never interleave and
+/// rotation happens once.
+static SINK: Lazy<Mutex<Option<RotatingLog>>> = Lazy::new(|| Mutex::new(None));
+
+/// Appends `line` to the activity log of the active profile. Write errors are
+/// dropped; the in-memory log still has the entry.
+pub fn append(line: &str) {
+    let Some(dir) = config::log_dir() else {
+        return;
+    };
+    let mut sink = SINK.lock();
+    // Switching profiles moves the log to the new profile's folder.
+    if sink.as_ref().is_none_or(|log| log.dir != dir) {
+        *sink = Some(RotatingLog::new(dir, MAX_FILE_BYTES, MAX_FILES));
+    }
+    if let Some(log) = sink.as_mut() {
+        let _ = log.write_line(line);
+    }
+}
+
+/// Where the live log file is written.
+pub fn current_path() -> Option<PathBuf> {
+    config::log_dir().map(|dir| dir.join(FILE_NAME))
+}
+
+/// `activity.log` plus `activity.log.1` (newest) to `activity.log.<max_files - 1>`.
+struct RotatingLog {
+    dir: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingLog {
+    fn new(dir: PathBuf, max_bytes: u64, max_files: usize) -> Self {
+        Self {
+            dir,
+            max_bytes,
+            max_files: max_files.max(1),
+            file: None,
+            size: 0,
+        }
+    }
+
+    fn path(&self, index: usize) -> PathBuf {
+        if index == 0 {
+            self.dir.join(FILE_NAME)
+        } else {
+            self.dir.join(format!("{FILE_NAME}.{index}"))
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.file.is_none() {
+            self.open()?;
+        }
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        if let Some(file) = self.file.as_mut() {
+            writeln!(file, "{line}")?;
+            self.size += len;
+        }
+        Ok(())
+    }
+
+    fn open(&mut self) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(0))?;
+        self.size = file.metadata()?.len();
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        let _ = fs::remove_file(self.path(self.max_files - 1));
+        for index in (0..self.max_files - 1).rev() {
+            let _ = fs::rename(self.path(index), self.path(index + 1));
+        }
+        self.open()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn rotation_keeps_a_bounded_number_of_files() {
+        let temp = tempdir().unwrap();
+        let mut log = RotatingLog::new(temp.path().to_path_buf(), 20, 3);
+        for index in 0..10 {
+            log.write_line(&format!("line {index:02} ......")).unwrap();
+        }
+
+        let mut names: Vec<String> = fs::read_dir(temp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["activity.log", "activity.log.1", "activity.log.2"]);
+        let read = |index| fs::read_to_string(log.path(index)).unwrap();
+        assert_eq!(read(0), "line 09 ......\n");
+        assert_eq!(read(1), "line 08 ......\n");
+        assert_eq!(read(2), "line 07 ......\n");
+    }
+}
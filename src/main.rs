@@ -1,5 +1,11 @@
 mod config;
 mod connection;
+mod diagnostics;
+mod health;
+mod hooks;
+mod log_file;
+mod pool;
+mod redact;
 mod secrets;
 mod security;
 mod model;
@@ -8,31 +14,80 @@ mod task_queue;
 mod watcher;
 mod view;
 
+use std::time::Duration;
+
 use gpui::*;
 use gpui_component::Root;
 
 use model::AppState;
 use view::AppView;
 
-fn main() {
-    let app = Application::new();
+/// Longest the window waits on close for running syncs to stop.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+/// macOS keeps the app alive without a window, so syncs carry on after a
+/// close and a window reopened from the dock picks them up.
+const KEEP_SYNCING_WHEN_CLOSED: bool = cfg!(target_os = "macos");
 
-    app.run(move |cx| {
-        gpui_component::init(cx);
+/// Stops background work and writes the final state before the window goes away.
+fn shutdown(state: &AppState) {
+    if !KEEP_SYNCING_WHEN_CLOSED {
+        stop_tasks();
+    }
+    // The next window starts its own watcher and listens to it.
+    watcher::shutdown();
+    config::save_state(&state.settings, &state.remote_targets);
+}
+
+fn stop_tasks() {
+    if !task_queue::shutdown(SHUTDOWN_TIMEOUT) {
+        eprintln!("task workers still busy after {SHUTDOWN_TIMEOUT:?}; exiting anyway");
+    }
+}
 
-        cx.spawn(async move |cx| {
-            let (initial_settings, initial_targets) = config::load_state();
+fn open_main_window(cx: &mut App) {
+    cx.spawn(async move |cx| {
+        let first_run = config::is_first_run();
+        let (initial_settings, initial_targets) = config::load_state();
 
-            cx.open_window(WindowOptions::default(), |window, cx| {
-                let state =
-                    cx.new(|_| AppState::new(initial_settings.clone(), initial_targets.clone()));
-                let view = cx.new(|_| AppView::new(state.clone()));
+        cx.open_window(WindowOptions::default(), |window, cx| {
+            let state = cx.new(|_| {
+                let mut state = AppState::new(initial_settings.clone(), initial_targets.clone());
+                if first_run {
+                    state.start_setup();
+                }
+                state
+            });
+            let view = cx.new(|_| AppView::new(state.clone()));
+            let closing = state.clone();
+            window.on_window_should_close(cx, move |_, cx| {
+                shutdown(closing.read(cx));
+                true
+            });
 
-                cx.new(|cx| Root::new(view.into(), window, cx))
-            })?;
+            cx.new(|cx| Root::new(view.into(), window, cx))
+        })?;
 
-            Ok::<_, anyhow::Error>(())
+        Ok::<_, anyhow::Error>(())
+    })
+    .detach();
+}
+
+fn main() {
+    let app = Application::new();
+    app.on_reopen(|cx| {
+        if cx.windows().is_empty() {
+            open_main_window(cx);
+        }
+    });
+
+    app.run(move |cx| {
+        gpui_component::init(cx);
+        cx.on_app_quit(|_| {
+            stop_tasks();
+            async {}
         })
         .detach();
+
+        open_main_window(cx);
     });
 }
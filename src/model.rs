@@ -1,12 +1,19 @@
 use std::{
-    collections::HashMap,
-    path::PathBuf,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    collections::{HashMap, HashSet, VecDeque},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use serde::{Deserialize, Serialize};
 
-use crate::sync::{PlanJobsResult, SyncJob};
+use crate::{
+    log_file, redact,
+    sync::{
+        self, DriftSummary, ExecutionJournal, ExecutionSummary, HeldDeletion, PlanJobsResult,
+        PlanStats, RuleSummary, SpaceCheck, SyncJob, TargetComparison,
+    },
+};
 
 pub type TargetId = u64;
 pub type SessionId = u64;
@@ -18,11 +25,260 @@ pub struct RemoteTarget {
     pub host: String,
     pub username: String,
     pub base_path: PathBuf,
+    /// Directory that relative rule `local` paths resolve against. Empty means
+    /// the home directory, so launching from a terminal or an icon agrees.
+    pub local_base: PathBuf,
     pub rules: Vec<SyncRule>,
     pub auth: AuthMethod,
+    /// Tried when `auth` is rejected. Always the other kind of method, so the
+    /// two never share a keychain slot.
+    pub fallback_auth: Option<AuthMethod>,
+    /// Disabled targets stay configured but are skipped by batch operations.
+    pub enabled: bool,
+    /// Set for imported targets whose secrets were not carried over.
+    pub credentials_pending: bool,
+    /// When false, secrets never reach the keychain; they live in memory for
+    /// the session and are asked for again at connect time.
+    pub remember_credentials: bool,
+    /// Treat `host` as an alias in `~/.ssh/config`; values set here still win.
+    pub use_ssh_config: bool,
+    /// Shift remote modification times by the measured server clock offset
+    /// before comparing them with local ones.
+    pub correct_clock_skew: bool,
+    /// Look up files one at a time when the server lists them without size or
+    /// modification time. Slower, but such files otherwise compare as empty.
+    pub stat_fallback: bool,
+    /// Never upload to or delete on the server, whatever the rules plan.
+    pub remote_read_only: bool,
+    /// Ping the server every `health_check_mins` and show the result in the sidebar.
+    pub monitor_health: bool,
+    pub algorithms: SshAlgorithms,
+    pub color: TargetColor,
+    /// Free-form labels such as `prod` or `personal`, used to filter the sidebar.
+    pub tags: Vec<String>,
+    /// Connections opened to this host at once; further work waits for a slot.
+    pub max_connections: usize,
+    /// Local address connections are made from, for multi-homed machines;
+    /// `None` lets the OS pick the interface.
+    pub source_address: Option<IpAddr>,
+    /// Send and receive buffer size of the SSH socket in KiB; 0 keeps the
+    /// OS default, which suits most links.
+    pub socket_buffer_kib: u32,
+    pub post_sync_hook: PostSyncHook,
+    pub remote_commands: RemoteCommands,
+}
+
+/// Socket buffer sizes the target form steps through, in KiB; 0 is the OS default.
+pub const SOCKET_BUFFER_STEPS_KIB: [u32; 7] = [0, 256, 512, 1024, 2048, 4096, 8192];
+
+/// Organizational color shown as a dot next to the target in the sidebar.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetColor {
+    #[default]
+    None,
+    Red,
+    Orange,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl TargetColor {
+    pub const ALL: [TargetColor; 6] = [
+        TargetColor::None,
+        TargetColor::Red,
+        TargetColor::Orange,
+        TargetColor::Green,
+        TargetColor::Blue,
+        TargetColor::Purple,
+    ];
+
+    pub fn rgb(self) -> Option<u32> {
+        match self {
+            TargetColor::None => None,
+            TargetColor::Red => Some(0xef4444),
+            TargetColor::Orange => Some(0xf97316),
+            TargetColor::Green => Some(0x22c55e),
+            TargetColor::Blue => Some(0x3b82f6),
+            TargetColor::Purple => Some(0xa855f7),
+        }
+    }
+}
+
+/// Splits a comma-separated tag list, dropping blanks and case-insensitive duplicates.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+        if !tags.iter().any(|existing| existing.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Advanced SSH algorithm preferences passed to libssh2 before the handshake.
+/// Empty lists keep the libssh2 defaults.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SshAlgorithms {
+    /// Comma-separated host key algorithms, e.g. `ssh-ed25519,rsa-sha2-512`.
+    #[serde(default)]
+    pub host_key: String,
+    /// Comma-separated key exchange algorithms, e.g. `curve25519-sha256`.
+    #[serde(default)]
+    pub kex: String,
+    /// Also offer SHA-1/DSA era algorithms (`ssh-rsa`, `diffie-hellman-group1-sha1`, ...).
+    #[serde(default)]
+    pub allow_legacy: bool,
+}
+
+/// Local shell command run after a sync of the target finishes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostSyncHook {
+    #[serde(default)]
+    pub command: String,
+    /// Hooks never run until switched on here; imports switch them off again.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Also run after syncs that had failures.
+    #[serde(default)]
+    pub run_on_failure: bool,
+}
+
+impl PostSyncHook {
+    /// Whether the hook should run after a sync with `failures` failed actions.
+    pub fn due(&self, failures: usize) -> bool {
+        self.enabled && !self.command.trim().is_empty() && (failures == 0 || self.run_on_failure)
+    }
+}
+
+/// Commands run on the server over the sync's own SSH session once a sync
+/// finishes without failures, e.g. `systemctl reload nginx`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteCommands {
+    #[serde(default)]
+    pub commands: Vec<String>,
+    /// Off until switched on here; imports switch it off again.
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+impl RemoteCommands {
+    /// The non-blank commands in run order; none while switched off.
+    pub fn due(&self) -> Vec<&str> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        self.commands
+            .iter()
+            .map(|command| command.trim())
+            .filter(|command| !command.is_empty())
+            .collect()
+    }
+}
+
+/// An edit that changes which files a target syncs, or which way.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScopeChange {
+    BasePath {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    LocalBase {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// A rule that has no counterpart after the edit.
+    RuleRemoved(String),
+    /// A rule that kept one of its roots but moved the other.
+    RuleMoved {
+        from: String,
+        to: String,
+    },
+    Direction {
+        rule: String,
+        from: SyncDirection,
+        to: SyncDirection,
+    },
 }
 
 impl RemoteTarget {
+    /// Authentication methods in the order they are attempted.
+    pub fn auth_methods(&self) -> impl Iterator<Item = &AuthMethod> {
+        std::iter::once(&self.auth).chain(self.fallback_auth.as_ref())
+    }
+
+    /// Whether connecting first needs a password that is not kept between sessions.
+    pub fn needs_session_secret(&self) -> bool {
+        !self.remember_credentials
+            && matches!(&self.auth, AuthMethod::Password { secret, .. } if secret.is_empty())
+    }
+
+    /// How `updated` narrows or redirects this target's sync. Filter edits are
+    /// left out: files they exclude are skipped, never deleted.
+    pub fn scope_changes(&self, updated: &RemoteTarget) -> Vec<ScopeChange> {
+        let mut changes = Vec::new();
+        if self.base_path != updated.base_path {
+            changes.push(ScopeChange::BasePath {
+                from: self.base_path.clone(),
+                to: updated.base_path.clone(),
+            });
+        }
+        if self.local_base != updated.local_base {
+            changes.push(ScopeChange::LocalBase {
+                from: self.local_base.clone(),
+                to: updated.local_base.clone(),
+            });
+        }
+        // Exact root matches are paired first so a moved rule can't claim
+        // another rule's unchanged counterpart.
+        let mut unmatched: Vec<&SyncRule> = updated.rules.iter().collect();
+        let mut leftover = Vec::new();
+        for rule in &self.rules {
+            let same = |new: &&SyncRule| new.local == rule.local && new.remote == rule.remote;
+            match unmatched.iter().position(same) {
+                Some(index) => {
+                    let kept = unmatched.remove(index);
+                    if kept.direction != rule.direction {
+                        changes.push(ScopeChange::Direction {
+                            rule: rule.label(),
+                            from: rule.direction,
+                            to: kept.direction,
+                        });
+                    }
+                }
+                None => leftover.push(rule),
+            }
+        }
+        for rule in leftover {
+            let shared = |new: &&SyncRule| new.local == rule.local || new.remote == rule.remote;
+            match unmatched.iter().position(shared) {
+                Some(index) => changes.push(ScopeChange::RuleMoved {
+                    from: rule.label(),
+                    to: unmatched.remove(index).label(),
+                }),
+                None => changes.push(ScopeChange::RuleRemoved(rule.label())),
+            }
+        }
+        changes
+    }
+
+    /// Gives each rule the last status of the rule in `previous` with the
+    /// same roots, so editing a target keeps what its rules last did.
+    pub fn keep_rule_statuses(&mut self, previous: &RemoteTarget) {
+        for rule in &mut self.rules {
+            rule.last_status = previous
+                .rules
+                .iter()
+                .find(|old| old.local == rule.local && old.remote == rule.remote)
+                .and_then(|old| old.last_status);
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own.eq_ignore_ascii_case(tag))
+    }
+
     pub fn summary(&self) -> String {
         format!(
             "{}@{}{}",
@@ -33,17 +289,15 @@ impl RemoteTarget {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 pub enum AuthMethod {
     Password {
         secret: String,
-        #[allow(dead_code)]
         stored: bool,
     },
     SshKey {
         private_key: PathBuf,
         passphrase: Option<String>,
-        #[allow(dead_code)]
         passphrase_stored: bool,
     },
 }
@@ -56,7 +310,6 @@ impl AuthMethod {
         }
     }
 
-    #[allow(dead_code)]
     pub fn ssh_key(path: PathBuf) -> Self {
         Self::SshKey {
             private_key: path,
@@ -70,7 +323,13 @@ impl AuthMethod {
         matches!(self, AuthMethod::Password { .. })
     }
 
-    #[allow(dead_code)]
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuthMethod::Password { .. } => "password",
+            AuthMethod::SshKey { .. } => "SSH key",
+        }
+    }
+
     pub fn secret(&self) -> Option<&str> {
         match self {
             AuthMethod::Password { secret, .. } => Some(secret.as_str()),
@@ -84,6 +343,419 @@ pub struct SyncRule {
     pub local: PathBuf,
     pub remote: PathBuf,
     pub direction: SyncDirection,
+    /// When false, entries whose name starts with `.` are ignored on both sides.
+    #[serde(default = "default_true")]
+    pub include_hidden: bool,
+    /// Follow symlinks to regular files instead of skipping them.
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    /// Listing aborts below this many directory levels, so a root like `/`
+    /// fails fast instead of walking the whole disk.
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+    /// Listing aborts once this many files are found on either side.
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+    /// Upload every file straight into the remote root under its bare name.
+    /// Only valid for push rules.
+    #[serde(default)]
+    pub flatten: bool,
+    /// Upload hard-linked files once and link the other names on the server.
+    /// Only valid for push rules.
+    #[serde(default)]
+    pub hard_links: bool,
+    /// Give uploaded files the local owner and group. Usually needs root on
+    /// the server; where it is refused the upload still counts.
+    #[serde(default)]
+    pub preserve_ownership: bool,
+    /// Never upload to or delete under this rule's remote root, whatever its
+    /// direction plans.
+    #[serde(default)]
+    pub remote_read_only: bool,
+    /// When a push uploads a file that already exists on the remote.
+    #[serde(default)]
+    pub overwrite_policy: OverwritePolicy,
+    /// How a file present on both sides is judged changed.
+    #[serde(default)]
+    pub compare_policy: ComparePolicy,
+    /// When several source files would land on the same destination file.
+    #[serde(default)]
+    pub collision_policy: CollisionPolicy,
+    /// The destination treats names differing only in case as one file, as
+    /// FAT, macOS and Windows filesystems do, so such names collide too.
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Which side wins when both changed. Only used by two-way rules.
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+    /// Globs such as `*.rs` or `src/**`; when non-empty, only matching files sync.
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Globs whose matches never sync. Wins over `include`.
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    /// Leave zero-byte files alone: they are neither copied nor deleted.
+    #[serde(default)]
+    pub skip_empty_files: bool,
+    /// Exact file names that never sync, e.g. `.DS_Store`.
+    #[serde(default = "default_skip_names")]
+    pub skip_names: Vec<String>,
+    /// Line endings text files are given as they are written to the other side.
+    #[serde(default)]
+    pub line_endings: LineEndings,
+    /// How the rule's latest sync ended; `None` until it has run once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_status: Option<RuleStatus>,
+}
+
+/// One rule's share of a finished sync, kept with the rule so it outlives the
+/// session that produced it.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct RuleStatus {
+    pub applied: usize,
+    pub conflicts: usize,
+    pub failed: usize,
+    pub finished_at: SystemTime,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverwritePolicy {
+    /// Upload only when the local copy is newer.
+    #[default]
+    NewerOnly,
+    /// Upload whenever the file exists on both sides.
+    AlwaysOverwrite,
+    /// Upload when size or modification time differ in either direction.
+    SizeOrTimeDiffers,
+}
+
+impl OverwritePolicy {
+    pub const ALL: [OverwritePolicy; 3] = [
+        OverwritePolicy::NewerOnly,
+        OverwritePolicy::AlwaysOverwrite,
+        OverwritePolicy::SizeOrTimeDiffers,
+    ];
+}
+
+/// What tells planning that a file present on both sides has changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparePolicy {
+    /// Modification times, moved onto the local clock first.
+    #[default]
+    ModifiedTime,
+    /// Sizes alone, for filesystems whose mtimes can't be trusted (FAT, some
+    /// network mounts). Two-way rules can't tell which side changed, so a
+    /// size difference there is a conflict.
+    SizeOnly,
+    /// Modification times, but same-size files whose contents hash the same
+    /// are skipped however far apart their times are.
+    Checksum,
+}
+
+impl ComparePolicy {
+    pub const ALL: [ComparePolicy; 3] = [
+        ComparePolicy::ModifiedTime,
+        ComparePolicy::SizeOnly,
+        ComparePolicy::Checksum,
+    ];
+}
+
+/// Line endings a rule writes text files with. Files with a NUL byte near the
+/// start count as binary and are always copied byte for byte.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEndings {
+    /// Copy every file as it is.
+    #[default]
+    Keep,
+    /// Unix `\n`.
+    Lf,
+    /// Windows `\r\n`.
+    Crlf,
+}
+
+impl LineEndings {
+    pub const ALL: [LineEndings; 3] = [LineEndings::Keep, LineEndings::Lf, LineEndings::Crlf];
+
+    /// Whether a rule rewriting to these endings would also decide by size.
+    /// The rewrite changes sizes, so such a rule would see every text file
+    /// as changed on every run.
+    pub fn clashes_with(
+        self,
+        direction: SyncDirection,
+        compare_policy: ComparePolicy,
+        overwrite_policy: OverwritePolicy,
+    ) -> bool {
+        self != LineEndings::Keep
+            && (compare_policy == ComparePolicy::SizeOnly
+                || (direction == SyncDirection::Push
+                    && overwrite_policy == OverwritePolicy::SizeOrTimeDiffers))
+    }
+}
+
+/// What planning does with source files that map to one destination file,
+/// through flattening or names that differ only in case.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionPolicy {
+    /// Leave all of them out of the plan and report the collision.
+    #[default]
+    Error,
+    /// Keep the first name and append `-1`, `-2`, … to the others.
+    Rename,
+    /// Transfer all of them; whichever is written last wins.
+    Overwrite,
+}
+
+impl CollisionPolicy {
+    pub const ALL: [CollisionPolicy; 3] = [
+        CollisionPolicy::Error,
+        CollisionPolicy::Rename,
+        CollisionPolicy::Overwrite,
+    ];
+}
+
+/// What a sync does with a file that changed on both sides.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Leave both copies alone and report the conflict.
+    #[default]
+    Skip,
+    /// Upload the local copy over the remote one.
+    KeepLocal,
+    /// Download the remote copy over the local one.
+    KeepRemote,
+}
+
+impl ConflictPolicy {
+    pub const ALL: [ConflictPolicy; 3] = [
+        ConflictPolicy::Skip,
+        ConflictPolicy::KeepLocal,
+        ConflictPolicy::KeepRemote,
+    ];
+}
+
+/// Marks the copy a conflict resolution saved before overwriting a file.
+const CONFLICT_BACKUP_MARKER: &str = ".conflict-";
+
+/// `rel_path` with `.conflict-<unix seconds of at>` appended to its name.
+pub fn conflict_backup_path(rel_path: &Path, at: SystemTime) -> PathBuf {
+    let secs = at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let mut name = rel_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!("{CONFLICT_BACKUP_MARKER}{secs}"));
+    rel_path.with_file_name(name)
+}
+
+/// Whether `rel_path` names a backup written by `conflict_backup_path`.
+pub fn is_conflict_backup(rel_path: &Path) -> bool {
+    rel_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.rsplit_once(CONFLICT_BACKUP_MARKER))
+        .is_some_and(|(_, secs)| !secs.is_empty() && secs.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Ends the name of a file that is still being transferred.
+const PARTIAL_TRANSFER_SUFFIX: &str = ".sftp-sync.part";
+
+/// Where an upload of `rel_path` is staged until it is complete. The name is
+/// stable so a later attempt finds what an interrupted one left behind.
+pub fn upload_staging_path(rel_path: &Path) -> PathBuf {
+    let name = rel_path.file_name().unwrap_or_default().to_string_lossy();
+    rel_path.with_file_name(format!(".{name}{PARTIAL_TRANSFER_SUFFIX}"))
+}
+
+/// Whether `rel_path` names a transfer staged on either side and not yet
+/// renamed into place.
+pub fn is_partial_transfer(rel_path: &Path) -> bool {
+    rel_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.') && name.ends_with(PARTIAL_TRANSFER_SUFFIX))
+}
+
+impl SyncRule {
+    pub fn new(
+        local: impl Into<PathBuf>,
+        remote: impl Into<PathBuf>,
+        direction: SyncDirection,
+    ) -> Self {
+        Self {
+            local: local.into(),
+            remote: remote.into(),
+            direction,
+            include_hidden: true,
+            follow_symlinks: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            flatten: false,
+            hard_links: false,
+            preserve_ownership: false,
+            remote_read_only: false,
+            overwrite_policy: OverwritePolicy::default(),
+            compare_policy: ComparePolicy::default(),
+            collision_policy: CollisionPolicy::default(),
+            case_insensitive: false,
+            conflict_policy: ConflictPolicy::default(),
+            include: Vec::new(),
+            ignore: Vec::new(),
+            skip_empty_files: false,
+            skip_names: default_skip_names(),
+            line_endings: LineEndings::default(),
+            last_status: None,
+        }
+    }
+
+    /// Whether `rel_path` passes the include and ignore lists. A pattern with
+    /// a `/` matches the whole relative path; one without matches any component.
+    /// Conflict backups, partial transfers and files named in `skip_names`
+    /// never sync.
+    pub fn selects(&self, rel_path: &Path) -> bool {
+        if is_conflict_backup(rel_path) || is_partial_transfer(rel_path) {
+            return false;
+        }
+        if let Some(name) = rel_path.file_name()
+            && self.skip_names.iter().any(|skip| name == skip.as_str())
+        {
+            return false;
+        }
+        let components: Vec<String> = rel_path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let path = components.join("/");
+        let matches = |pattern: &String| {
+            let pattern: Vec<char> = pattern.chars().collect();
+            if pattern.contains(&'/') {
+                glob_match(&pattern, &path.chars().collect::<Vec<_>>())
+            } else {
+                components
+                    .iter()
+                    .any(|component| glob_match(&pattern, &component.chars().collect::<Vec<_>>()))
+            }
+        };
+        if self.ignore.iter().any(matches) {
+            return false;
+        }
+        self.include.is_empty() || self.include.iter().any(matches)
+    }
+
+    /// Where a local relative path lands under the remote root.
+    pub fn remote_rel_path(&self, rel_path: &Path) -> PathBuf {
+        match rel_path.file_name() {
+            Some(name) if self.flatten => PathBuf::from(name),
+            _ => rel_path.to_path_buf(),
+        }
+    }
+
+    /// Short `local → remote` mapping used to attribute logs and failures.
+    pub fn label(&self) -> String {
+        format!("{} → {}", self.local.display(), self.remote.display())
+    }
+}
+
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+/// Safety valve against a rule rooted at `/` or a home directory.
+pub const DEFAULT_MAX_ENTRIES: usize = 1_000_000;
+/// Clutter left by file managers, skipped by new rules until edited.
+pub const DEFAULT_SKIP_NAMES: [&str; 3] = [".DS_Store", "Thumbs.db", "desktop.ini"];
+pub const DEFAULT_MAX_CONNECTIONS: usize = 2;
+pub const MAX_CONNECTIONS_LIMIT: usize = 8;
+
+/// `*` and `?` stay within one path segment; `**` spans segments, and a `**/`
+/// that starts a segment also matches no directory at all.
+///
+/// Runs in O(pattern × text): on a mismatch only the last `*` and the last
+/// `**` are retried, the `*` absorbing one more character of its segment and
+/// failing that the `**` one more character (or, as `**/`, one more folder).
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Pattern index just past the wildcard, and where its match ends now.
+    let mut star: Option<(usize, usize)> = None;
+    let mut globstar: Option<(usize, usize, bool)> = None;
+    while p < pattern.len() || t < text.len() {
+        match pattern.get(p) {
+            Some('*') if pattern.get(p + 1) == Some(&'*') => {
+                let folders =
+                    pattern.get(p + 2) == Some(&'/') && (p == 0 || pattern[p - 1] == '/');
+                let rest = if folders { p + 3 } else { p + 2 };
+                globstar = Some((rest, t, folders));
+                star = None;
+                p = rest;
+                continue;
+            }
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+                continue;
+            }
+            Some('?') if text.get(t).is_some_and(|&c| c != '/') => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            Some(&c) if c != '?' && text.get(t) == Some(&c) => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            _ => {}
+        }
+        if let Some((rest, end)) = star
+            && text.get(end).is_some_and(|&c| c != '/')
+        {
+            star = Some((rest, end + 1));
+            (p, t) = (rest, end + 1);
+            continue;
+        }
+        star = None;
+        let Some((rest, end, folders)) = globstar else {
+            return false;
+        };
+        let next = if folders {
+            match text[end..].iter().position(|&c| c == '/') {
+                Some(slash) => end + slash + 1,
+                None => return false,
+            }
+        } else if end < text.len() {
+            end + 1
+        } else {
+            return false;
+        };
+        globstar = Some((rest, next, folders));
+        (p, t) = (rest, next);
+    }
+    true
+}
+
+/// Splits a comma-separated list of globs, dropping blanks and duplicates.
+pub fn parse_patterns(text: &str) -> Vec<String> {
+    let mut patterns: Vec<String> = Vec::new();
+    for pattern in text.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        if !patterns.iter().any(|existing| existing == pattern) {
+            patterns.push(pattern.to_string());
+        }
+    }
+    patterns
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_depth() -> usize {
+    DEFAULT_MAX_DEPTH
+}
+
+fn default_max_entries() -> usize {
+    DEFAULT_MAX_ENTRIES
+}
+
+fn default_skip_names() -> Vec<String> {
+    DEFAULT_SKIP_NAMES.map(String::from).to_vec()
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
@@ -102,6 +774,59 @@ pub struct SyncSession {
     pub pending_actions: usize,
 }
 
+/// Finished runs kept in the "History" section.
+pub const SESSION_HISTORY_LIMIT: usize = 20;
+
+/// Outcome of a finished execution, kept after its sessions are re-planned away.
+#[derive(Clone)]
+pub struct SessionRecord {
+    pub target_id: TargetId,
+    pub target_name: String,
+    pub finished_at: SystemTime,
+    pub applied: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// First failure, or why the run stopped before applying anything.
+    pub error: Option<String>,
+}
+
+impl SessionRecord {
+    pub fn from_summary(target: &RemoteTarget, summary: &ExecutionSummary) -> Self {
+        let multi_rule = summary.rules.len() > 1;
+        Self {
+            target_id: target.id,
+            target_name: target.name.clone(),
+            finished_at: SystemTime::now(),
+            applied: summary.applied,
+            skipped: summary.skipped,
+            failed: summary.failures.len(),
+            error: summary.failures.first().map(|failure| {
+                if multi_rule {
+                    format!("{}: {}", failure.rule, failure.reason)
+                } else {
+                    failure.reason.clone()
+                }
+            }),
+        }
+    }
+
+    pub fn aborted(target: &RemoteTarget, reason: String) -> Self {
+        Self {
+            target_id: target.id,
+            target_name: target.name.clone(),
+            finished_at: SystemTime::now(),
+            applied: 0,
+            skipped: 0,
+            failed: 0,
+            error: Some(reason),
+        }
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
 #[derive(Clone)]
 pub enum SyncStatus {
     Idle,
@@ -112,11 +837,27 @@ pub enum SyncStatus {
     Completed,
 }
 
+impl SyncStatus {
+    /// Lower ranks need attention sooner: failures first, finished work last.
+    fn rank(&self) -> u8 {
+        match self {
+            SyncStatus::Failed { .. } => 0,
+            SyncStatus::AwaitingConfirmation => 1,
+            SyncStatus::Running { .. } => 2,
+            SyncStatus::Planning => 3,
+            SyncStatus::Idle => 4,
+            SyncStatus::Completed => 5,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct TransferLog {
     pub timestamp: SystemTime,
     pub level: LogLevel,
     pub message: String,
+    /// Target the entry concerns; `None` for app-wide events.
+    pub target_id: Option<TargetId>,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -141,9 +882,59 @@ pub struct TaskProgress {
     pub kind: TaskKind,
     pub completed: usize,
     pub total: usize,
+    /// Local and remote files listed so far while planning.
+    pub discovered: usize,
+    /// Still opening the SSH session; no files have been looked at yet.
+    pub connecting: bool,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+/// Width of the throughput graph, in one-second buckets.
+pub const THROUGHPUT_WINDOW_SECS: usize = 60;
+
+/// Cumulative transferred bytes sampled during an execution, kept for the
+/// last minute so the dashboard can show bursts and stalls.
+#[derive(Clone, Default)]
+pub struct ThroughputHistory {
+    baseline: u64,
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl ThroughputHistory {
+    pub fn record(&mut self, bytes: u64, now: Instant) {
+        self.samples.push_back((now, bytes));
+        let window = Duration::from_secs(THROUGHPUT_WINDOW_SECS as u64);
+        while let Some(&(at, bytes)) = self.samples.front() {
+            if now.saturating_duration_since(at) < window {
+                break;
+            }
+            self.baseline = bytes;
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.samples
+            .back()
+            .map(|&(_, bytes)| bytes)
+            .unwrap_or(self.baseline)
+    }
+
+    /// Bytes transferred per second, oldest first, ending at `now`.
+    pub fn per_second(&self, now: Instant) -> Vec<u64> {
+        let mut buckets = vec![0; THROUGHPUT_WINDOW_SECS];
+        let mut previous = self.baseline;
+        for &(at, bytes) in &self.samples {
+            let age = now.saturating_duration_since(at).as_secs() as usize;
+            if age < THROUGHPUT_WINDOW_SECS {
+                buckets[THROUGHPUT_WINDOW_SECS - 1 - age] += bytes.saturating_sub(previous);
+            }
+            previous = bytes;
+        }
+        buckets
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TaskKind {
     Planning,
     Executing,
@@ -155,9 +946,21 @@ impl TaskProgress {
             kind,
             completed,
             total: total.max(1),
+            discovered: 0,
+            connecting: false,
         }
     }
 
+    pub fn with_discovered(mut self, discovered: usize) -> Self {
+        self.discovered = discovered;
+        self
+    }
+
+    pub fn with_connecting(mut self, connecting: bool) -> Self {
+        self.connecting = connecting;
+        self
+    }
+
     pub fn percent(&self) -> f32 {
         let total = self.total.max(1) as f32;
         (self.completed as f32 / total).clamp(0.0, 1.0) * 100.0
@@ -175,9 +978,39 @@ pub enum Language {
 pub struct AppSettings {
     pub auto_connect: bool,
     pub watch_local_changes: bool,
+    /// Rescan interval used only when native filesystem events are unavailable.
+    pub watch_poll_secs: u64,
     pub confirm_destructive: bool,
+    /// Plans deleting at least this many files, or this percentage of the
+    /// destination, need an extra confirmation even without `confirm_destructive`.
+    pub mass_delete_count: usize,
+    pub mass_delete_percent: u8,
     pub limit_bandwidth: bool,
     pub bandwidth_mbps: u32,
+    /// Defer uploads of files whose size/mtime still change within the window.
+    pub stability_check: bool,
+    pub stability_window_ms: u64,
+    /// SSH keepalive interval in seconds during execution; 0 disables it.
+    pub keepalive_secs: u32,
+    /// Where downloads are staged before the final rename; `None` uses the
+    /// destination directory.
+    pub staging_dir: Option<PathBuf>,
+    pub target_sort: TargetSort,
+    pub session_sort: SessionSort,
+    /// Activity log filters: hide info entries, and entries of other targets.
+    pub log_problems_only: bool,
+    pub log_active_target_only: bool,
+    /// Also append log entries to rotating files in the config directory.
+    pub log_to_file: bool,
+    /// Save the copy a conflict resolution overwrites as a `.conflict-<time>` file.
+    pub backup_conflicts: bool,
+    /// Syncs apply transfers only; their deletions wait in `pending_deletions`
+    /// until approved one by one or in bulk.
+    pub review_deletions: bool,
+    /// Minutes between reachability checks of targets with `monitor_health`.
+    pub health_check_mins: u32,
+    /// Target selected when the app was last used; auto-connect starts there.
+    pub last_target: Option<TargetId>,
     pub language: Language,
 }
 
@@ -186,14 +1019,70 @@ impl Default for AppSettings {
         Self {
             auto_connect: true,
             watch_local_changes: true,
+            watch_poll_secs: 2,
             confirm_destructive: true,
+            mass_delete_count: 1000,
+            mass_delete_percent: 50,
             limit_bandwidth: false,
             bandwidth_mbps: 200,
+            stability_check: true,
+            stability_window_ms: 500,
+            keepalive_secs: 30,
+            staging_dir: None,
+            target_sort: TargetSort::default(),
+            session_sort: SessionSort::default(),
+            log_problems_only: false,
+            log_active_target_only: false,
+            log_to_file: false,
+            backup_conflicts: true,
+            review_deletions: false,
+            health_check_mins: 5,
+            last_target: None,
             language: Language::English,
         }
     }
 }
 
+/// Sidebar ordering; `Attention` keeps the configured order but lifts targets
+/// with failed or pending sessions to the top.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetSort {
+    #[default]
+    Attention,
+    Name,
+    LastRun,
+    Pending,
+}
+
+impl TargetSort {
+    pub fn next(self) -> Self {
+        match self {
+            TargetSort::Attention => TargetSort::Name,
+            TargetSort::Name => TargetSort::LastRun,
+            TargetSort::LastRun => TargetSort::Pending,
+            TargetSort::Pending => TargetSort::Attention,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionSort {
+    #[default]
+    Status,
+    Recent,
+}
+
+impl SessionSort {
+    pub fn next(self) -> Self {
+        match self {
+            SessionSort::Status => SessionSort::Recent,
+            SessionSort::Recent => SessionSort::Status,
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum ActiveView {
     Dashboard,
@@ -201,6 +1090,10 @@ pub enum ActiveView {
     TargetSettings,
 }
 
+/// Server clock offsets beyond this are reported: remote timestamps have
+/// one-second resolution, so anything smaller is noise.
+pub const CLOCK_SKEW_WARN_SECS: i64 = 2;
+
 pub struct AppState {
     pub remote_targets: Vec<RemoteTarget>,
     pub sessions: Vec<SyncSession>,
@@ -210,10 +1103,59 @@ pub struct AppState {
     pub active_view: ActiveView,
     pub target_form: Option<TargetFormMode>,
     pub connection_tests: HashMap<TargetId, ConnectionTestState>,
+    /// Remote folder checks of single rules, keyed by target and rule index.
+    pub rule_tests: HashMap<(TargetId, usize), ConnectionTestState>,
     pub jobs: Vec<SyncJob>,
     next_session_id: SessionId,
     pub task_progress: HashMap<TargetId, TaskProgress>,
+    /// Local changes since the last successful sync, per target.
+    pub drift: HashMap<TargetId, DriftSummary>,
+    /// Transfer history of running executions; dropped with their progress.
+    pub throughput: HashMap<TargetId, ThroughputHistory>,
+    /// Remote free space against the latest plan's uploads.
+    pub space_checks: HashMap<TargetId, SpaceCheck>,
+    /// Targets whose pending plan is a forced full sync.
+    pub forced_plans: HashSet<TargetId>,
+    /// Sidebar shows only targets carrying this tag.
+    pub tag_filter: Option<String>,
+    /// Plan preview lists files that are already in sync too, not just changes.
+    pub plan_show_all: bool,
+    /// Plan preview rows listed per target; grows a page at a time, resets on replan.
+    pub plan_rows_shown: HashMap<TargetId, usize>,
+    /// Latest remote-vs-remote comparison, keyed by the target it was started from.
+    pub comparisons: HashMap<TargetId, ComparisonReport>,
+    /// SFTP start directory reported while planning; relative remotes resolve under it.
+    pub remote_start_dirs: HashMap<TargetId, PathBuf>,
+    /// Seconds the server clock runs ahead of ours, as last measured.
+    pub clock_skews: HashMap<TargetId, i64>,
+    /// Targets already warned about listing files without their stats.
+    pub missing_stats_warned: HashSet<TargetId>,
+    /// Latest health check of each monitored target.
+    pub health: HashMap<TargetId, TargetHealth>,
+    /// Set while "Sync All" works through the enabled targets.
+    pub sync_all: Option<SyncAllProgress>,
+    /// Runs cut short in an earlier session, offered for resume.
+    pub interrupted: Vec<ExecutionJournal>,
+    /// Finished runs, newest first, capped at `SESSION_HISTORY_LIMIT`.
+    pub history: VecDeque<SessionRecord>,
+    /// Deletions the latest run of each target held for review.
+    pub pending_deletions: HashMap<TargetId, Vec<HeldDeletion>>,
     pub bootstrap_pending: bool,
+    /// Current page of the first-run setup while it is shown.
+    pub setup: Option<SetupStep>,
+}
+
+#[derive(Clone)]
+pub struct SyncAllProgress {
+    pub completed: usize,
+    pub total: usize,
+    pub current: String,
+}
+
+#[derive(Clone)]
+pub struct ComparisonReport {
+    pub other_name: String,
+    pub comparison: TargetComparison,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -222,6 +1164,49 @@ pub enum TargetFormMode {
     Edit(TargetId),
 }
 
+/// Pages of the first-run setup, which walks through the target form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetupStep {
+    Server,
+    Authentication,
+    Rule,
+}
+
+impl SetupStep {
+    pub const ALL: [SetupStep; 3] = [
+        SetupStep::Server,
+        SetupStep::Authentication,
+        SetupStep::Rule,
+    ];
+
+    pub fn index(self) -> usize {
+        Self::ALL.iter().position(|step| *step == self).unwrap_or(0)
+    }
+
+    pub fn next(self) -> Option<Self> {
+        Self::ALL.get(self.index() + 1).copied()
+    }
+
+    pub fn previous(self) -> Option<Self> {
+        self.index().checked_sub(1).map(|index| Self::ALL[index])
+    }
+}
+
+/// Outcome of a target's latest health check.
+#[derive(Clone)]
+pub struct TargetHealth {
+    pub checked_at: SystemTime,
+    /// Why the server could not be reached; `None` when it answered.
+    pub error: Option<String>,
+}
+
+impl TargetHealth {
+    /// Whether the next check is due, `interval` after this one.
+    pub fn is_due(&self, interval: Duration) -> bool {
+        !matches!(self.checked_at.elapsed(), Ok(elapsed) if elapsed < interval)
+    }
+}
+
 #[derive(Clone)]
 pub enum ConnectionTestState {
     InProgress,
@@ -229,6 +1214,22 @@ pub enum ConnectionTestState {
     Failure(String),
 }
 
+/// One target's card on the all-targets overview.
+#[derive(Clone)]
+pub struct TargetOverview {
+    pub target_id: TargetId,
+    pub name: String,
+    pub enabled: bool,
+    /// Actions waiting in the target's current plan.
+    pub pending: usize,
+    pub conflicts: usize,
+    /// When a sync of the target last finished.
+    pub last_sync: Option<SystemTime>,
+    /// Whether the server answered its latest health check, or else its
+    /// latest connection test; `None` when it hasn't been checked.
+    pub reachable: Option<bool>,
+}
+
 impl AppState {
     pub fn new(settings: AppSettings, remote_targets: Vec<RemoteTarget>) -> Self {
         let remote_targets = if remote_targets.is_empty() {
@@ -241,21 +1242,29 @@ impl AppState {
                 timestamp: SystemTime::now() - Duration::from_secs(45),
                 level: LogLevel::Info,
                 message: "Staged 5 uploads for Production".into(),
+                target_id: None,
             },
             TransferLog {
                 timestamp: SystemTime::now() - Duration::from_secs(120),
                 level: LogLevel::Info,
                 message: "Detected drift on Analytics/datasets".into(),
+                target_id: None,
             },
             TransferLog {
                 timestamp: SystemTime::now() - Duration::from_secs(600),
                 level: LogLevel::Info,
                 message: "Completed sync session #998".into(),
+                target_id: None,
             },
         ];
 
+        let active_target = settings
+            .last_target
+            .filter(|id| remote_targets.iter().any(|target| target.id == *id))
+            .or_else(|| remote_targets.first().map(|target| target.id));
+
         let state = Self {
-            active_target: remote_targets.first().map(|target| target.id),
+            active_target,
             active_view: ActiveView::Dashboard,
             settings,
             remote_targets,
@@ -263,15 +1272,61 @@ impl AppState {
             logs,
             target_form: None,
             connection_tests: HashMap::new(),
+            rule_tests: HashMap::new(),
             jobs: Vec::new(),
             next_session_id: 1,
             task_progress: HashMap::new(),
+            drift: HashMap::new(),
+            throughput: HashMap::new(),
+            space_checks: HashMap::new(),
+            forced_plans: HashSet::new(),
+            tag_filter: None,
+            plan_show_all: false,
+            plan_rows_shown: HashMap::new(),
+            comparisons: HashMap::new(),
+            remote_start_dirs: HashMap::new(),
+            clock_skews: HashMap::new(),
+            missing_stats_warned: HashSet::new(),
+            health: HashMap::new(),
+            sync_all: None,
+            interrupted: Vec::new(),
+            history: VecDeque::new(),
+            pending_deletions: HashMap::new(),
             bootstrap_pending: true,
+            setup: None,
         };
-
+        state.remember_secrets();
         state
     }
 
+    /// `text` with every configured password and passphrase masked. Library
+    /// errors can echo what they were given, so anything shown or logged
+    /// goes through this.
+    pub fn redact(&self, text: &str) -> String {
+        redact::redact(text, self.secrets())
+    }
+
+    /// Hands the current secrets to `redact::redact_known`; called whenever
+    /// the targets are saved.
+    pub fn remember_secrets(&self) {
+        redact::remember(self.secrets());
+    }
+
+    fn secrets(&self) -> impl Iterator<Item = &str> {
+        self.remote_targets
+            .iter()
+            .flat_map(RemoteTarget::auth_methods)
+            .filter_map(AuthMethod::secret)
+    }
+
+    /// Opens the first-run setup in place of the dashboard. The sample
+    /// targets are never planned, since the setup replaces them.
+    pub fn start_setup(&mut self) {
+        self.setup = Some(SetupStep::Server);
+        self.bootstrap_pending = false;
+        self.open_target_form(TargetFormMode::Create);
+    }
+
     pub fn next_target_id(&self) -> TargetId {
         self.remote_targets
             .iter()
@@ -281,32 +1336,102 @@ impl AppState {
             .saturating_add(1)
     }
 
+    /// Takes over the journals found at startup. Later sessions are numbered
+    /// after them so a new run never reuses a journaled id.
+    pub fn restore_interrupted(&mut self, journals: Vec<ExecutionJournal>) {
+        if let Some(last) = journals.iter().map(|journal| journal.session_id).max() {
+            self.next_session_id = self.next_session_id.max(last.saturating_add(1));
+        }
+        self.interrupted = journals;
+    }
+
     pub fn next_session_id(&mut self) -> SessionId {
         let id = self.next_session_id;
         self.next_session_id = self.next_session_id.saturating_add(1);
         id
     }
 
+    /// Shows the target form; every "create" and "edit" entry point goes through here.
+    pub fn open_target_form(&mut self, mode: TargetFormMode) {
+        self.target_form = Some(mode);
+        self.active_view = ActiveView::TargetSettings;
+    }
+
     pub fn log_event(&mut self, level: LogLevel, message: impl Into<String>) {
+        self.push_log(None, level, message.into());
+    }
+
+    pub fn log_target_event(
+        &mut self,
+        target_id: TargetId,
+        level: LogLevel,
+        message: impl Into<String>,
+    ) {
+        self.push_log(Some(target_id), level, message.into());
+    }
+
+    fn push_log(&mut self, target_id: Option<TargetId>, level: LogLevel, message: String) {
+        let message = self.redact(&message);
         let timestamp = SystemTime::now();
-        let message = message.into();
         let epoch_secs = timestamp
             .duration_since(UNIX_EPOCH)
             .map(|duration| duration.as_secs())
             .unwrap_or(0);
-        println!("[{epoch_secs}][{}] {message}", level.as_str());
+        let line = format!("[{epoch_secs}][{}] {message}", level.as_str());
+        println!("{line}");
+        if self.settings.log_to_file {
+            log_file::append(&line);
+        }
 
         self.logs.push(TransferLog {
             timestamp,
             level,
             message,
+            target_id,
         });
     }
 
+    /// Log entries passing the activity filters in settings, oldest first.
+    pub fn visible_logs(&self) -> Vec<TransferLog> {
+        self.logs
+            .iter()
+            .filter(|log| !self.settings.log_problems_only || log.level != LogLevel::Info)
+            .filter(|log| {
+                !self.settings.log_active_target_only
+                    || self.active_target.is_none()
+                    || log.target_id == self.active_target
+            })
+            .cloned()
+            .collect()
+    }
+
     pub fn apply_planned_jobs(&mut self, target_id: TargetId, result: PlanJobsResult) {
         self.jobs.retain(|job| job.target_id != target_id);
+        self.plan_rows_shown.remove(&target_id);
         for warning in result.warnings {
-            self.log_event(LogLevel::Warn, warning);
+            self.log_target_event(target_id, LogLevel::Warn, warning);
+        }
+        if let Some(start_dir) = result.start_dir {
+            self.remote_start_dirs.insert(target_id, start_dir);
+        }
+        if let Some(skew) = result.clock_skew {
+            self.record_clock_skew(target_id, skew);
+        }
+        if result.missing_stats > 0 {
+            self.warn_missing_stats(target_id, result.missing_stats);
+        }
+        match result.space {
+            Some(space) => {
+                self.space_checks.insert(target_id, space);
+            }
+            None => {
+                self.space_checks.remove(&target_id);
+            }
+        }
+        if result.forced {
+            self.forced_plans.insert(target_id);
+        } else {
+            self.forced_plans.remove(&target_id);
         }
         for planned in result.jobs {
             let id = self.next_session_id();
@@ -315,24 +1440,317 @@ impl AppState {
         self.refresh_sessions();
     }
 
+    /// Warns once per target, the first time its server lists files without
+    /// size or modification time.
+    fn warn_missing_stats(&mut self, target_id: TargetId, count: usize) {
+        if !self.missing_stats_warned.insert(target_id) {
+            return;
+        }
+        let Some(target) = self
+            .remote_targets
+            .iter()
+            .find(|target| target.id == target_id)
+        else {
+            return;
+        };
+        let handling = if target.stat_fallback {
+            "they were looked up one at a time, which slows planning"
+        } else {
+            "they compare as empty and dated 1970; enable the stat fallback for this target"
+        };
+        let message = format!(
+            "{} listed {count} files without size or modification time; {handling}",
+            target.name
+        );
+        self.log_target_event(target_id, LogLevel::Warn, message);
+    }
+
+    /// Keeps a measured server clock offset and warns once when it is large
+    /// enough to upset modification-time comparisons.
+    pub fn record_clock_skew(&mut self, target_id: TargetId, skew: i64) {
+        let previous = self.clock_skews.insert(target_id, skew);
+        let changed = previous.is_none_or(|old| (old - skew).abs() > CLOCK_SKEW_WARN_SECS);
+        if skew.abs() <= CLOCK_SKEW_WARN_SECS || !changed {
+            return;
+        }
+        let Some(target) = self
+            .remote_targets
+            .iter()
+            .find(|target| target.id == target_id)
+        else {
+            return;
+        };
+        let direction = if skew > 0 { "ahead of" } else { "behind" };
+        let handling = if target.correct_clock_skew {
+            "remote modification times are shifted to compensate"
+        } else {
+            "enable clock skew correction for this target to compensate"
+        };
+        let message = format!(
+            "Clock on {} runs {}s {direction} this machine; {handling}",
+            target.name,
+            skew.abs()
+        );
+        self.log_target_event(target_id, LogLevel::Warn, message);
+    }
+
+    /// Stores a health check result and logs when the target goes down or
+    /// comes back, rather than on every ping.
+    pub fn record_health(&mut self, target_id: TargetId, error: Option<String>) {
+        let was_down = self
+            .health
+            .get(&target_id)
+            .map(|health| health.error.is_some());
+        let down = error.is_some();
+        let error = error.map(|err| self.redact(&err));
+        self.health.insert(
+            target_id,
+            TargetHealth {
+                checked_at: SystemTime::now(),
+                error: error.clone(),
+            },
+        );
+        if was_down == Some(down) || (was_down.is_none() && !down) {
+            return;
+        }
+        let message = match error {
+            Some(err) => format!("Health check failed: {err}"),
+            None => "Health check succeeded again".to_string(),
+        };
+        let level = if down { LogLevel::Warn } else { LogLevel::Info };
+        self.log_target_event(target_id, level, message);
+    }
+
     pub fn set_task_progress(&mut self, target_id: TargetId, progress: TaskProgress) {
         self.task_progress.insert(target_id, progress);
     }
 
     pub fn clear_task_progress(&mut self, target_id: TargetId) {
         self.task_progress.remove(&target_id);
+        self.throughput.remove(&target_id);
+    }
+
+    pub fn record_throughput(&mut self, target_id: TargetId, bytes: u64) {
+        self.throughput
+            .entry(target_id)
+            .or_default()
+            .record(bytes, Instant::now());
+    }
+
+    pub fn record_history(&mut self, record: SessionRecord) {
+        self.history.push_front(record);
+        self.history.truncate(SESSION_HISTORY_LIMIT);
+    }
+
+    /// Stores each rule's share of a finished sync on the target's rules.
+    /// Summaries name the roots the plan resolved, so the rules are matched
+    /// by resolving them the same way.
+    pub fn record_rule_statuses(&mut self, target_id: TargetId, rules: &[RuleSummary]) {
+        let start_dir = self.remote_start_dirs.get(&target_id).cloned();
+        let Some(target) = self
+            .remote_targets
+            .iter_mut()
+            .find(|target| target.id == target_id)
+        else {
+            return;
+        };
+        let labels: Vec<String> = target
+            .rules
+            .iter()
+            .map(|rule| sync::resolved_rule(target, rule, start_dir.as_deref()).label())
+            .collect();
+        let finished_at = SystemTime::now();
+        for (rule, label) in target.rules.iter_mut().zip(labels) {
+            if let Some(summary) = rules.iter().find(|summary| summary.rule == label) {
+                rule.last_status = Some(RuleStatus {
+                    applied: summary.applied,
+                    conflicts: summary.skipped,
+                    failed: summary.failed,
+                    finished_at,
+                });
+            }
+        }
+    }
+
+    /// Rule checks go stale once the target's rules or credentials change.
+    pub fn forget_rule_tests(&mut self, target_id: TargetId) {
+        self.rule_tests.retain(|(id, _), _| *id != target_id);
+    }
+
+    /// What the target's planned jobs would do, summed over its rules.
+    pub fn plan_stats(&self, target_id: TargetId) -> PlanStats {
+        let mut stats = PlanStats::default();
+        for job in self.jobs.iter().filter(|job| job.target_id == target_id) {
+            stats.add(&job.plan.stats);
+        }
+        stats
+    }
+
+    /// Cards of every target for the overview shown when none is selected.
+    pub fn target_overviews(&self) -> Vec<TargetOverview> {
+        self.sorted_targets()
+            .into_iter()
+            .map(|target| {
+                let stats = self.plan_stats(target.id);
+                let pending = self
+                    .jobs
+                    .iter()
+                    .filter(|job| job.target_id == target.id)
+                    .map(|job| job.plan.actions.len())
+                    .sum();
+                let finished = self
+                    .history
+                    .iter()
+                    .filter(|record| record.target_id == target.id)
+                    .map(|record| record.finished_at);
+                let last_run = self
+                    .sessions
+                    .iter()
+                    .filter(|session| session.target_id == target.id)
+                    .filter_map(|session| session.last_run);
+                let tested = match self.connection_tests.get(&target.id) {
+                    Some(ConnectionTestState::Success(_)) => Some(true),
+                    Some(ConnectionTestState::Failure(_)) => Some(false),
+                    _ => None,
+                };
+                TargetOverview {
+                    target_id: target.id,
+                    name: target.name.clone(),
+                    enabled: target.enabled,
+                    pending,
+                    conflicts: stats.conflicts,
+                    last_sync: finished.chain(last_run).max(),
+                    reachable: self
+                        .health
+                        .get(&target.id)
+                        .map(|health| health.error.is_none())
+                        .or(tested),
+                }
+            })
+            .collect()
     }
 
     pub fn drop_jobs_for_target(&mut self, target_id: TargetId) {
         self.jobs.retain(|job| job.target_id != target_id);
+        self.space_checks.remove(&target_id);
+        self.forced_plans.remove(&target_id);
+        self.plan_rows_shown.remove(&target_id);
         self.task_progress.remove(&target_id);
+        self.throughput.remove(&target_id);
         self.refresh_sessions();
     }
 
+    /// Points every rule of the target at `direction` and drops its now stale
+    /// plan. Returns how many rules changed.
+    pub fn set_rule_directions(&mut self, target_id: TargetId, direction: SyncDirection) -> usize {
+        let Some(target) = self
+            .remote_targets
+            .iter_mut()
+            .find(|target| target.id == target_id)
+        else {
+            return 0;
+        };
+        let mut changed = 0;
+        for rule in target
+            .rules
+            .iter_mut()
+            .filter(|rule| rule.direction != direction)
+        {
+            rule.direction = direction;
+            changed += 1;
+        }
+        if changed > 0 {
+            self.drop_jobs_for_target(target_id);
+        }
+        changed
+    }
+
     fn refresh_sessions(&mut self) {
         self.sessions = self.jobs.iter().map(SyncJob::to_session).collect();
     }
 
+    /// Targets matching `tag_filter`, in the order chosen by `settings.target_sort`.
+    /// Sorting is stable, so ties keep their configured order.
+    pub fn sorted_targets(&self) -> Vec<RemoteTarget> {
+        let pending = |id: TargetId| -> usize {
+            self.sessions
+                .iter()
+                .filter(|session| session.target_id == id)
+                .map(|session| session.pending_actions)
+                .sum()
+        };
+        let last_run = |id: TargetId| {
+            self.sessions
+                .iter()
+                .filter(|session| session.target_id == id)
+                .filter_map(|session| session.last_run)
+                .max()
+        };
+        let needs_attention = |id: TargetId| {
+            self.sessions.iter().any(|session| {
+                session.target_id == id
+                    && matches!(
+                        session.status,
+                        SyncStatus::Failed { .. } | SyncStatus::AwaitingConfirmation
+                    )
+            })
+        };
+
+        let mut targets: Vec<RemoteTarget> = self
+            .remote_targets
+            .iter()
+            .filter(|target| {
+                self.tag_filter
+                    .as_deref()
+                    .is_none_or(|tag| target.has_tag(tag))
+            })
+            .cloned()
+            .collect();
+        match self.settings.target_sort {
+            TargetSort::Attention => targets.sort_by_key(|target| !needs_attention(target.id)),
+            TargetSort::Name => targets.sort_by_key(|target| target.name.to_lowercase()),
+            TargetSort::LastRun => {
+                targets.sort_by_key(|target| std::cmp::Reverse(last_run(target.id)))
+            }
+            TargetSort::Pending => {
+                targets.sort_by_key(|target| std::cmp::Reverse(pending(target.id)))
+            }
+        }
+        targets
+    }
+
+    /// Sessions in the order chosen by `settings.session_sort`.
+    /// Sessions with live execution progress merged in. `sessions` is rebuilt
+    /// from the plan on every refresh, so a running bar must not live there.
+    pub fn live_sessions(&self) -> Vec<SyncSession> {
+        self.sessions
+            .iter()
+            .cloned()
+            .map(|mut session| {
+                if let Some(progress) = self
+                    .task_progress
+                    .get(&session.target_id)
+                    .filter(|progress| progress.kind == TaskKind::Executing)
+                {
+                    session.status = SyncStatus::Running {
+                        progress: progress.percent() / 100.0,
+                    };
+                }
+                session
+            })
+            .collect()
+    }
+
+    pub fn sorted_sessions(&self) -> Vec<SyncSession> {
+        let mut sessions = self.live_sessions();
+        match self.settings.session_sort {
+            SessionSort::Status => sessions.sort_by_key(|session| session.status.rank()),
+            SessionSort::Recent => {
+                sessions.sort_by_key(|session| std::cmp::Reverse(session.last_run))
+            }
+        }
+        sessions
+    }
 }
 
 impl Default for AppState {
@@ -349,19 +1767,29 @@ pub fn sample_remote_targets() -> Vec<RemoteTarget> {
             host: "prod.example.com:22".into(),
             username: "deploy".into(),
             base_path: PathBuf::from("/srv/www"),
+            local_base: PathBuf::new(),
             rules: vec![
-                SyncRule {
-                    local: PathBuf::from("./apps/web"),
-                    remote: PathBuf::from("/web"),
-                    direction: SyncDirection::Push,
-                },
-                SyncRule {
-                    local: PathBuf::from("./secrets"),
-                    remote: PathBuf::from("/config"),
-                    direction: SyncDirection::Bidirectional,
-                },
+                SyncRule::new("./apps/web", "/web", SyncDirection::Push),
+                SyncRule::new("./secrets", "/config", SyncDirection::Bidirectional),
             ],
             auth: AuthMethod::password(String::new()),
+            fallback_auth: None,
+            enabled: true,
+            credentials_pending: false,
+            remember_credentials: true,
+            use_ssh_config: false,
+            correct_clock_skew: false,
+            stat_fallback: true,
+            remote_read_only: false,
+            monitor_health: false,
+            algorithms: SshAlgorithms::default(),
+            color: TargetColor::default(),
+            tags: Vec::new(),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            source_address: None,
+            socket_buffer_kib: 0,
+            post_sync_hook: PostSyncHook::default(),
+            remote_commands: RemoteCommands::default(),
         },
         RemoteTarget {
             id: 2,
@@ -369,12 +1797,26 @@ pub fn sample_remote_targets() -> Vec<RemoteTarget> {
             host: "analytics.internal:2200".into(),
             username: "etl".into(),
             base_path: PathBuf::from("/data"),
-            rules: vec![SyncRule {
-                local: PathBuf::from("./datasets"),
-                remote: PathBuf::from("/incoming"),
-                direction: SyncDirection::Pull,
-            }],
+            local_base: PathBuf::new(),
+            rules: vec![SyncRule::new("./datasets", "/incoming", SyncDirection::Pull)],
             auth: AuthMethod::password(String::new()),
+            fallback_auth: None,
+            enabled: true,
+            credentials_pending: false,
+            remember_credentials: true,
+            use_ssh_config: false,
+            correct_clock_skew: false,
+            stat_fallback: true,
+            remote_read_only: false,
+            monitor_health: false,
+            algorithms: SshAlgorithms::default(),
+            color: TargetColor::default(),
+            tags: Vec::new(),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            source_address: None,
+            socket_buffer_kib: 0,
+            post_sync_hook: PostSyncHook::default(),
+            remote_commands: RemoteCommands::default(),
         },
     ]
 }
@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Once},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use ssh2::{Session, Sftp};
+
+use crate::{
+    connection::{self, HostSlot},
+    model::{AuthMethod, RemoteTarget, SshAlgorithms, TargetId},
+};
+
+/// A pooled session nobody borrowed for this long is logged out, freeing its
+/// host slot.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// How often pooled sessions send a keepalive and are checked for idleness.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// One session per target, shared by plans, watcher-triggered plans and
+/// health checks. Each borrower gets its own SFTP channel on it, so they
+/// run side by side over a single login.
+static POOL: Lazy<Mutex<HashMap<TargetId, PoolSlot>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A target's session, locked on its own so one target's login doesn't hold
+/// up borrowers of another.
+type PoolSlot = Arc<Mutex<Option<PooledSession>>>;
+
+struct PooledSession {
+    /// Settings the session logged in with; an edited target reconnects.
+    login: Login,
+    session: Session,
+    /// Shared with every borrower, so the host slot is only freed once the
+    /// session is out of the pool and no borrower still uses it.
+    slot: Arc<HostSlot>,
+    last_used: Instant,
+}
+
+/// Everything of a target that goes into opening its session.
+#[derive(PartialEq)]
+struct Login {
+    host: String,
+    username: String,
+    auth: AuthMethod,
+    fallback_auth: Option<AuthMethod>,
+    use_ssh_config: bool,
+    algorithms: SshAlgorithms,
+    source_address: Option<IpAddr>,
+    socket_buffer_kib: u32,
+}
+
+fn login_of(target: &RemoteTarget) -> Login {
+    Login {
+        host: target.host.clone(),
+        username: target.username.clone(),
+        auth: target.auth.clone(),
+        fallback_auth: target.fallback_auth.clone(),
+        use_ssh_config: target.use_ssh_config,
+        algorithms: target.algorithms.clone(),
+        source_address: target.source_address,
+        socket_buffer_kib: target.socket_buffer_kib,
+    }
+}
+
+/// Opens an SFTP channel on the target's pooled session, logging in first
+/// when there is none yet. A session that can no longer open a channel is
+/// dropped from the pool and replaced by one fresh login, whose result
+/// decides. Borrowers still holding the dead one fail on their own.
+///
+/// A pooled session holds one of the host's connection slots, so a login
+/// waits like any other connection when the host is at its limit; idle
+/// pooled sessions of other targets on the host make way for it. The
+/// returned slot keeps it counted while the borrower is still at work.
+pub fn borrow(target: &RemoteTarget) -> Result<(Session, Sftp, Arc<HostSlot>)> {
+    start_sweeper();
+    let slot = POOL.lock().entry(target.id).or_default().clone();
+    // Held while checking and connecting, so concurrent borrowers of a
+    // target wait for one login instead of each starting their own.
+    let mut pooled = slot.lock();
+    let login = login_of(target);
+    if let Some(cached) = pooled.as_mut().filter(|cached| cached.login == login)
+        && let Ok(sftp) = connection::open_channel(|| cached.session.sftp())
+    {
+        cached.last_used = Instant::now();
+        return Ok((cached.session.clone(), sftp, cached.slot.clone()));
+    }
+    *pooled = None;
+    let (session, host_slot) = connection::establish_session(target)?;
+    session.set_keepalive(true, KEEPALIVE_INTERVAL.as_secs() as u32);
+    let sftp = connection::open_channel(|| session.sftp())
+        .context("failed to start an SFTP session")?;
+    let host_slot = Arc::new(host_slot);
+    *pooled = Some(PooledSession {
+        login,
+        session: session.clone(),
+        slot: host_slot.clone(),
+        last_used: Instant::now(),
+    });
+    Ok((session, sftp, host_slot))
+}
+
+/// Drops the pooled session of `target_id`; the next borrower logs in again.
+/// The connection closes once the last borrower still using it is done.
+pub fn forget(target_id: TargetId) {
+    POOL.lock().remove(&target_id);
+}
+
+/// Takes sessions to `target`'s host out of the pool when the host has no
+/// free slot left. Each frees its slot once its last borrower is done, so no
+/// new connection waits out the idle timeout.
+pub fn make_room(target: &RemoteTarget) {
+    if !HostSlot::is_full(&target.host, target.max_connections) {
+        return;
+    }
+    let slots: Vec<PoolSlot> = POOL.lock().values().cloned().collect();
+    for slot in slots {
+        // A slot being logged into is busy anyway.
+        let Some(mut pooled) = slot.try_lock() else {
+            continue;
+        };
+        if pooled.as_ref().is_some_and(|cached| cached.slot.is_for(&target.host)) {
+            *pooled = None;
+        }
+    }
+}
+
+fn start_sweeper() {
+    static STARTED: Once = Once::new();
+    STARTED.call_once(|| {
+        let _ = thread::Builder::new()
+            .name("session-pool".into())
+            .spawn(|| {
+                loop {
+                    thread::sleep(KEEPALIVE_INTERVAL);
+                    sweep();
+                }
+            });
+    });
+}
+
+/// Logs out sessions idle for `IDLE_TIMEOUT` or whose keepalive fails, and
+/// keeps the others from being dropped by the server or a NAT in between.
+fn sweep() {
+    let slots: Vec<PoolSlot> = POOL.lock().values().cloned().collect();
+    for slot in slots {
+        let Some(mut pooled) = slot.try_lock() else {
+            continue;
+        };
+        let expired = pooled.as_ref().is_some_and(|cached| {
+            cached.last_used.elapsed() >= IDLE_TIMEOUT || cached.session.keepalive_send().is_err()
+        });
+        if expired {
+            *pooled = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::model::sample_remote_targets;
+
+    #[test]
+    fn logins_do_not_wait_on_another_targets_idle_session() {
+        let mut targets = sample_remote_targets();
+        for target in &mut targets {
+            target.host = "pooled.example.com".into();
+            target.max_connections = 1;
+        }
+        let idle = targets[0].clone();
+        let other = targets[1].clone();
+        let pooled = PooledSession {
+            login: login_of(&idle),
+            session: Session::new().unwrap(),
+            slot: Arc::new(HostSlot::acquire(&idle.host, 1)),
+            last_used: Instant::now(),
+        };
+        POOL.lock().insert(idle.id, Arc::new(Mutex::new(Some(pooled))));
+
+        // The slot a borrow for `other` logs in with.
+        let (tx, rx) = mpsc::channel();
+        let waiter = thread::spawn(move || {
+            let _slot = HostSlot::for_target(&other);
+            tx.send(()).unwrap();
+        });
+        assert!(rx.recv_timeout(Duration::from_secs(2)).is_ok());
+        waiter.join().unwrap();
+        assert!(POOL.lock()[&idle.id].lock().is_none());
+    }
+}
@@ -0,0 +1,57 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+/// Stands in for a masked secret.
+pub const MASK: &str = "[redacted]";
+/// Shorter secrets are left alone; masking them would mangle ordinary words.
+const MIN_SECRET_CHARS: usize = 4;
+
+/// Secrets of the configured targets, for messages printed where the app
+/// state is out of reach, such as the watcher thread.
+static KNOWN_SECRETS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Replaces the secrets `redact_known` masks.
+pub fn remember<'a>(secrets: impl IntoIterator<Item = &'a str>) {
+    *KNOWN_SECRETS.lock() = secrets.into_iter().map(str::to_string).collect();
+}
+
+/// `redact` with the secrets last passed to `remember`.
+pub fn redact_known(message: &str) -> String {
+    let secrets = KNOWN_SECRETS.lock();
+    redact(message, secrets.iter().map(String::as_str))
+}
+
+/// Prints `message` to stderr with the known secrets masked.
+pub fn eprint(message: impl AsRef<str>) {
+    eprintln!("{}", redact_known(message.as_ref()));
+}
+
+/// Replaces every occurrence of one of `secrets` in `message` with `MASK`.
+pub fn redact<'a>(message: &str, secrets: impl IntoIterator<Item = &'a str>) -> String {
+    let mut secrets: Vec<&str> = secrets
+        .into_iter()
+        .filter(|secret| secret.chars().count() >= MIN_SECRET_CHARS)
+        .collect();
+    // Longest first, so a secret that contains another is masked whole.
+    secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+    secrets
+        .into_iter()
+        .fold(message.to_string(), |message, secret| {
+            message.replace(secret, MASK)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_secrets_are_masked() {
+        let message = "auth failed for deploy:hunter2hunter2 (tried hunter2hunter2, abc)";
+        assert_eq!(
+            redact(message, ["hunter2", "hunter2hunter2", "abc", ""]),
+            "auth failed for deploy:[redacted] (tried [redacted], abc)"
+        );
+        assert_eq!(redact("nothing to hide", ["s3cret"]), "nothing to hide");
+    }
+}
@@ -1,20 +1,30 @@
 use anyhow::{Context, Result};
 use keyring::Entry;
 
-use crate::model::TargetId;
+use crate::{
+    config::{self, DEFAULT_PROFILE},
+    model::TargetId,
+};
 
 const SERVICE_NAME: &str = "SFTP-SYNC";
 
+#[derive(Clone, Copy)]
 pub enum SecretSlot {
     Password(TargetId),
     KeyPassphrase(TargetId),
 }
 
 impl SecretSlot {
+    /// Target ids repeat across profiles, so other profiles prefix the key.
+    /// The default profile keeps the unprefixed keys it has always used.
     fn storage_key(&self) -> String {
-        match self {
+        let key = match self {
             SecretSlot::Password(id) => format!("target-{id}-password"),
             SecretSlot::KeyPassphrase(id) => format!("target-{id}-passphrase"),
+        };
+        match config::active_profile() {
+            profile if profile == DEFAULT_PROFILE => key,
+            profile => format!("profile-{profile}-{key}"),
         }
     }
 }
@@ -3,6 +3,8 @@ use std::{collections::HashMap, fs, path::PathBuf};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::config;
+
 const KNOWN_HOSTS_FILE: &str = "known_hosts.json";
 
 #[derive(Default, Serialize, Deserialize)]
@@ -15,12 +17,11 @@ fn storage_path() -> Option<PathBuf> {
 }
 
 fn load_hosts() -> KnownHosts {
-    if let Some(path) = storage_path() {
-        if let Ok(bytes) = fs::read(&path) {
-            if let Ok(hosts) = serde_json::from_slice::<KnownHosts>(&bytes) {
-                return hosts;
-            }
-        }
+    if let Some(path) = storage_path()
+        && let Ok(bytes) = fs::read(&path)
+        && let Ok(hosts) = serde_json::from_slice::<KnownHosts>(&bytes)
+    {
+        return hosts;
     }
     KnownHosts::default()
 }
@@ -32,7 +33,7 @@ fn persist(hosts: &KnownHosts) -> Result<()> {
         }
         let data =
             serde_json::to_vec_pretty(hosts).context("failed to serialize known hosts store")?;
-        fs::write(path, data).context("failed to write known hosts store")?;
+        config::write_atomic(&path, data).context("failed to write known hosts store")?;
     }
     Ok(())
 }
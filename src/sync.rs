@@ -1,19 +1,31 @@
 use std::{
-    collections::HashMap,
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
     ffi::OsStr,
-    fs,
-    io::{Read, Write},
+    fmt, fs,
+    io::{self, Read, Seek, SeekFrom, Write},
     path::{Component, Path, PathBuf},
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
     time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
 use ssh2::{OpenFlags, OpenType, Sftp};
 
 use crate::{
-    connection,
-    model::{RemoteTarget, SessionId, SyncDirection, SyncRule, SyncSession, SyncStatus, TargetId},
+    connection::{self, HostSlot},
+    hooks::HookOutcome,
+    model::{
+        conflict_backup_path, upload_staging_path, AppSettings, CollisionPolicy, ComparePolicy,
+        ConflictPolicy, LineEndings, OverwritePolicy, RemoteTarget, SessionId, SyncDirection, SyncRule,
+        SyncSession, SyncStatus, TargetId, DEFAULT_MAX_DEPTH, DEFAULT_MAX_ENTRIES,
+    },
+    pool, redact, watcher,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -21,6 +33,8 @@ pub enum EntryKind {
     File,
     #[allow(dead_code)]
     Directory,
+    /// A symlink that resolved to a regular file; size and mtime are the target's.
+    Symlink,
 }
 
 #[derive(Clone, Debug)]
@@ -32,40 +46,341 @@ pub struct FileEntry {
     pub modified: SystemTime,
 }
 
+/// Filters applied while walking a rule's root on either side.
+#[derive(Clone, Debug)]
+pub struct ListOptions {
+    pub include_hidden: bool,
+    /// Follow symlinks that point at regular files. Directory symlinks are
+    /// never followed to avoid cycles; dangling links are skipped.
+    pub follow_symlinks: bool,
+    pub max_depth: usize,
+    pub max_entries: usize,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        Self {
+            include_hidden: true,
+            follow_symlinks: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_entries: DEFAULT_MAX_ENTRIES,
+        }
+    }
+}
+
+impl ListOptions {
+    pub fn for_rule(rule: &SyncRule) -> Self {
+        Self {
+            include_hidden: rule.include_hidden,
+            follow_symlinks: rule.follow_symlinks,
+            max_depth: rule.max_depth,
+            max_entries: rule.max_entries,
+        }
+    }
+
+    /// Called before descending into `rel_path`; fails once the walk goes
+    /// deeper or wider than a sane sync root would.
+    fn check_limits(&self, root: &Path, rel_path: &Path, listing: &Listing) -> Result<()> {
+        if rel_path.components().count() > self.max_depth {
+            return Err(anyhow!(
+                "{} is nested more than {} directories deep; the sync root looks too broad, pick a narrower folder",
+                root.display(),
+                self.max_depth
+            ));
+        }
+        if listing.entries.len() >= self.max_entries {
+            return Err(anyhow!(
+                "{} holds more than {} files; the sync root looks too broad, pick a narrower folder",
+                root.display(),
+                self.max_entries
+            ));
+        }
+        Ok(())
+    }
+
+    fn skips(&self, name: &OsStr) -> bool {
+        !self.include_hidden && is_hidden(name)
+    }
+}
+
+fn is_hidden(name: &OsStr) -> bool {
+    name.to_str().is_some_and(|name| name.starts_with('.'))
+}
+
+/// Result of walking a root. Subtrees that could not be read are reported in
+/// `skipped` (relative path and reason) instead of failing the whole listing.
+#[derive(Default, Debug)]
+pub struct Listing {
+    pub entries: Vec<FileEntry>,
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
 pub trait LocalStore {
-    fn list(&self, root: &Path) -> Result<Vec<FileEntry>>;
+    fn list(&self, root: &Path, options: &ListOptions) -> Result<Listing>;
+
+    /// Like `list`, reporting discovered files as it goes. Stores that list in
+    /// one pass report once at the end.
+    fn list_with_progress(
+        &self,
+        root: &Path,
+        options: &ListOptions,
+        on_listed: ListProgress<'_>,
+    ) -> Result<Listing> {
+        let listing = self.list(root, options)?;
+        if !on_listed(listing.entries.len()) {
+            return Err(ListingCanceled.into());
+        }
+        Ok(listing)
+    }
     fn read_file(&self, root: &Path, rel_path: &Path) -> Result<Vec<u8>>;
     fn write_file(&self, root: &Path, rel_path: &Path, bytes: &[u8]) -> Result<()>;
     fn remove_file(&self, root: &Path, rel_path: &Path) -> Result<()>;
     fn ensure_dir(&self, root: &Path, rel_path: &Path) -> Result<()>;
+
+    /// The file at `rel_path`, or `None` when there is none. Stores that
+    /// can't stat a single file find it in a listing of `root`.
+    fn stat(&self, root: &Path, rel_path: &Path) -> Result<Option<FileEntry>> {
+        let listing = self.list(root, &ListOptions::default())?;
+        Ok(listing.entries.into_iter().find(|entry| entry.path == rel_path))
+    }
+
+    /// Device and inode of a file with more than one hard link, so its other
+    /// names can be recognised. `None` for single-link files and where the
+    /// platform can't tell.
+    fn link_id(&self, _root: &Path, _rel_path: &Path) -> Option<(u64, u64)> {
+        None
+    }
+
+    /// Owner, group and extended attribute names of a file. `None` where the
+    /// platform has no such notion.
+    fn ownership(&self, _root: &Path, _rel_path: &Path) -> Option<FileOwnership> {
+        None
+    }
+
+    /// Those of `rel_paths` that look like they are still being written, i.e.
+    /// whose size or mtime moved within `window`. Files that can't be stat'ed
+    /// count as settled; the transfer reports them properly.
+    fn unsettled(
+        &self,
+        _root: &Path,
+        _rel_paths: &[&Path],
+        _window: Duration,
+    ) -> HashSet<PathBuf> {
+        HashSet::new()
+    }
+}
+
+/// What `preserve_ownership` carries from a local file to its upload.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileOwnership {
+    pub uid: u32,
+    pub gid: u32,
+    /// Extended attribute names. SFTP has no way to set them, so they are
+    /// only reported.
+    pub xattrs: Vec<String>,
+}
+
+/// Receives the running number of files found while listing; returning
+/// `false` aborts the listing with `ListingCanceled`.
+pub type ListProgress<'a> = &'a mut dyn FnMut(usize) -> bool;
+
+/// Receives the size of each chunk of a file transfer as it is moved.
+pub type ChunkProgress<'a> = &'a mut dyn FnMut(usize);
+
+/// Transfers move in pieces this size, so bandwidth limiting and progress
+/// stay smooth within a single large file.
+pub const TRANSFER_CHUNK: usize = 64 * 1024;
+
+/// Smallest upload worth staging so an interruption can resume it; smaller
+/// files are cheaper to send again than to rename into place.
+const RESUMABLE_UPLOAD_MIN: usize = 16 * TRANSFER_CHUNK;
+
+/// The listing callback asked to stop, e.g. the user canceled planning.
+#[derive(Debug)]
+pub struct ListingCanceled;
+
+impl fmt::Display for ListingCanceled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "listing canceled")
+    }
 }
 
+impl std::error::Error for ListingCanceled {}
+
 pub trait RemoteStore {
-    fn list(&self, root: &Path) -> Result<Vec<FileEntry>>;
+    fn list(&self, root: &Path, options: &ListOptions) -> Result<Listing>;
+
+    /// Like `list`, reporting discovered files as it goes. Stores that list in
+    /// one round trip report once at the end.
+    fn list_with_progress(
+        &self,
+        root: &Path,
+        options: &ListOptions,
+        on_listed: ListProgress<'_>,
+    ) -> Result<Listing> {
+        let listing = self.list(root, options)?;
+        if !on_listed(listing.entries.len()) {
+            return Err(ListingCanceled.into());
+        }
+        Ok(listing)
+    }
     fn read_file(&self, root: &Path, rel_path: &Path) -> Result<Vec<u8>>;
     fn write_file(&self, root: &Path, rel_path: &Path, bytes: &[u8]) -> Result<()>;
     fn remove_file(&self, root: &Path, rel_path: &Path) -> Result<()>;
     fn ensure_dir(&self, root: &Path, rel_path: &Path) -> Result<()>;
+
+    /// The file at `rel_path`, or `None` when there is none. Stores that
+    /// can't stat a single file find it in a listing of `root`.
+    fn stat(&self, root: &Path, rel_path: &Path) -> Result<Option<FileEntry>> {
+        let listing = self.list(root, &ListOptions::default())?;
+        Ok(listing.entries.into_iter().find(|entry| entry.path == rel_path))
+    }
+
+    /// SHA-256 of `rel_path` as lowercase hex. Stores that can't hash in
+    /// place read the file and hash it here.
+    fn checksum(&self, root: &Path, rel_path: &Path) -> Result<String> {
+        self.read_file(root, rel_path).map(|bytes| content_hash(&bytes))
+    }
+
+    /// SHA-256 of `rel_path` computed where the file lies, or `None` when the
+    /// store could only hash it by reading it back.
+    fn checksum_in_place(&self, _root: &Path, _rel_path: &Path) -> Option<String> {
+        None
+    }
+
+    /// Like `read_file`, reporting each chunk as it arrives. Stores that read
+    /// in one round trip report the chunks afterwards.
+    fn read_file_chunked(
+        &self,
+        root: &Path,
+        rel_path: &Path,
+        on_chunk: ChunkProgress<'_>,
+    ) -> Result<Vec<u8>> {
+        let bytes = self.read_file(root, rel_path)?;
+        bytes
+            .chunks(TRANSFER_CHUNK)
+            .for_each(|chunk| on_chunk(chunk.len()));
+        Ok(bytes)
+    }
+
+    /// Like `write_file`, reporting each chunk before it is sent.
+    fn write_file_chunked(
+        &self,
+        root: &Path,
+        rel_path: &Path,
+        bytes: &[u8],
+        on_chunk: ChunkProgress<'_>,
+    ) -> Result<()> {
+        bytes
+            .chunks(TRANSFER_CHUNK)
+            .for_each(|chunk| on_chunk(chunk.len()));
+        self.write_file(root, rel_path, bytes)
+    }
+
+    /// Whether uploads can be staged with `write_file_at` and `rename_file`
+    /// and resumed after an interruption.
+    fn resumes_uploads(&self) -> bool {
+        false
+    }
+
+    /// Size of `rel_path`, or `None` when it doesn't exist.
+    fn file_size(&self, _root: &Path, _rel_path: &Path) -> Option<u64> {
+        None
+    }
+
+    /// Writes `bytes` into `rel_path` at `offset`, keeping what comes before
+    /// it. An `offset` of zero starts the file over.
+    fn write_file_at(
+        &self,
+        _root: &Path,
+        _rel_path: &Path,
+        _offset: u64,
+        _bytes: &[u8],
+        _on_chunk: ChunkProgress<'_>,
+    ) -> Result<()> {
+        Err(anyhow!("resuming uploads is not supported by this server"))
+    }
+
+    /// Moves `from` to `to`, replacing any file already there.
+    fn rename_file(&self, _root: &Path, _from: &Path, _to: &Path) -> Result<()> {
+        Err(anyhow!("renaming files is not supported by this server"))
+    }
+
+    /// Makes `link` another name for the existing file `original`.
+    fn hard_link(&self, _root: &Path, _original: &Path, _link: &Path) -> Result<()> {
+        Err(anyhow!("hard links are not supported by this server"))
+    }
+
+    /// Gives a file the numeric owner and group `uid` and `gid`.
+    fn set_owner(&self, _root: &Path, _rel_path: &Path, _uid: u32, _gid: u32) -> Result<()> {
+        Err(anyhow!("this server cannot change file ownership"))
+    }
+
+    /// Keeps an idle connection (and NAT mappings) alive between transfers.
+    fn keepalive(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Bytes available to us on the filesystem holding `root`, or `None` when
+    /// the server can't tell.
+    fn free_space(&self, _root: &Path) -> Result<Option<u64>> {
+        Ok(None)
+    }
+
+    /// Absolute directory the session starts in, which relative paths land under.
+    fn start_dir(&self) -> Result<Option<PathBuf>> {
+        Ok(None)
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum SyncAction {
     Upload {
         rel_path: PathBuf,
-        #[allow(dead_code)]
         size: u64,
+        /// Remote path chosen by the collision policy, instead of the rule's
+        /// mapping of `rel_path`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        renamed: Option<PathBuf>,
     },
     Download {
         rel_path: PathBuf,
-        #[allow(dead_code)]
         size: u64,
+        /// Local path chosen by the collision policy, instead of `rel_path`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        renamed: Option<PathBuf>,
     },
     DeleteRemote { rel_path: PathBuf },
     DeleteLocal { rel_path: PathBuf },
-    Conflict {
-        #[allow(dead_code)]
-        rel_path: PathBuf,
-    },
+    Conflict { rel_path: PathBuf },
+}
+
+impl SyncAction {
+    pub fn is_delete(&self) -> bool {
+        matches!(self, Self::DeleteRemote { .. } | Self::DeleteLocal { .. })
+    }
+
+    pub fn rel_path(&self) -> &Path {
+        match self {
+            Self::Upload { rel_path, .. }
+            | Self::Download { rel_path, .. }
+            | Self::DeleteRemote { rel_path }
+            | Self::DeleteLocal { rel_path }
+            | Self::Conflict { rel_path } => rel_path,
+        }
+    }
+}
+
+impl fmt::Display for SyncAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Upload { rel_path, .. } => write!(f, "upload {}", rel_path.display()),
+            Self::Download { rel_path, .. } => write!(f, "download {}", rel_path.display()),
+            Self::DeleteRemote { rel_path } => write!(f, "delete remote {}", rel_path.display()),
+            Self::DeleteLocal { rel_path } => write!(f, "delete local {}", rel_path.display()),
+            Self::Conflict { rel_path } => write!(f, "resolve conflict on {}", rel_path.display()),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -75,6 +390,81 @@ pub struct PlanStats {
     pub deletes_remote: usize,
     pub deletes_local: usize,
     pub conflicts: usize,
+    /// Source files that would have landed on the same destination file.
+    pub collisions: usize,
+    /// Rules whose source listed no files while the plan deletes on the other
+    /// side; such a plan never runs without confirmation.
+    pub empty_sources: usize,
+    /// Files left alone because both copies have the same content, though
+    /// their times differ.
+    pub identical: usize,
+}
+
+impl PlanStats {
+    pub fn add(&mut self, other: &PlanStats) {
+        self.uploads += other.uploads;
+        self.downloads += other.downloads;
+        self.deletes_remote += other.deletes_remote;
+        self.deletes_local += other.deletes_local;
+        self.conflicts += other.conflicts;
+        self.collisions += other.collisions;
+        self.empty_sources += other.empty_sources;
+        self.identical += other.identical;
+    }
+}
+
+/// File counts and byte totals for the transfers in a plan, split by direction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransferTotals {
+    pub upload_files: usize,
+    pub upload_bytes: u64,
+    pub download_files: usize,
+    pub download_bytes: u64,
+}
+
+impl TransferTotals {
+    pub fn from_actions<'a>(actions: impl IntoIterator<Item = &'a SyncAction>) -> Self {
+        let mut totals = Self::default();
+        for action in actions {
+            match action {
+                SyncAction::Upload { size, .. } => {
+                    totals.upload_files += 1;
+                    totals.upload_bytes = totals.upload_bytes.saturating_add(*size);
+                }
+                SyncAction::Download { size, .. } => {
+                    totals.download_files += 1;
+                    totals.download_bytes = totals.download_bytes.saturating_add(*size);
+                }
+                _ => {}
+            }
+        }
+        totals
+    }
+
+    pub fn files(&self) -> usize {
+        self.upload_files + self.download_files
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.upload_bytes.saturating_add(self.download_bytes)
+    }
+}
+
+/// One file in the plan preview.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PreviewRow {
+    pub rel_path: PathBuf,
+    pub change: PreviewChange,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreviewChange {
+    Upload,
+    Download,
+    DeleteRemote,
+    DeleteLocal,
+    Conflict,
+    Unchanged,
 }
 
 #[derive(Clone, Debug)]
@@ -85,17 +475,29 @@ pub struct SyncPlan {
     pub stats: PlanStats,
 }
 
+impl SyncPlan {
+    /// The plan in the order it runs: transfers and conflicts first, then
+    /// deletions, each phase keeping the planned order. Phasing a phased plan
+    /// changes nothing.
+    pub fn phased(&self) -> SyncPlan {
+        let (transfers, deletes): (Vec<_>, Vec<_>) =
+            self.actions.iter().cloned().partition(|action| !action.is_delete());
+        SyncPlan {
+            rule: self.rule.clone(),
+            actions: transfers.into_iter().chain(deletes).collect(),
+            stats: self.stats.clone(),
+        }
+    }
+}
+
 pub type FileIndex = HashMap<PathBuf, FileEntry>;
 
 #[derive(Clone)]
 pub struct SyncJob {
     pub id: SessionId,
     pub target_id: TargetId,
-    #[allow(dead_code)]
     pub rule: SyncRule,
-    #[allow(dead_code)]
     pub local_index: FileIndex,
-    #[allow(dead_code)]
     pub remote_index: FileIndex,
     pub plan: SyncPlan,
     pub created_at: SystemTime,
@@ -110,9 +512,19 @@ impl SyncJob {
         local: &L,
         remote: &R,
     ) -> Result<Self> {
-        let local_index = index_entries(local.list(&rule.local)?);
-        let remote_index = index_entries(remote.list(&rule.remote)?);
-        let (actions, stats) = diff_actions(rule, &local_index, &remote_index);
+        let (local_index, remote_index, _, collisions) =
+            build_indexes(rule, local, remote, &mut |_| true)?;
+        let (mut actions, mut stats) = diff_actions(rule, &local_index, &remote_index, 0, false);
+        collisions.apply(&mut actions, &mut stats);
+        skip_identical(
+            rule,
+            local,
+            remote,
+            &local_index,
+            &remote_index,
+            &mut actions,
+            &mut stats,
+        );
 
         Ok(Self {
             id,
@@ -133,6 +545,46 @@ impl SyncJob {
         self.plan.actions.len()
     }
 
+    /// Files of the plan sorted by path. Only the ones with a pending action
+    /// unless `show_all`, which adds every selected file that is in sync.
+    pub fn preview(&self, show_all: bool) -> Vec<PreviewRow> {
+        let mut rows: Vec<PreviewRow> = self
+            .plan
+            .actions
+            .iter()
+            .map(|action| {
+                let (rel_path, change) = match action {
+                    SyncAction::Upload { rel_path, .. } => (rel_path, PreviewChange::Upload),
+                    SyncAction::Download { rel_path, .. } => (rel_path, PreviewChange::Download),
+                    SyncAction::DeleteRemote { rel_path } => {
+                        (rel_path, PreviewChange::DeleteRemote)
+                    }
+                    SyncAction::DeleteLocal { rel_path } => (rel_path, PreviewChange::DeleteLocal),
+                    SyncAction::Conflict { rel_path } => (rel_path, PreviewChange::Conflict),
+                };
+                PreviewRow {
+                    rel_path: rel_path.clone(),
+                    change,
+                }
+            })
+            .collect();
+        if show_all {
+            let pending: HashSet<PathBuf> = rows.iter().map(|row| row.rel_path.clone()).collect();
+            let unchanged: HashSet<&PathBuf> = self
+                .local_index
+                .keys()
+                .chain(self.remote_index.keys())
+                .filter(|path| self.rule.selects(path) && !pending.contains(*path))
+                .collect();
+            rows.extend(unchanged.into_iter().map(|path| PreviewRow {
+                rel_path: path.clone(),
+                change: PreviewChange::Unchanged,
+            }));
+        }
+        rows.sort_by(|a, b| a.rel_path.cmp(&b.rel_path));
+        rows
+    }
+
     pub fn to_session(&self) -> SyncSession {
         let status = if self.plan.actions.is_empty() {
             SyncStatus::Idle
@@ -193,29 +645,226 @@ impl PlannedJob {
 pub struct PlanJobsResult {
     pub jobs: Vec<PlannedJob>,
     pub warnings: Vec<String>,
+    /// Free-space check for the planned uploads; `None` when nothing is uploaded.
+    pub space: Option<SpaceCheck>,
+    /// Directory relative remote paths were resolved against, if the server said.
+    pub start_dir: Option<PathBuf>,
+    /// Server clock offset applied to remote modification times, if measured.
+    pub clock_skew: Option<i64>,
+    /// Planned as a forced full sync.
+    pub forced: bool,
+    /// Remote entries the server listed without size, mtime or type.
+    pub missing_stats: usize,
+}
+
+/// Planned upload size against the remote free space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpaceCheck {
+    pub required: u64,
+    /// `None` when the server supports neither statvfs nor `df`.
+    pub available: Option<u64>,
+}
+
+impl SpaceCheck {
+    pub fn is_short(&self) -> bool {
+        self.available
+            .is_some_and(|available| available < self.required)
+    }
+}
+
+/// Destinations smaller than this never trip the percentage guard, so pruning
+/// a handful of files in a tiny tree does not ask twice.
+const MASS_DELETE_MIN_FILES: usize = 10;
+
+/// Planned deletions, measured against the files on the side being deleted from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MassDeletion {
+    pub deletions: usize,
+    pub destination_files: usize,
+}
+
+impl MassDeletion {
+    pub fn percent(&self) -> usize {
+        self.deletions * 100 / self.destination_files.max(1)
+    }
+}
+
+/// Returns the deletion volume when it reaches `max_count` files or
+/// `max_percent` of the destination, the signature of an empty or wrongly
+/// mounted source. A zero threshold disables that check.
+pub fn mass_deletion<'a>(
+    jobs: impl IntoIterator<Item = &'a SyncJob>,
+    max_count: usize,
+    max_percent: u8,
+) -> Option<MassDeletion> {
+    let mut total = MassDeletion {
+        deletions: 0,
+        destination_files: 0,
+    };
+    for job in jobs {
+        let (mut delete_local, mut delete_remote) = (0, 0);
+        for action in &job.plan.actions {
+            match action {
+                SyncAction::DeleteLocal { .. } => delete_local += 1,
+                SyncAction::DeleteRemote { .. } => delete_remote += 1,
+                _ => {}
+            }
+        }
+        if delete_local > 0 {
+            total.deletions += delete_local;
+            total.destination_files += job.local_index.len();
+        }
+        if delete_remote > 0 {
+            total.deletions += delete_remote;
+            total.destination_files += job.remote_index.len();
+        }
+    }
+
+    let by_count = max_count > 0 && total.deletions >= max_count;
+    let by_percent = max_percent > 0
+        && total.destination_files >= MASS_DELETE_MIN_FILES
+        && total.percent() >= usize::from(max_percent);
+    (total.deletions > 0 && (by_count || by_percent)).then_some(total)
 }
 
 #[derive(Default)]
 pub struct ExecutionSummary {
     pub applied: usize,
     pub skipped: usize,
-    pub failures: Vec<(SyncAction, String)>,
+    /// Uploads postponed because the local file was still changing.
+    pub deferred: usize,
+    /// Uploads and remote deletions not attempted because the remote is read-only.
+    pub refused: usize,
+    pub failures: Vec<ExecutionFailure>,
+    /// Applied actions whose owner or attributes could not be kept.
+    pub ownership_warnings: Vec<String>,
+    /// Per-rule breakdown, in job order.
+    pub rules: Vec<RuleSummary>,
+    /// The target's post-sync hook, when it was due.
+    pub hook: Option<Result<HookOutcome>>,
+    /// Remote commands run after the sync, in order, up to the first failure.
+    pub remote_commands: Vec<Result<RemoteCommandOutcome>>,
+    /// Deletions left for the user to approve, with `review_deletions` on.
+    pub held_deletions: Vec<HeldDeletion>,
+    /// Approved deletions skipped because a side changed after the sync
+    /// that held them.
+    pub stale_deletions: Vec<String>,
+    /// Actions a dry run walked past instead of applying, in run order.
+    pub would_apply: Vec<ExecutionLog>,
+}
+
+/// A deletion a sync did not apply, waiting for the user's approval.
+#[derive(Clone, Debug)]
+pub struct HeldDeletion {
+    /// The resolved rule the deletion was planned under.
+    pub rule: SyncRule,
+    pub action: SyncAction,
+    /// Each side's file when the sync ran; the deletion is skipped if either
+    /// has changed since.
+    pub local: Option<SnapshotEntry>,
+    pub remote: Option<SnapshotEntry>,
+}
+
+impl ExecutionSummary {
+    /// Why a remote command failed: its stderr, or the error that kept it
+    /// from running.
+    pub fn remote_command_failure(&self) -> Option<String> {
+        self.remote_commands
+            .iter()
+            .find_map(|outcome| match outcome {
+                Ok(outcome) if outcome.exit_code == 0 => None,
+                Ok(outcome) if outcome.stderr.is_empty() => Some(format!(
+                    "`{}` exited with code {}",
+                    outcome.command, outcome.exit_code
+                )),
+                Ok(outcome) => Some(outcome.stderr.clone()),
+                Err(err) => Some(format!("{err:#}")),
+            })
+    }
+
+    /// Failures sharing a root cause, in order of first occurrence, so an
+    /// outage logs one line per cause rather than one per file.
+    pub fn failure_groups(&self) -> Vec<FailureGroup> {
+        let mut groups: Vec<FailureGroup> = Vec::new();
+        for failure in &self.failures {
+            match groups.iter_mut().find(|group| group.cause == failure.cause) {
+                Some(group) => group.count += 1,
+                None => groups.push(FailureGroup {
+                    cause: failure.cause.clone(),
+                    count: 1,
+                    first: failure.clone(),
+                }),
+            }
+        }
+        groups
+    }
+}
+
+/// How a remote command run after a sync ended.
+#[derive(Clone, Debug)]
+pub struct RemoteCommandOutcome {
+    pub command: String,
+    pub exit_code: i32,
+    /// Trimmed output, cut to `COMMAND_OUTPUT_LIMIT` bytes.
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Remote command output kept for the log; the rest is dropped.
+const COMMAND_OUTPUT_LIMIT: usize = 4096;
+
+#[derive(Clone, Debug)]
+pub struct ExecutionFailure {
+    pub rule: String,
+    pub reason: String,
+    /// Innermost error, shared by every file hit by the same outage.
+    pub cause: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct FailureGroup {
+    pub cause: String,
+    pub count: usize,
+    pub first: ExecutionFailure,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RuleSummary {
+    pub rule: String,
+    pub applied: usize,
+    pub skipped: usize,
+    pub deferred: usize,
+    pub refused: usize,
+    pub failed: usize,
 }
 
+#[allow(dead_code)]
 pub struct SyncPlanner<'a, L: LocalStore, R: RemoteStore> {
     local: &'a L,
     remote: &'a R,
 }
 
+#[allow(dead_code)]
 impl<'a, L: LocalStore, R: RemoteStore> SyncPlanner<'a, L, R> {
     pub fn new(local: &'a L, remote: &'a R) -> Self {
         Self { local, remote }
     }
 
     pub fn plan(&self, rule: &SyncRule) -> Result<SyncPlan> {
-        let local_index = index_entries(self.local.list(&rule.local)?);
-        let remote_index = index_entries(self.remote.list(&rule.remote)?);
-        let (actions, stats) = diff_actions(rule, &local_index, &remote_index);
+        check_line_endings(rule)?;
+        let (local_index, remote_index, _, collisions) =
+            build_indexes(rule, self.local, self.remote, &mut |_| true)?;
+        let (mut actions, mut stats) = diff_actions(rule, &local_index, &remote_index, 0, false);
+        collisions.apply(&mut actions, &mut stats);
+        skip_identical(
+            rule,
+            self.local,
+            self.remote,
+            &local_index,
+            &remote_index,
+            &mut actions,
+            &mut stats,
+        );
 
         Ok(SyncPlan {
             rule: rule.clone(),
@@ -225,21 +874,152 @@ impl<'a, L: LocalStore, R: RemoteStore> SyncPlanner<'a, L, R> {
     }
 }
 
+/// Refuses rules that rewrite line endings yet compare sizes, which the
+/// rewrite changes; the form doesn't save them, but older configs may hold one.
+fn check_line_endings(rule: &SyncRule) -> Result<()> {
+    if rule
+        .line_endings
+        .clashes_with(rule.direction, rule.compare_policy, rule.overwrite_policy)
+    {
+        return Err(anyhow!(
+            "rewriting line endings changes file sizes; compare by modification time or \
+             checksum, and don't overwrite on size differences"
+        ));
+    }
+    Ok(())
+}
+
+/// SHA-256 of `bytes` as lowercase hex.
+pub fn content_hash(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// How far into a file `normalize_line_endings` looks for a NUL byte, the
+/// same heuristic git uses to tell binary files from text.
+const TEXT_SNIFF_LEN: usize = 8000;
+
+/// `bytes` with every line ending rewritten to `endings` when they look like
+/// text. Binary files, and every file under `LineEndings::Keep`, come back
+/// untouched. A lone `\r` is not a line ending and is kept.
+pub fn normalize_line_endings(bytes: &[u8], endings: LineEndings) -> Cow<'_, [u8]> {
+    if endings == LineEndings::Keep || bytes[..bytes.len().min(TEXT_SNIFF_LEN)].contains(&0) {
+        return Cow::Borrowed(bytes);
+    }
+    let mut normalized = Vec::with_capacity(bytes.len());
+    let mut rest = bytes.iter().peekable();
+    while let Some(&byte) = rest.next() {
+        match byte {
+            b'\r' if rest.peek() == Some(&&b'\n') => {}
+            b'\n' if endings == LineEndings::Crlf => normalized.extend_from_slice(b"\r\n"),
+            _ => normalized.push(byte),
+        }
+    }
+    Cow::Owned(normalized)
+}
+
+/// For checksum rules, drops the transfers and conflicts of files whose two
+/// copies are the same size and hash the same, whatever their times say. A
+/// file that can't be hashed on either side keeps its action. Rules that
+/// rewrite line endings hash both copies after rewriting them, so a file
+/// differing only in its line endings counts as identical.
+fn skip_identical<L: LocalStore + ?Sized, R: RemoteStore + ?Sized>(
+    rule: &SyncRule,
+    local: &L,
+    remote: &R,
+    local_index: &FileIndex,
+    remote_index: &FileIndex,
+    actions: &mut Vec<SyncAction>,
+    stats: &mut PlanStats,
+) {
+    if rule.compare_policy != ComparePolicy::Checksum {
+        return;
+    }
+    let identical = |rel_path: &PathBuf| {
+        let (Some(local_entry), Some(remote_entry)) =
+            (local_index.get(rel_path), remote_index.get(rel_path))
+        else {
+            return false;
+        };
+        let normalized = |bytes: Vec<u8>| {
+            content_hash(&normalize_line_endings(&bytes, rule.line_endings))
+        };
+        let remote_rel = rule.remote_rel_path(rel_path);
+        let (local_hash, remote_hash) = if rule.line_endings == LineEndings::Keep {
+            if local_entry.size != remote_entry.size {
+                return false;
+            }
+            let local_hash = local
+                .read_file(&rule.local, rel_path)
+                .map(|bytes| content_hash(&bytes));
+            (local_hash, remote.checksum(&rule.remote, &remote_rel))
+        } else {
+            // Sizes and server-side hashes both see the raw line endings.
+            (
+                local.read_file(&rule.local, rel_path).map(normalized),
+                remote.read_file(&rule.remote, &remote_rel).map(normalized),
+            )
+        };
+        matches!((local_hash, remote_hash), (Ok(a), Ok(b)) if a == b)
+    };
+    actions.retain(|action| {
+        let (rel_path, counter) = match action {
+            SyncAction::Upload {
+                rel_path,
+                renamed: None,
+                ..
+            } => (rel_path, &mut stats.uploads),
+            SyncAction::Download {
+                rel_path,
+                renamed: None,
+                ..
+            } => (rel_path, &mut stats.downloads),
+            SyncAction::Conflict { rel_path } => (rel_path, &mut stats.conflicts),
+            _ => return true,
+        };
+        if !identical(rel_path) {
+            return true;
+        }
+        *counter -= 1;
+        stats.identical += 1;
+        false
+    });
+}
+
+/// `clock_skew` is how many seconds the server clock runs ahead of ours;
+/// remote modification times are moved onto the local clock before comparing.
+/// With `force`, files on both sides are transferred in the rule's direction
+/// regardless of the overwrite policy; for two-way rules a tie goes to local.
+/// Size-only rules never look at modification times and leave same-size
+/// files alone, whatever the overwrite policy says. With `skip_empty_files`,
+/// a zero-byte file is never the source of a transfer nor deleted.
 fn diff_actions(
     rule: &SyncRule,
     local_index: &FileIndex,
     remote_index: &FileIndex,
+    clock_skew: i64,
+    force: bool,
 ) -> (Vec<SyncAction>, PlanStats) {
     let mut actions = Vec::new();
     let mut stats = PlanStats::default();
+    let remote_time = |entry: &FileEntry| on_local_clock(entry.modified, clock_skew);
+    let size_only = rule.compare_policy == ComparePolicy::SizeOnly;
+    let skipped_empty = |entry: &FileEntry| rule.skip_empty_files && entry.size == 0;
 
-    for (path, local_entry) in local_index {
+    // Filtered-out files are left alone on both sides, never treated as extraneous.
+    for (path, local_entry) in local_index.iter().filter(|(path, _)| rule.selects(path)) {
         match remote_index.get(path) {
             None => match rule.direction {
+                _ if skipped_empty(local_entry) => {}
                 SyncDirection::Push => {
                     actions.push(SyncAction::Upload {
                         rel_path: path.clone(),
                         size: local_entry.size,
+                        renamed: None,
                     });
                     stats.uploads += 1;
                 }
@@ -253,37 +1033,74 @@ fn diff_actions(
                     actions.push(SyncAction::Upload {
                         rel_path: path.clone(),
                         size: local_entry.size,
+                        renamed: None,
                     });
                     stats.uploads += 1;
                 }
             },
             Some(remote_entry) => match rule.direction {
+                _ if size_only && !force && local_entry.size == remote_entry.size => {}
+                SyncDirection::Push if skipped_empty(local_entry) => {}
+                SyncDirection::Pull if skipped_empty(remote_entry) => {}
                 SyncDirection::Push => {
-                    if newer(local_entry.modified, remote_entry.modified) {
+                    let policy = if force {
+                        OverwritePolicy::AlwaysOverwrite
+                    } else {
+                        rule.overwrite_policy
+                    };
+                    let upload = match policy {
+                        OverwritePolicy::AlwaysOverwrite => true,
+                        _ if size_only => true,
+                        OverwritePolicy::NewerOnly => {
+                            newer(local_entry.modified, remote_time(remote_entry))
+                        }
+                        OverwritePolicy::SizeOrTimeDiffers => {
+                            local_entry.size != remote_entry.size
+                                || newer(local_entry.modified, remote_time(remote_entry))
+                                || newer(remote_time(remote_entry), local_entry.modified)
+                        }
+                    };
+                    if upload {
                         actions.push(SyncAction::Upload {
                             rel_path: path.clone(),
                             size: local_entry.size,
+                            renamed: None,
                         });
                         stats.uploads += 1;
                     }
                 }
                 SyncDirection::Pull => {
-                    if newer(remote_entry.modified, local_entry.modified) {
+                    if force || size_only || newer(remote_time(remote_entry), local_entry.modified)
+                    {
                         actions.push(SyncAction::Download {
                             rel_path: path.clone(),
                             size: remote_entry.size,
+                            renamed: None,
                         });
                         stats.downloads += 1;
                     }
                 }
                 SyncDirection::Bidirectional => {
-                    let local_newer = newer(local_entry.modified, remote_entry.modified);
-                    let remote_newer = newer(remote_entry.modified, local_entry.modified);
+                    let (local_newer, remote_newer) = if size_only {
+                        // Without trusted times a size change can't say which side moved.
+                        let resized = local_entry.size != remote_entry.size;
+                        (resized, resized)
+                    } else {
+                        (
+                            newer(local_entry.modified, remote_time(remote_entry)),
+                            newer(remote_time(remote_entry), local_entry.modified),
+                        )
+                    };
+                    // A forced run still lets the newer side win; ties go to the local copy.
+                    let local_newer = local_newer || (force && !remote_newer);
+                    let local_newer = local_newer && !skipped_empty(local_entry);
+                    let remote_newer = remote_newer && !skipped_empty(remote_entry);
                     match (local_newer, remote_newer) {
                         (true, false) => {
                             actions.push(SyncAction::Upload {
                                 rel_path: path.clone(),
                                 size: local_entry.size,
+                                renamed: None,
                             });
                             stats.uploads += 1;
                         }
@@ -291,6 +1108,7 @@ fn diff_actions(
                             actions.push(SyncAction::Download {
                                 rel_path: path.clone(),
                                 size: remote_entry.size,
+                                renamed: None,
                             });
                             stats.downloads += 1;
                         }
@@ -307,8 +1125,8 @@ fn diff_actions(
         }
     }
 
-    for (path, remote_entry) in remote_index {
-        if local_index.contains_key(path) {
+    for (path, remote_entry) in remote_index.iter().filter(|(path, _)| rule.selects(path)) {
+        if local_index.contains_key(path) || skipped_empty(remote_entry) {
             continue;
         }
 
@@ -323,6 +1141,7 @@ fn diff_actions(
                 actions.push(SyncAction::Download {
                     rel_path: path.clone(),
                     size: remote_entry.size,
+                    renamed: None,
                 });
                 stats.downloads += 1;
             }
@@ -330,6 +1149,7 @@ fn diff_actions(
                 actions.push(SyncAction::Download {
                     rel_path: path.clone(),
                     size: remote_entry.size,
+                    renamed: None,
                 });
                 stats.downloads += 1;
             }
@@ -346,33 +1166,610 @@ fn index_entries(entries: Vec<FileEntry>) -> FileIndex {
         .collect()
 }
 
-#[allow(dead_code)]
-pub fn plan_jobs_for_target(target: &RemoteTarget) -> Result<PlanJobsResult> {
-    plan_jobs_with_progress(target, |_completed, _total| {})
-}
-
-pub fn plan_jobs_with_progress(
-    target: &RemoteTarget,
-    mut progress: impl FnMut(usize, usize),
-) -> Result<PlanJobsResult> {
-    let remote_store = SftpRemoteStore::connect(target)?;
-    let local_store = FsLocalStore::default();
-
-    let total_rules = target.rules.len().max(1);
-    progress(0, total_rules);
+/// Lists both sides of a rule and indexes them. Paths under a subtree that
+/// was unreadable on either side are dropped from both indexes so they are
+/// neither transferred nor deleted; each skipped subtree yields a warning.
+/// Colliding source files are then resolved by the rule's collision policy.
+fn build_indexes<L: LocalStore + ?Sized, R: RemoteStore + ?Sized>(
+    rule: &SyncRule,
+    local: &L,
+    remote: &R,
+    on_listed: ListProgress<'_>,
+) -> Result<(FileIndex, FileIndex, Vec<String>, Collisions)> {
+    if rule.flatten && rule.direction != SyncDirection::Push {
+        return Err(anyhow!(
+            "{} is flattened, which only works for local → remote rules",
+            rule.label()
+        ));
+    }
+    let options = ListOptions::for_rule(rule);
+    let local_listing = local.list_with_progress(&rule.local, &options, &mut *on_listed)?;
+    let local_found = local_listing.entries.len();
+    let remote_listing = remote.list_with_progress(&rule.remote, &options, &mut |found| {
+        on_listed(local_found + found)
+    })?;
 
-    let mut jobs = Vec::new();
     let mut warnings = Vec::new();
+    let mut skipped = Vec::new();
+    for (rel_path, reason) in local_listing.skipped {
+        warnings.push(format!(
+            "Skipped unreadable {}: {reason}",
+            rule.local.join(&rel_path).display()
+        ));
+        skipped.push(rel_path);
+    }
+    for (rel_path, reason) in remote_listing.skipped {
+        warnings.push(format!(
+            "Skipped unreadable {}: {reason}",
+            rule.remote.join(&rel_path).display()
+        ));
+        skipped.push(rel_path);
+    }
+
+    let mut local_index = index_entries(local_listing.entries);
+    let mut remote_index = index_entries(remote_listing.entries);
+    if !skipped.is_empty() {
+        let keep = |path: &PathBuf| !skipped.iter().any(|dir| path.starts_with(dir));
+        local_index.retain(|path, _| keep(path));
+        remote_index.retain(|path, _| keep(path));
+    }
+    // A flattened rule only manages the files directly in the remote root.
+    if rule.flatten {
+        remote_index.retain(|path, _| path.components().count() == 1);
+    }
+    let mut collisions = Collisions::default();
+    if rule.direction != SyncDirection::Pull {
+        collisions.uploads = resolve_collisions(
+            &mut local_index,
+            &mut remote_index,
+            |path| rule.remote_rel_path(path),
+            rule.collision_policy,
+            rule.case_insensitive,
+            &mut collisions.count,
+            &mut warnings,
+        );
+    }
+    if rule.direction != SyncDirection::Push {
+        collisions.downloads = resolve_collisions(
+            &mut remote_index,
+            &mut local_index,
+            Path::to_path_buf,
+            rule.collision_policy,
+            rule.case_insensitive,
+            &mut collisions.count,
+            &mut warnings,
+        );
+    }
+
+    Ok((local_index, remote_index, warnings, collisions))
+}
+
+/// Collisions found while indexing a rule, and the renames they produced.
+#[derive(Debug, Default)]
+struct Collisions {
+    count: usize,
+    uploads: HashMap<PathBuf, PathBuf>,
+    downloads: HashMap<PathBuf, PathBuf>,
+}
+
+impl Collisions {
+    fn apply(&self, actions: &mut [SyncAction], stats: &mut PlanStats) {
+        stats.collisions += self.count;
+        for action in actions {
+            match action {
+                SyncAction::Upload {
+                    rel_path, renamed, ..
+                } => *renamed = self.uploads.get(rel_path).cloned(),
+                SyncAction::Download {
+                    rel_path, renamed, ..
+                } => *renamed = self.downloads.get(rel_path).cloned(),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Applies `policy` to source files whose destination paths collide. With
+/// `fold_case`, names compare case-insensitively, as FAT, macOS and Windows
+/// filesystems store `A.txt` and `a.txt` as one file. The destination index is re-keyed by
+/// source path, so the diff pairs `logs/a/app.log` with a flattened `app.log`;
+/// destination files without a source keep their own name, so deleting them
+/// addresses the right file. Returns the source paths the policy renamed.
+fn resolve_collisions(
+    source: &mut FileIndex,
+    dest: &mut FileIndex,
+    dest_path: impl Fn(&Path) -> PathBuf,
+    policy: CollisionPolicy,
+    fold_case: bool,
+    count: &mut usize,
+    warnings: &mut Vec<String>,
+) -> HashMap<PathBuf, PathBuf> {
+    let key = |path: &Path| {
+        let name = path.to_string_lossy();
+        if fold_case {
+            name.to_lowercase()
+        } else {
+            name.into_owned()
+        }
+    };
+    let mut groups: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for path in source.keys() {
+        groups
+            .entry(key(&dest_path(path)))
+            .or_default()
+            .push(path.clone());
+    }
+    let mut taken: HashSet<String> = groups.keys().cloned().collect();
+    let mut groups: Vec<(String, Vec<PathBuf>)> = groups.into_iter().collect();
+    groups.sort();
+
+    let mut mapping: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut renames = HashMap::new();
+    for (group, mut sources) in groups {
+        if sources.len() == 1 {
+            mapping.push((dest_path(&sources[0]), sources.remove(0)));
+            continue;
+        }
+        sources.sort();
+        *count += sources.len();
+        let listed: Vec<String> = sources
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        let destination = dest_path(&sources[0]);
+        match policy {
+            CollisionPolicy::Error => {
+                warnings.push(format!(
+                    "Skipped {} files that would all be written to {}: {}",
+                    sources.len(),
+                    destination.display(),
+                    listed.join(", ")
+                ));
+                for path in &sources {
+                    source.remove(path);
+                }
+                dest.retain(|path, _| key(path) != group);
+            }
+            CollisionPolicy::Overwrite => {
+                warnings.push(format!(
+                    "{} files will all be written to {}, the last one wins: {}",
+                    sources.len(),
+                    destination.display(),
+                    listed.join(", ")
+                ));
+                for path in sources {
+                    mapping.push((dest_path(&path), path));
+                }
+            }
+            CollisionPolicy::Rename => {
+                let mut renamed = Vec::new();
+                for (index, path) in sources.into_iter().enumerate() {
+                    let mut to = dest_path(&path);
+                    if index > 0 {
+                        to = (index..)
+                            .map(|suffix| with_suffix(&to, suffix))
+                            .find(|candidate| taken.insert(key(candidate)))
+                            .unwrap_or(to);
+                        renamed.push(format!("{} → {}", path.display(), to.display()));
+                        renames.insert(path.clone(), to.clone());
+                    }
+                    mapping.push((to, path));
+                }
+                warnings.push(format!(
+                    "Renamed {} files that would overwrite {}: {}",
+                    renamed.len(),
+                    destination.display(),
+                    renamed.join(", ")
+                ));
+            }
+        }
+    }
+
+    // Destination names are matched by key too, so a source `readme.md`
+    // pairs with an existing `README.md` instead of leaving it to be deleted.
+    let mut dest_names: HashMap<String, PathBuf> =
+        dest.keys().map(|path| (key(path), path.clone())).collect();
+    let mut rekeyed = FileIndex::new();
+    for (to, from) in mapping {
+        if let Some(entry) = dest_names
+            .remove(&key(&to))
+            .and_then(|path| dest.remove(&path))
+        {
+            rekeyed.insert(from, entry);
+        }
+    }
+    for (path, entry) in dest.drain() {
+        rekeyed.entry(path).or_insert(entry);
+    }
+    *dest = rekeyed;
+    renames
+}
+
+/// `logs/app.log` with suffix 1 becomes `logs/app-1.log`.
+fn with_suffix(path: &Path, suffix: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}-{suffix}.{}", extension.to_string_lossy()),
+        None => format!("{stem}-{suffix}"),
+    };
+    path.with_file_name(name)
+}
+
+/// Size and whole-second mtime of a local file at the last successful sync.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub size: u64,
+    pub modified_secs: u64,
+}
+
+impl SnapshotEntry {
+    fn from_entry(entry: &FileEntry) -> Self {
+        Self {
+            size: entry.size,
+            modified_secs: entry
+                .modified
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|age| age.as_secs())
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Local file state after the last successful sync, keyed by rule local root.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TargetSnapshot {
+    pub rules: HashMap<PathBuf, HashMap<PathBuf, SnapshotEntry>>,
+}
+
+impl TargetSnapshot {
+    pub fn from_jobs<'a>(jobs: impl IntoIterator<Item = &'a SyncJob>) -> Self {
+        let rules = jobs
+            .into_iter()
+            .map(|job| {
+                let files = job
+                    .local_index
+                    .iter()
+                    .map(|(path, entry)| (path.clone(), SnapshotEntry::from_entry(entry)))
+                    .collect();
+                (job.rule.local.clone(), files)
+            })
+            .collect();
+        Self { rules }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DriftSummary {
+    pub added: usize,
+    pub modified: usize,
+    pub removed: usize,
+}
+
+impl DriftSummary {
+    pub fn is_clean(&self) -> bool {
+        self.added == 0 && self.modified == 0 && self.removed == 0
+    }
+}
+
+/// Local changes since `snapshot` was taken, from stat data only.
+pub fn local_drift(snapshot: &HashMap<PathBuf, SnapshotEntry>, local: &FileIndex) -> DriftSummary {
+    let mut drift = DriftSummary::default();
+    for (path, entry) in local {
+        match snapshot.get(path) {
+            None => drift.added += 1,
+            Some(previous) if *previous != SnapshotEntry::from_entry(entry) => drift.modified += 1,
+            Some(_) => {}
+        }
+    }
+    drift.removed = snapshot
+        .keys()
+        .filter(|path| !local.contains_key(*path))
+        .count();
+    drift
+}
+
+/// Sums `local_drift` over the target's rules without touching the network.
+/// Rules that were never synced are ignored.
+pub fn local_drift_for_target(
+    target: &RemoteTarget,
+    snapshot: &TargetSnapshot,
+) -> Result<DriftSummary> {
+    let local_store = FsLocalStore::default();
+    let mut total = DriftSummary::default();
+    for rule in &target.rules {
+        let root = resolve_local_root(target, &rule.local);
+        let Some(files) = snapshot.rules.get(&root) else {
+            continue;
+        };
+        let listing = local_store.list(&root, &ListOptions::for_rule(rule))?;
+        let drift = local_drift(files, &index_entries(listing.entries));
+        total.added += drift.added;
+        total.modified += drift.modified;
+        total.removed += drift.removed;
+    }
+    Ok(total)
+}
+
+/// Remote-vs-remote differences between two targets, e.g. staging and prod.
+/// Paths are prefixed with the rule's configured remote path.
+#[derive(Clone, Debug, Default)]
+pub struct TargetComparison {
+    pub only_left: Vec<PathBuf>,
+    pub only_right: Vec<PathBuf>,
+    /// Present on both sides with a different size or modification time.
+    pub differing: Vec<PathBuf>,
+    pub identical: usize,
+    /// Rule remote paths configured on only one of the two targets.
+    pub unmatched_rules: Vec<PathBuf>,
+}
+
+impl TargetComparison {
+    pub fn is_identical(&self) -> bool {
+        self.only_left.is_empty()
+            && self.only_right.is_empty()
+            && self.differing.is_empty()
+            && self.unmatched_rules.is_empty()
+    }
+}
+
+fn compare_indexes(
+    prefix: &Path,
+    left: &FileIndex,
+    right: &FileIndex,
+    comparison: &mut TargetComparison,
+) {
+    for (path, entry) in left {
+        match right.get(path) {
+            None => comparison.only_left.push(prefix.join(path)),
+            Some(other)
+                if entry.size != other.size
+                    || newer(entry.modified, other.modified)
+                    || newer(other.modified, entry.modified) =>
+            {
+                comparison.differing.push(prefix.join(path))
+            }
+            Some(_) => comparison.identical += 1,
+        }
+    }
+    comparison.only_right.extend(
+        right
+            .keys()
+            .filter(|path| !left.contains_key(*path))
+            .map(|path| prefix.join(path)),
+    );
+}
+
+/// Lists the rule roots of both targets over SFTP and compares them without
+/// transferring anything. Rules are paired by their configured remote path.
+pub fn compare_targets(left: &RemoteTarget, right: &RemoteTarget) -> Result<TargetComparison> {
+    let left_store = SftpRemoteStore::connect(left)?;
+    let right_store = SftpRemoteStore::connect(right)?;
+    compare_remotes(left, &left_store, right, &right_store)
+}
+
+fn compare_remotes<A: RemoteStore, B: RemoteStore>(
+    left: &RemoteTarget,
+    left_store: &A,
+    right: &RemoteTarget,
+    right_store: &B,
+) -> Result<TargetComparison> {
+    let mut comparison = TargetComparison::default();
+    let left_start = left_store.start_dir().ok().flatten();
+    let right_start = right_store.start_dir().ok().flatten();
+    for rule in &left.rules {
+        let Some(other) = right.rules.iter().find(|other| other.remote == rule.remote) else {
+            comparison.unmatched_rules.push(rule.remote.clone());
+            continue;
+        };
+        let left_listing = left_store.list(
+            &resolved_remote_root(left, &rule.remote, left_start.as_deref()),
+            &ListOptions::for_rule(rule),
+        )?;
+        let right_listing = right_store.list(
+            &resolved_remote_root(right, &other.remote, right_start.as_deref()),
+            &ListOptions::for_rule(other),
+        )?;
+        compare_indexes(
+            &rule.remote,
+            &index_entries(left_listing.entries),
+            &index_entries(right_listing.entries),
+            &mut comparison,
+        );
+    }
+    comparison.unmatched_rules.extend(
+        right
+            .rules
+            .iter()
+            .filter(|other| !left.rules.iter().any(|rule| rule.remote == other.remote))
+            .map(|other| other.remote.clone()),
+    );
+
+    comparison.only_left.sort();
+    comparison.only_right.sort();
+    comparison.differing.sort();
+    Ok(comparison)
+}
+
+/// Local trees above either limit are flagged by the preflight as suspiciously large.
+const PREFLIGHT_HUGE_FILES: usize = 100_000;
+const PREFLIGHT_HUGE_BYTES: u64 = 20 * 1024 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PreflightIssue {
+    /// Only reported for rules that upload; pulls create the folder.
+    Missing,
+    NotADirectory,
+    Unreadable(String),
+    /// Subtrees that could not be listed and would be left out of a sync.
+    SkippedSubtrees(usize),
+    Empty,
+    Huge,
+}
+
+impl fmt::Display for PreflightIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreflightIssue::Missing => write!(f, "local folder does not exist"),
+            PreflightIssue::NotADirectory => write!(f, "local path is not a folder"),
+            PreflightIssue::Unreadable(reason) => write!(f, "cannot list local folder: {reason}"),
+            PreflightIssue::SkippedSubtrees(count) => {
+                write!(f, "{count} unreadable subfolders would be skipped")
+            }
+            PreflightIssue::Empty => write!(f, "local folder is empty"),
+            PreflightIssue::Huge => write!(f, "local folder is unusually large"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RulePreflight {
+    /// `SyncRule::label` of the checked rule.
+    pub rule: String,
+    pub files: usize,
+    pub bytes: u64,
+    pub issues: Vec<PreflightIssue>,
+}
+
+/// Checks every rule's local side without connecting: existence, readability
+/// and approximate size.
+pub fn local_preflight(target: &RemoteTarget) -> Vec<RulePreflight> {
+    let local_store = FsLocalStore::default();
+    target
+        .rules
+        .iter()
+        .map(|rule| {
+            let uploads = rule.direction != SyncDirection::Pull;
+            let mut report = RulePreflight {
+                rule: rule.label(),
+                files: 0,
+                bytes: 0,
+                issues: Vec::new(),
+            };
+            let root = resolve_local_root(target, &rule.local);
+            match fs::metadata(&root) {
+                Err(_) if uploads => report.issues.push(PreflightIssue::Missing),
+                Err(_) => {}
+                Ok(metadata) if !metadata.is_dir() => {
+                    report.issues.push(PreflightIssue::NotADirectory)
+                }
+                Ok(_) => match local_store.list(&root, &ListOptions::for_rule(rule)) {
+                    Ok(listing) => {
+                        report.files = listing.entries.len();
+                        report.bytes = listing.entries.iter().map(|entry| entry.size).sum();
+                        if !listing.skipped.is_empty() {
+                            report
+                                .issues
+                                .push(PreflightIssue::SkippedSubtrees(listing.skipped.len()));
+                        }
+                        if report.files == 0 && uploads {
+                            report.issues.push(PreflightIssue::Empty);
+                        }
+                        if report.files > PREFLIGHT_HUGE_FILES
+                            || report.bytes > PREFLIGHT_HUGE_BYTES
+                        {
+                            report.issues.push(PreflightIssue::Huge);
+                        }
+                    }
+                    Err(err) => report
+                        .issues
+                        .push(PreflightIssue::Unreadable(format!("{err:#}"))),
+                },
+            }
+            report
+        })
+        .collect()
+}
+
+/// What `check_rule_remote` confirmed about a rule's remote folder.
+#[derive(Clone, Debug)]
+pub struct RuleCheck {
+    pub root: PathBuf,
+    /// A probe file was created and removed again, so uploads can land there.
+    pub writable: bool,
+}
+
+/// Connects and checks one rule's remote folder: it must be a listable
+/// directory, and rules that upload must be able to create a file in it.
+pub fn check_rule_remote(target: &RemoteTarget, rule: &SyncRule) -> Result<RuleCheck> {
+    let store = SftpRemoteStore::connect(target)?;
+    let start_dir = store.start_dir().ok().flatten();
+    let root = resolved_remote_root(target, &rule.remote, start_dir.as_deref());
+    let writable = rule.direction != SyncDirection::Pull;
+    store.probe_dir(&root, writable)?;
+    Ok(RuleCheck { root, writable })
+}
+
+#[allow(dead_code)]
+pub fn plan_jobs_for_target(target: &RemoteTarget) -> Result<PlanJobsResult> {
+    plan_jobs_with_progress(target, false, |_completed, _total| {}, |_found| true)
+}
+
+/// `progress` gets completed/total rules; `listed` gets the local and remote
+/// files found so far across rules and can cancel planning by returning `false`. With
+/// `force`, every selected source file is transferred whatever its timestamp.
+pub fn plan_jobs_with_progress(
+    target: &RemoteTarget,
+    force: bool,
+    mut progress: impl FnMut(usize, usize),
+    mut listed: impl FnMut(usize) -> bool,
+) -> Result<PlanJobsResult> {
+    let remote_store = SftpRemoteStore::borrow(target)?;
+    let local_store = FsLocalStore::default();
+    let start_dir = remote_store.start_dir().ok().flatten();
+    let mut warnings = Vec::new();
+    let clock_skew = if target.correct_clock_skew {
+        let dir = start_dir.clone().unwrap_or_else(|| PathBuf::from("."));
+        match connection::measure_clock_skew(&remote_store.sftp, &dir) {
+            Ok(skew) => Some(skew),
+            Err(err) => {
+                warnings.push(format!(
+                    "Could not measure the clock of {}, comparing times uncorrected: {err:#}",
+                    target.name
+                ));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let total_rules = target.rules.len().max(1);
+    progress(0, total_rules);
+
+    let context = PlanContext {
+        start_dir: start_dir.as_deref(),
+        clock_skew: clock_skew.unwrap_or(0),
+        force,
+    };
+    let mut jobs = Vec::new();
+    let mut discovered = 0;
 
     for (index, rule) in target.rules.iter().enumerate() {
-        match plan_single_job(target, rule, &local_store, &remote_store) {
+        let mut found_in_rule = 0;
+        let mut on_listed = |found: usize| {
+            found_in_rule = found;
+            listed(discovered + found)
+        };
+        match plan_single_job(
+            target,
+            rule,
+            context,
+            &local_store,
+            &remote_store,
+            &mut warnings,
+            &mut on_listed,
+        ) {
             Ok(job) => jobs.push(job),
+            Err(err) if err.is::<ListingCanceled>() => {
+                return Err(anyhow!("planning for {} was canceled", target.name));
+            }
             Err(err) => warnings.push(format!(
                 "Failed to plan rule {} for {}: {err}",
-                rule.local.display(),
+                resolve_local_root(target, &rule.local).display(),
                 target.name
             )),
         }
+        discovered += found_in_rule;
         progress(index + 1, total_rules);
     }
 
@@ -383,21 +1780,112 @@ pub fn plan_jobs_with_progress(
         ));
     }
 
-    Ok(PlanJobsResult { jobs, warnings })
+    let space = check_free_space(&jobs, &remote_store);
+    Ok(PlanJobsResult {
+        jobs,
+        warnings,
+        space,
+        start_dir,
+        clock_skew,
+        forced: force,
+        missing_stats: remote_store.missing_stats(),
+    })
+}
+
+/// Compares the planned upload bytes with the tightest free space among the
+/// upload roots. Roots on different filesystems make this conservative.
+fn check_free_space<R: RemoteStore>(jobs: &[PlannedJob], remote: &R) -> Option<SpaceCheck> {
+    let mut required: u64 = 0;
+    let mut available: Option<u64> = None;
+    for job in jobs {
+        let uploads = TransferTotals::from_actions(&job.actions).upload_bytes;
+        if uploads == 0 {
+            continue;
+        }
+        required = required.saturating_add(uploads);
+        if let Ok(Some(free)) = remote.free_space(&job.rule.remote) {
+            available = Some(available.map_or(free, |min| min.min(free)));
+        }
+    }
+    (required > 0).then_some(SpaceCheck {
+        required,
+        available,
+    })
+}
+
+/// Reads the "Available" column (1K blocks) from `df -Pk` output.
+fn parse_df_available(output: &str) -> Option<u64> {
+    let line = output.lines().nth(1)?;
+    let blocks: u64 = line.split_whitespace().nth(3)?.parse().ok()?;
+    Some(blocks.saturating_mul(1024))
+}
+
+/// Settings of one planning run, shared by every rule of the target.
+#[derive(Clone, Copy)]
+struct PlanContext<'a> {
+    start_dir: Option<&'a Path>,
+    /// Seconds the server clock runs ahead of ours; 0 when not corrected.
+    clock_skew: i64,
+    /// Transfer every selected source file, ignoring timestamps and policy.
+    force: bool,
 }
 
 fn plan_single_job<L: LocalStore, R: RemoteStore>(
     target: &RemoteTarget,
     rule: &SyncRule,
+    context: PlanContext<'_>,
     local: &L,
     remote: &R,
+    warnings: &mut Vec<String>,
+    on_listed: ListProgress<'_>,
 ) -> Result<PlannedJob> {
-    let mut resolved_rule = rule.clone();
-    resolved_rule.remote = resolve_remote_root(&target.base_path, &rule.remote);
+    check_line_endings(rule)?;
+    for (side, path) in [("local", &rule.local), ("remote", &rule.remote)] {
+        expand_env_vars(path)
+            .map_err(|err| anyhow!("{side} path {}: {err}", path.display()))?;
+    }
+    let resolved_rule = resolved_rule(target, rule, context.start_dir);
 
-    let local_index = index_entries(local.list(&resolved_rule.local)?);
-    let remote_index = index_entries(remote.list(&resolved_rule.remote)?);
-    let (actions, stats) = diff_actions(&resolved_rule, &local_index, &remote_index);
+    let (mut local_index, mut remote_index, listing_warnings, collisions) =
+        build_indexes(&resolved_rule, local, remote, on_listed)?;
+    warnings.extend(listing_warnings);
+    let nested = nested_rule_roots(target, rule, &resolved_rule, context.start_dir, warnings);
+    if !nested.is_empty() {
+        let outside = |path: &PathBuf| !nested.iter().any(|root| path.starts_with(root));
+        local_index.retain(|path, _| outside(path));
+        remote_index.retain(|path, _| outside(path));
+    }
+    let (mut actions, mut stats) = diff_actions(
+        &resolved_rule,
+        &local_index,
+        &remote_index,
+        context.clock_skew,
+        context.force,
+    );
+    collisions.apply(&mut actions, &mut stats);
+    if !context.force {
+        skip_identical(
+            &resolved_rule,
+            local,
+            remote,
+            &local_index,
+            &remote_index,
+            &mut actions,
+            &mut stats,
+        );
+    }
+    if let Some(side) = empty_source(&local_index, &remote_index, &actions) {
+        stats.empty_sources = 1;
+        let (folder, other) = match side {
+            SyncDirection::Pull => (resolved_rule.remote.display(), "local"),
+            _ => (resolved_rule.local.display(), "remote"),
+        };
+        warnings.push(format!(
+            "Rule {} of {}: {folder} lists no files, so the plan deletes every {other} file; it will only run after confirmation",
+            rule.label(),
+            target.name
+        ));
+    }
 
     Ok(PlannedJob {
         target_id: target.id,
@@ -410,7 +1898,77 @@ fn plan_single_job<L: LocalStore, R: RemoteStore>(
     })
 }
 
+/// The side that came back empty while the plan deletes files on the other,
+/// as the direction reading from it (`Push` for local, `Pull` for remote).
+/// An empty source is almost always a wrong or unmounted folder, not a
+/// request to wipe the destination.
+fn empty_source(
+    local_index: &FileIndex,
+    remote_index: &FileIndex,
+    actions: &[SyncAction],
+) -> Option<SyncDirection> {
+    actions.iter().find_map(|action| match action {
+        SyncAction::DeleteRemote { .. } if local_index.is_empty() => Some(SyncDirection::Push),
+        SyncAction::DeleteLocal { .. } if remote_index.is_empty() => Some(SyncDirection::Pull),
+        _ => None,
+    })
+}
+
+/// Folders inside `resolved`'s roots that are the roots of other rules of the
+/// target, relative to those roots. Their files belong to the other rule, so
+/// this one must neither transfer nor delete them. Rules sharing a remote
+/// root cannot be told apart and are reported instead.
+fn nested_rule_roots(
+    target: &RemoteTarget,
+    rule: &SyncRule,
+    resolved: &SyncRule,
+    start_dir: Option<&Path>,
+    warnings: &mut Vec<String>,
+) -> Vec<PathBuf> {
+    let own = target
+        .rules
+        .iter()
+        .position(|other| std::ptr::eq(other, rule));
+    let mut nested = Vec::new();
+    for (index, other) in target.rules.iter().enumerate() {
+        if Some(index) == own {
+            continue;
+        }
+        let local_root = resolve_local_root(target, &other.local);
+        let remote_root = resolved_remote_root(target, &other.remote, start_dir);
+        if remote_root == resolved.remote {
+            // Warn from the earlier rule only, so the pair is reported once.
+            if own.is_some_and(|own| own < index) {
+                warnings.push(format!(
+                    "Rules {} and {} of {} share the remote folder {}; files of one may be deleted by the other",
+                    rule.label(),
+                    other.label(),
+                    target.name,
+                    remote_root.display()
+                ));
+            }
+            continue;
+        }
+        let inside = [
+            local_root.strip_prefix(&resolved.local).ok(),
+            remote_root.strip_prefix(&resolved.remote).ok(),
+        ];
+        for rel in inside.into_iter().flatten() {
+            if !rel.as_os_str().is_empty() && !nested.iter().any(|known| known == rel) {
+                nested.push(rel.to_path_buf());
+            }
+        }
+    }
+    nested
+}
+
 fn resolve_remote_root(base_path: &Path, rule_remote: &Path) -> PathBuf {
+    // Variables come from this machine's environment, as in local paths.
+    let rule_remote = &expand_env_vars_lossy(rule_remote);
+    // `~` is the login home, which is where the session starts.
+    if let Ok(rest) = rule_remote.strip_prefix("~") {
+        return rest.to_path_buf();
+    }
     if rule_remote.is_absolute() {
         return rule_remote.to_path_buf();
     }
@@ -426,555 +1984,4311 @@ fn resolve_remote_root(base_path: &Path, rule_remote: &Path) -> PathBuf {
     base_path.join(rule_remote)
 }
 
-#[allow(dead_code)]
-pub fn execute_jobs_for_target(target: &RemoteTarget, jobs: &[SyncJob]) -> Result<ExecutionSummary> {
-    execute_jobs_with_progress(target, jobs, None, |_completed, _total| {})
-}
-
-pub fn execute_jobs_with_progress(
+/// Remote root of a rule, with a relative result anchored at the session's
+/// start directory (usually the login home) when it is known.
+pub fn resolved_remote_root(
     target: &RemoteTarget,
-    jobs: &[SyncJob],
-    bandwidth_limit_mbps: Option<u32>,
-    mut progress: impl FnMut(usize, usize),
-) -> Result<ExecutionSummary> {
-    if jobs.is_empty() {
-        progress(1, 1);
-        return Ok(ExecutionSummary::default());
+    rule_remote: &Path,
+    start_dir: Option<&Path>,
+) -> PathBuf {
+    let root = resolve_remote_root(&target.base_path, rule_remote);
+    match start_dir {
+        Some(start_dir) if root.as_os_str().is_empty() => start_dir.to_path_buf(),
+        Some(start_dir) if root.is_relative() => start_dir.join(root),
+        _ => root,
     }
+}
 
-    let remote_store = SftpRemoteStore::connect(target)
-        .with_context(|| format!("failed to connect to {}", target.host))?;
-    let local_store = FsLocalStore::default();
-    let limiter = bandwidth_limit_mbps.map(|mbps| {
-        let bytes_per_sec = (mbps as u64).saturating_mul(125_000);
-        Mutex::new(BandwidthLimiter::new(bytes_per_sec))
-    });
-    let executor = SyncExecutor::new(&local_store, &remote_store, limiter);
+/// Rule remote path for a folder picked while browsing from `root`, the
+/// resolved base path: relative when inside it, so the rule keeps following
+/// the base path, and absolute otherwise.
+pub fn rule_remote_for(picked: &Path, root: &Path) -> PathBuf {
+    match picked.strip_prefix(root) {
+        Ok(rel) if !rel.as_os_str().is_empty() => rel.to_path_buf(),
+        _ => picked.to_path_buf(),
+    }
+}
 
-    let total_actions: usize = jobs.iter().map(|job| job.plan.actions.len()).sum();
-    let mut summary = ExecutionSummary::default();
-    let mut completed = 0;
-    progress(completed, total_actions.max(1));
+/// `rule` with both roots resolved as planning resolves them.
+pub fn resolved_rule(target: &RemoteTarget, rule: &SyncRule, start_dir: Option<&Path>) -> SyncRule {
+    let mut resolved = rule.clone();
+    resolved.local = resolve_local_root(target, &rule.local);
+    resolved.remote = resolved_remote_root(target, &rule.remote, start_dir);
+    resolved
+}
 
-    for job in jobs {
-        for log in executor.execute(&job.plan) {
-            match log.status {
-                ActionStatus::Applied => summary.applied += 1,
-                ActionStatus::SkippedConflict => summary.skipped += 1,
-                ActionStatus::Failed(reason) => {
-                    summary.failures.push((log.action.clone(), reason));
-                }
-            }
-            completed += 1;
-            progress(completed, total_actions.max(1));
+/// Absolute local root of a rule. Environment variables are expanded first;
+/// relative paths and `~` then resolve against the target's `local_base`,
+/// then the home directory, never the working directory.
+pub fn resolve_local_root(target: &RemoteTarget, rule_local: &Path) -> PathBuf {
+    let home = dirs::home_dir().unwrap_or_default();
+    let expand = |path: &Path| {
+        let path = expand_env_vars_lossy(path);
+        match path.strip_prefix("~") {
+            Ok(rest) => home.join(rest),
+            Err(_) => path,
         }
-    }
+    };
 
-    Ok(summary)
+    let local = expand(rule_local);
+    let resolved = if local.is_absolute() {
+        local
+    } else {
+        home.join(expand(&target.local_base)).join(local)
+    };
+    resolved
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
 }
 
-pub struct SftpRemoteStore {
-    _session: ssh2::Session,
-    sftp: Sftp,
+/// `path` with `$NAME`, `${NAME}` and `%NAME%` replaced by the environment
+/// variables they name. An unset variable is an error naming it; a `$` or `%`
+/// not followed by a name is kept as is.
+pub fn expand_env_vars(path: &Path) -> Result<PathBuf> {
+    expand_vars_with(path, |name| std::env::var_os(name))
 }
 
-impl SftpRemoteStore {
-    pub fn connect(target: &RemoteTarget) -> Result<Self> {
-        let session = connection::establish_session(target)
-            .with_context(|| format!("failed to connect to {}", target.host))?;
-        let sftp = session.sftp().context("failed to start SFTP subsystem")?;
-        Ok(Self {
-            _session: session,
-            sftp,
-        })
-    }
+/// Like `expand_env_vars`, but leaves the path untouched when a variable is
+/// unset, for display and lookups. Planning reports the error instead.
+fn expand_env_vars_lossy(path: &Path) -> PathBuf {
+    expand_env_vars(path).unwrap_or_else(|_| path.to_path_buf())
+}
 
-    fn collect_entries(
-        &self,
-        root: &Path,
-        rel_path: &Path,
-        out: &mut Vec<FileEntry>,
-    ) -> Result<()> {
-        let dir_path = if rel_path.as_os_str().is_empty() {
-            root.to_path_buf()
+fn expand_vars_with(
+    path: &Path,
+    lookup: impl Fn(&str) -> Option<std::ffi::OsString>,
+) -> Result<PathBuf> {
+    let Some(text) = path.to_str().filter(|text| text.contains(['$', '%'])) else {
+        return Ok(path.to_path_buf());
+    };
+    let is_name = |name: &str| {
+        !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+    };
+
+    let mut expanded = std::ffi::OsString::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(['$', '%']) {
+        expanded.push(&rest[..start]);
+        let marker = &rest[start..start + 1];
+        let after = &rest[start + 1..];
+        let (name, consumed) = if marker == "%" {
+            match after.split_once('%') {
+                Some((name, _)) if is_name(name) => (name, name.len() + 1),
+                _ => ("", 0),
+            }
+        } else if let Some(braced) = after.strip_prefix('{') {
+            match braced.split_once('}') {
+                Some((name, _)) if is_name(name) => (name, name.len() + 2),
+                _ => ("", 0),
+            }
         } else {
-            root.join(rel_path)
+            let len = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..len], len)
         };
+        if name.is_empty() {
+            expanded.push(marker);
+            rest = after;
+            continue;
+        }
+        let value = lookup(name)
+            .ok_or_else(|| anyhow!("environment variable {name} is not set"))?;
+        expanded.push(value);
+        rest = &after[consumed..];
+    }
+    expanded.push(rest);
+    Ok(PathBuf::from(expanded))
+}
 
-        for (entry_path, stat) in self
-            .sftp
-            .readdir(&dir_path)
-            .with_context(|| format!("failed to read {}", dir_path.display()))?
-        {
-            let Some(name) = entry_path.file_name() else {
-                continue;
-            };
+/// Remote path of a rule's root, or of `rel_path` under it, as the server sees it.
+fn remote_path(target: &RemoteTarget, rule: &SyncRule, rel_path: Option<&Path>) -> String {
+    let root = resolve_remote_root(&target.base_path, &rule.remote);
+    let path = rel_path.map_or(root.clone(), |rel_path| {
+        root.join(rule.remote_rel_path(rel_path))
+    });
+    path.to_string_lossy().replace('\\', "/")
+}
 
-            if name == OsStr::new(".") || name == OsStr::new("..") {
-                continue;
-            }
+/// `sftp://user@host:port/abs/path` link for sharing a synced rule or file.
+/// Paths relative to the login directory use the common `/~/` form.
+pub fn sftp_url(target: &RemoteTarget, rule: &SyncRule, rel_path: Option<&Path>) -> String {
+    let (host, port) = share_host_port(target);
+    let path = remote_path(target, rule, rel_path);
+    let path = match path.strip_prefix('/') {
+        Some(absolute) => format!("/{absolute}"),
+        None => format!("/~/{path}"),
+    };
+    format!(
+        "sftp://{}@{host}:{port}{}",
+        percent_encode(&target.username, false),
+        percent_encode(&path, true)
+    )
+}
 
-            let child_rel = if rel_path.as_os_str().is_empty() {
-                PathBuf::from(name)
-            } else {
-                rel_path.join(name)
-            };
+/// Equivalent `scp` command that downloads the rule root or file into `.`.
+pub fn scp_command(target: &RemoteTarget, rule: &SyncRule, rel_path: Option<&Path>) -> String {
+    let (host, port) = share_host_port(target);
+    let path = remote_path(target, rule, rel_path);
+    let recursive = if rel_path.is_none() { "-r " } else { "" };
+    format!(
+        "scp {recursive}-P {port} '{}@{host}:{}' .",
+        target.username,
+        path.replace('\'', "'\\''")
+    )
+}
 
-            if stat.is_dir() {
-                self.collect_entries(root, &child_rel, out)?;
-            } else if stat.is_file() {
-                out.push(FileEntry {
-                    path: child_rel,
-                    kind: EntryKind::File,
-                    size: stat.size.unwrap_or(0),
-                    modified: stat
-                        .mtime
-                        .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
-                        .unwrap_or(SystemTime::UNIX_EPOCH),
-                });
-            }
-        }
+/// The planned actions of `jobs` as an approximate `scp`/`ssh` script, for
+/// review or running by hand. Deletions are commented out.
+pub fn plan_script<'a>(
+    target: &RemoteTarget,
+    jobs: impl IntoIterator<Item = &'a SyncJob>,
+) -> String {
+    let (host, port) = share_host_port(target);
+    let login = format!("{}@{host}", target.username);
+    let remote_spec = |path: &Path| {
+        let path = path.to_string_lossy().replace('\\', "/");
+        shell_quote(Path::new(&format!("{login}:{path}")))
+    };
+    // Quoted twice: once for the local shell, once for the one ssh starts remotely.
+    let remote_arg = |path: &Path| {
+        let path = path.to_string_lossy().replace('\\', "/");
+        shell_quote(Path::new(&shell_quote(Path::new(&path))))
+    };
+    let ssh = format!("ssh -p {port} {}", shell_quote(Path::new(&login)));
 
-        Ok(())
-    }
+    let mut script = vec![
+        "#!/bin/sh".to_string(),
+        comment(&format!(
+            "Sync plan for {} ({login}), ADVISORY ONLY.",
+            target.name
+        )),
+        "# An approximation of what the app would do; filters, overwrite checks and".to_string(),
+        "# timestamps are not reproduced. Review every line before running it.".to_string(),
+        "# Deletions are commented out; remove the leading \"# \" to run them.".to_string(),
+    ];
+    for job in jobs {
+        let rule = &job.plan.rule;
+        if job.plan.actions.is_empty() {
+            continue;
+        }
+        script.push(String::new());
+        script.push(comment(&rule.label()));
+        for action in &job.plan.actions {
+            let line = match action {
+                SyncAction::Upload {
+                    rel_path, renamed, ..
+                } => {
+                    let remote_rel = renamed
+                        .clone()
+                        .unwrap_or_else(|| rule.remote_rel_path(rel_path));
+                    format!(
+                        "scp -P {port} {} {}",
+                        shell_quote(&rule.local.join(rel_path)),
+                        remote_spec(&rule.remote.join(remote_rel))
+                    )
+                }
+                SyncAction::Download {
+                    rel_path, renamed, ..
+                } => format!(
+                    "scp -P {port} {} {}",
+                    remote_spec(&rule.remote.join(rel_path)),
+                    shell_quote(&rule.local.join(renamed.as_deref().unwrap_or(rel_path)))
+                ),
+                SyncAction::DeleteRemote { rel_path } => comment(&format!(
+                    "{ssh} rm -- {}",
+                    remote_arg(&rule.remote.join(rule.remote_rel_path(rel_path)))
+                )),
+                SyncAction::DeleteLocal { rel_path } => {
+                    comment(&format!("rm -- {}", shell_quote(&rule.local.join(rel_path))))
+                }
+                SyncAction::Conflict { rel_path } => {
+                    comment(&format!("conflict, skipped: {}", rel_path.display()))
+                }
+            };
+            script.push(line);
+        }
+    }
+    script.push(String::new());
+    script.join("\n")
+}
 
-    fn absolute_path(&self, root: &Path, rel_path: &Path) -> PathBuf {
-        if rel_path.as_os_str().is_empty() {
-            root.to_path_buf()
-        } else if rel_path.is_absolute() {
-            rel_path.to_path_buf()
-        } else {
-            root.join(rel_path)
+/// Host and port with IPv6 literals bracketed, as URLs and `scp` expect.
+fn share_host_port(target: &RemoteTarget) -> (String, u16) {
+    let (host, port) = connection::split_host_port(&target.host);
+    if host.contains(':') && !host.starts_with('[') {
+        (format!("[{host}]"), port)
+    } else {
+        (host, port)
+    }
+}
+
+fn percent_encode(text: &str, keep_slashes: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(byte as char)
+            }
+            b'/' if keep_slashes => out.push('/'),
+            _ => out.push_str(&format!("%{byte:02X}")),
         }
     }
+    out
 }
 
-impl RemoteStore for SftpRemoteStore {
-    fn list(&self, root: &Path) -> Result<Vec<FileEntry>> {
-        let mut entries = Vec::new();
-        self.collect_entries(root, Path::new(""), &mut entries)?;
-        Ok(entries)
+/// Executor knobs derived from `AppSettings`.
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionOptions {
+    pub bandwidth_limit_mbps: Option<u32>,
+    /// Defer uploads whose size or mtime changes within this window.
+    pub stability_window: Option<Duration>,
+    /// Idle time after which SSH keepalives are sent during execution.
+    pub keepalive_interval: Option<Duration>,
+    /// Staging directory for downloads; `None` stages next to the destination.
+    pub staging_dir: Option<PathBuf>,
+    /// Checked before each action; once set the run stops with an error.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Where each job's `ExecutionJournal` is kept while it runs.
+    pub journal_dir: Option<PathBuf>,
+    /// Keep the copy a conflict resolution overwrites.
+    pub backup_conflicts: bool,
+    /// Hold every deletion for review instead of applying it.
+    pub review_deletions: bool,
+    /// Walk every action without touching either side; see `ActionStatus::WouldApply`.
+    pub dry_run: bool,
+}
+
+impl ExecutionOptions {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            bandwidth_limit_mbps: settings.limit_bandwidth.then_some(settings.bandwidth_mbps),
+            stability_window: settings
+                .stability_check
+                .then(|| Duration::from_millis(settings.stability_window_ms)),
+            keepalive_interval: (settings.keepalive_secs > 0)
+                .then(|| Duration::from_secs(settings.keepalive_secs as u64)),
+            staging_dir: settings.staging_dir.clone(),
+            cancel: None,
+            journal_dir: None,
+            backup_conflicts: settings.backup_conflicts,
+            review_deletions: settings.review_deletions,
+            dry_run: false,
+        }
     }
 
-    fn read_file(&self, root: &Path, rel_path: &Path) -> Result<Vec<u8>> {
-        let path = self.absolute_path(root, rel_path);
-        let mut file = self
-            .sftp
-            .open(&path)
-            .with_context(|| format!("failed to open {}", path.display()))?;
-        let mut buf = Vec::new();
-        file.read_to_end(&mut buf)
-            .with_context(|| format!("failed to read {}", path.display()))?;
-        Ok(buf)
+    fn canceled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
     }
+}
 
-    fn write_file(&self, root: &Path, rel_path: &Path, bytes: &[u8]) -> Result<()> {
-        let path = self.absolute_path(root, rel_path);
-        if let Some(parent) = rel_path.parent() {
-            self.ensure_dir(root, parent)?;
+#[allow(dead_code)]
+pub fn execute_jobs_for_target(target: &RemoteTarget, jobs: &[SyncJob]) -> Result<ExecutionSummary> {
+    execute_jobs_with_progress(
+        target,
+        jobs,
+        &ExecutionOptions::default(),
+        |_completed, _total, _bytes| {},
+    )
+}
+
+/// Why a held deletion should no longer be applied: a side's file is not
+/// what the sync that held it saw, or can't be checked.
+fn changed_since_held<L: LocalStore + ?Sized, R: RemoteStore + ?Sized>(
+    local: &L,
+    remote: &R,
+    deletion: &HeldDeletion,
+) -> Option<String> {
+    let rel_path = deletion.action.rel_path();
+    let rule = &deletion.rule;
+    let sides = [
+        ("local", local.stat(&rule.local, rel_path), &deletion.local),
+        (
+            "remote",
+            remote.stat(&rule.remote, &rule.remote_rel_path(rel_path)),
+            &deletion.remote,
+        ),
+    ];
+    for (side, current, held) in sides {
+        match current {
+            Ok(current) if current.as_ref().map(SnapshotEntry::from_entry) == *held => {}
+            Ok(_) => {
+                return Some(format!(
+                    "{} changed on the {side} side since the sync; not deleted",
+                    rel_path.display()
+                ));
+            }
+            Err(err) => {
+                return Some(format!(
+                    "could not re-check {} on the {side} side; not deleted: {err:#}",
+                    rel_path.display()
+                ));
+            }
         }
-        let mut file = self
-            .sftp
-            .open_mode(
-                &path,
-                OpenFlags::WRITE | OpenFlags::TRUNCATE | OpenFlags::CREATE,
-                0o644,
-                OpenType::File,
-            )
-            .with_context(|| format!("failed to open {} for write", path.display()))?;
-        file.write_all(bytes)
-            .with_context(|| format!("failed to write {}", path.display()))
     }
+    None
+}
 
-    fn remove_file(&self, root: &Path, rel_path: &Path) -> Result<()> {
-        let path = self.absolute_path(root, rel_path);
-        self.sftp
-            .unlink(&path)
-            .with_context(|| format!("failed to remove {}", path.display()))
+/// Applies deletions a sync held for review, one plan per rule they came
+/// from. Each is first re-checked on both sides and skipped if either changed
+/// since the sync. Read-only remotes still refuse theirs.
+pub fn apply_held_deletions(
+    target: &RemoteTarget,
+    deletions: &[HeldDeletion],
+    options: &ExecutionOptions,
+) -> Result<ExecutionSummary> {
+    let remote_store = SftpRemoteStore::connect(target)
+        .with_context(|| format!("failed to connect to {}", target.host))?;
+    let local_store = FsLocalStore::with_staging_dir(options.staging_dir.clone());
+    let mut summary = ExecutionSummary::default();
+    let mut plans: Vec<SyncPlan> = Vec::new();
+    for deletion in deletions {
+        if let Some(warning) = changed_since_held(&local_store, &remote_store, deletion) {
+            summary.stale_deletions.push(warning);
+            continue;
+        }
+        let label = deletion.rule.label();
+        match plans.iter_mut().find(|plan| plan.rule.label() == label) {
+            Some(plan) => plan.actions.push(deletion.action.clone()),
+            None => plans.push(SyncPlan {
+                rule: deletion.rule.clone(),
+                actions: vec![deletion.action.clone()],
+                stats: PlanStats::default(),
+            }),
+        }
     }
 
-    fn ensure_dir(&self, root: &Path, rel_path: &Path) -> Result<()> {
-        let target = self.absolute_path(root, rel_path);
-        let mut current = PathBuf::new();
+    for plan in &plans {
+        let mut rule_summary = RuleSummary {
+            rule: plan.rule.label(),
+            ..RuleSummary::default()
+        };
+        let executor = SyncExecutor::new(&local_store, &remote_store, None)
+            .with_remote_read_only(target.remote_read_only);
+        for log in executor.execute_iter(plan) {
+            match log.status {
+                ActionStatus::Applied => rule_summary.applied += 1,
+                ActionStatus::Refused => rule_summary.refused += 1,
+                ActionStatus::Failed { reason, cause } => {
+                    rule_summary.failed += 1;
+                    summary.failures.push(ExecutionFailure {
+                        rule: log.rule,
+                        reason,
+                        cause,
+                    });
+                }
+                _ => rule_summary.deferred += 1,
+            }
+        }
+        summary.applied += rule_summary.applied;
+        summary.deferred += rule_summary.deferred;
+        summary.refused += rule_summary.refused;
+        summary.rules.push(rule_summary);
+    }
+    Ok(summary)
+}
 
-        for component in target.components() {
-            match component {
-                Component::RootDir => {
-                    current.push(Path::new("/"));
-                    continue;
+pub fn execute_jobs_with_progress(
+    target: &RemoteTarget,
+    jobs: &[SyncJob],
+    options: &ExecutionOptions,
+    mut progress: impl FnMut(usize, usize, u64),
+) -> Result<ExecutionSummary> {
+    if jobs.is_empty() {
+        progress(1, 1, 0);
+        return Ok(ExecutionSummary::default());
+    }
+    if options.canceled() {
+        return Err(anyhow!("execution was aborted before it started"));
+    }
+
+    let connect = || -> Result<SftpRemoteStore> {
+        let store = SftpRemoteStore::connect(target)
+            .with_context(|| format!("failed to connect to {}", target.host))?;
+        if let Some(interval) = options.keepalive_interval {
+            store.set_keepalive(interval);
+        }
+        Ok(store)
+    };
+    let mut remote_store = connect()?;
+    let mut reconnected = false;
+    let local_store = FsLocalStore::with_staging_dir(options.staging_dir.clone());
+    let limiter = || {
+        options.bandwidth_limit_mbps.map(|mbps| {
+            let bytes_per_sec = (mbps as u64).saturating_mul(125_000);
+            Mutex::new(BandwidthLimiter::new(bytes_per_sec))
+        })
+    };
+    let total_actions: usize = jobs.iter().map(|job| job.plan.actions.len()).sum();
+    let mut summary = ExecutionSummary::default();
+    let mut completed = 0;
+    let mut transferred: u64 = 0;
+    progress(completed, total_actions.max(1), transferred);
+
+    // Bytes of the action in flight, so progress moves within a large file.
+    let progress = RefCell::new(progress);
+    let finished = Cell::new((0, 0));
+    let in_flight = Cell::new(0u64);
+    let on_chunk = |bytes: usize| {
+        in_flight.set(in_flight.get() + bytes as u64);
+        let (completed, transferred) = finished.get();
+        (progress.borrow_mut())(
+            completed,
+            total_actions.max(1),
+            transferred + in_flight.get(),
+        );
+    };
+
+    // This run supersedes whatever was left of an earlier one for the target.
+    // A dry run changes nothing, so it has nothing to journal either.
+    let journal_dir = options.journal_dir.as_deref().filter(|_| !options.dry_run);
+    if let Some(dir) = journal_dir {
+        ExecutionJournal::discard_target(dir, target.id);
+    }
+
+    for job in jobs {
+        let mut rule_summary = RuleSummary {
+            rule: job.plan.rule.label(),
+            ..RuleSummary::default()
+        };
+        // In the order the executor runs it, so journal indexes and the
+        // restart point after a reconnect line up with what actually ran.
+        let phased = job.plan.phased();
+        let mut journal = journal_dir
+            .filter(|_| !phased.actions.is_empty())
+            .and_then(|dir| JournalWriter::create(dir, job, &phased.actions).ok());
+        // Index of the first action not run yet; moves on after a reconnect.
+        let mut start = 0;
+        let mut transfer_failed = false;
+        loop {
+            let remaining;
+            let plan = if start == 0 {
+                &phased
+            } else {
+                remaining = SyncPlan {
+                    rule: phased.rule.clone(),
+                    actions: phased.actions[start..].to_vec(),
+                    stats: PlanStats::default(),
+                };
+                &remaining
+            };
+            let executor = SyncExecutor::new(&local_store, &remote_store, limiter())
+                .with_stability_window(options.stability_window)
+                .with_chunk_progress(&on_chunk)
+                .with_remote_read_only(target.remote_read_only)
+                .with_conflict_backups(options.backup_conflicts)
+                .with_deletes_held(transfer_failed)
+                .with_deletes_for_review(options.review_deletions)
+                .with_dry_run(options.dry_run);
+            let mut lost_at = None;
+            for (offset, log) in executor.execute_iter(plan).enumerate() {
+                let index = start + offset;
+                // One dead session would fail every action left; stop and reconnect instead.
+                if matches!(log.status, ActionStatus::Failed { .. }) && !remote_store.is_connected()
+                {
+                    lost_at = Some(index);
+                    break;
                 }
-                Component::Prefix(_) => {
-                    current.push(component.as_os_str());
-                    continue;
+                if matches!(log.status, ActionStatus::Failed { .. }) && !log.action.is_delete() {
+                    transfer_failed = true;
+                }
+                summary.ownership_warnings.extend(log.warning);
+                match log.status {
+                    ActionStatus::Applied => {
+                        rule_summary.applied += 1;
+                        if let Some(journal) = journal.as_mut() {
+                            journal.record(index);
+                        }
+                        if let SyncAction::Upload { size, .. } | SyncAction::Download { size, .. } =
+                            log.action
+                        {
+                            transferred = transferred.saturating_add(size);
+                        }
+                    }
+                    ActionStatus::SkippedConflict => rule_summary.skipped += 1,
+                    ActionStatus::Deferred => rule_summary.deferred += 1,
+                    ActionStatus::Refused => rule_summary.refused += 1,
+                    ActionStatus::HeldForReview => {
+                        let snapshot = |index: &FileIndex| {
+                            index.get(log.action.rel_path()).map(SnapshotEntry::from_entry)
+                        };
+                        summary.held_deletions.push(HeldDeletion {
+                            rule: phased.rule.clone(),
+                            local: snapshot(&job.local_index),
+                            remote: snapshot(&job.remote_index),
+                            action: log.action,
+                        });
+                    }
+                    ActionStatus::WouldApply => summary.would_apply.push(ExecutionLog {
+                        rule: log.rule,
+                        action: log.action,
+                        status: ActionStatus::WouldApply,
+                        warning: None,
+                    }),
+                    ActionStatus::Failed { reason, cause } => {
+                        rule_summary.failed += 1;
+                        summary.failures.push(ExecutionFailure {
+                            rule: log.rule,
+                            reason,
+                            cause,
+                        });
+                    }
+                }
+                completed += 1;
+                finished.set((completed, transferred));
+                in_flight.set(0);
+                (progress.borrow_mut())(completed, total_actions.max(1), transferred);
+                // Actions run as the iterator advances, so stop before pulling the next.
+                if options.canceled() {
+                    return Err(anyhow!(
+                        "execution was aborted after {completed} of {total_actions} actions"
+                    ));
                 }
-                Component::CurDir | Component::ParentDir => continue,
-                Component::Normal(part) => current.push(part),
             }
-
-            if current.as_os_str().is_empty() {
-                continue;
+            let Some(index) = lost_at else {
+                break;
+            };
+            in_flight.set(0);
+            let unprocessed = total_actions - completed;
+            if reconnected {
+                return Err(anyhow!(
+                    "connection to {} was lost again; {unprocessed} actions were not processed",
+                    target.host
+                ));
             }
+            reconnected = true;
+            remote_store = connect().with_context(|| {
+                format!(
+                    "connection to {} was lost; {unprocessed} actions were not processed",
+                    target.host
+                )
+            })?;
+            start = index;
+        }
+        if let Some(journal) = journal {
+            journal.finish();
+        }
+        summary.applied += rule_summary.applied;
+        summary.skipped += rule_summary.skipped;
+        summary.deferred += rule_summary.deferred;
+        summary.refused += rule_summary.refused;
+        summary.rules.push(rule_summary);
+    }
 
-            if self.sftp.stat(&current).is_ok() {
-                continue;
+    if summary.failures.is_empty() && !options.dry_run {
+        for command in target.remote_commands.due() {
+            let outcome = remote_store.run_command(command);
+            let succeeded = matches!(&outcome, Ok(outcome) if outcome.exit_code == 0);
+            summary.remote_commands.push(outcome);
+            if !succeeded {
+                break;
             }
+        }
+    }
 
-            self.sftp
-                .mkdir(&current, 0o755)
-                .with_context(|| format!("mkdir {}", current.display()))?;
+    Ok(summary)
+}
+
+/// One job's plan as written when its run started, plus the actions applied
+/// before the run was interrupted.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExecutionJournal {
+    pub session_id: SessionId,
+    pub target_id: TargetId,
+    pub rule: SyncRule,
+    pub actions: Vec<SyncAction>,
+    /// Indexes into `actions`, one journal line each.
+    #[serde(skip)]
+    pub applied: HashSet<usize>,
+}
+
+impl ExecutionJournal {
+    fn path(dir: &Path, session_id: SessionId) -> PathBuf {
+        dir.join(format!("{session_id}.jsonl"))
+    }
+
+    /// Journals left behind by runs that never finished, oldest session first.
+    /// Unreadable files are skipped.
+    pub fn load_all(dir: &Path) -> Vec<Self> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut journals: Vec<Self> = entries
+            .flatten()
+            .filter(|entry| entry.path().extension() == Some(OsStr::new("jsonl")))
+            .filter_map(|entry| Self::read(&entry.path()).ok())
+            .collect();
+        journals.sort_by_key(|journal| journal.session_id);
+        journals
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let mut lines = contents.lines();
+        let header = lines.next().ok_or_else(|| anyhow!("empty journal"))?;
+        let mut journal: Self = serde_json::from_str(header).context("invalid journal header")?;
+        // A line torn by a crash fails to parse and is ignored; that action reruns.
+        journal.applied = lines.filter_map(|line| line.trim().parse().ok()).collect();
+        Ok(journal)
+    }
+
+    pub fn discard(dir: &Path, session_id: SessionId) {
+        let _ = fs::remove_file(Self::path(dir, session_id));
+    }
+
+    fn discard_target(dir: &Path, target_id: TargetId) {
+        for journal in Self::load_all(dir) {
+            if journal.target_id == target_id {
+                Self::discard(dir, journal.session_id);
+            }
         }
+    }
 
-        Ok(())
+    pub fn remaining(&self) -> Vec<SyncAction> {
+        self.actions
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !self.applied.contains(index))
+            .map(|(_, action)| action.clone())
+            .collect()
     }
-}
 
-fn newer(lhs: SystemTime, rhs: SystemTime) -> bool {
-    const SKEW: Duration = Duration::from_millis(500);
-    lhs.duration_since(rhs)
-        .map(|delta| delta > SKEW)
-        .unwrap_or(false)
+    /// The unapplied rest of the job, under its original session id.
+    pub fn into_sync_job(self) -> SyncJob {
+        let actions = self.remaining();
+        SyncJob {
+            id: self.session_id,
+            target_id: self.target_id,
+            rule: self.rule.clone(),
+            local_index: FileIndex::new(),
+            remote_index: FileIndex::new(),
+            plan: SyncPlan {
+                rule: self.rule,
+                actions,
+                stats: PlanStats::default(),
+            },
+            created_at: SystemTime::now(),
+        }
+    }
 }
 
-pub struct SyncExecutor<'a, L: LocalStore, R: RemoteStore> {
-    local: &'a L,
-    remote: &'a R,
-    limiter: Option<Mutex<BandwidthLimiter>>,
+/// Appends to a job's journal as actions are applied; removed once the job
+/// runs to the end, so only interrupted runs leave a file behind.
+struct JournalWriter {
+    path: PathBuf,
+    file: fs::File,
 }
 
-#[derive(Clone, Debug)]
-pub enum ActionStatus {
-    Applied,
-    SkippedConflict,
-    Failed(String),
+impl JournalWriter {
+    fn create(dir: &Path, job: &SyncJob, actions: &[SyncAction]) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        let header = ExecutionJournal {
+            session_id: job.id,
+            target_id: job.target_id,
+            rule: job.rule.clone(),
+            actions: actions.to_vec(),
+            applied: HashSet::new(),
+        };
+        let path = ExecutionJournal::path(dir, job.id);
+        let mut file = fs::File::create(&path)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        serde_json::to_writer(&mut file, &header).context("failed to serialize journal")?;
+        file.write_all(b"\n")?;
+        Ok(Self { path, file })
+    }
+
+    fn record(&mut self, index: usize) {
+        // A missed line only means the action is repeated on resume.
+        let _ = writeln!(self.file, "{index}");
+    }
+
+    fn finish(self) {
+        let _ = fs::remove_file(&self.path);
+    }
 }
 
-#[derive(Clone, Debug)]
-pub struct ExecutionLog {
-    pub action: SyncAction,
-    pub status: ActionStatus,
+pub struct SftpRemoteStore {
+    session: ssh2::Session,
+    sftp: Sftp,
+    /// Stat entries that `readdir` returned incomplete; see `lacks_stats`.
+    stat_fallback: bool,
+    missing_stats: AtomicUsize,
+    /// Shared with the pool on a pooled session.
+    _slot: Arc<HostSlot>,
 }
 
-impl<'a, L: LocalStore, R: RemoteStore> SyncExecutor<'a, L, R> {
-    fn new(
-        local: &'a L,
-        remote: &'a R,
-        limiter: Option<Mutex<BandwidthLimiter>>,
-    ) -> Self {
-        Self {
-            local,
-            remote,
-            limiter,
-        }
+impl SftpRemoteStore {
+    pub fn connect(target: &RemoteTarget) -> Result<Self> {
+        let (session, slot) = connection::establish_session(target)
+            .with_context(|| format!("failed to connect to {}", target.host))?;
+        let sftp = connection::open_channel(|| session.sftp())
+            .context("failed to start SFTP subsystem")?;
+        Ok(Self {
+            session,
+            sftp,
+            stat_fallback: target.stat_fallback,
+            missing_stats: AtomicUsize::new(0),
+            _slot: Arc::new(slot),
+        })
     }
 
-    pub fn execute(&self, plan: &SyncPlan) -> Vec<ExecutionLog> {
-        plan.actions
-            .iter()
-            .map(|action| {
-                let status = match action {
-                    SyncAction::Upload { rel_path, .. } => self
-                        .local
-                        .read_file(&plan.rule.local, rel_path)
-                        .and_then(|bytes| {
-                            let parent = rel_path.parent().unwrap_or(Path::new(""));
-                            self.remote.ensure_dir(&plan.rule.remote, parent)?;
-                            self.throttle(bytes.len());
-                            self.remote.write_file(&plan.rule.remote, rel_path, &bytes)
-                        })
-                        .map(|_| ActionStatus::Applied)
-                        .unwrap_or_else(|err| ActionStatus::Failed(err.to_string())),
-                    SyncAction::Download { rel_path, .. } => self
-                        .remote
-                        .read_file(&plan.rule.remote, rel_path)
-                        .and_then(|bytes| {
-                            let parent = rel_path.parent().unwrap_or(Path::new(""));
-                            self.local.ensure_dir(&plan.rule.local, parent)?;
-                            self.throttle(bytes.len());
-                            self.local.write_file(&plan.rule.local, rel_path, &bytes)
-                        })
-                        .map(|_| ActionStatus::Applied)
-                        .unwrap_or_else(|err| ActionStatus::Failed(err.to_string())),
-                    SyncAction::DeleteRemote { rel_path } => self
-                        .remote
-                        .remove_file(&plan.rule.remote, rel_path)
-                        .map(|_| ActionStatus::Applied)
-                        .unwrap_or_else(|err| ActionStatus::Failed(err.to_string())),
-                    SyncAction::DeleteLocal { rel_path } => self
-                        .local
-                        .remove_file(&plan.rule.local, rel_path)
-                        .map(|_| ActionStatus::Applied)
-                        .unwrap_or_else(|err| ActionStatus::Failed(err.to_string())),
-                    SyncAction::Conflict { .. } => ActionStatus::SkippedConflict,
-                };
+    /// A channel on the target's pooled session, shared with other plans and
+    /// health checks. Don't change session-wide settings such as keepalives
+    /// on it.
+    pub fn borrow(target: &RemoteTarget) -> Result<Self> {
+        let (session, sftp, slot) = pool::borrow(target)
+            .with_context(|| format!("failed to connect to {}", target.host))?;
+        Ok(Self {
+            session,
+            sftp,
+            stat_fallback: target.stat_fallback,
+            missing_stats: AtomicUsize::new(0),
+            _slot: slot,
+        })
+    }
 
-                ExecutionLog {
-                    action: action.clone(),
-                    status,
-                }
-            })
-            .collect()
+    /// Entries listed so far whose `readdir` stats were incomplete, whether
+    /// or not they were looked up again.
+    pub fn missing_stats(&self) -> usize {
+        self.missing_stats.load(Ordering::Relaxed)
     }
 
-    fn throttle(&self, bytes: usize) {
-        if let Some(limiter) = &self.limiter {
-            if let Ok(mut guard) = limiter.lock() {
-                guard.consume(bytes as u64);
-            }
+    fn df_available(&self, root: &Path) -> Option<u64> {
+        let mut channel = connection::open_channel(|| self.session.channel_session()).ok()?;
+        let command = format!("df -Pk {}", shell_quote(root));
+        channel.exec(&command).ok()?;
+        let mut output = String::new();
+        channel.read_to_string(&mut output).ok()?;
+        let _ = channel.wait_close();
+        parse_df_available(&output)
+    }
+
+    /// Runs `command` through the login shell on the server, over this
+    /// session, and waits for it to exit.
+    pub fn run_command(&self, command: &str) -> Result<RemoteCommandOutcome> {
+        let mut channel = connection::open_channel(|| self.session.channel_session())
+            .context("failed to open a command channel")?;
+        channel
+            .exec(command)
+            .with_context(|| format!("failed to run `{command}`"))?;
+        let read = |stream: &mut dyn Read| -> Result<String> {
+            let mut bytes = Vec::new();
+            stream
+                .read_to_end(&mut bytes)
+                .with_context(|| format!("failed to read the output of `{command}`"))?;
+            bytes.truncate(COMMAND_OUTPUT_LIMIT);
+            Ok(String::from_utf8_lossy(&bytes).trim().to_string())
+        };
+        let stdout = read(&mut channel)?;
+        let stderr = read(&mut channel.stderr())?;
+        channel
+            .wait_close()
+            .with_context(|| format!("`{command}` did not finish"))?;
+        let exit_code = channel
+            .exit_status()
+            .with_context(|| format!("no exit status for `{command}`"))?;
+        Ok(RemoteCommandOutcome {
+            command: command.to_string(),
+            exit_code,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Whether the session still answers requests. A dropped connection
+    /// fails within the socket timeout instead of hanging.
+    pub fn is_connected(&self) -> bool {
+        self.sftp.realpath(Path::new(".")).is_ok()
+    }
+
+    /// Asks libssh2 to send keepalives once the connection has been idle for
+    /// `interval`; they go out from `RemoteStore::keepalive`.
+    pub fn set_keepalive(&self, interval: Duration) {
+        let secs = interval.as_secs().clamp(1, u32::MAX as u64) as u32;
+        self.session.set_keepalive(true, secs);
+    }
+
+    /// Fails unless `dir` is a listable directory and, with `write`, one a
+    /// file can be created in. The probe file is removed again.
+    fn probe_dir(&self, dir: &Path, write: bool) -> Result<()> {
+        let stat = self
+            .sftp
+            .stat(dir)
+            .with_context(|| format!("{} does not exist or is not accessible", dir.display()))?;
+        if !stat.is_dir() {
+            return Err(anyhow!("{} is not a folder", dir.display()));
+        }
+        self.sftp
+            .opendir(dir)
+            .with_context(|| format!("cannot list {}", dir.display()))?;
+        if write {
+            let probe = dir.join(format!(".sftp-sync-probe-{}", std::process::id()));
+            self.sftp
+                .create(&probe)
+                .with_context(|| format!("cannot create files in {}", dir.display()))?;
+            self.sftp
+                .unlink(&probe)
+                .with_context(|| format!("failed to remove probe file {}", probe.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Where the remote folder picker opens: the target's base path, anchored
+    /// at the login directory when relative or empty.
+    pub fn browse_root(&self, target: &RemoteTarget) -> PathBuf {
+        let start_dir = self.start_dir().ok().flatten();
+        if target.base_path.as_os_str().is_empty() {
+            return start_dir.unwrap_or_else(|| PathBuf::from("/"));
+        }
+        resolved_remote_root(target, Path::new(""), start_dir.as_deref())
+    }
+
+    /// Sorted names of the subdirectories of `dir`.
+    pub fn list_dirs(&self, dir: &Path) -> Result<Vec<String>> {
+        let entries = self
+            .sftp
+            .readdir(dir)
+            .with_context(|| format!("failed to read {}", dir.display()))?;
+        let mut names: Vec<String> = entries
+            .into_iter()
+            .filter(|(_, stat)| stat.is_dir())
+            .filter_map(|(path, _)| Some(path.file_name()?.to_string_lossy().to_string()))
+            .filter(|name| name != "." && name != "..")
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    fn collect_entries(
+        &self,
+        root: &Path,
+        rel_path: &Path,
+        options: &ListOptions,
+        out: &mut Listing,
+        on_listed: ListProgress<'_>,
+    ) -> Result<()> {
+        options.check_limits(root, rel_path, out)?;
+        let dir_path = if rel_path.as_os_str().is_empty() {
+            root.to_path_buf()
+        } else {
+            root.join(rel_path)
+        };
+
+        let dir_entries = match self.sftp.readdir(&dir_path) {
+            Ok(entries) => entries,
+            Err(err) if !rel_path.as_os_str().is_empty() => {
+                out.skipped.push((rel_path.to_path_buf(), err.to_string()));
+                return Ok(());
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", dir_path.display()));
+            }
+        };
+
+        for (entry_path, mut stat) in dir_entries {
+            let Some(name) = entry_path.file_name() else {
+                continue;
+            };
+
+            if name == OsStr::new(".") || name == OsStr::new("..") || options.skips(name) {
+                continue;
+            }
+
+            if lacks_stats(&stat) {
+                self.missing_stats.fetch_add(1, Ordering::Relaxed);
+                // lstat, so a symlink is still seen as one.
+                if self.stat_fallback
+                    && let Ok(full) = self.sftp.lstat(&dir_path.join(name))
+                {
+                    stat = full;
+                }
+            }
+
+            let child_rel = if rel_path.as_os_str().is_empty() {
+                PathBuf::from(name)
+            } else {
+                rel_path.join(name)
+            };
+
+            let file_type = stat.file_type();
+            if file_type.is_dir() {
+                self.collect_entries(root, &child_rel, options, out, &mut *on_listed)?;
+            } else if file_type.is_file() {
+                out.entries
+                    .push(remote_file_entry(child_rel, EntryKind::File, &stat));
+                if !on_listed(out.entries.len()) {
+                    return Err(ListingCanceled.into());
+                }
+            } else if file_type.is_symlink() {
+                if !options.follow_symlinks {
+                    continue;
+                }
+                let link_path = dir_path.join(name);
+                match self.sftp.stat(&link_path) {
+                    Ok(target) if target.is_file() => {
+                        out.entries
+                            .push(remote_file_entry(child_rel, EntryKind::Symlink, &target));
+                    }
+                    Ok(_) => redact::eprint(format!(
+                        "skipping symlink {} (target is not a regular file)",
+                        link_path.display()
+                    )),
+                    Err(_) => redact::eprint(format!(
+                        "skipping dangling symlink {}",
+                        link_path.display()
+                    )),
+                }
+            } else {
+                redact::eprint(format!("skipping special file {}", dir_path.join(name).display()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn absolute_path(&self, root: &Path, rel_path: &Path) -> PathBuf {
+        if rel_path.as_os_str().is_empty() {
+            root.to_path_buf()
+        } else if rel_path.is_absolute() {
+            rel_path.to_path_buf()
+        } else {
+            root.join(rel_path)
+        }
+    }
+}
+
+impl RemoteStore for SftpRemoteStore {
+    fn list(&self, root: &Path, options: &ListOptions) -> Result<Listing> {
+        self.list_with_progress(root, options, &mut |_| true)
+    }
+
+    fn list_with_progress(
+        &self,
+        root: &Path,
+        options: &ListOptions,
+        on_listed: ListProgress<'_>,
+    ) -> Result<Listing> {
+        let mut listing = Listing::default();
+        self.collect_entries(root, Path::new(""), options, &mut listing, on_listed)?;
+        Ok(listing)
+    }
+
+    fn read_file(&self, root: &Path, rel_path: &Path) -> Result<Vec<u8>> {
+        self.read_file_chunked(root, rel_path, &mut |_| {})
+    }
+
+    fn write_file(&self, root: &Path, rel_path: &Path, bytes: &[u8]) -> Result<()> {
+        self.write_file_chunked(root, rel_path, bytes, &mut |_| {})
+    }
+
+    fn read_file_chunked(
+        &self,
+        root: &Path,
+        rel_path: &Path,
+        on_chunk: ChunkProgress<'_>,
+    ) -> Result<Vec<u8>> {
+        let path = self.absolute_path(root, rel_path);
+        let mut file = self
+            .sftp
+            .open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        let mut buf = Vec::new();
+        let mut chunk = vec![0; TRANSFER_CHUNK];
+        loop {
+            let read = file
+                .read(&mut chunk)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            if read == 0 {
+                return Ok(buf);
+            }
+            buf.extend_from_slice(&chunk[..read]);
+            on_chunk(read);
+        }
+    }
+
+    fn write_file_chunked(
+        &self,
+        root: &Path,
+        rel_path: &Path,
+        bytes: &[u8],
+        on_chunk: ChunkProgress<'_>,
+    ) -> Result<()> {
+        let path = self.absolute_path(root, rel_path);
+        if let Some(parent) = rel_path.parent() {
+            self.ensure_dir(root, parent)?;
+        }
+        let mut file = self
+            .sftp
+            .open_mode(
+                &path,
+                OpenFlags::WRITE | OpenFlags::TRUNCATE | OpenFlags::CREATE,
+                0o644,
+                OpenType::File,
+            )
+            .with_context(|| format!("failed to open {} for write", path.display()))?;
+        for chunk in bytes.chunks(TRANSFER_CHUNK) {
+            on_chunk(chunk.len());
+            file.write_all(chunk)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn checksum(&self, root: &Path, rel_path: &Path) -> Result<String> {
+        // Hashing on the server spares the download; without `sha256sum` read it back.
+        match self.checksum_in_place(root, rel_path) {
+            Some(hash) => Ok(hash),
+            None => self.read_file(root, rel_path).map(|bytes| content_hash(&bytes)),
+        }
+    }
+
+    fn checksum_in_place(&self, root: &Path, rel_path: &Path) -> Option<String> {
+        let path = self.absolute_path(root, rel_path);
+        let outcome = self
+            .run_command(&format!("sha256sum -- {}", shell_quote(&path)))
+            .ok()?;
+        let hash = outcome.stdout.split_whitespace().next()?;
+        (outcome.exit_code == 0 && hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+            .then(|| hash.to_ascii_lowercase())
+    }
+
+    fn resumes_uploads(&self) -> bool {
+        true
+    }
+
+    fn file_size(&self, root: &Path, rel_path: &Path) -> Option<u64> {
+        let path = self.absolute_path(root, rel_path);
+        self.sftp.stat(&path).ok().and_then(|stat| stat.size)
+    }
+
+    fn write_file_at(
+        &self,
+        root: &Path,
+        rel_path: &Path,
+        offset: u64,
+        bytes: &[u8],
+        on_chunk: ChunkProgress<'_>,
+    ) -> Result<()> {
+        let path = self.absolute_path(root, rel_path);
+        let mut flags = OpenFlags::WRITE | OpenFlags::CREATE;
+        if offset == 0 {
+            flags |= OpenFlags::TRUNCATE;
+        }
+        let mut file = self
+            .sftp
+            .open_mode(&path, flags, 0o644, OpenType::File)
+            .with_context(|| format!("failed to open {} for write", path.display()))?;
+        file.seek(SeekFrom::Start(offset))
+            .with_context(|| format!("failed to seek in {}", path.display()))?;
+        for chunk in bytes.chunks(TRANSFER_CHUNK) {
+            on_chunk(chunk.len());
+            file.write_all(chunk)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn rename_file(&self, root: &Path, from: &Path, to: &Path) -> Result<()> {
+        let from = self.absolute_path(root, from);
+        let to = self.absolute_path(root, to);
+        if self.sftp.rename(&from, &to, None).is_ok() {
+            return Ok(());
+        }
+        // SFTP v3 servers refuse to rename over an existing file.
+        let _ = self.sftp.unlink(&to);
+        self.sftp
+            .rename(&from, &to, None)
+            .with_context(|| format!("failed to move {} to {}", from.display(), to.display()))
+    }
+
+    fn remove_file(&self, root: &Path, rel_path: &Path) -> Result<()> {
+        let path = self.absolute_path(root, rel_path);
+        self.sftp
+            .unlink(&path)
+            .with_context(|| format!("failed to remove {}", path.display()))
+    }
+
+    fn stat(&self, root: &Path, rel_path: &Path) -> Result<Option<FileEntry>> {
+        let path = self.absolute_path(root, rel_path);
+        match self.sftp.stat(&path) {
+            Ok(stat) => Ok(Some(remote_file_entry(
+                rel_path.to_path_buf(),
+                EntryKind::File,
+                &stat,
+            ))),
+            // LIBSSH2_FX_NO_SUCH_FILE
+            Err(err) if err.code() == ssh2::ErrorCode::SFTP(2) => Ok(None),
+            Err(err) => Err(err).with_context(|| format!("failed to stat {}", path.display())),
+        }
+    }
+
+    fn set_owner(&self, root: &Path, rel_path: &Path, uid: u32, gid: u32) -> Result<()> {
+        let path = self.absolute_path(root, rel_path);
+        let stat = ssh2::FileStat {
+            size: None,
+            uid: Some(uid),
+            gid: Some(gid),
+            perm: None,
+            atime: None,
+            mtime: None,
+        };
+        self.sftp
+            .setstat(&path, stat)
+            .with_context(|| format!("failed to change the owner of {}", path.display()))
+    }
+
+    fn ensure_dir(&self, root: &Path, rel_path: &Path) -> Result<()> {
+        let target = self.absolute_path(root, rel_path);
+        let mut current = PathBuf::new();
+
+        for component in target.components() {
+            match component {
+                Component::RootDir => {
+                    current.push(Path::new("/"));
+                    continue;
+                }
+                Component::Prefix(_) => {
+                    current.push(component.as_os_str());
+                    continue;
+                }
+                Component::CurDir | Component::ParentDir => continue,
+                Component::Normal(part) => current.push(part),
+            }
+
+            if current.as_os_str().is_empty() {
+                continue;
+            }
+
+            if self.sftp.stat(&current).is_ok() {
+                continue;
+            }
+
+            self.sftp
+                .mkdir(&current, 0o755)
+                .with_context(|| format!("mkdir {}", current.display()))?;
+        }
+
+        Ok(())
+    }
+
+    fn hard_link(&self, root: &Path, original: &Path, link: &Path) -> Result<()> {
+        let original = self.absolute_path(root, original);
+        let link = self.absolute_path(root, link);
+        // SFTP has no portable hard link request, so this needs a shell with `ln`.
+        let outcome = self.run_command(&format!(
+            "ln -f -- {} {}",
+            shell_quote(&original),
+            shell_quote(&link)
+        ))?;
+        if outcome.exit_code != 0 {
+            return Err(anyhow!(
+                "failed to link {} to {}: {}",
+                link.display(),
+                original.display(),
+                outcome.stderr
+            ));
+        }
+        Ok(())
+    }
+
+    fn keepalive(&self) -> Result<()> {
+        self.session
+            .keepalive_send()
+            .map(|_| ())
+            .context("failed to send keepalive")
+    }
+
+    fn free_space(&self, root: &Path) -> Result<Option<u64>> {
+        // statvfs@openssh.com first; plain `df` covers servers without the extension.
+        if let Ok(stat) = self.sftp.opendir(root).and_then(|mut dir| dir.statvfs()) {
+            return Ok(Some(stat.f_bavail.saturating_mul(stat.f_frsize)));
+        }
+        Ok(self.df_available(root))
+    }
+
+    fn start_dir(&self) -> Result<Option<PathBuf>> {
+        let path = self
+            .sftp
+            .realpath(Path::new("."))
+            .context("failed to resolve the SFTP start directory")?;
+        Ok(Some(path))
+    }
+}
+
+/// Single-quotes `path` for a POSIX shell.
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', "'\\''"))
+}
+
+/// `text` as one shell comment line. Control characters are escaped, since a
+/// newline in a file or target name would otherwise end the comment and turn
+/// the rest into a command.
+fn comment(text: &str) -> String {
+    let mut line = String::from("# ");
+    for c in text.chars() {
+        if c.is_control() {
+            line.extend(c.escape_default());
+        } else {
+            line.push(c);
+        }
+    }
+    line
+}
+
+/// Minimal servers may leave attributes out of `readdir` replies. Without a
+/// type the entry can't be walked; a file without size or mtime would
+/// compare as empty and dated 1970.
+fn lacks_stats(stat: &ssh2::FileStat) -> bool {
+    stat.perm.is_none() || (!stat.is_dir() && (stat.size.is_none() || stat.mtime.is_none()))
+}
+
+fn remote_file_entry(path: PathBuf, kind: EntryKind, stat: &ssh2::FileStat) -> FileEntry {
+    FileEntry {
+        path,
+        kind,
+        size: stat.size.unwrap_or(0),
+        modified: stat
+            .mtime
+            .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap_or(SystemTime::UNIX_EPOCH),
+    }
+}
+
+fn on_local_clock(time: SystemTime, skew: i64) -> SystemTime {
+    let offset = Duration::from_secs(skew.unsigned_abs());
+    let shifted = if skew >= 0 {
+        time.checked_sub(offset)
+    } else {
+        time.checked_add(offset)
+    };
+    shifted.unwrap_or(time)
+}
+
+fn newer(lhs: SystemTime, rhs: SystemTime) -> bool {
+    const SKEW: Duration = Duration::from_millis(500);
+    lhs.duration_since(rhs)
+        .map(|delta| delta > SKEW)
+        .unwrap_or(false)
+}
+
+pub struct SyncExecutor<'a, L: LocalStore, R: RemoteStore> {
+    local: &'a L,
+    remote: &'a R,
+    limiter: Option<Mutex<BandwidthLimiter>>,
+    stability_window: Option<Duration>,
+    /// Told about every transferred chunk, after the limiter let it through.
+    on_chunk: Option<&'a dyn Fn(usize)>,
+    /// Refuse every remote write, on top of each rule's own flag.
+    remote_read_only: bool,
+    /// Save the losing copy of a resolved conflict before overwriting it.
+    conflict_backups: bool,
+    /// A transfer of this job already failed, so its deletions must not run.
+    deletes_held: bool,
+    /// Deletions are handed back for review instead of being applied.
+    deletes_for_review: bool,
+    /// Decide every action as usual but stop short of applying it.
+    dry_run: bool,
+}
+
+/// A local file locked by another process (a Windows sharing violation).
+/// Uploads hitting it are deferred to the next run instead of failing.
+#[derive(Debug)]
+pub struct FileInUse(pub PathBuf);
+
+impl fmt::Display for FileInUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is in use by another process", self.0.display())
+    }
+}
+
+impl std::error::Error for FileInUse {}
+
+fn is_sharing_violation(err: &io::Error) -> bool {
+    // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION.
+    cfg!(windows) && matches!(err.raw_os_error(), Some(32 | 33))
+}
+
+#[derive(Clone, Debug)]
+pub enum ActionStatus {
+    Applied,
+    SkippedConflict,
+    /// Left for the next run, e.g. the file was still being written.
+    Deferred,
+    /// Not attempted at all, e.g. a write to a read-only remote.
+    Refused,
+    /// A deletion left for the user to approve after the run.
+    HeldForReview,
+    /// What a dry run reports where the action would have been applied.
+    WouldApply,
+    Failed {
+        reason: String,
+        cause: String,
+    },
+}
+
+impl ActionStatus {
+    fn failed(err: anyhow::Error) -> Self {
+        Self::Failed {
+            reason: err.to_string(),
+            cause: err.root_cause().to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ExecutionLog {
+    /// `SyncRule::label` of the plan this action came from.
+    pub rule: String,
+    pub action: SyncAction,
+    pub status: ActionStatus,
+    /// A side effect that didn't stick although the action did, e.g. the
+    /// server refused to change an upload's owner.
+    pub warning: Option<String>,
+}
+
+impl<'a, L: LocalStore, R: RemoteStore> SyncExecutor<'a, L, R> {
+    fn new(
+        local: &'a L,
+        remote: &'a R,
+        limiter: Option<Mutex<BandwidthLimiter>>,
+    ) -> Self {
+        Self {
+            local,
+            remote,
+            limiter,
+            stability_window: None,
+            on_chunk: None,
+            remote_read_only: false,
+            conflict_backups: false,
+            deletes_held: false,
+            deletes_for_review: false,
+            dry_run: false,
+        }
+    }
+
+    pub fn with_stability_window(mut self, window: Option<Duration>) -> Self {
+        self.stability_window = window;
+        self
+    }
+
+    pub fn with_chunk_progress(mut self, on_chunk: &'a dyn Fn(usize)) -> Self {
+        self.on_chunk = Some(on_chunk);
+        self
+    }
+
+    pub fn with_remote_read_only(mut self, read_only: bool) -> Self {
+        self.remote_read_only = read_only;
+        self
+    }
+
+    pub fn with_conflict_backups(mut self, backups: bool) -> Self {
+        self.conflict_backups = backups;
+        self
+    }
+
+    pub fn with_deletes_held(mut self, held: bool) -> Self {
+        self.deletes_held = held;
+        self
+    }
+
+    pub fn with_deletes_for_review(mut self, review: bool) -> Self {
+        self.deletes_for_review = review;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn execute(&self, plan: &SyncPlan) -> Vec<ExecutionLog> {
+        self.execute_iter(plan).collect()
+    }
+
+    /// Lazily applies the plan, one action per item, so callers can report
+    /// progress as each action finishes. Actions run in `SyncPlan::phased`
+    /// order, and once a transfer fails the deletions are deferred, so a
+    /// failed copy never costs the original.
+    pub fn execute_iter<'p>(
+        &'p self,
+        plan: &'p SyncPlan,
+    ) -> impl Iterator<Item = ExecutionLog> + 'p {
+        let rule = plan.rule.label();
+        // Remote path of the first upload of each hard-linked local file.
+        let mut linked: HashMap<(u64, u64), PathBuf> = HashMap::new();
+        let mut transfer_failed = self.deletes_held;
+        let (transfers, deletes): (Vec<_>, Vec<_>) =
+            plan.actions.iter().partition(|action| !action.is_delete());
+        let unsettled = self.unsettled_uploads(plan);
+        transfers
+            .into_iter()
+            .chain(deletes)
+            .map(move |action| {
+                // Cheap when nothing is due; a failed keepalive surfaces on the next transfer.
+                let _ = self.remote.keepalive();
+                let mut warning = None;
+                let status = match action {
+                    _ if transfer_failed && action.is_delete() => ActionStatus::Deferred,
+                    _ if self.deletes_for_review && action.is_delete() => {
+                        ActionStatus::HeldForReview
+                    }
+                    // Checked before anything else so no plan, forced or not, gets past it.
+                    SyncAction::Upload { .. } | SyncAction::DeleteRemote { .. }
+                        if self.remote_locked(&plan.rule) =>
+                    {
+                        ActionStatus::Refused
+                    }
+                    SyncAction::Conflict { .. }
+                        if plan.rule.conflict_policy == ConflictPolicy::KeepLocal
+                            && self.remote_locked(&plan.rule) =>
+                    {
+                        ActionStatus::Refused
+                    }
+                    SyncAction::Upload { rel_path, .. } if unsettled.contains(rel_path) => {
+                        ActionStatus::Deferred
+                    }
+                    // Past every check a real run makes, so the outcome matches one.
+                    SyncAction::Conflict { .. }
+                        if self.dry_run && plan.rule.conflict_policy == ConflictPolicy::Skip =>
+                    {
+                        ActionStatus::SkippedConflict
+                    }
+                    _ if self.dry_run => ActionStatus::WouldApply,
+                    SyncAction::Upload {
+                        rel_path, renamed, ..
+                    } => {
+                        let remote_rel = renamed
+                            .clone()
+                            .unwrap_or_else(|| plan.rule.remote_rel_path(rel_path));
+                        let link_id = plan
+                            .rule
+                            .hard_links
+                            .then(|| self.local.link_id(&plan.rule.local, rel_path))
+                            .flatten();
+                        let original = link_id.and_then(|id| linked.get(&id));
+                        // Servers without `ln` get a plain upload instead.
+                        if original.is_some_and(|original| {
+                            self.link_remote(&plan.rule, original, &remote_rel).is_ok()
+                        }) {
+                            ActionStatus::Applied
+                        } else {
+                            let status = self.upload(&plan.rule, rel_path, &remote_rel);
+                            if matches!(status, ActionStatus::Applied) && plan.rule.preserve_ownership
+                            {
+                                warning = self.copy_ownership(&plan.rule, rel_path, &remote_rel);
+                            }
+                            if let (Some(id), ActionStatus::Applied) = (link_id, &status) {
+                                linked.entry(id).or_insert(remote_rel);
+                            }
+                            status
+                        }
+                    }
+                    SyncAction::Download {
+                        rel_path, renamed, ..
+                    } => {
+                        let local_rel = renamed.as_deref().unwrap_or(rel_path);
+                        self.download(&plan.rule, rel_path, local_rel)
+                    }
+                    SyncAction::DeleteRemote { rel_path } => self
+                        .remote
+                        .remove_file(&plan.rule.remote, &plan.rule.remote_rel_path(rel_path))
+                        .map(|_| ActionStatus::Applied)
+                        .unwrap_or_else(ActionStatus::failed),
+                    SyncAction::DeleteLocal { rel_path } => self
+                        .local
+                        .remove_file(&plan.rule.local, rel_path)
+                        .map(|_| ActionStatus::Applied)
+                        .unwrap_or_else(ActionStatus::failed),
+                    SyncAction::Conflict { rel_path } => match plan.rule.conflict_policy {
+                        ConflictPolicy::Skip => ActionStatus::SkippedConflict,
+                        ConflictPolicy::KeepLocal => self.keep_local(&plan.rule, rel_path),
+                        ConflictPolicy::KeepRemote => self.keep_remote(&plan.rule, rel_path),
+                    },
+                };
+                if matches!(status, ActionStatus::Failed { .. }) && !action.is_delete() {
+                    transfer_failed = true;
+                }
+
+                ExecutionLog {
+                    rule: rule.clone(),
+                    action: action.clone(),
+                    status,
+                    warning,
+                }
+            })
+    }
+
+    fn upload(&self, rule: &SyncRule, rel_path: &Path, remote_rel: &Path) -> ActionStatus {
+        self.local
+            .read_file(&rule.local, rel_path)
+            .and_then(|bytes| {
+                let bytes = normalize_line_endings(&bytes, rule.line_endings);
+                let parent = remote_rel.parent().unwrap_or(Path::new(""));
+                self.remote.ensure_dir(&rule.remote, parent)?;
+                if self.remote.resumes_uploads() && bytes.len() >= RESUMABLE_UPLOAD_MIN {
+                    return self.upload_resumable(rule, remote_rel, &bytes);
+                }
+                self.remote
+                    .write_file_chunked(&rule.remote, remote_rel, &bytes, &mut |len| {
+                        self.transfer_chunk(len)
+                    })
+            })
+            .map(|_| ActionStatus::Applied)
+            .unwrap_or_else(|err| {
+                if err.downcast_ref::<FileInUse>().is_some() {
+                    ActionStatus::Deferred
+                } else {
+                    ActionStatus::failed(err)
+                }
+            })
+    }
+
+    /// Uploads `bytes` through a staged copy that is renamed into place once
+    /// complete. A staged copy left by an interrupted attempt is continued
+    /// from where it stopped instead of being sent again.
+    fn upload_resumable(&self, rule: &SyncRule, remote_rel: &Path, bytes: &[u8]) -> Result<()> {
+        let staged = upload_staging_path(remote_rel);
+        let offset = self.resume_offset(&rule.remote, &staged, bytes);
+        self.remote.write_file_at(
+            &rule.remote,
+            &staged,
+            offset as u64,
+            &bytes[offset..],
+            &mut |len| self.transfer_chunk(len),
+        )?;
+        // Only a complete copy replaces the destination. A resumed one must
+        // also hash like the source where the server can tell without a
+        // download; a fresh one was written in full by this very call.
+        let complete = self.remote.file_size(&rule.remote, &staged) == Some(bytes.len() as u64);
+        let intact = offset == 0
+            || self
+                .remote
+                .checksum_in_place(&rule.remote, &staged)
+                .is_none_or(|hash| hash == content_hash(bytes));
+        if !complete || !intact {
+            let _ = self.remote.remove_file(&rule.remote, &staged);
+            return Err(anyhow!(
+                "staged copy of {} does not match the local file",
+                remote_rel.display()
+            ));
+        }
+        self.remote.rename_file(&rule.remote, &staged, remote_rel)
+    }
+
+    /// How much of `bytes` the staged copy already holds. Its whole content
+    /// has to hash like the same prefix of the source, so a leftover from an
+    /// older version of the file starts over rather than being spliced into
+    /// the new one. Servers that can't hash in place start over too, since
+    /// checking the prefix would mean downloading it.
+    fn resume_offset(&self, root: &Path, staged: &Path, bytes: &[u8]) -> usize {
+        let Some(len) = self.remote.file_size(root, staged) else {
+            return 0;
+        };
+        let len = len as usize;
+        if len == 0 || len > bytes.len() {
+            return 0;
+        }
+        match self.remote.checksum_in_place(root, staged) {
+            Some(hash) if hash == content_hash(&bytes[..len]) => len,
+            _ => 0,
+        }
+    }
+
+    /// Gives an upload the local file's owner and group. What the platform or
+    /// server can't carry over comes back as a warning; the upload stands.
+    fn copy_ownership(&self, rule: &SyncRule, rel_path: &Path, remote_rel: &Path) -> Option<String> {
+        let Some(ownership) = self.local.ownership(&rule.local, rel_path) else {
+            return Some(format!(
+                "{}: file ownership is not available on this system",
+                rel_path.display()
+            ));
+        };
+        let mut problems = Vec::new();
+        if let Err(err) =
+            self.remote
+                .set_owner(&rule.remote, remote_rel, ownership.uid, ownership.gid)
+        {
+            problems.push(format!("{err:#}"));
+        }
+        if !ownership.xattrs.is_empty() {
+            problems.push(format!(
+                "extended attributes {} can't be set over SFTP",
+                ownership.xattrs.join(", ")
+            ));
+        }
+        (!problems.is_empty())
+            .then(|| format!("{}: {}", remote_rel.display(), problems.join("; ")))
+    }
+
+    fn download(&self, rule: &SyncRule, rel_path: &Path, local_rel: &Path) -> ActionStatus {
+        self.remote
+            .read_file_chunked(&rule.remote, rel_path, &mut |len| self.transfer_chunk(len))
+            .and_then(|bytes| {
+                let parent = local_rel.parent().unwrap_or(Path::new(""));
+                self.local.ensure_dir(&rule.local, parent)?;
+                let bytes = normalize_line_endings(&bytes, rule.line_endings);
+                self.local.write_file(&rule.local, local_rel, &bytes)
+            })
+            .map(|_| ActionStatus::Applied)
+            .unwrap_or_else(ActionStatus::failed)
+    }
+
+    /// Resolves a conflict for the local copy, saving the remote one next to
+    /// it first when backups are on.
+    fn keep_local(&self, rule: &SyncRule, rel_path: &Path) -> ActionStatus {
+        let remote_rel = rule.remote_rel_path(rel_path);
+        if self.conflict_backups {
+            let backup = conflict_backup_path(&remote_rel, SystemTime::now());
+            let saved = self
+                .remote
+                .read_file(&rule.remote, &remote_rel)
+                .and_then(|bytes| self.remote.write_file(&rule.remote, &backup, &bytes))
+                .context("failed to back up the remote copy");
+            if let Err(err) = saved {
+                return ActionStatus::failed(err);
+            }
+        }
+        self.upload(rule, rel_path, &remote_rel)
+    }
+
+    /// Resolves a conflict for the remote copy, saving the local one next to
+    /// it first when backups are on.
+    fn keep_remote(&self, rule: &SyncRule, rel_path: &Path) -> ActionStatus {
+        if self.conflict_backups {
+            let backup = conflict_backup_path(rel_path, SystemTime::now());
+            let saved = self
+                .local
+                .read_file(&rule.local, rel_path)
+                .and_then(|bytes| self.local.write_file(&rule.local, &backup, &bytes))
+                .context("failed to back up the local copy");
+            if let Err(err) = saved {
+                return ActionStatus::failed(err);
+            }
+        }
+        self.download(rule, rel_path, rel_path)
+    }
+
+    fn remote_locked(&self, rule: &SyncRule) -> bool {
+        self.remote_read_only || rule.remote_read_only
+    }
+
+    fn link_remote(&self, rule: &SyncRule, original: &Path, link: &Path) -> Result<()> {
+        let parent = link.parent().unwrap_or(Path::new(""));
+        self.remote.ensure_dir(&rule.remote, parent)?;
+        self.remote.hard_link(&rule.remote, original, link)
+    }
+
+    /// Uploads whose source is still being written. Checked for the whole plan
+    /// up front, so fresh files share one wait instead of taking one each.
+    fn unsettled_uploads(&self, plan: &SyncPlan) -> HashSet<PathBuf> {
+        let Some(window) = self.stability_window else {
+            return HashSet::new();
+        };
+        // Refused anyway, so not worth the wait.
+        if self.remote_locked(&plan.rule) {
+            return HashSet::new();
+        }
+        let uploads: Vec<&Path> = plan
+            .actions
+            .iter()
+            .filter_map(|action| match action {
+                SyncAction::Upload { rel_path, .. } => Some(rel_path.as_path()),
+                _ => None,
+            })
+            .collect();
+        self.local.unsettled(&plan.rule.local, &uploads, window)
+    }
+
+    fn transfer_chunk(&self, bytes: usize) {
+        if let Some(limiter) = &self.limiter
+            && let Ok(mut guard) = limiter.lock()
+        {
+            guard.consume(bytes as u64);
+        }
+        if let Some(on_chunk) = self.on_chunk {
+            on_chunk(bytes);
+        }
+    }
+}
+
+/// Token bucket fed once per chunk. It holds at most one chunk of credit, so
+/// the average rate stays at the limit even over short transfers.
+struct BandwidthLimiter {
+    limit_per_sec: f64,
+    allowance: f64,
+    last_check: Instant,
+    clock: Box<dyn LimiterClock>,
+}
+
+/// Where the limiter reads the time and waits; tests substitute a clock that
+/// records the waits instead of sleeping.
+trait LimiterClock: Send {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+struct SystemClock;
+
+impl LimiterClock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+impl BandwidthLimiter {
+    const BURST: f64 = TRANSFER_CHUNK as f64;
+
+    fn new(limit_bytes_per_sec: u64) -> Self {
+        Self::with_clock(limit_bytes_per_sec, Box::new(SystemClock))
+    }
+
+    fn with_clock(limit_bytes_per_sec: u64, clock: Box<dyn LimiterClock>) -> Self {
+        Self {
+            limit_per_sec: limit_bytes_per_sec as f64,
+            allowance: Self::BURST,
+            last_check: clock.now(),
+            clock,
+        }
+    }
+
+    /// Sleeps until `bytes` fit under the limit. The wait is booked as debt
+    /// that the next call pays off from the elapsed time.
+    fn consume(&mut self, bytes: u64) {
+        let now = self.clock.now();
+        let elapsed = now.duration_since(self.last_check).as_secs_f64();
+        self.last_check = now;
+        self.allowance = (self.allowance + elapsed * self.limit_per_sec).min(Self::BURST);
+        self.allowance -= bytes as f64;
+
+        let sleep_seconds = -self.allowance / self.limit_per_sec;
+        if sleep_seconds.is_finite() && sleep_seconds > 0.0 {
+            self.clock.sleep(Duration::from_secs_f64(sleep_seconds));
+        }
+    }
+}
+
+/// File contents and modification time, keyed by absolute remote path.
+type MemoryEntries = HashMap<PathBuf, (Vec<u8>, SystemTime)>;
+
+#[allow(dead_code)]
+#[derive(Default, Clone)]
+pub struct InMemoryRemote {
+    entries: Arc<Mutex<MemoryEntries>>,
+}
+
+#[allow(dead_code)]
+impl InMemoryRemote {
+    fn now() -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+impl RemoteStore for InMemoryRemote {
+    fn list(&self, _root: &Path, options: &ListOptions) -> Result<Listing> {
+        let entries = self.entries.lock().unwrap();
+        let entries = entries
+            .iter()
+            .filter(|(path, _)| {
+                !path
+                    .components()
+                    .any(|component| options.skips(component.as_os_str()))
+            })
+            .map(|(path, (bytes, modified))| FileEntry {
+                path: path.clone(),
+                kind: EntryKind::File,
+                size: bytes.len() as u64,
+                modified: *modified,
+            })
+            .collect();
+        Ok(Listing {
+            entries,
+            skipped: Vec::new(),
+        })
+    }
+
+    fn read_file(&self, _root: &Path, rel_path: &Path) -> Result<Vec<u8>> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(rel_path)
+            .map(|(bytes, _)| bytes.clone())
+            .with_context(|| format!("remote missing {}", rel_path.display()))
+    }
+
+    fn write_file(&self, _root: &Path, rel_path: &Path, bytes: &[u8]) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(rel_path.to_path_buf(), (bytes.to_vec(), Self::now()));
+        Ok(())
+    }
+
+    fn remove_file(&self, _root: &Path, rel_path: &Path) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(rel_path);
+        Ok(())
+    }
+
+    fn ensure_dir(&self, _root: &Path, _rel_path: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    fn hard_link(&self, _root: &Path, original: &Path, link: &Path) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries
+            .get(original)
+            .cloned()
+            .with_context(|| format!("remote missing {}", original.display()))?;
+        entries.insert(link.to_path_buf(), entry);
+        Ok(())
+    }
+
+    fn resumes_uploads(&self) -> bool {
+        true
+    }
+
+    fn file_size(&self, _root: &Path, rel_path: &Path) -> Option<u64> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(rel_path).map(|(bytes, _)| bytes.len() as u64)
+    }
+
+    fn write_file_at(
+        &self,
+        _root: &Path,
+        rel_path: &Path,
+        offset: u64,
+        bytes: &[u8],
+        on_chunk: ChunkProgress<'_>,
+    ) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries
+            .entry(rel_path.to_path_buf())
+            .or_insert_with(|| (Vec::new(), Self::now()));
+        entry.0.truncate(offset as usize);
+        entry.0.resize(offset as usize, 0);
+        for chunk in bytes.chunks(TRANSFER_CHUNK) {
+            on_chunk(chunk.len());
+            entry.0.extend_from_slice(chunk);
+        }
+        entry.1 = Self::now();
+        Ok(())
+    }
+
+    fn rename_file(&self, _root: &Path, from: &Path, to: &Path) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries
+            .remove(from)
+            .with_context(|| format!("remote missing {}", from.display()))?;
+        entries.insert(to.to_path_buf(), entry);
+        Ok(())
+    }
+}
+
+/// Numbers staging files, so parallel downloads of same-named files into a
+/// shared staging folder don't write over each other.
+static STAGING_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Default)]
+pub struct FsLocalStore {
+    /// Where downloads are staged before being renamed into place. `None`
+    /// stages next to the destination so the rename stays atomic.
+    staging_dir: Option<PathBuf>,
+}
+
+impl FsLocalStore {
+    pub fn with_staging_dir(staging_dir: Option<PathBuf>) -> Self {
+        Self { staging_dir }
+    }
+
+    fn staging_path(&self, target: &Path) -> PathBuf {
+        let dir = self
+            .staging_dir
+            .as_deref()
+            .or_else(|| target.parent())
+            .unwrap_or(Path::new("."));
+        let name = target
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let seq = STAGING_SEQ.fetch_add(1, Ordering::Relaxed);
+        dir.join(format!(".{name}.{}-{seq}.sftp-sync.part", std::process::id()))
+    }
+
+    fn full_path(root: &Path, rel_path: &Path) -> PathBuf {
+        if rel_path.as_os_str().is_empty() {
+            root.to_path_buf()
+        } else {
+            root.join(rel_path)
+        }
+    }
+
+    fn collect(
+        root: &Path,
+        rel_path: &Path,
+        options: &ListOptions,
+        output: &mut Listing,
+        on_listed: ListProgress<'_>,
+    ) -> Result<()> {
+        options.check_limits(root, rel_path, output)?;
+        let dir = Self::full_path(root, rel_path);
+        if !dir.exists() {
+            return Ok(());
+        }
+
+        let read_dir = match fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir,
+            Err(err) if !rel_path.as_os_str().is_empty() => {
+                output.skipped.push((rel_path.to_path_buf(), err.to_string()));
+                return Ok(());
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", dir.display()));
+            }
+        };
+
+        for entry in read_dir {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    output.skipped.push((rel_path.to_path_buf(), err.to_string()));
+                    continue;
+                }
+            };
+            let file_name = entry.file_name();
+            if options.skips(&file_name) {
+                continue;
+            }
+            let child_rel = rel_path.join(file_name);
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(err) => {
+                    output.skipped.push((child_rel, err.to_string()));
+                    continue;
+                }
+            };
+            let file_type = metadata.file_type();
+            if file_type.is_dir() {
+                Self::collect(root, &child_rel, options, output, &mut *on_listed)?;
+            } else if file_type.is_file() {
+                output.entries.push(FileEntry {
+                    path: child_rel,
+                    kind: EntryKind::File,
+                    size: metadata.len(),
+                    modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                });
+                if !on_listed(output.entries.len()) {
+                    return Err(ListingCanceled.into());
+                }
+            } else if file_type.is_symlink() {
+                if !options.follow_symlinks {
+                    continue;
+                }
+                let link_path = entry.path();
+                match fs::metadata(&link_path) {
+                    Ok(target) if target.is_file() => output.entries.push(FileEntry {
+                        path: child_rel,
+                        kind: EntryKind::Symlink,
+                        size: target.len(),
+                        modified: target.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    }),
+                    Ok(_) => redact::eprint(format!(
+                        "skipping symlink {} (target is not a regular file)",
+                        link_path.display()
+                    )),
+                    Err(_) => redact::eprint(format!(
+                        "skipping dangling symlink {}",
+                        link_path.display()
+                    )),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl LocalStore for FsLocalStore {
+    fn list(&self, root: &Path, options: &ListOptions) -> Result<Listing> {
+        self.list_with_progress(root, options, &mut |_| true)
+    }
+
+    fn list_with_progress(
+        &self,
+        root: &Path,
+        options: &ListOptions,
+        on_listed: ListProgress<'_>,
+    ) -> Result<Listing> {
+        let mut listing = Listing::default();
+        Self::collect(root, Path::new(""), options, &mut listing, on_listed)?;
+        Ok(listing)
+    }
+
+    fn read_file(&self, root: &Path, rel_path: &Path) -> Result<Vec<u8>> {
+        let path = Self::full_path(root, rel_path);
+        fs::read(&path).map_err(|err| {
+            if is_sharing_violation(&err) {
+                anyhow::Error::new(FileInUse(path.clone()))
+            } else {
+                anyhow::Error::new(err).context(format!("failed to read {}", path.display()))
+            }
+        })
+    }
+
+    fn write_file(&self, root: &Path, rel_path: &Path, bytes: &[u8]) -> Result<()> {
+        let path = Self::full_path(root, rel_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+
+        // Write to a staging file and rename, so readers never see a partial file.
+        let staged = self.staging_path(&path);
+        if let Some(dir) = staged.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        let result = fs::write(&staged, bytes)
+            .with_context(|| format!("failed to write {}", staged.display()))
+            .and_then(|_| {
+                // Noted before and after the move so the watcher never sees it first.
+                watcher::note_own_write(&path);
+                fs::rename(&staged, &path)
+                    .or_else(|_| {
+                        // A staging dir on another filesystem can't be renamed across.
+                        fs::copy(&staged, &path).map(|_| ())
+                    })
+                    .with_context(|| format!("failed to move download into {}", path.display()))
+            });
+        let _ = fs::remove_file(&staged);
+        watcher::note_own_write(&path);
+        result
+    }
+
+    fn remove_file(&self, root: &Path, rel_path: &Path) -> Result<()> {
+        let path = Self::full_path(root, rel_path);
+        if path.exists() {
+            watcher::note_own_write(&path);
+            fs::remove_file(&path)
+                .with_context(|| format!("failed to remove {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn ensure_dir(&self, root: &Path, rel_path: &Path) -> Result<()> {
+        let path = Self::full_path(root, rel_path);
+        watcher::note_own_write(&path);
+        fs::create_dir_all(&path).with_context(|| format!("failed to create {}", path.display()))
+    }
+
+    fn stat(&self, root: &Path, rel_path: &Path) -> Result<Option<FileEntry>> {
+        let path = Self::full_path(root, rel_path);
+        match fs::metadata(&path) {
+            Ok(metadata) => Ok(Some(FileEntry {
+                path: rel_path.to_path_buf(),
+                kind: EntryKind::File,
+                size: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            })),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err).with_context(|| format!("failed to stat {}", path.display())),
+        }
+    }
+
+    #[cfg(unix)]
+    fn link_id(&self, root: &Path, rel_path: &Path) -> Option<(u64, u64)> {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = fs::metadata(Self::full_path(root, rel_path)).ok()?;
+        (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+    }
+
+    #[cfg(unix)]
+    fn ownership(&self, root: &Path, rel_path: &Path) -> Option<FileOwnership> {
+        use std::os::unix::fs::MetadataExt;
+        let path = Self::full_path(root, rel_path);
+        let metadata = fs::metadata(&path).ok()?;
+        let xattrs = xattr::list(&path)
+            .map(|names| {
+                names
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(FileOwnership {
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            xattrs,
+        })
+    }
+
+    fn unsettled(&self, root: &Path, rel_paths: &[&Path], window: Duration) -> HashSet<PathBuf> {
+        let stat = |rel_path: &Path| {
+            let metadata = fs::metadata(Self::full_path(root, rel_path)).ok()?;
+            Some((metadata.len(), metadata.modified().ok()))
+        };
+        // Files untouched for longer than the window are settled; skip the wait.
+        let now = SystemTime::now();
+        let fresh: Vec<(&Path, (u64, Option<SystemTime>))> = rel_paths
+            .iter()
+            .filter_map(|rel_path| Some((*rel_path, stat(rel_path)?)))
+            .filter(|(_, (_, modified))| {
+                modified.is_some_and(|modified| {
+                    now.duration_since(modified)
+                        .map(|age| age < window)
+                        .unwrap_or(true)
+                })
+            })
+            .collect();
+        if fresh.is_empty() {
+            return HashSet::new();
+        }
+
+        std::thread::sleep(window);
+        fresh
+            .into_iter()
+            .filter(|(rel_path, before)| stat(rel_path).is_some_and(|after| after != *before))
+            .map(|(rel_path, _)| rel_path.to_path_buf())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, time::Duration};
+    use tempfile::tempdir;
+
+    /// A planned job of target 1 running `actions` under `rule`, with empty
+    /// indexes and stats.
+    fn job_for(rule: SyncRule, actions: Vec<SyncAction>) -> SyncJob {
+        SyncJob {
+            id: 1,
+            target_id: 1,
+            rule: rule.clone(),
+            local_index: FileIndex::new(),
+            remote_index: FileIndex::new(),
+            plan: SyncPlan {
+                rule,
+                actions,
+                stats: PlanStats::default(),
+            },
+            created_at: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn planner_detects_uploads_and_downloads() {
+        let temp = tempdir().unwrap();
+        let local_root = temp.path().join("local");
+        fs::create_dir_all(&local_root).unwrap();
+        fs::write(local_root.join("only_local.txt"), b"local").unwrap();
+        fs::write(local_root.join("stale.txt"), b"outdated").unwrap();
+
+        thread::sleep(Duration::from_millis(600));
+
+        let remote = InMemoryRemote::default();
+        remote
+            .write_file(
+                Path::new("/remote"),
+                Path::new("only_remote.txt"),
+                b"remote",
+            )
+            .unwrap();
+        remote
+            .write_file(Path::new("/remote"), Path::new("stale.txt"), b"fresh")
+            .unwrap();
+
+        let rule = SyncRule::new(local_root.clone(), "/remote", SyncDirection::Bidirectional);
+
+        let local_store = FsLocalStore::default();
+        let planner = SyncPlanner::new(&local_store, &remote);
+        let plan = planner.plan(&rule).unwrap();
+
+        assert_eq!(plan.stats.uploads, 1);
+        assert_eq!(plan.stats.downloads, 2);
+        assert_eq!(plan.actions.len(), 3);
+
+        let totals = TransferTotals::from_actions(&plan.actions);
+        assert_eq!(totals.upload_files, 1);
+        assert_eq!(totals.upload_bytes, 5);
+        assert_eq!(totals.download_files, 2);
+        assert_eq!(totals.download_bytes, 11);
+    }
+
+    #[test]
+    fn executor_applies_plan_against_mock_remote() {
+        let temp = tempdir().unwrap();
+        let local_root = temp.path().join("local");
+        fs::create_dir_all(&local_root).unwrap();
+        fs::write(local_root.join("upload.txt"), b"payload").unwrap();
+
+        let remote = InMemoryRemote::default();
+        let rule = SyncRule::new(local_root.clone(), "/remote", SyncDirection::Push);
+
+        let local_store = FsLocalStore::default();
+        let planner = SyncPlanner::new(&local_store, &remote);
+        let plan = planner.plan(&rule).unwrap();
+        assert_eq!(plan.stats.uploads, 1);
+
+        let executor_store = FsLocalStore::default();
+        let executor = SyncExecutor::new(&executor_store, &remote, None);
+        let logs = executor.execute(&plan);
+        assert!(matches!(logs[0].status, ActionStatus::Applied));
+
+        let bytes = remote
+            .read_file(Path::new("/remote"), Path::new("upload.txt"))
+            .unwrap();
+        assert_eq!(bytes, b"payload");
+    }
+
+    #[test]
+    fn held_deletions_are_skipped_once_either_side_changes() {
+        let temp = tempdir().unwrap();
+        let local_root = temp.path().join("local");
+        fs::create_dir_all(&local_root).unwrap();
+        let local = FsLocalStore::default();
+        let remote = InMemoryRemote::default();
+        let rule = SyncRule::new(local_root.clone(), "/remote", SyncDirection::Push);
+        let rel_path = Path::new("gone.txt");
+        remote.write_file(&rule.remote, rel_path, b"old").unwrap();
+
+        let held = HeldDeletion {
+            action: SyncAction::DeleteRemote {
+                rel_path: rel_path.to_path_buf(),
+            },
+            local: None,
+            remote: remote
+                .stat(&rule.remote, rel_path)
+                .unwrap()
+                .as_ref()
+                .map(SnapshotEntry::from_entry),
+            rule,
+        };
+        assert_eq!(changed_since_held(&local, &remote, &held), None);
+
+        remote.write_file(&held.rule.remote, rel_path, b"rewritten").unwrap();
+        let warning = changed_since_held(&local, &remote, &held).unwrap();
+        assert!(warning.contains("remote side"), "{warning}");
+
+        remote.write_file(&held.rule.remote, rel_path, b"old").unwrap();
+        fs::write(local_root.join(rel_path), b"back").unwrap();
+        let warning = changed_since_held(&local, &remote, &held).unwrap();
+        assert!(warning.contains("local side"), "{warning}");
+    }
+
+    #[test]
+    fn bandwidth_limit_paces_every_chunk_of_a_large_file() {
+        let temp = tempdir().unwrap();
+        let local_root = temp.path().join("local");
+        fs::create_dir_all(&local_root).unwrap();
+        let size = 32 * TRANSFER_CHUNK;
+        fs::write(local_root.join("big.bin"), vec![7u8; size]).unwrap();
+
+        let remote = InMemoryRemote::default();
+        let rule = SyncRule::new(local_root.clone(), "/remote", SyncDirection::Push);
+        let local_store = FsLocalStore::default();
+        let plan = SyncPlanner::new(&local_store, &remote).plan(&rule).unwrap();
+
+        /// Advances only by the waits it is asked for, so transfers take no
+        /// time of their own.
+        #[derive(Clone)]
+        struct FakeClock {
+            now: Arc<Mutex<Instant>>,
+            sleeps: Arc<Mutex<Vec<Duration>>>,
+        }
+
+        impl LimiterClock for FakeClock {
+            fn now(&self) -> Instant {
+                *self.now.lock().unwrap()
+            }
+
+            fn sleep(&self, duration: Duration) {
+                *self.now.lock().unwrap() += duration;
+                self.sleeps.lock().unwrap().push(duration);
+            }
+        }
+
+        let limit = 4_000_000u64;
+        let clock = FakeClock {
+            now: Arc::new(Mutex::new(Instant::now())),
+            sleeps: Arc::default(),
+        };
+        let chunks = RefCell::new(Vec::new());
+        let on_chunk = |bytes: usize| chunks.borrow_mut().push(bytes);
+        let executor = SyncExecutor::new(
+            &local_store,
+            &remote,
+            Some(Mutex::new(BandwidthLimiter::with_clock(limit, Box::new(clock.clone())))),
+        )
+        .with_chunk_progress(&on_chunk);
+        let logs = executor.execute(&plan);
+
+        assert!(matches!(logs[0].status, ActionStatus::Applied));
+        assert_eq!(chunks.borrow().len(), 32);
+        assert_eq!(chunks.borrow().iter().sum::<usize>(), size);
+        // The first chunk is covered by the burst; every later one waits
+        // exactly as long as the limit takes to send it.
+        let sleeps = clock.sleeps.lock().unwrap();
+        assert_eq!(sleeps.len(), 31);
+        let per_chunk = TRANSFER_CHUNK as f64 / limit as f64;
+        for sleep in sleeps.iter() {
+            assert!((sleep.as_secs_f64() - per_chunk).abs() < 1e-6, "{sleep:?}");
+        }
+    }
+
+    #[test]
+    fn uploads_are_deferred_while_the_file_is_still_growing() {
+        let temp = tempdir().unwrap();
+        let local_root = temp.path().join("local");
+        fs::create_dir_all(&local_root).unwrap();
+        let growing = local_root.join("growing.log");
+        fs::write(&growing, b"partial").unwrap();
+        // Just as fresh, but finished: waited for along with the growing one.
+        fs::write(local_root.join("done.log"), b"complete").unwrap();
+
+        let remote = InMemoryRemote::default();
+        let rule = SyncRule::new(local_root.clone(), "/remote", SyncDirection::Push);
+        let local_store = FsLocalStore::default();
+        let plan = SyncPlanner::new(&local_store, &remote).plan(&rule).unwrap();
+        assert_eq!(plan.stats.uploads, 2);
+        let status_of = |logs: &[ExecutionLog], name: &str| {
+            logs.iter()
+                .find(|log| {
+                    matches!(&log.action, SyncAction::Upload { rel_path, .. } if rel_path == name)
+                })
+                .map(|log| log.status.clone())
+                .unwrap()
+        };
+
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            let mut file = fs::OpenOptions::new().append(true).open(&growing).unwrap();
+            file.write_all(b" and more").unwrap();
+        });
+        let executor = SyncExecutor::new(&local_store, &remote, None)
+            .with_stability_window(Some(Duration::from_millis(300)));
+        let logs = executor.execute(&plan);
+        writer.join().unwrap();
+
+        assert!(matches!(status_of(&logs, "growing.log"), ActionStatus::Deferred));
+        assert!(matches!(status_of(&logs, "done.log"), ActionStatus::Applied));
+        assert!(remote
+            .read_file(Path::new("/remote"), Path::new("growing.log"))
+            .is_err());
+
+        let logs = executor.execute(&plan);
+        assert!(matches!(status_of(&logs, "growing.log"), ActionStatus::Applied));
+    }
+
+    #[test]
+    fn local_drift_counts_changes_since_snapshot() {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let entry = |path: &str, size, secs| {
+            (
+                PathBuf::from(path),
+                FileEntry {
+                    path: PathBuf::from(path),
+                    kind: EntryKind::File,
+                    size,
+                    modified: at(secs),
+                },
+            )
+        };
+        let previous: FileIndex = [
+            entry("same.txt", 1, 10),
+            entry("edited.txt", 2, 10),
+            entry("gone.txt", 3, 10),
+        ]
+        .into_iter()
+        .collect();
+        let snapshot: HashMap<_, _> = previous
+            .iter()
+            .map(|(path, entry)| (path.clone(), SnapshotEntry::from_entry(entry)))
+            .collect();
+
+        let current: FileIndex = [
+            entry("same.txt", 1, 10),
+            entry("edited.txt", 2, 20),
+            entry("new.txt", 4, 20),
+        ]
+        .into_iter()
+        .collect();
+
+        let drift = local_drift(&snapshot, &current);
+        assert_eq!(
+            drift,
+            DriftSummary {
+                added: 1,
+                modified: 1,
+                removed: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn df_output_reports_available_bytes() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/sda1         41152736  30000000   9039580      77% /\n";
+        assert_eq!(parse_df_available(output), Some(9_039_580 * 1024));
+        assert_eq!(
+            parse_df_available("df: /missing: No such file or directory\n"),
+            None
+        );
+
+        let check = SpaceCheck {
+            required: 2_000,
+            available: Some(1_000),
+        };
+        assert!(check.is_short());
+        assert!(!SpaceCheck {
+            available: None,
+            ..check
+        }
+        .is_short());
+    }
+
+    #[test]
+    fn downloads_are_staged_and_renamed_into_place() {
+        let temp = tempdir().unwrap();
+        let root = temp.path().join("local");
+        let staging = temp.path().join("staging");
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("nested").join("file.txt"), b"old").unwrap();
+
+        let store = FsLocalStore::with_staging_dir(Some(staging.clone()));
+        store
+            .write_file(&root, Path::new("nested/file.txt"), b"new contents")
+            .unwrap();
+
+        assert_eq!(
+            fs::read(root.join("nested").join("file.txt")).unwrap(),
+            b"new contents"
+        );
+        assert_eq!(fs::read_dir(&staging).unwrap().count(), 0);
+        assert_eq!(fs::read_dir(root.join("nested")).unwrap().count(), 1);
+        // `a/index.html` and `b/index.html` share the staging folder.
+        assert_ne!(
+            store.staging_path(&root.join("a/index.html")),
+            store.staging_path(&root.join("b/index.html"))
+        );
+    }
+
+    #[test]
+    fn hidden_entries_are_ignored_on_both_sides_when_excluded() {
+        let temp = tempdir().unwrap();
+        let local_root = temp.path().join("local");
+        fs::create_dir_all(local_root.join(".cache")).unwrap();
+        fs::write(local_root.join("visible.txt"), b"visible").unwrap();
+        fs::write(local_root.join(".env"), b"secret").unwrap();
+        fs::write(local_root.join(".cache").join("blob"), b"cached").unwrap();
+
+        let remote = InMemoryRemote::default();
+        remote
+            .write_file(Path::new("/remote"), Path::new(".profile"), b"remote dotfile")
+            .unwrap();
+
+        let rule = SyncRule {
+            include_hidden: false,
+            ..SyncRule::new(local_root.clone(), "/remote", SyncDirection::Push)
+        };
+
+        let local_store = FsLocalStore::default();
+        let planner = SyncPlanner::new(&local_store, &remote);
+        let plan = planner.plan(&rule).unwrap();
+
+        assert_eq!(plan.stats.uploads, 1);
+        assert_eq!(plan.stats.deletes_remote, 0);
+        assert!(matches!(
+            &plan.actions[0],
+            SyncAction::Upload { rel_path, .. } if rel_path == Path::new("visible.txt")
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn local_listing_follows_file_symlinks_and_skips_dangling_ones() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::write(root.join("target.txt"), b"payload").unwrap();
+        std::os::unix::fs::symlink(root.join("target.txt"), root.join("link.txt")).unwrap();
+        std::os::unix::fs::symlink(root.join("missing.txt"), root.join("dangling.txt")).unwrap();
+
+        let skipped = FsLocalStore::default()
+            .list(root, &ListOptions::default())
+            .unwrap();
+        assert_eq!(skipped.entries.len(), 1);
+
+        let options = ListOptions {
+            follow_symlinks: true,
+            ..ListOptions::default()
+        };
+        let listing = FsLocalStore::default().list(root, &options).unwrap();
+        assert_eq!(listing.entries.len(), 2);
+        let link = listing
+            .entries
+            .iter()
+            .find(|entry| entry.path == Path::new("link.txt"))
+            .unwrap();
+        assert_eq!(link.kind, EntryKind::Symlink);
+        assert_eq!(link.size, 7);
+    }
+
+    #[test]
+    fn locked_local_file_is_deferred_as_in_use() {
+        struct LockedLocal;
+
+        impl LocalStore for LockedLocal {
+            fn list(&self, _root: &Path, _options: &ListOptions) -> Result<Listing> {
+                unreachable!()
+            }
+            fn read_file(&self, root: &Path, rel_path: &Path) -> Result<Vec<u8>> {
+                Err(FileInUse(root.join(rel_path)).into())
+            }
+            fn write_file(&self, _root: &Path, _rel_path: &Path, _bytes: &[u8]) -> Result<()> {
+                unreachable!()
+            }
+            fn remove_file(&self, _root: &Path, _rel_path: &Path) -> Result<()> {
+                unreachable!()
+            }
+            fn ensure_dir(&self, _root: &Path, _rel_path: &Path) -> Result<()> {
+                unreachable!()
+            }
+        }
+
+        let remote = InMemoryRemote::default();
+        let rule = SyncRule::new("/local", "/remote", SyncDirection::Push);
+        let plan = SyncPlan {
+            rule,
+            actions: vec![SyncAction::Upload {
+                rel_path: PathBuf::from("notes.docx"),
+                size: 4,
+                renamed: None,
+            }],
+            stats: PlanStats::default(),
+        };
+        let logs = SyncExecutor::new(&LockedLocal, &remote, None).execute(&plan);
+
+        assert!(matches!(&logs[0].status, ActionStatus::Deferred));
+        let listing = remote
+            .list(Path::new("/remote"), &ListOptions::default())
+            .unwrap();
+        assert!(listing.entries.is_empty());
+    }
+
+    #[test]
+    fn resolved_conflicts_back_up_the_overwritten_copy() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("notes.txt"), b"local edit").unwrap();
+        let remote = InMemoryRemote::default();
+        remote
+            .write_file(Path::new("/remote"), Path::new("notes.txt"), b"remote")
+            .unwrap();
+        let mut rule = SyncRule::new(temp.path(), "/remote", SyncDirection::Bidirectional);
+        rule.compare_policy = ComparePolicy::SizeOnly;
+        rule.conflict_policy = ConflictPolicy::KeepLocal;
+
+        let local = FsLocalStore::default();
+        let plan = SyncPlanner::new(&local, &remote).plan(&rule).unwrap();
+        assert_eq!(plan.stats.conflicts, 1);
+        let logs = SyncExecutor::new(&local, &remote, None)
+            .with_conflict_backups(true)
+            .execute(&plan);
+        assert!(matches!(logs[0].status, ActionStatus::Applied));
+
+        let listing = remote
+            .list(Path::new("/remote"), &ListOptions::for_rule(&rule))
+            .unwrap();
+        let backup = listing
+            .entries
+            .iter()
+            .map(|entry| entry.path.clone())
+            .find(|path| crate::model::is_conflict_backup(path))
+            .unwrap();
+        assert!(backup.to_string_lossy().starts_with("notes.txt.conflict-"));
+        let read = |path: &Path| remote.read_file(Path::new("/remote"), path).unwrap();
+        assert_eq!(read(&backup), b"remote");
+        assert_eq!(read(Path::new("notes.txt")), b"local edit");
+        // The backup itself is never offered for syncing.
+        let plan = SyncPlanner::new(&local, &remote).plan(&rule).unwrap();
+        assert!(plan.actions.is_empty());
+
+        fs::write(temp.path().join("notes.txt"), b"local again").unwrap();
+        rule.conflict_policy = ConflictPolicy::KeepRemote;
+        let plan = SyncPlanner::new(&local, &remote).plan(&rule).unwrap();
+        let logs = SyncExecutor::new(&local, &remote, None)
+            .with_conflict_backups(true)
+            .execute(&plan);
+        assert!(matches!(logs[0].status, ActionStatus::Applied));
+        assert_eq!(
+            fs::read(temp.path().join("notes.txt")).unwrap(),
+            b"local edit"
+        );
+        let backups: Vec<_> = fs::read_dir(temp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| crate::model::is_conflict_backup(path))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read(&backups[0]).unwrap(), b"local again");
+    }
+
+    #[test]
+    fn deletions_wait_for_transfers_and_are_held_when_one_fails() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("new.txt"), b"new").unwrap();
+        let remote = InMemoryRemote::default();
+        remote
+            .write_file(Path::new("/remote"), Path::new("old.txt"), b"original")
+            .unwrap();
+        let rule = SyncRule::new(temp.path(), "/remote", SyncDirection::Push);
+        let upload = |name: &str| SyncAction::Upload {
+            rel_path: PathBuf::from(name),
+            size: 3,
+            renamed: None,
+        };
+        let plan = SyncPlan {
+            rule,
+            actions: vec![
+                SyncAction::DeleteRemote {
+                    rel_path: PathBuf::from("old.txt"),
+                },
+                upload("new.txt"),
+                // Vanished since planning, so its upload fails.
+                upload("gone.txt"),
+            ],
+            stats: PlanStats::default(),
+        };
+
+        let local = FsLocalStore::default();
+        let logs = SyncExecutor::new(&local, &remote, None).execute(&plan);
+        assert!(matches!(logs[0].status, ActionStatus::Applied));
+        assert!(matches!(logs[1].status, ActionStatus::Failed { .. }));
+        assert!(logs[2].action.is_delete());
+        assert!(matches!(&logs[2].status, ActionStatus::Deferred));
+        assert_eq!(
+            remote
+                .read_file(Path::new("/remote"), Path::new("old.txt"))
+                .unwrap(),
+            b"original"
+        );
+
+        // With every transfer through, the deletion runs after them.
+        let mut plan = plan;
+        plan.actions.pop();
+        let logs = SyncExecutor::new(&local, &remote, None).execute(&plan);
+        assert!(matches!(logs[0].action, SyncAction::Upload { .. }));
+        assert!(matches!(logs[1].status, ActionStatus::Applied));
+        assert!(remote
+            .read_file(Path::new("/remote"), Path::new("old.txt"))
+            .is_err());
+    }
+
+    #[test]
+    fn deletions_held_for_review_leave_files_until_approved() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("new.txt"), b"new").unwrap();
+        fs::write(temp.path().join("stale.txt"), b"stale").unwrap();
+        let remote = InMemoryRemote::default();
+        remote
+            .write_file(Path::new("/remote"), Path::new("old.txt"), b"original")
+            .unwrap();
+        let rule = SyncRule::new(temp.path(), "/remote", SyncDirection::Push);
+        let plan = SyncPlan {
+            rule,
+            actions: vec![
+                SyncAction::DeleteRemote {
+                    rel_path: PathBuf::from("old.txt"),
+                },
+                SyncAction::Upload {
+                    rel_path: PathBuf::from("new.txt"),
+                    size: 3,
+                    renamed: None,
+                },
+                SyncAction::DeleteLocal {
+                    rel_path: PathBuf::from("stale.txt"),
+                },
+            ],
+            stats: PlanStats::default(),
+        };
+
+        let local = FsLocalStore::default();
+        let logs = SyncExecutor::new(&local, &remote, None)
+            .with_deletes_for_review(true)
+            .execute(&plan);
+        assert!(matches!(logs[0].status, ActionStatus::Applied));
+        assert!(logs[1..]
+            .iter()
+            .all(|log| matches!(log.status, ActionStatus::HeldForReview)));
+        assert!(temp.path().join("stale.txt").exists());
+        assert!(remote
+            .read_file(Path::new("/remote"), Path::new("old.txt"))
+            .is_ok());
+
+        // Approving one runs just that deletion.
+        let approved = SyncPlan {
+            rule: plan.rule.clone(),
+            actions: vec![logs[1].action.clone()],
+            stats: PlanStats::default(),
+        };
+        let logs = SyncExecutor::new(&local, &remote, None).execute(&approved);
+        assert!(matches!(logs[0].status, ActionStatus::Applied));
+        assert!(remote
+            .read_file(Path::new("/remote"), Path::new("old.txt"))
+            .is_err());
+        assert!(temp.path().join("stale.txt").exists());
+    }
+
+    #[test]
+    fn dry_runs_decide_every_action_without_applying_any() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("new.txt"), b"new").unwrap();
+        let remote = InMemoryRemote::default();
+        remote
+            .write_file(Path::new("/remote"), Path::new("old.txt"), b"original")
+            .unwrap();
+        remote
+            .write_file(Path::new("/remote"), Path::new("fresh.txt"), b"fresh")
+            .unwrap();
+        let rule = SyncRule::new(temp.path(), "/remote", SyncDirection::Bidirectional);
+        let plan = SyncPlan {
+            rule,
+            actions: vec![
+                SyncAction::DeleteRemote {
+                    rel_path: PathBuf::from("old.txt"),
+                },
+                SyncAction::Upload {
+                    rel_path: PathBuf::from("new.txt"),
+                    size: 3,
+                    renamed: None,
+                },
+                SyncAction::Download {
+                    rel_path: PathBuf::from("fresh.txt"),
+                    size: 5,
+                    renamed: None,
+                },
+                SyncAction::Conflict {
+                    rel_path: PathBuf::from("both.txt"),
+                },
+            ],
+            stats: PlanStats::default(),
+        };
+
+        let local = FsLocalStore::default();
+        let logs = SyncExecutor::new(&local, &remote, None)
+            .with_dry_run(true)
+            .execute(&plan);
+        // Same order as a real run: transfers first, deletions last.
+        assert!(logs.last().unwrap().action.is_delete());
+        assert!(matches!(logs[2].status, ActionStatus::SkippedConflict));
+        assert!([&logs[0], &logs[1], &logs[3]]
+            .iter()
+            .all(|log| matches!(log.status, ActionStatus::WouldApply)));
+        assert!(remote
+            .read_file(Path::new("/remote"), Path::new("new.txt"))
+            .is_err());
+        assert!(remote
+            .read_file(Path::new("/remote"), Path::new("old.txt"))
+            .is_ok());
+        assert!(!temp.path().join("fresh.txt").exists());
+
+        // Whatever a real run would refuse is still refused.
+        let logs = SyncExecutor::new(&local, &remote, None)
+            .with_dry_run(true)
+            .with_remote_read_only(true)
+            .execute(&plan);
+        assert!(matches!(logs[0].status, ActionStatus::Refused));
+        assert!(matches!(logs[1].status, ActionStatus::WouldApply));
+    }
+
+    #[test]
+    fn read_only_remote_refuses_uploads_and_remote_deletes() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("local.txt"), b"local").unwrap();
+        let remote = InMemoryRemote::default();
+        remote
+            .write_file(Path::new("/remote"), Path::new("keep.txt"), b"remote")
+            .unwrap();
+        let mut rule = SyncRule::new(temp.path(), "/remote", SyncDirection::Bidirectional);
+        rule.remote_read_only = true;
+        let plan = SyncPlan {
+            rule,
+            actions: vec![
+                SyncAction::Upload {
+                    rel_path: PathBuf::from("local.txt"),
+                    size: 5,
+                    renamed: None,
+                },
+                SyncAction::DeleteRemote {
+                    rel_path: PathBuf::from("keep.txt"),
+                },
+                SyncAction::Download {
+                    rel_path: PathBuf::from("keep.txt"),
+                    size: 6,
+                    renamed: None,
+                },
+            ],
+            stats: PlanStats::default(),
+        };
+
+        let local = FsLocalStore::default();
+        let logs = SyncExecutor::new(&local, &remote, None).execute(&plan);
+        // Deletions run last, after the download.
+        assert!(matches!(&logs[0].status, ActionStatus::Refused));
+        assert!(matches!(logs[1].status, ActionStatus::Applied));
+        assert!(matches!(&logs[2].status, ActionStatus::Refused));
+        assert!(remote
+            .read_file(Path::new("/remote"), Path::new("local.txt"))
+            .is_err());
+        assert_eq!(
+            remote
+                .read_file(Path::new("/remote"), Path::new("keep.txt"))
+                .unwrap(),
+            b"remote"
+        );
+
+        // The target-wide switch guards rules that do not set the flag.
+        let mut plan = plan;
+        plan.rule.remote_read_only = false;
+        let logs = SyncExecutor::new(&local, &remote, None)
+            .with_remote_read_only(true)
+            .execute(&plan);
+        assert!(matches!(&logs[0].status, ActionStatus::Refused));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_linked_files_are_uploaded_once() {
+        struct CountingLocal {
+            inner: FsLocalStore,
+            reads: std::cell::Cell<usize>,
+        }
+
+        impl LocalStore for CountingLocal {
+            fn list(&self, root: &Path, options: &ListOptions) -> Result<Listing> {
+                self.inner.list(root, options)
+            }
+            fn read_file(&self, root: &Path, rel_path: &Path) -> Result<Vec<u8>> {
+                self.reads.set(self.reads.get() + 1);
+                self.inner.read_file(root, rel_path)
+            }
+            fn write_file(&self, _root: &Path, _rel_path: &Path, _bytes: &[u8]) -> Result<()> {
+                unreachable!()
+            }
+            fn remove_file(&self, _root: &Path, _rel_path: &Path) -> Result<()> {
+                unreachable!()
+            }
+            fn ensure_dir(&self, _root: &Path, _rel_path: &Path) -> Result<()> {
+                unreachable!()
+            }
+            fn link_id(&self, root: &Path, rel_path: &Path) -> Option<(u64, u64)> {
+                self.inner.link_id(root, rel_path)
+            }
+        }
+
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::write(root.join("a.bin"), b"shared").unwrap();
+        fs::hard_link(root.join("a.bin"), root.join("b.bin")).unwrap();
+        fs::write(root.join("c.bin"), b"single").unwrap();
+
+        let local = CountingLocal {
+            inner: FsLocalStore::default(),
+            reads: Default::default(),
+        };
+        let remote = InMemoryRemote::default();
+        let mut rule = SyncRule::new(root, "/remote", SyncDirection::Push);
+        rule.hard_links = true;
+        let plan = SyncPlanner::new(&local, &remote).plan(&rule).unwrap();
+        let logs = SyncExecutor::new(&local, &remote, None).execute(&plan);
+
+        assert!(logs
+            .iter()
+            .all(|log| matches!(log.status, ActionStatus::Applied)));
+        assert_eq!(local.reads.get(), 2);
+        for name in ["a.bin", "b.bin"] {
+            let bytes = remote.read_file(Path::new("/remote"), Path::new(name));
+            assert_eq!(bytes.unwrap(), b"shared");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn preserved_ownership_is_set_remotely_or_reported() {
+        use std::os::unix::fs::MetadataExt;
+
+        #[derive(Default)]
+        struct OwningRemote {
+            inner: InMemoryRemote,
+            owners: std::cell::RefCell<Vec<(PathBuf, u32, u32)>>,
+        }
+
+        impl RemoteStore for OwningRemote {
+            fn list(&self, root: &Path, options: &ListOptions) -> Result<Listing> {
+                self.inner.list(root, options)
+            }
+            fn read_file(&self, root: &Path, rel_path: &Path) -> Result<Vec<u8>> {
+                self.inner.read_file(root, rel_path)
+            }
+            fn write_file(&self, root: &Path, rel_path: &Path, bytes: &[u8]) -> Result<()> {
+                self.inner.write_file(root, rel_path, bytes)
+            }
+            fn remove_file(&self, root: &Path, rel_path: &Path) -> Result<()> {
+                self.inner.remove_file(root, rel_path)
+            }
+            fn ensure_dir(&self, root: &Path, rel_path: &Path) -> Result<()> {
+                self.inner.ensure_dir(root, rel_path)
+            }
+            fn set_owner(&self, _root: &Path, rel_path: &Path, uid: u32, gid: u32) -> Result<()> {
+                self.owners
+                    .borrow_mut()
+                    .push((rel_path.to_path_buf(), uid, gid));
+                Ok(())
+            }
+        }
+
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("a.txt"), b"owned").unwrap();
+        let metadata = fs::metadata(temp.path().join("a.txt")).unwrap();
+        let mut rule = SyncRule::new(temp.path(), "/remote", SyncDirection::Push);
+        rule.preserve_ownership = true;
+        let local = FsLocalStore::default();
+
+        let remote = OwningRemote::default();
+        let plan = SyncPlanner::new(&local, &remote).plan(&rule).unwrap();
+        let logs = SyncExecutor::new(&local, &remote, None).execute(&plan);
+        assert!(matches!(logs[0].status, ActionStatus::Applied));
+        assert_eq!(
+            *remote.owners.borrow(),
+            vec![(PathBuf::from("a.txt"), metadata.uid(), metadata.gid())]
+        );
+
+        // A server that refuses still gets the file; the refusal is a warning.
+        let remote = InMemoryRemote::default();
+        let plan = SyncPlanner::new(&local, &remote).plan(&rule).unwrap();
+        let logs = SyncExecutor::new(&local, &remote, None).execute(&plan);
+        assert!(matches!(logs[0].status, ActionStatus::Applied));
+        assert!(logs[0]
+            .warning
+            .as_deref()
+            .is_some_and(|warning| warning.contains("cannot change file ownership")));
+        assert_eq!(
+            remote
+                .read_file(Path::new("/remote"), Path::new("a.txt"))
+                .unwrap(),
+            b"owned"
+        );
+    }
+
+    #[test]
+    fn listing_aborts_when_root_is_too_broad() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("a/b/c")).unwrap();
+        fs::write(root.join("a/b/c/deep.txt"), b"deep").unwrap();
+        fs::write(root.join("top.txt"), b"top").unwrap();
+
+        let shallow = ListOptions {
+            max_depth: 2,
+            ..ListOptions::default()
+        };
+        let err = FsLocalStore::default().list(root, &shallow).unwrap_err();
+        assert!(err.to_string().contains("too broad"));
+
+        let narrow = ListOptions {
+            max_entries: 1,
+            ..ListOptions::default()
+        };
+        assert!(FsLocalStore::default().list(root, &narrow).is_err());
+
+        let mut rule = SyncRule::new(root, "/remote", SyncDirection::Push);
+        rule.max_entries = 1;
+        let err = FsLocalStore::default()
+            .list(root, &ListOptions::for_rule(&rule))
+            .unwrap_err();
+        assert!(err.to_string().contains("more than 1 files"));
+        assert_eq!(
+            FsLocalStore::default()
+                .list(root, &ListOptions::default())
+                .unwrap()
+                .entries
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn unreadable_local_subtree_does_not_delete_remote_copies() {
+        struct PartialLocal;
+
+        impl LocalStore for PartialLocal {
+            fn list(&self, _root: &Path, _options: &ListOptions) -> Result<Listing> {
+                Ok(Listing {
+                    entries: Vec::new(),
+                    skipped: vec![(PathBuf::from("locked"), "permission denied".into())],
+                })
+            }
+            fn read_file(&self, _root: &Path, _rel_path: &Path) -> Result<Vec<u8>> {
+                unreachable!()
+            }
+            fn write_file(&self, _root: &Path, _rel_path: &Path, _bytes: &[u8]) -> Result<()> {
+                unreachable!()
+            }
+            fn remove_file(&self, _root: &Path, _rel_path: &Path) -> Result<()> {
+                unreachable!()
+            }
+            fn ensure_dir(&self, _root: &Path, _rel_path: &Path) -> Result<()> {
+                unreachable!()
+            }
+        }
+
+        let remote = InMemoryRemote::default();
+        remote
+            .write_file(Path::new("/remote"), Path::new("locked/data.bin"), b"keep")
+            .unwrap();
+        remote
+            .write_file(Path::new("/remote"), Path::new("stray.txt"), b"gone")
+            .unwrap();
+
+        let rule = SyncRule::new("/local", "/remote", SyncDirection::Push);
+        let (local_index, remote_index, warnings, _) =
+            build_indexes(&rule, &PartialLocal, &remote, &mut |_| true).unwrap();
+        let (actions, stats) = diff_actions(&rule, &local_index, &remote_index, 0, false);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(stats.deletes_remote, 1);
+        assert!(matches!(
+            &actions[0],
+            SyncAction::DeleteRemote { rel_path } if rel_path == Path::new("stray.txt")
+        ));
+    }
+
+    #[test]
+    fn flattened_rule_uploads_by_name_and_skips_collisions() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        for (path, body) in [("a/app.log", "a"), ("b/app.log", "b"), ("c/x.txt", "x")] {
+            fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            fs::write(root.join(path), body).unwrap();
+        }
+        let remote = InMemoryRemote::default();
+        for path in ["app.log", "stray.txt", "sub/deep.txt"] {
+            remote
+                .write_file(Path::new("/remote"), Path::new(path), b"old")
+                .unwrap();
+        }
+
+        let rule = SyncRule {
+            flatten: true,
+            ..SyncRule::new(root, "/remote", SyncDirection::Push)
+        };
+        let local = FsLocalStore::default();
+        let (local_index, remote_index, warnings, collisions) =
+            build_indexes(&rule, &local, &remote, &mut |_| true).unwrap();
+        let (mut actions, mut stats) = diff_actions(&rule, &local_index, &remote_index, 0, false);
+        collisions.apply(&mut actions, &mut stats);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!((stats.uploads, stats.deletes_remote), (1, 1));
+        assert_eq!(stats.collisions, 2);
+        let plan = SyncPlan {
+            rule,
+            actions,
+            stats,
+        };
+        let logs = SyncExecutor::new(&local, &remote, None).execute(&plan);
+        assert!(logs
+            .iter()
+            .all(|log| matches!(log.status, ActionStatus::Applied)));
+
+        let read = |path: &str| remote.read_file(Path::new("/remote"), Path::new(path));
+        assert_eq!(read("x.txt").unwrap(), b"x");
+        assert_eq!(read("app.log").unwrap(), b"old");
+        assert!(read("stray.txt").is_err());
+        assert!(read("sub/deep.txt").is_ok());
+    }
+
+    #[test]
+    fn rename_policy_gives_colliding_files_stable_suffixed_names() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        for (path, body) in [("a/app.log", "a"), ("b/app.log", "b")] {
+            fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            fs::write(root.join(path), body).unwrap();
+        }
+        let remote = InMemoryRemote::default();
+        let rule = SyncRule {
+            flatten: true,
+            collision_policy: CollisionPolicy::Rename,
+            ..SyncRule::new(root, "/remote", SyncDirection::Push)
+        };
+        let local = FsLocalStore::default();
+
+        let plan = SyncPlanner::new(&local, &remote).plan(&rule).unwrap();
+        assert_eq!((plan.stats.uploads, plan.stats.collisions), (2, 2));
+        SyncExecutor::new(&local, &remote, None).execute(&plan);
+        let read = |path: &str| remote.read_file(Path::new("/remote"), Path::new(path));
+        assert_eq!(read("app.log").unwrap(), b"a");
+        assert_eq!(read("app-1.log").unwrap(), b"b");
+
+        // The renamed copy pairs with its source again instead of being deleted.
+        let replan = SyncPlanner::new(&local, &remote).plan(&rule).unwrap();
+        assert!(replan.actions.is_empty());
+    }
+
+    #[test]
+    fn names_differing_only_in_case_collide_on_case_insensitive_destinations() {
+        let temp = tempdir().unwrap();
+        let remote = InMemoryRemote::default();
+        for path in ["README.md", "Readme.md", "notes.txt"] {
+            remote
+                .write_file(Path::new("/remote"), Path::new(path), b"x")
+                .unwrap();
+        }
+        let mut rule = SyncRule::new(temp.path(), "/remote", SyncDirection::Pull);
+        let local = FsLocalStore::default();
+        let planner = SyncPlanner::new(&local, &remote);
+
+        let plan = planner.plan(&rule).unwrap();
+        assert_eq!((plan.stats.downloads, plan.stats.collisions), (3, 0));
+
+        rule.case_insensitive = true;
+        let plan = planner.plan(&rule).unwrap();
+        assert_eq!((plan.stats.downloads, plan.stats.collisions), (1, 2));
+    }
+
+    #[test]
+    fn case_insensitive_destinations_pair_names_differing_only_in_case() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("readme.md"), b"local").unwrap();
+        let remote = InMemoryRemote::default();
+        remote
+            .write_file(Path::new("/remote"), Path::new("README.md"), b"remote")
+            .unwrap();
+        let mut rule = SyncRule::new(temp.path(), "/remote", SyncDirection::Push);
+        let local = FsLocalStore::default();
+        let planner = SyncPlanner::new(&local, &remote);
+
+        let plan = planner.plan(&rule).unwrap();
+        assert_eq!(plan.stats.deletes_remote, 1);
+
+        rule.case_insensitive = true;
+        let plan = planner.plan(&rule).unwrap();
+        assert_eq!(plan.stats.deletes_remote, 0);
+        assert!(!plan.actions.iter().any(SyncAction::is_delete));
+    }
+
+    #[test]
+    fn push_overwrite_policy_decides_matched_files() {
+        let entry = |path: &str, size: u64, age_secs: u64| {
+            let entry = FileEntry {
+                path: PathBuf::from(path),
+                kind: EntryKind::File,
+                size,
+                modified: SystemTime::UNIX_EPOCH + Duration::from_secs(1_000 - age_secs),
+            };
+            (entry.path.clone(), entry)
+        };
+        let local: FileIndex = [
+            entry("same.txt", 1, 0),
+            entry("older.txt", 1, 60),
+            entry("resized.txt", 2, 0),
+        ]
+        .into_iter()
+        .collect();
+        let remote: FileIndex = [
+            entry("same.txt", 1, 0),
+            entry("older.txt", 1, 0),
+            entry("resized.txt", 1, 0),
+        ]
+        .into_iter()
+        .collect();
+
+        let uploads = |overwrite_policy| {
+            let rule = SyncRule {
+                overwrite_policy,
+                ..SyncRule::new("/local", "/remote", SyncDirection::Push)
+            };
+            diff_actions(&rule, &local, &remote, 0, false).1.uploads
+        };
+        assert_eq!(uploads(OverwritePolicy::NewerOnly), 0);
+        assert_eq!(uploads(OverwritePolicy::AlwaysOverwrite), 3);
+        assert_eq!(uploads(OverwritePolicy::SizeOrTimeDiffers), 2);
+    }
+
+    #[test]
+    fn size_only_rules_ignore_modification_times() {
+        let entry = |path: &str, size: u64, modified_secs: u64| {
+            let entry = FileEntry {
+                path: PathBuf::from(path),
+                kind: EntryKind::File,
+                size,
+                modified: SystemTime::UNIX_EPOCH + Duration::from_secs(modified_secs),
+            };
+            (entry.path.clone(), entry)
+        };
+        // A mount that reports fresh times on every listing.
+        let local: FileIndex = [
+            entry("touched.txt", 1, 5_000),
+            entry("resized.txt", 2, 5_000),
+        ]
+        .into_iter()
+        .collect();
+        let remote: FileIndex = [
+            entry("touched.txt", 1, 1_000),
+            entry("resized.txt", 1, 9_000),
+        ]
+        .into_iter()
+        .collect();
+
+        let stats = |direction, overwrite_policy| {
+            let rule = SyncRule {
+                compare_policy: ComparePolicy::SizeOnly,
+                overwrite_policy,
+                ..SyncRule::new("/local", "/remote", direction)
+            };
+            let (_, stats) = diff_actions(&rule, &local, &remote, 0, false);
+            (stats.uploads, stats.downloads, stats.conflicts)
+        };
+        for policy in OverwritePolicy::ALL {
+            assert_eq!(stats(SyncDirection::Push, policy), (1, 0, 0));
+        }
+        let newer_only = OverwritePolicy::NewerOnly;
+        assert_eq!(stats(SyncDirection::Pull, newer_only), (0, 1, 0));
+        assert_eq!(stats(SyncDirection::Bidirectional, newer_only), (0, 0, 1));
+    }
+
+    #[test]
+    fn checksum_rules_skip_identical_content_whatever_the_times() {
+        let temp = tempdir().unwrap();
+        let remote = InMemoryRemote::default();
+        for (name, remote_bytes) in [("same.txt", b"same"), ("edited.txt", b"old!")] {
+            remote
+                .write_file(Path::new("/remote"), Path::new(name), remote_bytes)
+                .unwrap();
+        }
+        // Local copies an hour ahead on one side and a day behind on the other.
+        for (name, bytes) in [("same.txt", b"same"), ("edited.txt", b"new!")] {
+            fs::write(temp.path().join(name), bytes).unwrap();
+        }
+        let local_file = |name: &str| fs::File::options().write(true).open(temp.path().join(name));
+        let hour = Duration::from_secs(3600);
+        local_file("same.txt")
+            .unwrap()
+            .set_modified(SystemTime::now() + hour)
+            .unwrap();
+        local_file("edited.txt")
+            .unwrap()
+            .set_modified(SystemTime::now() + hour)
+            .unwrap();
+        let local = FsLocalStore::default();
+
+        let plan = |compare_policy, direction| {
+            let rule = SyncRule {
+                compare_policy,
+                ..SyncRule::new(temp.path(), "/remote", direction)
+            };
+            SyncPlanner::new(&local, &remote).plan(&rule).unwrap()
+        };
+        let by_time = plan(ComparePolicy::ModifiedTime, SyncDirection::Push);
+        assert_eq!(by_time.stats.uploads, 2);
+
+        let by_content = plan(ComparePolicy::Checksum, SyncDirection::Push);
+        assert!(matches!(
+            by_content.actions.as_slice(),
+            [SyncAction::Upload { rel_path, .. }] if rel_path == Path::new("edited.txt")
+        ));
+        assert_eq!((by_content.stats.uploads, by_content.stats.identical), (1, 1));
+
+        local_file("same.txt")
+            .unwrap()
+            .set_modified(SystemTime::now() - 24 * hour)
+            .unwrap();
+        fs::remove_file(temp.path().join("edited.txt")).unwrap();
+        remote
+            .remove_file(Path::new("/remote"), Path::new("edited.txt"))
+            .unwrap();
+        for direction in [SyncDirection::Pull, SyncDirection::Bidirectional] {
+            let plan = plan(ComparePolicy::Checksum, direction);
+            assert!(plan.actions.is_empty(), "{direction:?}: {:?}", plan.actions);
+        }
+    }
+
+    #[test]
+    fn line_endings_are_rewritten_for_text_only() {
+        let lf = |bytes: &[u8]| normalize_line_endings(bytes, LineEndings::Lf).into_owned();
+        let crlf = |bytes: &[u8]| normalize_line_endings(bytes, LineEndings::Crlf).into_owned();
+        assert_eq!(lf(b"a\r\nb\rc\n"), b"a\nb\rc\n");
+        assert_eq!(crlf(b"a\nb\r\n"), b"a\r\nb\r\n");
+        let binary = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        assert_eq!(lf(binary), binary);
+        assert_eq!(
+            normalize_line_endings(b"a\r\n", LineEndings::Keep).as_ref(),
+            b"a\r\n"
+        );
+
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("notes.txt"), b"one\r\ntwo\r\n").unwrap();
+        fs::write(temp.path().join("same.txt"), b"same\r\n").unwrap();
+        let remote = InMemoryRemote::default();
+        remote
+            .write_file(Path::new("/remote"), Path::new("same.txt"), b"same\n")
+            .unwrap();
+        fs::File::options()
+            .write(true)
+            .open(temp.path().join("same.txt"))
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(3600))
+            .unwrap();
+        let local = FsLocalStore::default();
+        let rule = SyncRule {
+            line_endings: LineEndings::Lf,
+            compare_policy: ComparePolicy::Checksum,
+            ..SyncRule::new(temp.path(), "/remote", SyncDirection::Push)
+        };
+        let sized = SyncRule {
+            compare_policy: ComparePolicy::SizeOnly,
+            ..rule.clone()
+        };
+        assert!(SyncPlanner::new(&local, &remote).plan(&sized).is_err());
+
+        let plan = SyncPlanner::new(&local, &remote).plan(&rule).unwrap();
+        // Differing only in line endings, `same.txt` compares equal.
+        assert_eq!((plan.stats.uploads, plan.stats.identical), (1, 1));
+
+        let logs = SyncExecutor::new(&local, &remote, None).execute(&plan);
+        assert!(matches!(logs[0].status, ActionStatus::Applied));
+        assert_eq!(
+            remote
+                .read_file(Path::new("/remote"), Path::new("notes.txt"))
+                .unwrap(),
+            b"one\ntwo\n"
+        );
+    }
+
+    #[test]
+    fn readdir_entries_without_size_mtime_or_type_lack_stats() {
+        let stat = |perm, size, mtime| ssh2::FileStat {
+            size,
+            uid: None,
+            gid: None,
+            perm,
+            atime: None,
+            mtime,
+        };
+        const FILE: u32 = 0o100644;
+        const DIR: u32 = 0o040755;
+        assert!(!lacks_stats(&stat(Some(FILE), Some(3), Some(1_000))));
+        assert!(lacks_stats(&stat(Some(FILE), None, Some(1_000))));
+        assert!(lacks_stats(&stat(Some(FILE), Some(3), None)));
+        assert!(lacks_stats(&stat(None, Some(3), Some(1_000))));
+        // Directories are only walked, so their type is all that matters.
+        assert!(!lacks_stats(&stat(Some(DIR), None, None)));
+    }
+
+    #[test]
+    fn forced_diff_transfers_unchanged_files_in_the_rule_direction() {
+        let entry = |path: &str| {
+            let entry = FileEntry {
+                path: PathBuf::from(path),
+                kind: EntryKind::File,
+                size: 1,
+                modified: SystemTime::UNIX_EPOCH + Duration::from_secs(1_000),
+            };
+            (entry.path.clone(), entry)
+        };
+        let index: FileIndex = [entry("same.txt"), entry("cache/skip.bin")]
+            .into_iter()
+            .collect();
+
+        let stats = |direction| {
+            let rule = SyncRule {
+                ignore: vec!["cache/**".into()],
+                ..SyncRule::new("/local", "/remote", direction)
+            };
+            let (_, stats) = diff_actions(&rule, &index, &index, 0, true);
+            (stats.uploads, stats.downloads)
+        };
+        assert_eq!(stats(SyncDirection::Push), (1, 0));
+        assert_eq!(stats(SyncDirection::Pull), (0, 1));
+        assert_eq!(stats(SyncDirection::Bidirectional), (1, 0));
+    }
+
+    #[test]
+    fn clock_skew_moves_remote_times_onto_the_local_clock() {
+        let entry = |modified_secs: u64| {
+            let entry = FileEntry {
+                path: PathBuf::from("index.html"),
+                kind: EntryKind::File,
+                size: 1,
+                modified: SystemTime::UNIX_EPOCH + Duration::from_secs(modified_secs),
+            };
+            (entry.path.clone(), entry)
+        };
+        // Edited locally after the last upload, on a server running 5 minutes fast.
+        let local: FileIndex = [entry(1_000)].into_iter().collect();
+        let remote: FileIndex = [entry(1_240)].into_iter().collect();
+        let rule = SyncRule::new("/local", "/remote", SyncDirection::Bidirectional);
+
+        let (_, uncorrected) = diff_actions(&rule, &local, &remote, 0, false);
+        assert_eq!((uncorrected.uploads, uncorrected.downloads), (0, 1));
+        let (_, corrected) = diff_actions(&rule, &local, &remote, 300, false);
+        assert_eq!((corrected.uploads, corrected.downloads), (1, 0));
+    }
+
+    #[test]
+    fn plan_preview_lists_only_pending_changes_unless_asked_for_all() {
+        let entry = |path: &str, size: u64| {
+            let entry = FileEntry {
+                path: PathBuf::from(path),
+                kind: EntryKind::File,
+                size,
+                modified: SystemTime::UNIX_EPOCH,
+            };
+            (entry.path.clone(), entry)
+        };
+        let local: FileIndex = [entry("b.txt", 1), entry("a.txt", 2), entry("skip.log", 1)]
+            .into_iter()
+            .collect();
+        let remote: FileIndex = [entry("b.txt", 1), entry("a.txt", 1), entry("old.txt", 1)]
+            .into_iter()
+            .collect();
+        let rule = SyncRule {
+            overwrite_policy: OverwritePolicy::SizeOrTimeDiffers,
+            ignore: vec!["*.log".into()],
+            ..SyncRule::new("/local", "/remote", SyncDirection::Push)
+        };
+        let (actions, _) = diff_actions(&rule, &local, &remote, 0, false);
+        let job = SyncJob {
+            local_index: local,
+            remote_index: remote,
+            ..job_for(rule, actions)
+        };
+
+        let row = |path: &str, change| PreviewRow {
+            rel_path: PathBuf::from(path),
+            change,
+        };
+        assert_eq!(
+            job.preview(false),
+            vec![
+                row("a.txt", PreviewChange::Upload),
+                row("old.txt", PreviewChange::DeleteRemote),
+            ]
+        );
+        assert_eq!(
+            job.preview(true),
+            vec![
+                row("a.txt", PreviewChange::Upload),
+                row("b.txt", PreviewChange::Unchanged),
+                row("old.txt", PreviewChange::DeleteRemote),
+            ]
+        );
+    }
+
+    #[test]
+    fn include_and_ignore_lists_limit_actions_without_deleting_filtered_files() {
+        let entry = |path: &str| {
+            let entry = FileEntry {
+                path: PathBuf::from(path),
+                kind: EntryKind::File,
+                size: 1,
+                modified: SystemTime::UNIX_EPOCH,
+            };
+            (entry.path.clone(), entry)
+        };
+        let local: FileIndex = [
+            entry("src/main.rs"),
+            entry("Cargo.toml"),
+            entry("README.md"),
+            entry("target/debug/build.rs"),
+        ]
+        .into_iter()
+        .collect();
+        let remote: FileIndex = [
+            entry("src/old.rs"),
+            entry("notes.txt"),
+            entry("target/stale.rs"),
+        ]
+        .into_iter()
+        .collect();
+        let rule = SyncRule {
+            include: vec!["*.rs".into(), "Cargo.toml".into()],
+            ignore: vec!["target".into()],
+            ..SyncRule::new("/local", "/remote", SyncDirection::Push)
+        };
+
+        let (actions, stats) = diff_actions(&rule, &local, &remote, 0, false);
+        assert_eq!(stats.uploads, 2);
+        assert_eq!(stats.deletes_remote, 1);
+        assert!(actions.iter().any(|action| matches!(
+            action,
+            SyncAction::DeleteRemote { rel_path } if rel_path == Path::new("src/old.rs")
+        )));
+
+        let nested = SyncRule {
+            include: vec!["src/**/*.rs".into()],
+            ..SyncRule::new("/local", "/remote", SyncDirection::Push)
+        };
+        assert!(nested.selects(Path::new("src/main.rs")));
+        assert!(nested.selects(Path::new("src/net/tcp.rs")));
+        assert!(!nested.selects(Path::new("benches/main.rs")));
+
+        let any_depth = SyncRule {
+            include: vec!["**/build/*.log".into()],
+            ..SyncRule::new("/local", "/remote", SyncDirection::Push)
+        };
+        assert!(any_depth.selects(Path::new("build/a.log")));
+        assert!(any_depth.selects(Path::new("x/y/build/a.log")));
+        assert!(!any_depth.selects(Path::new("x/build/y/a.log")));
+        assert!(!any_depth.selects(Path::new("rebuild/a.log")));
+
+        // Retrying every split of the text for each star made this take
+        // exponential time.
+        let starry = SyncRule {
+            include: vec!["**a**a**a**a**a**a**a**a**a**a**b".into()],
+            ..SyncRule::new("/local", "/remote", SyncDirection::Push)
+        };
+        assert!(!starry.selects(Path::new(&"a/".repeat(40))));
+    }
+
+    #[test]
+    fn empty_and_listed_files_are_neither_pushed_nor_deleted() {
+        let entry = |path: &str, size: u64| {
+            let entry = FileEntry {
+                path: PathBuf::from(path),
+                kind: EntryKind::File,
+                size,
+                modified: SystemTime::UNIX_EPOCH,
+            };
+            (entry.path.clone(), entry)
+        };
+        let local: FileIndex = [
+            entry("build.lock", 0),
+            entry("app.js", 10),
+            entry("img/.DS_Store", 6),
+        ]
+        .into_iter()
+        .collect();
+        let remote: FileIndex = [entry("stale.tmp", 0), entry("Thumbs.db", 3)]
+            .into_iter()
+            .collect();
+        let rule = SyncRule {
+            skip_empty_files: true,
+            ..SyncRule::new("/local", "/remote", SyncDirection::Push)
+        };
+
+        let (actions, stats) = diff_actions(&rule, &local, &remote, 0, false);
+        assert_eq!((stats.uploads, stats.deletes_remote), (1, 0));
+        assert!(matches!(
+            &actions[0],
+            SyncAction::Upload { rel_path, .. } if rel_path == Path::new("app.js")
+        ));
+
+        // The default name list is editable; without it those files sync again.
+        let rule = SyncRule {
+            skip_names: Vec::new(),
+            ..rule
+        };
+        let (_, stats) = diff_actions(&rule, &local, &remote, 0, false);
+        assert_eq!((stats.uploads, stats.deletes_remote), (2, 1));
+    }
+
+    #[test]
+    fn comparing_targets_reports_three_way_differences() {
+        let staging_store = InMemoryRemote::default();
+        let prod_store = InMemoryRemote::default();
+        for (store, path, body) in [
+            (&staging_store, "same.txt", "same"),
+            (&prod_store, "same.txt", "same"),
+            (&staging_store, "changed.txt", "v2"),
+            (&prod_store, "changed.txt", "v1-long"),
+            (&staging_store, "new.txt", "new"),
+            (&prod_store, "old.txt", "old"),
+        ] {
+            store
+                .write_file(Path::new("/srv"), Path::new(path), body.as_bytes())
+                .unwrap();
         }
+
+        let mut targets = crate::model::sample_remote_targets();
+        let mut prod = targets.remove(0);
+        prod.rules.truncate(1);
+        let mut staging = prod.clone();
+        staging
+            .rules
+            .push(SyncRule::new("./extra", "/extra", SyncDirection::Push));
+
+        let comparison = compare_remotes(&staging, &staging_store, &prod, &prod_store).unwrap();
+        assert_eq!(comparison.only_left, vec![PathBuf::from("/web/new.txt")]);
+        assert_eq!(comparison.only_right, vec![PathBuf::from("/web/old.txt")]);
+        assert_eq!(
+            comparison.differing,
+            vec![PathBuf::from("/web/changed.txt")]
+        );
+        assert_eq!(comparison.unmatched_rules, vec![PathBuf::from("/extra")]);
+        assert!(!comparison.is_identical());
     }
-}
 
-struct BandwidthLimiter {
-    limit_per_sec: f64,
-    allowance: f64,
-    last_check: Instant,
-}
+    #[test]
+    fn listing_stops_when_progress_callback_cancels() {
+        let temp = tempdir().unwrap();
+        let remote = InMemoryRemote::default();
+        remote
+            .write_file(Path::new("/remote"), Path::new("a.txt"), b"a")
+            .unwrap();
 
-impl BandwidthLimiter {
-    fn new(limit_bytes_per_sec: u64) -> Self {
-        Self {
-            limit_per_sec: limit_bytes_per_sec as f64,
-            allowance: limit_bytes_per_sec as f64,
-            last_check: Instant::now(),
-        }
+        let rule = SyncRule::new(temp.path(), "/remote", SyncDirection::Push);
+        let mut seen = 0;
+        let err = build_indexes(&rule, &FsLocalStore::default(), &remote, &mut |found| {
+            seen = found;
+            false
+        })
+        .unwrap_err();
+
+        assert!(err.is::<ListingCanceled>());
+        assert_eq!(seen, 1);
     }
 
-    fn consume(&mut self, bytes: u64) {
-        let now = Instant::now();
-        let elapsed = now.duration_since(self.last_check).as_secs_f64();
-        self.last_check = now;
-        self.allowance =
-            (self.allowance + elapsed * self.limit_per_sec).min(self.limit_per_sec);
+    #[test]
+    fn local_scan_reports_each_file_before_the_remote_listing() {
+        let temp = tempdir().unwrap();
+        fs::create_dir(temp.path().join("nested")).unwrap();
+        fs::write(temp.path().join("a.txt"), b"a").unwrap();
+        fs::write(temp.path().join("nested/b.txt"), b"b").unwrap();
+        let remote = InMemoryRemote::default();
+        remote
+            .write_file(Path::new("/remote"), Path::new("c.txt"), b"c")
+            .unwrap();
 
-        let bytes_needed = bytes as f64;
-        if self.allowance >= bytes_needed {
-            self.allowance -= bytes_needed;
-            return;
-        }
+        let rule = SyncRule::new(temp.path(), "/remote", SyncDirection::Push);
+        let mut counts = Vec::new();
+        build_indexes(&rule, &FsLocalStore::default(), &remote, &mut |found| {
+            counts.push(found);
+            true
+        })
+        .unwrap();
+        // One report per local file, then the remote ones on top.
+        assert_eq!(counts, vec![1, 2, 3]);
 
-        let deficit = bytes_needed - self.allowance;
-        let sleep_seconds = deficit / self.limit_per_sec;
-        if sleep_seconds.is_finite() && sleep_seconds > 0.0 {
-            std::thread::sleep(Duration::from_secs_f64(sleep_seconds));
-        }
-        self.allowance = self.limit_per_sec - deficit.clamp(0.0, self.limit_per_sec);
+        let err = build_indexes(&rule, &FsLocalStore::default(), &remote, &mut |_| false)
+            .unwrap_err();
+        assert!(err.is::<ListingCanceled>());
     }
-}
 
-#[derive(Default, Clone)]
-pub struct InMemoryRemote {
-    entries: Arc<Mutex<HashMap<PathBuf, (Vec<u8>, SystemTime)>>>,
-}
+    #[test]
+    fn failure_groups_coalesce_by_root_cause() {
+        let failure = |path: &str, cause: &str| ExecutionFailure {
+            rule: "web".into(),
+            reason: format!("failed to remove {path}"),
+            cause: cause.into(),
+        };
+        let summary = ExecutionSummary {
+            failures: vec![
+                failure("a.txt", "connection reset"),
+                failure("b.txt", "permission denied"),
+                failure("c.txt", "connection reset"),
+                failure("d.txt", "connection reset"),
+            ],
+            ..ExecutionSummary::default()
+        };
 
-impl InMemoryRemote {
-    fn now() -> SystemTime {
-        SystemTime::now()
+        let groups = summary.failure_groups();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            (groups[0].cause.as_str(), groups[0].count),
+            ("connection reset", 3)
+        );
+        assert_eq!(groups[0].first.reason, "failed to remove a.txt");
+        assert_eq!(
+            (groups[1].cause.as_str(), groups[1].count),
+            ("permission denied", 1)
+        );
     }
-}
 
-impl RemoteStore for InMemoryRemote {
-    fn list(&self, _root: &Path) -> Result<Vec<FileEntry>> {
-        let entries = self.entries.lock().unwrap();
-        Ok(entries
-            .iter()
-            .map(|(path, (bytes, modified))| FileEntry {
-                path: path.clone(),
-                kind: EntryKind::File,
-                size: bytes.len() as u64,
-                modified: *modified,
+    #[test]
+    fn remote_command_failure_prefers_stderr_over_exit_code() {
+        let outcome = |command: &str, exit_code, stderr: &str| {
+            Ok(RemoteCommandOutcome {
+                command: command.into(),
+                exit_code,
+                stdout: String::new(),
+                stderr: stderr.into(),
             })
-            .collect())
-    }
+        };
+        let failure = |remote_commands| {
+            ExecutionSummary {
+                remote_commands,
+                ..ExecutionSummary::default()
+            }
+            .remote_command_failure()
+        };
 
-    fn read_file(&self, _root: &Path, rel_path: &Path) -> Result<Vec<u8>> {
-        let entries = self.entries.lock().unwrap();
-        entries
-            .get(rel_path)
-            .map(|(bytes, _)| bytes.clone())
-            .with_context(|| format!("remote missing {}", rel_path.display()))
+        assert_eq!(failure(vec![outcome("true", 0, "")]), None);
+        assert_eq!(
+            failure(vec![
+                outcome("true", 0, ""),
+                outcome("reload", 1, "unit not found")
+            ]),
+            Some("unit not found".into())
+        );
+        assert_eq!(
+            failure(vec![outcome("false", 1, "")]),
+            Some("`false` exited with code 1".into())
+        );
     }
 
-    fn write_file(&self, _root: &Path, rel_path: &Path, bytes: &[u8]) -> Result<()> {
-        let mut entries = self.entries.lock().unwrap();
-        entries.insert(rel_path.to_path_buf(), (bytes.to_vec(), Self::now()));
-        Ok(())
-    }
+    #[test]
+    fn mass_deletion_trips_on_count_or_share_of_destination() {
+        let job_deleting = |deletes: usize, remote_files: usize| {
+            let rule = SyncRule::new("/local", "/remote", SyncDirection::Push);
+            let remote_index: FileIndex = (0..remote_files)
+                .map(|index| {
+                    let path = PathBuf::from(format!("file{index}.txt"));
+                    let entry = FileEntry {
+                        path: path.clone(),
+                        kind: EntryKind::File,
+                        size: 1,
+                        modified: SystemTime::UNIX_EPOCH,
+                    };
+                    (path, entry)
+                })
+                .collect();
+            let actions = (0..deletes)
+                .map(|index| SyncAction::DeleteRemote {
+                    rel_path: PathBuf::from(format!("file{index}.txt")),
+                })
+                .collect();
+            SyncJob {
+                remote_index,
+                ..job_for(rule, actions)
+            }
+        };
 
-    fn remove_file(&self, _root: &Path, rel_path: &Path) -> Result<()> {
-        let mut entries = self.entries.lock().unwrap();
-        entries.remove(rel_path);
-        Ok(())
+        let emptied = mass_deletion(&[job_deleting(40, 40)], 1000, 50).unwrap();
+        assert_eq!(emptied.percent(), 100);
+        assert!(mass_deletion(&[job_deleting(5, 100)], 1000, 50).is_none());
+        assert!(mass_deletion(&[job_deleting(3, 4)], 1000, 50).is_none());
+        assert_eq!(
+            mass_deletion(&[job_deleting(5, 100)], 5, 0).map(|found| found.deletions),
+            Some(5)
+        );
+        assert!(mass_deletion(&[job_deleting(40, 40)], 0, 0).is_none());
     }
 
-    fn ensure_dir(&self, _root: &Path, _rel_path: &Path) -> Result<()> {
-        Ok(())
-    }
-}
+    #[test]
+    fn local_preflight_flags_missing_and_empty_roots() {
+        let temp = tempdir().unwrap();
+        let filled = temp.path().join("filled");
+        let empty = temp.path().join("empty");
+        fs::create_dir_all(&filled).unwrap();
+        fs::create_dir_all(&empty).unwrap();
+        fs::write(filled.join("a.txt"), b"hello").unwrap();
 
-#[derive(Default)]
-pub struct FsLocalStore;
+        let mut target = crate::model::sample_remote_targets().remove(0);
+        target.rules = vec![
+            SyncRule::new(&filled, "/filled", SyncDirection::Push),
+            SyncRule::new(&empty, "/empty", SyncDirection::Push),
+            SyncRule::new(temp.path().join("missing"), "/missing", SyncDirection::Push),
+            SyncRule::new(temp.path().join("pulled"), "/pulled", SyncDirection::Pull),
+        ];
+        let reports = local_preflight(&target);
 
-impl FsLocalStore {
-    fn full_path(root: &Path, rel_path: &Path) -> PathBuf {
-        if rel_path.as_os_str().is_empty() {
-            root.to_path_buf()
-        } else {
-            root.join(rel_path)
-        }
+        assert!(reports[0].issues.is_empty());
+        assert_eq!((reports[0].files, reports[0].bytes), (1, 5));
+        assert_eq!(reports[1].issues, vec![PreflightIssue::Empty]);
+        assert_eq!(reports[2].issues, vec![PreflightIssue::Missing]);
+        assert!(reports[3].issues.is_empty());
     }
 
-    fn collect(root: &Path, rel_path: &Path, output: &mut Vec<FileEntry>) -> Result<()> {
-        let dir = Self::full_path(root, rel_path);
-        if !dir.exists() {
-            return Ok(());
-        }
+    #[test]
+    fn share_links_use_resolved_remote_root() {
+        let mut target = crate::model::sample_remote_targets().remove(1);
+        target.username = "etl user".into();
+        let rule = SyncRule::new("./datasets", "incoming", SyncDirection::Pull);
 
-        for entry in fs::read_dir(&dir)? {
-            let entry = entry?;
-            let file_name = entry.file_name();
-            let child_rel = rel_path.join(file_name);
-            let metadata = entry.metadata()?;
-            if metadata.is_dir() {
-                Self::collect(root, &child_rel, output)?;
-            } else if metadata.is_file() {
-                output.push(FileEntry {
-                    path: child_rel,
-                    kind: EntryKind::File,
-                    size: metadata.len(),
-                    modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
-                });
-            }
-        }
+        assert_eq!(
+            sftp_url(&target, &rule, Some(Path::new("q1/report 1.csv"))),
+            "sftp://etl%20user@analytics.internal:2200/data/incoming/q1/report%201.csv"
+        );
+        assert_eq!(
+            scp_command(&target, &rule, None),
+            "scp -r -P 2200 'etl user@analytics.internal:/data/incoming' ."
+        );
 
-        Ok(())
+        target.base_path = PathBuf::new();
+        assert_eq!(
+            sftp_url(&target, &rule, None),
+            "sftp://etl%20user@analytics.internal:2200/~/incoming"
+        );
     }
-}
 
-impl LocalStore for FsLocalStore {
-    fn list(&self, root: &Path) -> Result<Vec<FileEntry>> {
-        let mut entries = Vec::new();
-        Self::collect(root, Path::new(""), &mut entries)?;
-        Ok(entries)
+    #[test]
+    fn plan_script_comments_out_deletions() {
+        let mut target = crate::model::sample_remote_targets().remove(1);
+        target.username = "etl".into();
+        let rule = SyncRule::new("/home/me/data", "/data/in", SyncDirection::Bidirectional);
+        let actions = vec![
+            SyncAction::Upload {
+                rel_path: PathBuf::from("a b.csv"),
+                size: 1,
+                renamed: None,
+            },
+            SyncAction::Download {
+                rel_path: PathBuf::from("it's.txt"),
+                size: 1,
+                renamed: None,
+            },
+            SyncAction::DeleteRemote {
+                rel_path: PathBuf::from("old.csv"),
+            },
+            SyncAction::DeleteLocal {
+                rel_path: PathBuf::from("gone.txt"),
+            },
+        ];
+        let job = job_for(rule, actions);
+
+        let script = plan_script(&target, [&job]);
+        let body: Vec<&str> = script.lines().skip_while(|line| !line.is_empty()).collect();
+        assert_eq!(
+            body,
+            [
+                "",
+                "# /home/me/data → /data/in",
+                "scp -P 2200 '/home/me/data/a b.csv' 'etl@analytics.internal:/data/in/a b.csv'",
+                r"scp -P 2200 'etl@analytics.internal:/data/in/it'\''s.txt' '/home/me/data/it'\''s.txt'",
+                r"# ssh -p 2200 'etl@analytics.internal' rm -- ''\''/data/in/old.csv'\'''",
+                "# rm -- '/home/me/data/gone.txt'",
+            ]
+        );
+        assert!(script.contains("ADVISORY ONLY"));
     }
 
-    fn read_file(&self, root: &Path, rel_path: &Path) -> Result<Vec<u8>> {
-        let path = Self::full_path(root, rel_path);
-        fs::read(&path).with_context(|| format!("failed to read {}", path.display()))
+    #[test]
+    fn plan_script_escapes_newlines_in_comments() {
+        let mut target = crate::model::sample_remote_targets().remove(1);
+        target.name = "evil\nrm -rf ~".into();
+        let rule = SyncRule::new("/home/me/data\nrm -rf ~", "/data/in", SyncDirection::Push);
+        let actions = vec![
+            SyncAction::DeleteRemote {
+                rel_path: PathBuf::from("a\nrm -rf ~"),
+            },
+            SyncAction::DeleteLocal {
+                rel_path: PathBuf::from("b\r\nrm -rf ~"),
+            },
+            SyncAction::Conflict {
+                rel_path: PathBuf::from("c\nrm -rf ~"),
+            },
+        ];
+        let job = job_for(rule, actions);
+
+        let script = plan_script(&target, [&job]);
+        assert!(
+            script
+                .lines()
+                .all(|line| line.is_empty() || line.starts_with('#')),
+            "{script}"
+        );
+        assert!(script.contains("# conflict, skipped: c\\nrm -rf ~"));
     }
 
-    fn write_file(&self, root: &Path, rel_path: &Path, bytes: &[u8]) -> Result<()> {
-        let path = Self::full_path(root, rel_path);
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create {}", parent.display()))?;
-        }
-        fs::write(&path, bytes).with_context(|| format!("failed to write {}", path.display()))
+    #[test]
+    fn resolve_remote_root_joins_base_path() {
+        let resolved =
+            super::resolve_remote_root(Path::new("/srv/www"), Path::new("apps/web"));
+        assert_eq!(resolved, PathBuf::from("/srv/www/apps/web"));
     }
 
-    fn remove_file(&self, root: &Path, rel_path: &Path) -> Result<()> {
-        let path = Self::full_path(root, rel_path);
-        if path.exists() {
-            fs::remove_file(&path)
-                .with_context(|| format!("failed to remove {}", path.display()))?;
-        }
-        Ok(())
+    #[test]
+    fn resolve_remote_root_preserves_absolute_paths() {
+        let resolved = super::resolve_remote_root(Path::new("/srv/www"), Path::new("/data"));
+        assert_eq!(resolved, PathBuf::from("/data"));
     }
 
-    fn ensure_dir(&self, root: &Path, rel_path: &Path) -> Result<()> {
-        let path = Self::full_path(root, rel_path);
-        fs::create_dir_all(&path).with_context(|| format!("failed to create {}", path.display()))
+    #[test]
+    fn resolve_remote_root_handles_empty_relative_path() {
+        let resolved = super::resolve_remote_root(Path::new("/srv/www"), Path::new(""));
+        assert_eq!(resolved, PathBuf::from("/srv/www"));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::{thread, time::Duration};
-    use tempfile::tempdir;
+    #[test]
+    fn rule_remote_for_prefers_paths_relative_to_the_base() {
+        let root = Path::new("/srv/www");
+        assert_eq!(
+            super::rule_remote_for(Path::new("/srv/www/apps/web"), root),
+            PathBuf::from("apps/web")
+        );
+        assert_eq!(
+            super::rule_remote_for(Path::new("/var/log"), root),
+            PathBuf::from("/var/log")
+        );
+        assert_eq!(
+            super::rule_remote_for(root, root),
+            PathBuf::from("/srv/www")
+        );
+    }
 
     #[test]
-    fn planner_detects_uploads_and_downloads() {
+    fn rules_nested_under_one_base_leave_each_others_files_alone() {
         let temp = tempdir().unwrap();
-        let local_root = temp.path().join("local");
-        fs::create_dir_all(&local_root).unwrap();
-        fs::write(local_root.join("only_local.txt"), b"local").unwrap();
-        fs::write(local_root.join("stale.txt"), b"outdated").unwrap();
-
-        thread::sleep(Duration::from_millis(600));
+        let site = temp.path().join("site");
+        let api = temp.path().join("api");
+        fs::create_dir_all(&site).unwrap();
+        fs::create_dir_all(&api).unwrap();
+        fs::write(site.join("index.html"), b"home").unwrap();
+        fs::write(api.join("handler.txt"), b"api").unwrap();
 
-        let remote = InMemoryRemote::default();
-        remote
-            .write_file(
-                Path::new("/remote"),
-                Path::new("only_remote.txt"),
-                b"remote",
-            )
+        // The mock ignores roots, so each rule gets the listing of its own folder.
+        let base = Path::new("/srv/www");
+        let base_remote = InMemoryRemote::default();
+        base_remote
+            .write_file(base, Path::new("index.html"), b"home")
             .unwrap();
-        remote
-            .write_file(Path::new("/remote"), Path::new("stale.txt"), b"fresh")
+        base_remote
+            .write_file(base, Path::new("stale.html"), b"old")
+            .unwrap();
+        base_remote
+            .write_file(base, Path::new("api/handler.txt"), b"api")
+            .unwrap();
+        let api_remote = InMemoryRemote::default();
+        api_remote
+            .write_file(&base.join("api"), Path::new("handler.txt"), b"api")
             .unwrap();
 
-        let rule = SyncRule {
-            local: local_root.clone(),
-            remote: PathBuf::from("/remote"),
-            direction: SyncDirection::Bidirectional,
+        let mut target = crate::model::sample_remote_targets().remove(0);
+        target.base_path = base.to_path_buf();
+        target.rules = vec![
+            SyncRule::new(site, "/srv/www", SyncDirection::Push),
+            SyncRule::new(api, "api", SyncDirection::Push),
+        ];
+        let context = PlanContext {
+            start_dir: None,
+            clock_skew: 0,
+            force: false,
+        };
+        let deleted = |rule: &SyncRule, remote: &InMemoryRemote| {
+            let job = plan_single_job(
+                &target,
+                rule,
+                context,
+                &FsLocalStore::default(),
+                remote,
+                &mut Vec::new(),
+                &mut |_| true,
+            )
+            .unwrap();
+            job.actions
+                .iter()
+                .filter_map(|action| match action {
+                    SyncAction::DeleteRemote { rel_path } => Some(rel_path.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
         };
 
-        let local_store = FsLocalStore::default();
-        let planner = SyncPlanner::new(&local_store, &remote);
-        let plan = planner.plan(&rule).unwrap();
-
-        assert_eq!(plan.stats.uploads, 1);
-        assert_eq!(plan.stats.downloads, 2);
-        assert_eq!(plan.actions.len(), 3);
+        assert_eq!(
+            deleted(&target.rules[0], &base_remote),
+            vec![PathBuf::from("stale.html")]
+        );
+        assert!(deleted(&target.rules[1], &api_remote).is_empty());
     }
 
     #[test]
-    fn executor_applies_plan_against_mock_remote() {
+    fn empty_source_blocks_the_plan_instead_of_wiping_the_destination() {
         let temp = tempdir().unwrap();
-        let local_root = temp.path().join("local");
-        fs::create_dir_all(&local_root).unwrap();
-        fs::write(local_root.join("upload.txt"), b"payload").unwrap();
-
         let remote = InMemoryRemote::default();
-        let rule = SyncRule {
-            local: local_root.clone(),
-            remote: PathBuf::from("/remote"),
-            direction: SyncDirection::Push,
+        for name in ["index.html", "about.html", "logo.png"] {
+            remote
+                .write_file(Path::new("/srv/www"), Path::new(name), b"live")
+                .unwrap();
+        }
+        let mut target = crate::model::sample_remote_targets().remove(0);
+        target.rules = vec![SyncRule::new(temp.path(), "/srv/www", SyncDirection::Push)];
+        let context = PlanContext {
+            start_dir: None,
+            clock_skew: 0,
+            force: false,
         };
+        let mut warnings = Vec::new();
+        let job = plan_single_job(
+            &target,
+            &target.rules[0],
+            context,
+            &FsLocalStore::default(),
+            &remote,
+            &mut warnings,
+            &mut |_| true,
+        )
+        .unwrap();
 
-        let local_store = FsLocalStore::default();
-        let planner = SyncPlanner::new(&local_store, &remote);
-        let plan = planner.plan(&rule).unwrap();
-        assert_eq!(plan.stats.uploads, 1);
+        assert_eq!(job.stats.empty_sources, 1);
+        assert_eq!(job.stats.deletes_remote, 3);
+        assert!(warnings.iter().any(|warning| warning.contains("lists no files")));
 
-        let executor_store = FsLocalStore::default();
-        let executor = SyncExecutor::new(&executor_store, &remote, None);
-        let logs = executor.execute(&plan);
-        assert!(matches!(logs[0].status, ActionStatus::Applied));
+        // A source with files in it is not flagged, whatever it deletes.
+        fs::write(temp.path().join("index.html"), b"live").unwrap();
+        let job = plan_single_job(
+            &target,
+            &target.rules[0],
+            context,
+            &FsLocalStore::default(),
+            &remote,
+            &mut Vec::new(),
+            &mut |_| true,
+        )
+        .unwrap();
+        assert_eq!(job.stats.empty_sources, 0);
+        assert_eq!(job.stats.deletes_remote, 2);
+    }
 
-        let bytes = remote
-            .read_file(Path::new("/remote"), Path::new("upload.txt"))
-            .unwrap();
-        assert_eq!(bytes, b"payload");
+    #[test]
+    fn resolved_remote_root_anchors_relative_paths_at_start_dir() {
+        let mut target = crate::model::sample_remote_targets().remove(0);
+        target.base_path = PathBuf::new();
+        let start_dir = Some(Path::new("/home/deploy"));
+        assert_eq!(
+            super::resolved_remote_root(&target, Path::new("site"), start_dir),
+            PathBuf::from("/home/deploy/site")
+        );
+        assert_eq!(
+            super::resolved_remote_root(&target, Path::new("/var/www"), start_dir),
+            PathBuf::from("/var/www")
+        );
+        assert_eq!(
+            super::resolved_remote_root(&target, Path::new("site"), None),
+            PathBuf::from("site")
+        );
+        // `~` is the login home even under a base path.
+        target.base_path = PathBuf::from("/srv/www");
+        assert_eq!(
+            super::resolved_remote_root(&target, Path::new("~/backups"), start_dir),
+            PathBuf::from("/home/deploy/backups")
+        );
+        assert_eq!(
+            super::resolved_remote_root(&target, Path::new("~"), start_dir),
+            PathBuf::from("/home/deploy")
+        );
     }
 
     #[test]
-    fn resolve_remote_root_joins_base_path() {
-        let resolved =
-            super::resolve_remote_root(Path::new("/srv/www"), Path::new("apps/web"));
-        assert_eq!(resolved, PathBuf::from("/srv/www/apps/web"));
+    fn expand_vars_replaces_every_syntax_and_reports_unset_names() {
+        let lookup = |name: &str| match name {
+            "HOME" => Some("/home/dev".into()),
+            "USERPROFILE" => Some(r"C:\Users\dev".into()),
+            "APP" => Some("web".into()),
+            _ => None,
+        };
+        let expand = |path: &str| super::expand_vars_with(Path::new(path), lookup);
+
+        assert_eq!(
+            expand("$HOME/projects/${APP}_v2").unwrap(),
+            PathBuf::from("/home/dev/projects/web_v2")
+        );
+        assert_eq!(
+            expand(r"%USERPROFILE%\code").unwrap(),
+            PathBuf::from(r"C:\Users\dev\code")
+        );
+        // Markers without a name stay literal.
+        assert_eq!(
+            expand("/srv/100%/$/${}").unwrap(),
+            PathBuf::from("/srv/100%/$/${}")
+        );
+        let err = expand("$HOME/$MISSING_DIR/site").unwrap_err();
+        assert_eq!(err.to_string(), "environment variable MISSING_DIR is not set");
     }
 
     #[test]
-    fn resolve_remote_root_preserves_absolute_paths() {
-        let resolved = super::resolve_remote_root(Path::new("/srv/www"), Path::new("/data"));
-        assert_eq!(resolved, PathBuf::from("/data"));
+    fn resolve_local_root_ignores_working_directory() {
+        let mut target = crate::model::sample_remote_targets().remove(0);
+        target.local_base = PathBuf::from("/home/dev/projects");
+        assert_eq!(
+            super::resolve_local_root(&target, Path::new("./apps/web")),
+            PathBuf::from("/home/dev/projects/apps/web")
+        );
+        assert_eq!(
+            super::resolve_local_root(&target, Path::new("/var/www")),
+            PathBuf::from("/var/www")
+        );
+
+        target.local_base = PathBuf::new();
+        let home = dirs::home_dir().unwrap_or_default();
+        assert_eq!(
+            super::resolve_local_root(&target, Path::new("./apps/web")),
+            home.join("apps/web")
+        );
     }
 
     #[test]
-    fn resolve_remote_root_handles_empty_relative_path() {
-        let resolved = super::resolve_remote_root(Path::new("/srv/www"), Path::new(""));
-        assert_eq!(resolved, PathBuf::from("/srv/www"));
+    fn interrupted_journal_resumes_only_unapplied_actions() {
+        let temp = tempdir().unwrap();
+        let rule = SyncRule::new("/local", "/remote", SyncDirection::Push);
+        let actions: Vec<SyncAction> = (0..4)
+            .map(|index| SyncAction::Upload {
+                rel_path: PathBuf::from(format!("file{index}.txt")),
+                size: 1,
+                renamed: None,
+            })
+            .collect();
+        let job = SyncJob {
+            id: 7,
+            target_id: 2,
+            ..job_for(rule, actions)
+        };
+
+        let mut writer = JournalWriter::create(temp.path(), &job, &job.plan.actions).unwrap();
+        writer.record(0);
+        writer.record(2);
+        // Simulate a crash: the writer goes away without finishing.
+        drop(writer);
+
+        let mut journals = ExecutionJournal::load_all(temp.path());
+        assert_eq!(journals.len(), 1);
+        let resumed = journals.remove(0).into_sync_job();
+        assert_eq!(resumed.id, 7);
+        assert_eq!(resumed.target_id, 2);
+        let paths: Vec<_> = resumed
+            .plan
+            .actions
+            .iter()
+            .map(|action| match action {
+                SyncAction::Upload { rel_path, .. } => rel_path.clone(),
+                other => panic!("unexpected action {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("file1.txt"), PathBuf::from("file3.txt")]
+        );
+
+        JournalWriter::create(temp.path(), &job, &job.plan.actions).unwrap().finish();
+        assert!(ExecutionJournal::load_all(temp.path()).is_empty());
+    }
+
+    #[test]
+    fn interrupted_upload_resumes_from_the_staged_copy() {
+        use sha2::{Digest, Sha256};
+
+        /// Drops the connection once after `cut_after` bytes of a staged write.
+        struct FlakyRemote {
+            inner: InMemoryRemote,
+            cut_after: Cell<Option<usize>>,
+            sent: Cell<usize>,
+            read_back: Cell<usize>,
+            hashes_in_place: Cell<bool>,
+        }
+
+        impl RemoteStore for FlakyRemote {
+            fn list(&self, root: &Path, options: &ListOptions) -> Result<Listing> {
+                self.inner.list(root, options)
+            }
+            fn read_file(&self, root: &Path, rel_path: &Path) -> Result<Vec<u8>> {
+                self.read_back.set(self.read_back.get() + 1);
+                self.inner.read_file(root, rel_path)
+            }
+            fn write_file(&self, root: &Path, rel_path: &Path, bytes: &[u8]) -> Result<()> {
+                self.inner.write_file(root, rel_path, bytes)
+            }
+            fn remove_file(&self, root: &Path, rel_path: &Path) -> Result<()> {
+                self.inner.remove_file(root, rel_path)
+            }
+            fn ensure_dir(&self, root: &Path, rel_path: &Path) -> Result<()> {
+                self.inner.ensure_dir(root, rel_path)
+            }
+            fn checksum_in_place(&self, root: &Path, rel_path: &Path) -> Option<String> {
+                self.hashes_in_place
+                    .get()
+                    .then(|| self.inner.checksum(root, rel_path).ok())
+                    .flatten()
+            }
+            fn resumes_uploads(&self) -> bool {
+                true
+            }
+            fn file_size(&self, root: &Path, rel_path: &Path) -> Option<u64> {
+                self.inner.file_size(root, rel_path)
+            }
+            fn write_file_at(
+                &self,
+                root: &Path,
+                rel_path: &Path,
+                offset: u64,
+                bytes: &[u8],
+                on_chunk: ChunkProgress<'_>,
+            ) -> Result<()> {
+                self.sent.set(self.sent.get() + bytes.len());
+                if let Some(cut) = self.cut_after.take() {
+                    self.inner
+                        .write_file_at(root, rel_path, offset, &bytes[..cut], on_chunk)?;
+                    return Err(anyhow!("connection reset"));
+                }
+                self.inner
+                    .write_file_at(root, rel_path, offset, bytes, on_chunk)
+            }
+            fn rename_file(&self, root: &Path, from: &Path, to: &Path) -> Result<()> {
+                self.inner.rename_file(root, from, to)
+            }
+        }
+
+        let temp = tempdir().unwrap();
+        let source: Vec<u8> = (0..RESUMABLE_UPLOAD_MIN * 3).map(|i| (i % 251) as u8).collect();
+        fs::write(temp.path().join("big.bin"), &source).unwrap();
+        let rule = SyncRule::new(temp.path(), "/remote", SyncDirection::Push);
+        let local = FsLocalStore::default();
+        let cut = 5 * TRANSFER_CHUNK + 123;
+        let remote = FlakyRemote {
+            inner: InMemoryRemote::default(),
+            cut_after: Cell::new(Some(cut)),
+            sent: Cell::new(0),
+            read_back: Cell::new(0),
+            hashes_in_place: Cell::new(true),
+        };
+        let staged = upload_staging_path(Path::new("big.bin"));
+
+        let plan = SyncPlanner::new(&local, &remote).plan(&rule).unwrap();
+        let logs = SyncExecutor::new(&local, &remote, None).execute(&plan);
+        assert!(matches!(logs[0].status, ActionStatus::Failed { .. }));
+        assert_eq!(remote.file_size(&rule.remote, &staged), Some(cut as u64));
+        assert!(remote.file_size(&rule.remote, Path::new("big.bin")).is_none());
+
+        // The staged copy is not itself something to sync.
+        let plan = SyncPlanner::new(&local, &remote).plan(&rule).unwrap();
+        assert_eq!(plan.actions.len(), 1);
+        remote.sent.set(0);
+        let logs = SyncExecutor::new(&local, &remote, None).execute(&plan);
+        assert!(matches!(logs[0].status, ActionStatus::Applied));
+        assert_eq!(remote.sent.get(), source.len() - cut);
+        assert!(remote.file_size(&rule.remote, &staged).is_none());
+
+        let uploaded = remote.read_file(&rule.remote, Path::new("big.bin")).unwrap();
+        assert_eq!(Sha256::digest(&uploaded), Sha256::digest(&source));
+
+        // A leftover whose last chunk matches but whose start doesn't is
+        // sent again in full.
+        let mut leftover = source[..cut].to_vec();
+        leftover[0] ^= 0xff;
+        remote.write_file(&rule.remote, &staged, &leftover).unwrap();
+        remote.remove_file(&rule.remote, Path::new("big.bin")).unwrap();
+        let plan = SyncPlanner::new(&local, &remote).plan(&rule).unwrap();
+        remote.sent.set(0);
+        let logs = SyncExecutor::new(&local, &remote, None).execute(&plan);
+        assert!(matches!(logs[0].status, ActionStatus::Applied));
+        assert_eq!(remote.sent.get(), source.len());
+        let uploaded = remote.read_file(&rule.remote, Path::new("big.bin")).unwrap();
+        assert_eq!(Sha256::digest(&uploaded), Sha256::digest(&source));
+
+        // A server that can't hash in place gets the file again in full
+        // rather than having the leftover downloaded to check it.
+        remote.hashes_in_place.set(false);
+        remote.write_file(&rule.remote, &staged, &source[..cut]).unwrap();
+        remote.remove_file(&rule.remote, Path::new("big.bin")).unwrap();
+        let plan = SyncPlanner::new(&local, &remote).plan(&rule).unwrap();
+        remote.sent.set(0);
+        remote.read_back.set(0);
+        let logs = SyncExecutor::new(&local, &remote, None).execute(&plan);
+        assert!(matches!(logs[0].status, ActionStatus::Applied));
+        assert_eq!((remote.sent.get(), remote.read_back.get()), (source.len(), 0));
     }
 }
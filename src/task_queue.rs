@@ -1,41 +1,249 @@
-use std::{thread, thread::available_parallelism};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    thread::available_parallelism,
+    time::{Duration, Instant},
+};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_channel::{bounded, Receiver as AsyncReceiver, Sender as AsyncSender};
 use crossbeam_channel::{unbounded, Receiver as SyncReceiver, Sender as SyncSender};
 use once_cell::sync::Lazy;
 
 use crate::{
-    model::{AppSettings, RemoteTarget},
+    config, hooks,
+    model::{AppSettings, RemoteTarget, TargetId, TaskKind},
     sync::{
-        execute_jobs_with_progress, plan_jobs_with_progress, ExecutionSummary, PlanJobsResult,
-        SyncJob,
+        execute_jobs_with_progress, plan_jobs_with_progress, ExecutionOptions, ExecutionSummary,
+        PlanJobsResult, SyncJob,
     },
 };
 
 pub enum TaskEvent<T> {
-    Progress { completed: usize, total: usize },
+    /// `bytes` is the running total transferred so far; always 0 while planning.
+    /// `discovered` counts local and remote files listed so far; always 0 while executing.
+    /// `connecting` is set while the worker is still opening the SSH session.
+    Progress {
+        completed: usize,
+        total: usize,
+        bytes: u64,
+        discovered: usize,
+        connecting: bool,
+    },
     Finished(Result<T>),
 }
 
+/// Minimum spacing between forwarded progress events (~20/sec).
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(50);
+/// Percentage jump that is forwarded even inside the interval.
+const PROGRESS_STEP_PERCENT: usize = 5;
+
+/// Coalesces per-action progress so large syncs don't flood the bounded
+/// responder channel. Completion (`completed == total`) always passes.
+struct ProgressThrottle {
+    last_sent: Option<Instant>,
+    last_percent: usize,
+    sent_final: bool,
+}
+
+impl ProgressThrottle {
+    fn new() -> Self {
+        Self {
+            last_sent: None,
+            last_percent: 0,
+            sent_final: false,
+        }
+    }
+
+    fn admit(&mut self, completed: usize, total: usize, now: Instant) -> bool {
+        let total = total.max(1);
+        let completed = completed.min(total);
+        let percent = completed * 100 / total;
+        let is_final = completed == total;
+        if is_final && self.sent_final {
+            return false;
+        }
+
+        let due = self
+            .last_sent
+            .is_none_or(|last| now.duration_since(last) >= PROGRESS_INTERVAL);
+        let jumped = percent >= self.last_percent + PROGRESS_STEP_PERCENT;
+        if !(is_final || due || jumped) {
+            return false;
+        }
+
+        self.last_sent = Some(now);
+        self.last_percent = percent;
+        self.sent_final |= is_final;
+        true
+    }
+}
+
+fn send_progress<T>(
+    respond_to: &AsyncSender<TaskEvent<T>>,
+    throttle: &mut ProgressThrottle,
+    completed: usize,
+    total: usize,
+    bytes: u64,
+    discovered: usize,
+) {
+    let total = total.max(1);
+    if throttle.admit(completed, total, Instant::now()) {
+        let _ = respond_to.send_blocking(TaskEvent::Progress {
+            completed: completed.min(total),
+            total,
+            bytes,
+            discovered,
+            connecting: false,
+        });
+    }
+}
+
+/// Sent unthrottled before the worker connects, so a slow handshake shows
+/// up as such. The next `send_progress` always passes and ends the phase.
+fn send_connecting<T>(respond_to: &AsyncSender<TaskEvent<T>>, total: usize) {
+    let _ = respond_to.send_blocking(TaskEvent::Progress {
+        completed: 0,
+        total: total.max(1),
+        bytes: 0,
+        discovered: 0,
+        connecting: true,
+    });
+}
+
+/// Progress of a running task as its worker last reported it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActiveTask {
+    pub kind: TaskKind,
+    pub completed: usize,
+    pub total: usize,
+    pub connecting: bool,
+}
+
+/// Running tasks by target, so a window opened mid-sync can show work it did
+/// not submit and has no receiver for.
+static ACTIVE_TASKS: Lazy<Mutex<HashMap<TargetId, ActiveTask>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn track(target_id: TargetId, task: ActiveTask) {
+    if let Ok(mut tasks) = ACTIVE_TASKS.lock() {
+        tasks.insert(target_id, task);
+    }
+}
+
+/// Leaves a task of the other kind for the same target in place.
+fn untrack(target_id: TargetId, kind: TaskKind) {
+    let Ok(mut tasks) = ACTIVE_TASKS.lock() else {
+        return;
+    };
+    if tasks.get(&target_id).is_some_and(|task| task.kind == kind) {
+        tasks.remove(&target_id);
+    }
+}
+
+/// Tasks a worker is running right now, with their latest progress.
+pub fn active_tasks() -> HashMap<TargetId, ActiveTask> {
+    ACTIVE_TASKS
+        .lock()
+        .map(|tasks| tasks.clone())
+        .unwrap_or_default()
+}
+
+/// Receiving side of a submitted task. Always yields exactly one
+/// `Finished` event: if the worker goes away without reporting, a failure is
+/// synthesized so the UI never stays "in progress".
+pub struct TaskReceiver<T> {
+    inner: AsyncReceiver<TaskEvent<T>>,
+    finished: bool,
+}
+
+impl<T> TaskReceiver<T> {
+    fn new(inner: AsyncReceiver<TaskEvent<T>>) -> Self {
+        Self {
+            inner,
+            finished: false,
+        }
+    }
+
+    pub async fn recv(&mut self) -> Option<TaskEvent<T>> {
+        if self.finished {
+            return None;
+        }
+        let event = self.inner.recv().await.unwrap_or_else(|_| {
+            TaskEvent::Finished(Err(anyhow!(
+                "task worker stopped before reporting a result"
+            )))
+        });
+        if matches!(event, TaskEvent::Finished(_)) {
+            self.finished = true;
+        }
+        Some(event)
+    }
+}
+
+/// Runs a task body, turning a panic into an error so the worker survives
+/// and the caller still gets its `Finished` event.
+fn run_guarded<T>(task: impl FnOnce() -> Result<T>) -> Result<T> {
+    catch_unwind(AssertUnwindSafe(task)).unwrap_or_else(|_| Err(anyhow!("task worker panicked")))
+}
+
 type PlanResponder = AsyncSender<TaskEvent<PlanJobsResult>>;
 type ExecuteResponder = AsyncSender<TaskEvent<ExecutionSummary>>;
 
 enum TaskMessage {
     Plan {
         target: RemoteTarget,
+        force: bool,
+        cancel: Arc<AtomicBool>,
         respond_to: PlanResponder,
     },
     Execute {
         target: RemoteTarget,
         jobs: Vec<SyncJob>,
         settings: AppSettings,
+        dry_run: bool,
+        cancel: Arc<AtomicBool>,
         respond_to: ExecuteResponder,
     },
 }
 
+impl TaskMessage {
+    /// Answers a task that will never run and releases its cancel flag.
+    fn discard(self) {
+        let reason = || anyhow!("discarded by abort all before it started");
+        match self {
+            TaskMessage::Plan {
+                target,
+                cancel,
+                respond_to,
+                ..
+            } => {
+                PLAN_CANCELS.forget(target.id, &cancel);
+                let _ = respond_to.send_blocking(TaskEvent::Finished(Err(reason())));
+            }
+            TaskMessage::Execute {
+                target,
+                cancel,
+                respond_to,
+                ..
+            } => {
+                EXECUTE_CANCELS.forget(target.id, &cancel);
+                let _ = respond_to.send_blocking(TaskEvent::Finished(Err(reason())));
+            }
+        }
+    }
+}
+
 struct TaskQueue {
     sender: SyncSender<TaskMessage>,
+    /// Kept so queued messages can be drained without a worker.
+    pending: SyncReceiver<TaskMessage>,
 }
 
 impl TaskQueue {
@@ -44,7 +252,10 @@ impl TaskQueue {
         for index in 0..worker_count {
             spawn_worker(rx.clone(), index);
         }
-        Self { sender: tx }
+        Self {
+            sender: tx,
+            pending: rx,
+        }
     }
 
     fn submit(&self, task: TaskMessage) {
@@ -52,52 +263,152 @@ impl TaskQueue {
     }
 }
 
+/// Answers every task still waiting in the queue; returns how many there were.
+fn discard_pending(pending: &SyncReceiver<TaskMessage>) -> usize {
+    pending.try_iter().map(TaskMessage::discard).count()
+}
+
 fn spawn_worker(receiver: SyncReceiver<TaskMessage>, index: usize) {
     thread::Builder::new()
         .name(format!("task-worker-{index}"))
         .spawn(move || {
             while let Ok(task) = receiver.recv() {
                 match task {
-                    TaskMessage::Plan { target, respond_to } => {
+                    TaskMessage::Plan {
+                        target,
+                        force,
+                        cancel,
+                        respond_to,
+                    } => {
                         let rules_total = target.rules.len().max(1);
-                        let _ = respond_to.send_blocking(TaskEvent::Progress {
-                            completed: 0,
-                            total: rules_total,
-                        });
-                        let result = plan_jobs_with_progress(&target, |completed, total| {
-                            let total = total.max(1);
-                            let _ = respond_to.send_blocking(TaskEvent::Progress {
-                                completed: completed.min(total),
-                                total,
-                            });
+                        let throttle = RefCell::new(ProgressThrottle::new());
+                        let rules_done = Cell::new(0);
+                        let discovered = Cell::new(0);
+                        let report = || {
+                            track(
+                                target.id,
+                                ActiveTask {
+                                    kind: TaskKind::Planning,
+                                    completed: rules_done.get(),
+                                    total: rules_total,
+                                    connecting: false,
+                                },
+                            );
+                            send_progress(
+                                &respond_to,
+                                &mut throttle.borrow_mut(),
+                                rules_done.get(),
+                                rules_total,
+                                0,
+                                discovered.get(),
+                            );
+                        };
+                        track(
+                            target.id,
+                            ActiveTask {
+                                kind: TaskKind::Planning,
+                                completed: 0,
+                                total: rules_total,
+                                connecting: true,
+                            },
+                        );
+                        send_connecting(&respond_to, rules_total);
+                        let result = run_guarded(|| {
+                            plan_jobs_with_progress(
+                                &target,
+                                force,
+                                |completed, _total| {
+                                    rules_done.set(completed);
+                                    report();
+                                },
+                                |found| {
+                                    discovered.set(found);
+                                    report();
+                                    !cancel.load(Ordering::Relaxed)
+                                },
+                            )
                         });
+                        if result.is_ok() {
+                            rules_done.set(rules_total);
+                            report();
+                        }
+                        untrack(target.id, TaskKind::Planning);
+                        PLAN_CANCELS.forget(target.id, &cancel);
                         let _ = respond_to.send_blocking(TaskEvent::Finished(result));
                     }
                     TaskMessage::Execute {
                         target,
                         jobs,
                         settings,
+                        dry_run,
+                        cancel,
                         respond_to,
                     } => {
                         let total_actions: usize =
                             jobs.iter().map(|job| job.plan.actions.len()).sum::<usize>().max(1);
-                        let _ = respond_to.send_blocking(TaskEvent::Progress {
-                            completed: 0,
-                            total: total_actions,
-                        });
-                        let limit = if settings.limit_bandwidth {
-                            Some(settings.bandwidth_mbps)
-                        } else {
-                            None
+                        let mut throttle = ProgressThrottle::new();
+                        track(
+                            target.id,
+                            ActiveTask {
+                                kind: TaskKind::Executing,
+                                completed: 0,
+                                total: total_actions,
+                                connecting: true,
+                            },
+                        );
+                        send_connecting(&respond_to, total_actions);
+                        let options = ExecutionOptions {
+                            cancel: Some(cancel.clone()),
+                            journal_dir: config::journal_dir(),
+                            dry_run,
+                            ..ExecutionOptions::from_settings(&settings)
                         };
-                        let result =
-                            execute_jobs_with_progress(&target, &jobs, limit, |completed, total| {
-                                let total = total.max(1);
-                                let _ = respond_to.send_blocking(TaskEvent::Progress {
-                                    completed: completed.min(total),
-                                    total,
-                                });
-                            });
+                        let mut transferred = 0;
+                        let result = run_guarded(|| {
+                            execute_jobs_with_progress(
+                                &target,
+                                &jobs,
+                                &options,
+                                |completed, total, bytes| {
+                                    transferred = bytes;
+                                    track(
+                                        target.id,
+                                        ActiveTask {
+                                            kind: TaskKind::Executing,
+                                            completed,
+                                            total,
+                                            connecting: false,
+                                        },
+                                    );
+                                    send_progress(
+                                        &respond_to,
+                                        &mut throttle,
+                                        completed,
+                                        total,
+                                        bytes,
+                                        0,
+                                    );
+                                },
+                            )
+                        });
+                        if result.is_ok() {
+                            send_progress(
+                                &respond_to,
+                                &mut throttle,
+                                total_actions,
+                                total_actions,
+                                transferred,
+                                0,
+                            );
+                        }
+                        let result = result.map(|mut summary| {
+                            if !dry_run {
+                                summary.hook = hooks::run_post_sync(&target, &summary);
+                            }
+                            summary
+                        });
+                        untrack(target.id, TaskKind::Executing);
+                        EXECUTE_CANCELS.forget(target.id, &cancel);
                         let _ = respond_to.send_blocking(TaskEvent::Finished(result));
                     }
                 }
@@ -113,26 +424,267 @@ static TASK_QUEUE: Lazy<TaskQueue> = Lazy::new(|| {
     TaskQueue::new(workers)
 });
 
-pub fn submit_plan(target: RemoteTarget) -> AsyncReceiver<TaskEvent<PlanJobsResult>> {
+/// Cancel flags of tasks still queued or running, by target.
+#[derive(Default)]
+struct CancelRegistry(Mutex<HashMap<TargetId, Vec<Arc<AtomicBool>>>>);
+
+impl CancelRegistry {
+    fn register(&self, target_id: TargetId) -> Arc<AtomicBool> {
+        let cancel = Arc::new(AtomicBool::new(false));
+        if let Ok(mut cancels) = self.0.lock() {
+            cancels.entry(target_id).or_default().push(cancel.clone());
+        }
+        cancel
+    }
+
+    /// Flags every task of `target_id`; returns how many were flagged.
+    fn cancel(&self, target_id: TargetId) -> usize {
+        let Ok(cancels) = self.0.lock() else {
+            return 0;
+        };
+        cancels.get(&target_id).map_or(0, |flags| raise(flags))
+    }
+
+    fn cancel_all(&self) -> usize {
+        let Ok(cancels) = self.0.lock() else {
+            return 0;
+        };
+        cancels.values().map(|flags| raise(flags)).sum()
+    }
+
+    fn is_idle(&self) -> bool {
+        self.0.lock().map_or(true, |cancels| cancels.is_empty())
+    }
+
+    fn forget(&self, target_id: TargetId, cancel: &Arc<AtomicBool>) {
+        let Ok(mut cancels) = self.0.lock() else {
+            return;
+        };
+        if let Some(flags) = cancels.get_mut(&target_id) {
+            flags.retain(|flag| !Arc::ptr_eq(flag, cancel));
+            if flags.is_empty() {
+                cancels.remove(&target_id);
+            }
+        }
+    }
+}
+
+fn raise(flags: &[Arc<AtomicBool>]) -> usize {
+    for flag in flags {
+        flag.store(true, Ordering::Relaxed);
+    }
+    flags.len()
+}
+
+static PLAN_CANCELS: Lazy<CancelRegistry> = Lazy::new(CancelRegistry::default);
+static EXECUTE_CANCELS: Lazy<CancelRegistry> = Lazy::new(CancelRegistry::default);
+
+/// How often `shutdown` re-checks for running tasks.
+const SHUTDOWN_POLL: Duration = Duration::from_millis(20);
+
+/// Waits until every registry is empty, i.e. no task is queued or running.
+fn wait_until_idle(registries: &[&CancelRegistry], timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while !registries.iter().all(|registry| registry.is_idle()) {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(SHUTDOWN_POLL);
+    }
+    true
+}
+
+pub fn submit_plan(target: RemoteTarget) -> TaskReceiver<PlanJobsResult> {
+    submit_plan_task(target, false)
+}
+
+/// Plans a forced full sync: every selected file is transferred in the rule
+/// direction, whatever its timestamps say.
+pub fn submit_full_plan(target: RemoteTarget) -> TaskReceiver<PlanJobsResult> {
+    submit_plan_task(target, true)
+}
+
+fn submit_plan_task(target: RemoteTarget, force: bool) -> TaskReceiver<PlanJobsResult> {
     let (tx, rx) = bounded(16);
+    let cancel = PLAN_CANCELS.register(target.id);
     TASK_QUEUE.submit(TaskMessage::Plan {
         target,
+        force,
+        cancel,
         respond_to: tx,
     });
-    rx
+    TaskReceiver::new(rx)
+}
+
+/// Stops plans for `target_id` at their next listed file; they then finish
+/// with an error. Returns whether a plan was in flight.
+pub fn cancel_plan(target_id: TargetId) -> bool {
+    PLAN_CANCELS.cancel(target_id) > 0
+}
+
+/// What `abort_all` stopped.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AbortSummary {
+    /// Tasks that never started; they finish with a "discarded" error.
+    pub discarded: usize,
+    /// Running tasks flagged to stop at their next file or action.
+    pub interrupted: usize,
+}
+
+/// Cancels every plan and execution: queued tasks are dropped from the
+/// queue, running ones stop at their next checkpoint.
+pub fn abort_all() -> AbortSummary {
+    let discarded = discard_pending(&TASK_QUEUE.pending);
+    AbortSummary {
+        discarded,
+        interrupted: PLAN_CANCELS.cancel_all() + EXECUTE_CANCELS.cancel_all(),
+    }
+}
+
+/// Aborts everything like `abort_all`, then gives running tasks up to
+/// `timeout` to reach their checkpoint and finish. Returns whether the
+/// workers went idle in time.
+pub fn shutdown(timeout: Duration) -> bool {
+    abort_all();
+    wait_until_idle(&[&PLAN_CANCELS, &EXECUTE_CANCELS], timeout)
 }
 
 pub fn submit_execute(
     target: RemoteTarget,
     jobs: Vec<SyncJob>,
     settings: AppSettings,
-) -> AsyncReceiver<TaskEvent<ExecutionSummary>> {
+) -> TaskReceiver<ExecutionSummary> {
+    submit_execute_task(target, jobs, settings, false)
+}
+
+/// Runs `jobs` as a dry run: every action is decided as in a real run, then
+/// reported instead of applied.
+pub fn submit_simulation(
+    target: RemoteTarget,
+    jobs: Vec<SyncJob>,
+    settings: AppSettings,
+) -> TaskReceiver<ExecutionSummary> {
+    submit_execute_task(target, jobs, settings, true)
+}
+
+fn submit_execute_task(
+    target: RemoteTarget,
+    jobs: Vec<SyncJob>,
+    settings: AppSettings,
+    dry_run: bool,
+) -> TaskReceiver<ExecutionSummary> {
     let (tx, rx) = bounded(16);
+    let cancel = EXECUTE_CANCELS.register(target.id);
     TASK_QUEUE.submit(TaskMessage::Execute {
         target,
         jobs,
         settings,
+        dry_run,
+        cancel,
         respond_to: tx,
     });
-    rx
+    TaskReceiver::new(rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    fn poll_ready<F: Future>(future: F) -> F::Output {
+        let mut cx = Context::from_waker(Waker::noop());
+        match pin!(future).poll(&mut cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future was not ready"),
+        }
+    }
+
+    #[test]
+    fn throttle_coalesces_bursts_but_always_passes_completion() {
+        let mut throttle = ProgressThrottle::new();
+        let start = Instant::now();
+        assert!(throttle.admit(0, 1000, start));
+        let forwarded = (1..1000)
+            .filter(|completed| throttle.admit(*completed, 1000, start))
+            .count();
+        assert!(forwarded <= 100 / PROGRESS_STEP_PERCENT);
+        assert!(throttle.admit(1000, 1000, start));
+        assert!(!throttle.admit(1000, 1000, start));
+    }
+
+    #[test]
+    fn dropped_worker_still_yields_a_finished_event() {
+        let (tx, rx) = bounded::<TaskEvent<()>>(1);
+        drop(tx);
+        let mut receiver = TaskReceiver::new(rx);
+        let event = poll_ready(receiver.recv());
+        assert!(matches!(event, Some(TaskEvent::Finished(Err(_)))));
+        assert!(poll_ready(receiver.recv()).is_none());
+    }
+
+    #[test]
+    fn discarding_pending_tasks_answers_each_one() {
+        let (sender, pending) = unbounded();
+        let mut receivers = Vec::new();
+        for target in crate::model::sample_remote_targets() {
+            let (tx, rx) = bounded(1);
+            let cancel = PLAN_CANCELS.register(target.id);
+            sender
+                .send(TaskMessage::Plan {
+                    target,
+                    force: false,
+                    cancel,
+                    respond_to: tx,
+                })
+                .unwrap();
+            receivers.push(TaskReceiver::new(rx));
+        }
+
+        assert_eq!(discard_pending(&pending), 2);
+        assert_eq!(discard_pending(&pending), 0);
+        for mut receiver in receivers {
+            let Some(TaskEvent::Finished(Err(err))) = poll_ready(receiver.recv()) else {
+                panic!("discarded task did not finish with an error");
+            };
+            assert!(err.to_string().contains("discarded"));
+        }
+    }
+
+    #[test]
+    fn shutdown_wait_is_bounded_by_the_timeout() {
+        let registry = Arc::new(CancelRegistry::default());
+        let cancel = registry.register(7);
+        assert!(!wait_until_idle(&[&registry], Duration::from_millis(30)));
+
+        let worker = {
+            let registry = registry.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(30));
+                registry.forget(7, &cancel);
+            })
+        };
+        assert!(wait_until_idle(&[&registry], Duration::from_secs(5)));
+        worker.join().unwrap();
+    }
+
+    #[test]
+    fn finished_plan_leaves_a_running_execution_tracked() {
+        let target_id = 9_001;
+        let executing = ActiveTask {
+            kind: TaskKind::Executing,
+            completed: 3,
+            total: 10,
+            connecting: false,
+        };
+        track(target_id, executing);
+        untrack(target_id, TaskKind::Planning);
+        assert_eq!(active_tasks().get(&target_id), Some(&executing));
+
+        untrack(target_id, TaskKind::Executing);
+        assert!(!active_tasks().contains_key(&target_id));
+    }
 }
@@ -1,26 +1,46 @@
 use std::{
-    collections::hash_map::DefaultHasher,
+    collections::{HashMap, hash_map::DefaultHasher},
     hash::{Hash, Hasher},
-    path::PathBuf,
-    time::{Duration, SystemTime},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
 };
 
 use crate::{
-    config::save_state,
-    connection,
+    config::{self, save_state},
+    connection::{self, PortOrigin},
+    diagnostics, health,
+    hooks::HookOutcome,
+    log_file,
     model::{
-        ActiveView, AppSettings, AppState, AuthMethod, ConnectionTestState, Language, LogLevel,
-        RemoteTarget, SyncDirection, SyncRule, SyncSession, SyncStatus, TargetFormMode, TargetId,
-        TaskKind, TaskProgress,
+        ActiveView, AppSettings, AppState, AuthMethod, CLOCK_SKEW_WARN_SECS, CollisionPolicy,
+        ComparePolicy, ComparisonReport, ConflictPolicy, ConnectionTestState,
+        DEFAULT_MAX_CONNECTIONS, DEFAULT_MAX_DEPTH, DEFAULT_MAX_ENTRIES, DEFAULT_SKIP_NAMES,
+        Language, LineEndings, LogLevel, MAX_CONNECTIONS_LIMIT, OverwritePolicy, PostSyncHook,
+        RemoteCommands, RemoteTarget, RuleStatus, SOCKET_BUFFER_STEPS_KIB, ScopeChange,
+        SessionRecord, SessionSort, SetupStep, SshAlgorithms, SyncAllProgress, SyncDirection,
+        SyncRule, SyncSession, SyncStatus, TargetColor, TargetFormMode, TargetHealth, TargetId,
+        TargetOverview, TargetSort, TaskKind, TaskProgress, ThroughputHistory, parse_patterns,
+        parse_tags,
     },
-    sync::{SyncAction, SyncJob},
-    task_queue::{self, TaskEvent},
-    watcher::{self, WatchTarget},
+    pool,
+    secrets::{self, SecretSlot},
+    sync::{
+        self, DriftSummary, ExecutionJournal, ExecutionOptions, ExecutionSummary, HeldDeletion,
+        PlanJobsResult, PlanStats, PreviewChange, PreviewRow, RemoteCommandOutcome,
+        SftpRemoteStore, SpaceCheck, SyncAction, SyncJob, TargetSnapshot, TransferTotals,
+    },
+    task_queue::{self, ActiveTask, TaskEvent, TaskReceiver},
+    watcher::{self, WatchBackend, WatchTarget},
 };
-use anyhow::Error;
+use anyhow::{Error, Result, anyhow};
 use gpui::{
-    App, AppContext, AsyncApp, Axis, Context, Div, Entity, IntoElement, ParentElement as _, Render,
-    Styled as _, Window, div, prelude::FluentBuilder as _,
+    App, AppContext, AsyncApp, Axis, ClipboardItem, Context, Div, ElementId, Entity, ExternalPaths,
+    InteractiveElement as _, IntoElement, ParentElement as _, PathPromptOptions, Render,
+    Stateful, StatefulInteractiveElement as _, Styled as _, Window, div,
+    prelude::FluentBuilder as _, px, rgb,
 };
 use gpui_component::{
     ActiveTheme, ContextModal, Disableable, Icon, IconName, Root, Sizable as _, StyledExt,
@@ -38,23 +58,97 @@ pub struct AppView {
     state: Entity<AppState>,
     target_form_view: Option<Entity<TargetFormView>>,
     current_form_mode: Option<TargetFormMode>,
-    bootstrapped: bool,
+    profile_input: Option<Entity<InputState>>,
     watch_listener_started: bool,
     last_watch_signature: Option<u64>,
     auto_connect_triggered: bool,
+    drift_checked: bool,
+    health_monitor_started: bool,
 }
 
+/// How often the health monitor looks for targets whose check is due.
+const HEALTH_TICK: Duration = Duration::from_secs(30);
+
 impl AppView {
     pub fn new(state: Entity<AppState>) -> Self {
         Self {
             state,
             target_form_view: None,
             current_form_mode: None,
-            bootstrapped: false,
+            profile_input: None,
             watch_listener_started: false,
             last_watch_signature: None,
             auto_connect_triggered: false,
+            drift_checked: false,
+            health_monitor_started: false,
+        }
+    }
+
+    /// Pings each monitored target once `health_check_mins` have passed since
+    /// its last check. Targets whose password is only asked at connect time
+    /// are left out, as there is no one to ask.
+    fn ensure_health_monitor(&mut self, cx: &mut Context<Self>) {
+        if self.health_monitor_started {
+            return;
         }
+        self.health_monitor_started = true;
+
+        let handle = self.state.clone();
+        let app: &mut App = cx;
+        app.spawn(async move |cx| {
+            loop {
+                let Ok(due) = handle.update(cx, |state, _| {
+                    let unmonitored = state
+                        .health
+                        .keys()
+                        .copied()
+                        .filter(|id| {
+                            !state
+                                .remote_targets
+                                .iter()
+                                .any(|target| target.id == *id && target.monitor_health)
+                        })
+                        .collect::<Vec<_>>();
+                    for id in unmonitored {
+                        state.health.remove(&id);
+                        pool::forget(id);
+                    }
+                    let mins = u64::from(state.settings.health_check_mins.max(1));
+                    let interval = Duration::from_secs(mins * 60);
+                    state
+                        .remote_targets
+                        .iter()
+                        .filter(|target| {
+                            target.enabled
+                                && target.monitor_health
+                                && !target.needs_session_secret()
+                        })
+                        .filter(|target| {
+                            state
+                                .health
+                                .get(&target.id)
+                                .is_none_or(|health| health.is_due(interval))
+                        })
+                        .cloned()
+                        .collect::<Vec<RemoteTarget>>()
+                }) else {
+                    break;
+                };
+                for target in due {
+                    let probe = target.clone();
+                    let result = cx
+                        .background_spawn(async move { health::check(&probe) })
+                        .await;
+                    let _ = handle.update(cx, |state, cx| {
+                        state.record_health(target.id, result.err().map(|err| format!("{err:#}")));
+                        cx.notify();
+                    });
+                }
+                cx.background_executor().timer(HEALTH_TICK).await;
+            }
+            Ok::<_, Error>(())
+        })
+        .detach();
     }
 
     fn ensure_watch_listener(&mut self, cx: &mut Context<Self>) {
@@ -75,7 +169,8 @@ impl AppView {
                             state
                                 .remote_targets
                                 .iter()
-                                .find(|target| target.id == event.target_id)
+                                .find(|target| target.id == event.target_id && target.enabled)
+                                .filter(|target| !target.needs_session_secret())
                                 .cloned()
                         })
                         .ok()
@@ -100,13 +195,15 @@ impl AppView {
         self.watch_listener_started = true;
     }
 
-    fn configure_watchers(&mut self, enabled: bool, targets: &[RemoteTarget]) {
+    fn configure_watchers(&mut self, settings: &AppSettings, targets: &[RemoteTarget]) {
+        let enabled = settings.watch_local_changes;
         let mut hasher = DefaultHasher::new();
         enabled.hash(&mut hasher);
-        for target in targets {
+        settings.watch_poll_secs.hash(&mut hasher);
+        for target in targets.iter().filter(|target| target.enabled) {
             target.id.hash(&mut hasher);
             for rule in &target.rules {
-                rule.local.hash(&mut hasher);
+                sync::resolve_local_root(target, &rule.local).hash(&mut hasher);
             }
         }
         let signature = hasher.finish();
@@ -118,24 +215,83 @@ impl AppView {
         let configs = if enabled {
             targets
                 .iter()
+                .filter(|target| target.enabled)
                 .map(|target| WatchTarget {
                     target_id: target.id,
-                    roots: target.rules.iter().map(|rule| rule.local.clone()).collect(),
+                    roots: target
+                        .rules
+                        .iter()
+                        .map(|rule| sync::resolve_local_root(target, &rule.local))
+                        .collect(),
                 })
                 .collect()
         } else {
             Vec::new()
         };
 
-        watcher::configure(enabled, configs);
+        watcher::configure(
+            enabled,
+            configs,
+            Duration::from_secs(settings.watch_poll_secs.max(1)),
+        );
     }
 
     fn bootstrap_targets(&mut self, targets: &[RemoteTarget], cx: &mut Context<Self>) {
         let handle = self.state.clone();
-        for target in targets.to_vec() {
+        // Work a previous window started keeps going; follow it instead of
+        // treating its journal as interrupted or planning over it.
+        let running = task_queue::active_tasks();
+        let journal_dir = config::journal_dir();
+        let (journals, orphaned): (Vec<_>, Vec<_>) = journal_dir
+            .as_deref()
+            .map(ExecutionJournal::load_all)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|journal| {
+                !running
+                    .get(&journal.target_id)
+                    .is_some_and(|task| task.kind == TaskKind::Executing)
+            })
+            .partition(|journal| targets.iter().any(|target| target.id == journal.target_id));
+        if let Some(dir) = journal_dir.as_deref() {
+            for journal in &orphaned {
+                ExecutionJournal::discard(dir, journal.session_id);
+            }
+        }
+        handle.update(cx, |state, _| {
+            for journal in &journals {
+                let Some(target) = targets.iter().find(|target| target.id == journal.target_id)
+                else {
+                    continue;
+                };
+                state.log_target_event(
+                    target.id,
+                    LogLevel::Warn,
+                    format!(
+                        "Sync of {} for {} was interrupted with {} of {} actions left",
+                        journal.rule.label(),
+                        target.name,
+                        journal.remaining().len(),
+                        journal.actions.len()
+                    ),
+                );
+            }
+            state.restore_interrupted(journals);
+        });
+        // Session-only passwords are asked for on the first manual connect instead.
+        for target in targets
+            .iter()
+            .filter(|target| {
+                target.enabled
+                    && !target.needs_session_secret()
+                    && !running.contains_key(&target.id)
+            })
+            .cloned()
+        {
             schedule_plan_for_target(
                 &handle,
                 target.clone(),
+                false,
                 Some(format!(
                     "Preparing sync plan for {}",
                     target.name
@@ -144,11 +300,12 @@ impl AppView {
             );
         }
 
+        reattach_running_tasks(&handle, targets, running, cx);
+
         handle.update(cx, |state, cx| {
             state.bootstrap_pending = false;
             cx.notify();
         });
-        self.bootstrapped = true;
     }
 
     fn schedule_plan_for_target_async(
@@ -170,7 +327,7 @@ impl AppView {
 
         let _ = state_handle.update(cx, |state, cx| {
             if let Some(message) = log_message.clone() {
-                state.log_event(LogLevel::Info, message);
+                state.log_target_event(target_id, LogLevel::Info, message);
             }
 
             let mut touched = false;
@@ -202,16 +359,24 @@ impl AppView {
             cx.notify();
         });
 
-        let receiver = task_queue::submit_plan(target.clone());
+        let mut receiver = task_queue::submit_plan(target.clone());
         let handle = state_handle.clone();
         cx.spawn(async move |cx| {
-            while let Ok(event) = receiver.recv().await {
+            while let Some(event) = receiver.recv().await {
                 match event {
-                    TaskEvent::Progress { completed, total } => {
+                    TaskEvent::Progress {
+                        completed,
+                        total,
+                        discovered,
+                        connecting,
+                        ..
+                    } => {
                         let _ = handle.update(cx, |state, cx| {
                             state.set_task_progress(
                                 target_id,
-                                TaskProgress::new(TaskKind::Planning, completed, total),
+                                TaskProgress::new(TaskKind::Planning, completed, total)
+                                    .with_discovered(discovered)
+                                    .with_connecting(connecting),
                             );
                             cx.notify();
                         });
@@ -231,11 +396,13 @@ impl AppView {
                                         .filter(|job| job.target_id == target_id)
                                         .map(|job| job.pending_actions())
                                         .sum();
-                                    state.log_event(
+                                    state.log_target_event(
+                                        target_id,
                                         LogLevel::Info,
                                         format!(
                                             "Sync plan ready for {} ({} actions)",
-                                            target_name, pending
+                                            target_name,
+                                            format_count(pending)
                                         ),
                                     );
                                     cx.notify();
@@ -243,7 +410,8 @@ impl AppView {
                             }
                             Err(err) => {
                                 let _ = handle.update(cx, |state, cx| {
-                                    state.log_event(
+                                    state.log_target_event(
+                                        target_id,
                                         LogLevel::Error,
                                         format!(
                                             "Failed to prepare sync plan for {}: {err}",
@@ -276,41 +444,188 @@ impl Render for AppView {
             target_form_mode,
             connection_tests,
             task_progress_map,
+            throughput_map,
             bootstrap_pending,
+            sorted_targets,
+            sorted_sessions,
+            tag_filter,
         ) = {
             let state = self.state.read(cx);
             (
                 state.remote_targets.clone(),
-                state.sessions.clone(),
-                state.logs.clone(),
+                state.live_sessions(),
+                state.visible_logs(),
                 state.active_target,
                 state.active_view,
                 state.settings.clone(),
                 state.target_form,
                 state.connection_tests.clone(),
                 state.task_progress.clone(),
+                state.throughput.clone(),
                 state.bootstrap_pending,
+                state.sorted_targets(),
+                state.sorted_sessions(),
+                state.tag_filter.clone(),
             )
         };
         let language = settings.language;
+        let plan_totals = active_target_id.map(|target_id| {
+            TransferTotals::from_actions(
+                self.state
+                    .read(cx)
+                    .jobs
+                    .iter()
+                    .filter(|job| job.target_id == target_id)
+                    .flat_map(|job| job.plan.actions.iter()),
+            )
+        });
+        let rule_tests = self.state.read(cx).rule_tests.clone();
+        let plan_collisions: usize = active_target_id.map_or(0, |target_id| {
+            self.state
+                .read(cx)
+                .jobs
+                .iter()
+                .filter(|job| job.target_id == target_id)
+                .map(|job| job.plan.stats.collisions)
+                .sum()
+        });
+        let plan_identical: usize = active_target_id.map_or(0, |target_id| {
+            self.state
+                .read(cx)
+                .jobs
+                .iter()
+                .filter(|job| job.target_id == target_id)
+                .map(|job| job.plan.stats.identical)
+                .sum()
+        });
+
+        let plan_show_all = self.state.read(cx).plan_show_all;
+        // Rows point into `plan_rules` by index so the share links of a row
+        // are only built for the rows on screen.
+        let (plan_rules, plan_preview): (Vec<SyncRule>, Vec<(usize, PreviewRow)>) =
+            active_target_id
+                .map(|target_id| {
+                    let jobs: Vec<&SyncJob> = self
+                        .state
+                        .read(cx)
+                        .jobs
+                        .iter()
+                        .filter(|job| job.target_id == target_id)
+                        .collect();
+                    let rows = jobs
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(index, job)| {
+                            job.preview(plan_show_all)
+                                .into_iter()
+                                .map(move |row| (index, row))
+                        })
+                        .collect();
+                    (jobs.into_iter().map(|job| job.rule.clone()).collect(), rows)
+                })
+                .unwrap_or_default();
+        let plan_pending = plan_preview
+            .iter()
+            .any(|(_, row)| row.change != PreviewChange::Unchanged);
+
+        let space_check = active_target_id
+            .and_then(|target_id| self.state.read(cx).space_checks.get(&target_id).copied());
+        let plan_forced = active_target_id
+            .is_some_and(|target_id| self.state.read(cx).forced_plans.contains(&target_id));
+        let clock_skew = active_target_id
+            .and_then(|target_id| self.state.read(cx).clock_skews.get(&target_id).copied())
+            .filter(|skew| skew.abs() > CLOCK_SKEW_WARN_SECS);
+        // A failed test, health check or sync offers a reconnect, until a
+        // test or reconnect gets through again.
+        let connection_failed = active_target_id.is_some_and(|target_id| {
+            let state = self.state.read(cx);
+            match state.connection_tests.get(&target_id) {
+                Some(ConnectionTestState::Failure(_)) => true,
+                Some(ConnectionTestState::Success(_)) => false,
+                _ => {
+                    state
+                        .health
+                        .get(&target_id)
+                        .is_some_and(|health| health.error.is_some())
+                        || state.sessions.iter().any(|session| {
+                            session.target_id == target_id
+                                && matches!(session.status, SyncStatus::Failed { .. })
+                        })
+                }
+            }
+        });
+        let active_drift = active_target_id
+            .and_then(|target_id| self.state.read(cx).drift.get(&target_id).copied());
+        let comparison = active_target_id
+            .and_then(|target_id| self.state.read(cx).comparisons.get(&target_id).cloned());
+        let interrupted: Vec<ExecutionJournal> = active_target_id
+            .map(|target_id| {
+                self.state
+                    .read(cx)
+                    .interrupted
+                    .iter()
+                    .filter(|journal| journal.target_id == target_id)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        let pending_deletions: Vec<HeldDeletion> = active_target_id
+            .and_then(|target_id| self.state.read(cx).pending_deletions.get(&target_id).cloned())
+            .unwrap_or_default();
+        let remote_start_dir = active_target_id.and_then(|target_id| {
+            self.state
+                .read(cx)
+                .remote_start_dirs
+                .get(&target_id)
+                .cloned()
+        });
+
+        if !self.drift_checked {
+            self.drift_checked = true;
+            if let Some(target) =
+                active_target_id.and_then(|id| remote_targets.iter().find(|target| target.id == id))
+            {
+                refresh_drift(&self.state, target.clone(), cx);
+            }
+        }
 
-        if !self.bootstrapped && bootstrap_pending {
+        // Pending again after a profile switch, which rebuilds the state.
+        if bootstrap_pending {
             self.bootstrap_targets(&remote_targets, cx);
         }
 
         self.ensure_watch_listener(cx);
-        self.configure_watchers(settings.watch_local_changes, &remote_targets);
-
-        if settings.auto_connect && !self.auto_connect_triggered {
-            if let Some(active_id) = active_target_id {
-                if let Some(target) = remote_targets
-                    .iter()
-                    .find(|t| t.id == active_id)
-                    .cloned()
-                {
-                    run_connection_test(&self.state, target, language, cx);
-                    self.auto_connect_triggered = true;
-                }
+        self.configure_watchers(&settings, &remote_targets);
+        self.ensure_health_monitor(cx);
+
+        // Bootstrap planning goes first; auto-connect runs once it has been kicked off.
+        if settings.auto_connect && !self.auto_connect_triggered && !bootstrap_pending {
+            self.auto_connect_triggered = true;
+            if let Some(target) = active_target_id
+                .and_then(|id| remote_targets.iter().find(|t| t.id == id))
+                .filter(|target| target.enabled)
+                .cloned()
+            {
+                let handle = self.state.clone();
+                window.defer(cx, move |window, cx| {
+                    let probe_handle = handle.clone();
+                    with_session_secret(
+                        &handle,
+                        target,
+                        language,
+                        window,
+                        cx,
+                        move |target, _, cx| {
+                            run_connection_test_with_retries(
+                                &probe_handle,
+                                target,
+                                language,
+                                AUTO_CONNECT_ATTEMPTS,
+                                cx,
+                            );
+                        },
+                    );
+                });
             }
         }
 
@@ -340,9 +655,11 @@ impl Render for AppView {
         let mut workspace_items = vec![
             SidebarMenuItem::new(tr(language, "Overview", "概览", "總覽"))
                 .icon(Icon::new(IconName::LayoutDashboard).small())
-                .active(matches!(active_view, ActiveView::Dashboard))
+                .active(active_view == ActiveView::Dashboard && active_target_id.is_none())
                 .on_click(move |_, _, cx| {
                     overview_handle.update(cx, |state, cx| {
+                        // Without a selected target the overview covers all of them.
+                        state.active_target = None;
                         state.active_view = ActiveView::Dashboard;
                         cx.notify();
                     });
@@ -375,7 +692,7 @@ impl Render for AppView {
 
         let workspace_menu = SidebarMenu::new().children(workspace_items);
 
-        let sidebar_menu = SidebarMenu::new().children(remote_targets.iter().map(|target| {
+        let sidebar_menu = SidebarMenu::new().children(sorted_targets.iter().map(|target| {
             let target_id = target.id;
             let rule_count = target.rules.len();
             let pending = sessions
@@ -388,9 +705,15 @@ impl Render for AppView {
                         )
                 })
                 .count();
-            let suffix_tag = if pending > 0 {
+            let suffix_tag = if !target.enabled {
+                Tag::secondary()
+                    .small()
+                    .rounded_full()
+                    .child(tr(language, "disabled", "已停用", "已停用"))
+            } else if pending > 0 {
                 Tag::warning().small().rounded_full().child(format!(
-                    "{pending} {}",
+                    "{} {}",
+                    format_count(pending),
                     tr(language, "pending", "待处理", "待處理")
                 ))
             } else {
@@ -399,22 +722,54 @@ impl Render for AppView {
                     tr(language, "rules", "规则", "規則")
                 ))
             };
+            let badge = div().id(("plan_stats", target_id)).child(suffix_tag);
+            let badge = if target.enabled && pending > 0 {
+                let lines = plan_stats_lines(&self.state.read(cx).plan_stats(target_id), language);
+                badge.tooltip(move |_, cx| {
+                    let lines = lines.clone();
+                    cx.new(|_| PlanStatsTooltip { lines }).into()
+                })
+            } else {
+                badge
+            };
+            let health = target
+                .monitor_health
+                .then(|| self.state.read(cx).health.get(&target_id).cloned())
+                .flatten();
             let handle = self.state.clone();
+            let selected_target = target.clone();
 
             SidebarMenuItem::new(target.name.clone())
-                .icon(Icon::new(IconName::Globe).small())
-                .suffix(suffix_tag)
-                .active(active_view == ActiveView::Dashboard && active_target_id == Some(target_id))
-                .on_click(move |_, _, cx| {
-                    handle.update(cx, |state, cx| {
-                        state.active_target = Some(target_id);
-                        state.active_view = ActiveView::Dashboard;
-                        cx.notify();
-                    });
+                .icon(if target.enabled {
+                    Icon::new(IconName::Globe).small()
+                } else {
+                    Icon::new(IconName::Globe)
+                        .small()
+                        .text_color(cx.theme().muted_foreground.opacity(0.5))
                 })
+                .suffix(
+                    div()
+                        .h_flex()
+                        .gap_1()
+                        .items_center()
+                        .when_some(health, |this, health| {
+                            this.child(render_health_dot(target_id, health, language, cx))
+                        })
+                        .when_some(target.color.rgb(), |this, color| {
+                            this.child(div().size_2().rounded_full().bg(rgb(color)))
+                        })
+                        .child(badge),
+                )
+                .active(active_view == ActiveView::Dashboard && active_target_id == Some(target_id))
+                .on_click(move |_, _, cx| select_target(&handle, selected_target.clone(), cx))
         }));
 
         let add_target_handle = self.state.clone();
+        let target_sort_handle = self.state.clone();
+        let abort_all_handle = self.state.clone();
+        let tasks_running = !task_progress_map.is_empty();
+        let tag_filter_handle = self.state.clone();
+        let target_sort = settings.target_sort;
         let sidebar = Sidebar::left()
             .header(
                 SidebarHeader::new().child(
@@ -447,17 +802,74 @@ impl Render for AppView {
             .child(SidebarGroup::new(tr(language, "Targets", "目标", "目標")).child(sidebar_menu))
             .footer(
                 SidebarFooter::new().child(
-                    Button::new("add_target")
-                        .ghost()
-                        .small()
-                        .icon(Icon::new(IconName::Plus).small())
-                        .label(tr(language, "Add Target", "新增目标", "新增目標"))
-                        .on_click(move |_, _, cx| {
-                            add_target_handle.update(cx, |state, cx| {
-                                state.active_view = ActiveView::TargetSettings;
-                                state.target_form = Some(TargetFormMode::Create);
-                                cx.notify();
-                            });
+                    div()
+                        .h_flex()
+                        .gap_1()
+                        .child(
+                            Button::new("add_target")
+                                .ghost()
+                                .small()
+                                .icon(Icon::new(IconName::Plus).small())
+                                .label(tr(language, "Add Target", "新增目标", "新增目標"))
+                                .on_click(move |_, _, cx| {
+                                    add_target_handle.update(cx, |state, cx| {
+                                        state.open_target_form(TargetFormMode::Create);
+                                        cx.notify();
+                                    });
+                                }),
+                        )
+                        .child(
+                            Button::new("target_sort")
+                                .ghost()
+                                .small()
+                                .icon(Icon::new(IconName::ChevronsUpDown).small())
+                                .label(target_sort_label(target_sort, language))
+                                .on_click(move |_, _, cx| {
+                                    target_sort_handle.update(cx, |state, cx| {
+                                        state.settings.target_sort =
+                                            state.settings.target_sort.next();
+                                        persist_state(state);
+                                        cx.notify();
+                                    });
+                                }),
+                        )
+                        .when_some(tag_filter, |this, tag| {
+                            this.child(
+                                Button::new("clear_tag_filter")
+                                    .ghost()
+                                    .small()
+                                    .icon(Icon::new(IconName::CircleX).small())
+                                    .label(tag)
+                                    .on_click(move |_, _, cx| {
+                                        tag_filter_handle.update(cx, |state, cx| {
+                                            state.tag_filter = None;
+                                            cx.notify();
+                                        });
+                                    }),
+                            )
+                        })
+                        .when(tasks_running, |this| {
+                            this.child(
+                                Button::new("abort_all")
+                                    .ghost()
+                                    .small()
+                                    .icon(Icon::new(IconName::CircleX).small())
+                                    .label(tr(language, "Abort All", "全部中止", "全部中止"))
+                                    .on_click(move |_, _, cx| {
+                                        let aborted = task_queue::abort_all();
+                                        abort_all_handle.update(cx, |state, cx| {
+                                            state.sync_all = None;
+                                            state.log_event(
+                                                LogLevel::Warn,
+                                                format!(
+                                                    "Aborted all tasks: {} queued discarded, {} running stopped",
+                                                    aborted.discarded, aborted.interrupted
+                                                ),
+                                            );
+                                            cx.notify();
+                                        });
+                                    }),
+                            )
                         }),
                 ),
             );
@@ -471,53 +883,192 @@ impl Render for AppView {
                     let delete_handle = self.state.clone();
                     let target_id = target.id;
                     let task_progress = task_progress_map.get(&target.id).cloned();
-                    let rule_list =
-                        target
-                            .rules
-                            .iter()
-                            .fold(div().v_flex().gap_2(), |builder, rule| {
-                                builder.child(
-                                    div()
-                                        .h_flex()
-                                        .justify_between()
-                                        .items_center()
-                                        .gap_3()
-                                        .p_3()
-                                        .rounded(cx.theme().radius)
-                                        .bg(cx.theme().muted.opacity(0.15))
-                                        .child(
-                                            div()
-                                                .v_flex()
-                                                .gap_1()
-                                                .child(format!(
-                                                    "{} → {}",
-                                                    rule.local.display(),
-                                                    rule.remote.display()
-                                                ))
-                                                .child(
-                                                    div()
-                                                        .text_sm()
-                                                        .text_color(
-                                                            cx.theme()
-                                                                .muted_foreground
-                                                                .opacity(0.9),
-                                                        )
-                                                        .child(tr(
+                    let throughput = task_progress
+                        .as_ref()
+                        .filter(|progress| progress.kind == TaskKind::Executing)
+                        .and_then(|_| throughput_map.get(&target.id).cloned());
+                    let rule_list = target.rules.iter().enumerate().fold(
+                        div().v_flex().gap_2(),
+                        |builder, (index, rule)| {
+                            let sftp_link = sync::sftp_url(&target, rule, None);
+                            let scp_command = sync::scp_command(&target, rule, None);
+                            let local_root = sync::resolve_local_root(&target, &rule.local);
+                            let remote_root = sync::resolved_remote_root(
+                                &target,
+                                &rule.remote,
+                                remote_start_dir.as_deref(),
+                            );
+                            let link_handle = self.state.clone();
+                            let scp_handle = self.state.clone();
+                            let rule_test_handle = self.state.clone();
+                            let rule_target = target.clone();
+                            let rule_test = rule_tests.get(&(target.id, index)).cloned();
+                            let rule_testing =
+                                matches!(rule_test, Some(ConnectionTestState::InProgress));
+                            builder.child(
+                                div()
+                                    .h_flex()
+                                    .justify_between()
+                                    .items_center()
+                                    .gap_3()
+                                    .p_3()
+                                    .rounded(cx.theme().radius)
+                                    .bg(cx.theme().muted.opacity(0.15))
+                                    .child(
+                                        div()
+                                            .v_flex()
+                                            .gap_1()
+                                            .child(
+                                                div()
+                                                    .h_flex()
+                                                    .gap_2()
+                                                    .items_center()
+                                                    .child(rule.label())
+                                                    .when_some(rule.last_status, |row, status| {
+                                                        row.child(rule_status_tag(
+                                                            &status, language,
+                                                        ))
+                                                    }),
+                                            )
+                                            .child(
+                                                div()
+                                                    .text_sm()
+                                                    .text_color(
+                                                        cx.theme().muted_foreground.opacity(0.9),
+                                                    )
+                                                    .child(format!(
+                                                        "{} {}",
+                                                        tr(
                                                             language,
-                                                            "Mapped path",
-                                                            "映射路径",
-                                                            "對應路徑",
-                                                        )),
+                                                            "Local folder:",
+                                                            "本地目录：",
+                                                            "本機資料夾：",
+                                                        ),
+                                                        local_root.display()
+                                                    )),
+                                            )
+                                            .child(
+                                                div()
+                                                    .text_sm()
+                                                    .text_color(
+                                                        cx.theme().muted_foreground.opacity(0.9),
+                                                    )
+                                                    .child(format!(
+                                                        "{} {}{}",
+                                                        tr(
+                                                            language,
+                                                            "Remote folder:",
+                                                            "远程目录：",
+                                                            "遠端資料夾：",
+                                                        ),
+                                                        remote_root.display(),
+                                                        if remote_root.is_relative() {
+                                                            tr(
+                                                                language,
+                                                                " (under the login folder)",
+                                                                "（位于登录目录下）",
+                                                                "（位於登入資料夾下）",
+                                                            )
+                                                        } else {
+                                                            ""
+                                                        }
+                                                    )),
+                                            ),
+                                    )
+                                    .child(
+                                        div()
+                                            .h_flex()
+                                            .gap_2()
+                                            .items_center()
+                                            .child(
+                                                Button::new(("copy_sftp_link", index))
+                                                    .ghost()
+                                                    .small()
+                                                    .label(tr(
+                                                        language,
+                                                        "Copy link",
+                                                        "复制链接",
+                                                        "複製連結",
+                                                    ))
+                                                    .on_click(move |_, _, cx| {
+                                                        copy_share_link(
+                                                            &link_handle,
+                                                            &sftp_link,
+                                                            cx,
+                                                        );
+                                                    }),
+                                            )
+                                            .child(
+                                                Button::new(("copy_scp_command", index))
+                                                    .ghost()
+                                                    .small()
+                                                    .label(tr(
+                                                        language,
+                                                        "Copy scp",
+                                                        "复制 scp",
+                                                        "複製 scp",
+                                                    ))
+                                                    .on_click(move |_, _, cx| {
+                                                        copy_share_link(
+                                                            &scp_handle,
+                                                            &scp_command,
+                                                            cx,
+                                                        );
+                                                    }),
+                                            )
+                                            .child(
+                                                Button::new(("test_rule", index))
+                                                    .ghost()
+                                                    .small()
+                                                    .label(tr(
+                                                        language,
+                                                        "Test folder",
+                                                        "测试目录",
+                                                        "測試資料夾",
+                                                    ))
+                                                    .disabled(rule_testing)
+                                                    .on_click(move |_, window, cx| {
+                                                        let handle = rule_test_handle.clone();
+                                                        with_session_secret(
+                                                            &rule_test_handle,
+                                                            rule_target.clone(),
+                                                            language,
+                                                            window,
+                                                            cx,
+                                                            move |target, _, cx| {
+                                                                run_rule_test(
+                                                                    &handle, target, index,
+                                                                    language, cx,
+                                                                );
+                                                            },
+                                                        );
+                                                    }),
+                                            )
+                                            .when_some(rule_test, |this, status| {
+                                                this.child(render_connection_status_tag(
+                                                    Some(&status),
+                                                    language,
+                                                ))
+                                            })
+                                            .when(
+                                                target.remote_read_only || rule.remote_read_only,
+                                                |this| {
+                                                    this.child(
+                                                        Tag::danger().small().rounded_full().child(
+                                                            tr(language, "Read-only", "只读", "唯讀"),
+                                                        ),
+                                                    )
+                                                },
+                                            )
+                                            .child(
+                                                Tag::info().small().rounded_full().child(
+                                                    direction_label(rule.direction, language),
                                                 ),
-                                        )
-                                        .child(
-                                            Tag::info()
-                                                .small()
-                                                .rounded_full()
-                                                .child(direction_label(rule.direction, language)),
-                                        ),
-                                )
-                            });
+                                            ),
+                                    ),
+                            )
+                        },
+                    );
 
                     div()
                         .v_flex()
@@ -541,17 +1092,105 @@ impl Render for AppView {
                                                 .text_xl()
                                                 .font_semibold()
                                                 .child(target.name.clone()),
-                                        ),
+                                        )
+                                        .children(target.tags.iter().enumerate().map(
+                                            |(index, tag)| {
+                                                let filter_handle = self.state.clone();
+                                                let tag = tag.clone();
+                                                div()
+                                                    .id(("target_tag", index))
+                                                    .cursor_pointer()
+                                                    .child(
+                                                        Tag::secondary()
+                                                            .small()
+                                                            .rounded_full()
+                                                            .child(tag.clone()),
+                                                    )
+                                                    .on_click(move |_, _, cx| {
+                                                        filter_handle.update(cx, |state, cx| {
+                                                            state.tag_filter = Some(tag.clone());
+                                                            cx.notify();
+                                                        });
+                                                    })
+                                            },
+                                        ))
+                                        .child({
+                                            let toggle_handle = self.state.clone();
+                                            Switch::new(("target_enabled", target_id))
+                                                .checked(target.enabled)
+                                                .label(tr(language, "Enabled", "启用", "啟用"))
+                                                .on_click(move |next, _, cx| {
+                                                    let enabled = *next;
+                                                    toggle_handle.update(cx, |state, cx| {
+                                                        if let Some(target) = state
+                                                            .remote_targets
+                                                            .iter_mut()
+                                                            .find(|target| target.id == target_id)
+                                                        {
+                                                            target.enabled = enabled;
+                                                        }
+                                                        persist_state(state);
+                                                        cx.notify();
+                                                    });
+                                                })
+                                        }),
                                 )
                                 .child(
                                     div()
                                         .text_sm()
                                         .text_color(cx.theme().muted_foreground)
                                         .child(target.summary()),
-                                ),
+                                )
+                                .when(target.needs_session_secret(), |this| {
+                                    this.child(Tag::info().small().rounded_full().child(tr(
+                                        language,
+                                        "Password asked for when connecting",
+                                        "连接时询问密码",
+                                        "連線時詢問密碼",
+                                    )))
+                                })
+                                .when(target.remote_read_only, |this| {
+                                    this.child(Tag::danger().small().rounded_full().child(tr(
+                                        language,
+                                        "Read-only remote: uploads and remote deletions are refused",
+                                        "远程只读：拒绝上传和远程删除",
+                                        "遠端唯讀：拒絕上傳與遠端刪除",
+                                    )))
+                                })
+                                .when(target.credentials_pending, |this| {
+                                    this.child(
+                                        Tag::warning().small().rounded_full().child(tr(
+                                            language,
+                                            "Credentials needed: edit this target to enter them",
+                                            "需要凭据：请编辑此目标并重新输入",
+                                            "需要憑證：請編輯此目標並重新輸入",
+                                        )),
+                                    )
+                                })
+                                .when_some(active_drift, |this, drift| {
+                                    this.child(render_drift_summary(drift, language, cx))
+                                })
+                                .when(target.algorithms.allow_legacy, |this| {
+                                    this.child(
+                                        Tag::warning().small().rounded_full().child(tr(
+                                            language,
+                                            "Legacy SSH algorithms allowed",
+                                            "已允许旧版 SSH 算法",
+                                            "已允許舊版 SSH 演算法",
+                                        )),
+                                    )
+                                }),
                         )
                         .when_some(task_progress, |this, progress| {
-                            this.child(render_task_progress(progress, language))
+                            this.child(render_task_progress(
+                                progress,
+                                target_id,
+                                &target.host,
+                                language,
+                            ))
+                        })
+                        .when_some(throughput, |this, history| {
+                            this.child(render_throughput(&history, language, cx))
                         })
                         .child(
                             div()
@@ -598,12 +1237,90 @@ impl Render for AppView {
                                 .gap_2()
                                 .child(
                                     div()
-                                        .text_sm()
-                                        .text_color(cx.theme().muted_foreground)
-                                        .child(tr(language, "Sync rules", "同步规则", "同步規則")),
+                                        .h_flex()
+                                        .justify_between()
+                                        .items_center()
+                                        .gap_2()
+                                        .child(
+                                            div()
+                                                .text_sm()
+                                                .text_color(cx.theme().muted_foreground)
+                                                .child(tr(
+                                                    language,
+                                                    "Sync rules",
+                                                    "同步规则",
+                                                    "同步規則",
+                                                )),
+                                        )
+                                        .when(target.rules.len() > 1, |this| {
+                                            this.child(render_bulk_direction(
+                                                &self.state,
+                                                &target,
+                                                language,
+                                                cx,
+                                            ))
+                                        }),
                                 )
                                 .child(rule_list),
                         )
+                        .when(remote_targets.len() > 1, |this| {
+                            this.child(render_compare_targets(
+                                &self.state,
+                                &target,
+                                &remote_targets,
+                                language,
+                                cx,
+                            ))
+                        })
+                        .when_some(comparison, |this, report| {
+                            this.child(render_comparison(&self.state, target_id, report, language, cx))
+                        })
+                        .when(!interrupted.is_empty(), |this| {
+                            this.child(render_interrupted(
+                                &self.state,
+                                &target,
+                                &interrupted,
+                                language,
+                                cx,
+                            ))
+                        })
+                        .when(!pending_deletions.is_empty(), |this| {
+                            this.child(render_pending_deletions(
+                                &self.state,
+                                &target,
+                                &pending_deletions,
+                                language,
+                                cx,
+                            ))
+                        })
+                        .when_some(
+                            plan_totals.filter(|totals| {
+                                totals.files() > 0
+                                    || plan_collisions > 0
+                                    || plan_identical > 0
+                                    || plan_pending
+                            }),
+                            |this, totals| {
+                                this.child(render_plan_summary(
+                                    totals,
+                                    space_check,
+                                    plan_collisions,
+                                    plan_identical,
+                                    plan_forced,
+                                    language,
+                                    cx,
+                                ))
+                                .child(render_plan_preview(
+                                    &self.state,
+                                    &target,
+                                    &plan_rules,
+                                    &plan_preview,
+                                    plan_show_all,
+                                    language,
+                                    cx,
+                                ))
+                            },
+                        )
                         .child(
                             div()
                                 .h_flex()
@@ -629,175 +1346,382 @@ impl Render for AppView {
                                         ))
                                         .icon(Icon::new(IconName::SquareTerminal).small())
                                         .disabled(is_testing)
-                                        .on_click(move |_, _, cx| {
+                                        .on_click(move |_, window, cx| {
                                             let handle = test_handle.clone();
-                                            let target_clone = target_for_test.clone();
-                                            cx.spawn({
-                                                async move |cx| {
-                                                    let _ = handle.update(cx, |state, cx| {
-                                                        state.connection_tests.insert(
-                                                            target_clone.id,
-                                                            ConnectionTestState::InProgress,
-                                                        );
-                                                        cx.notify();
-                                                    });
-
-                                                    let result =
-                                                        connection::test_connection(&target_clone);
-
-                                                    let _ = handle.update(cx, |state, cx| {
-                                                        let status = match result {
-                                                            Ok(_) => ConnectionTestState::Success(
-                                                                tr(
-                                                                    language,
-                                                                    "Connection OK",
-                                                                    "连接成功",
-                                                                    "連線成功",
-                                                                )
-                                                                .into(),
-                                                            ),
-                                                            Err(err) => {
-                                                                ConnectionTestState::Failure(
-                                                                    err.to_string(),
-                                                                )
-                                                            }
-                                                        };
-                                                        state
-                                                            .connection_tests
-                                                            .insert(target_clone.id, status);
-                                                        cx.notify();
-                                                    });
-
-                                                    Ok::<_, Error>(())
-                                                }
-                                            })
-                                            .detach();
+                                            with_session_secret(
+                                                &test_handle,
+                                                target_for_test.clone(),
+                                                language,
+                                                window,
+                                                cx,
+                                                move |target_clone, _, cx| {
+                                                    let handle = handle.clone();
+                                                    cx.spawn({
+                                                        async move |cx| {
+                                                            let _ = handle.update(cx, |state, cx| {
+                                                                state.connection_tests.insert(
+                                                                    target_clone.id,
+                                                                    ConnectionTestState::InProgress,
+                                                                );
+                                                                cx.notify();
+                                                            });
+
+                                                            let result =
+                                                                connection::test_connection(&target_clone);
+
+                                                            let _ = handle.update(cx, |state, cx| {
+                                                                let status = match result {
+                                                                    Ok(check) => {
+                                                                        if let Some(skew) = check.clock_skew {
+                                                                            state.record_clock_skew(target_clone.id, skew);
+                                                                        }
+                                                                        ConnectionTestState::Success(format!(
+                                                                            "{} ({})",
+                                                                            tr(
+                                                                                language,
+                                                                                "Connection OK",
+                                                                                "连接成功",
+                                                                                "連線成功",
+                                                                            ),
+                                                                            check.method
+                                                                        ))
+                                                                    }
+                                                                    Err(err) => {
+                                                                        ConnectionTestState::Failure(
+                                                                            state.redact(&format!(
+                                                                                "{err:#}"
+                                                                            )),
+                                                                        )
+                                                                    }
+                                                                };
+                                                                state
+                                                                    .connection_tests
+                                                                    .insert(target_clone.id, status);
+                                                                cx.notify();
+                                                            });
+
+                                                            Ok::<_, Error>(())
+                                                        }
+                                                    })
+                                                    .detach();
+                                                },
+                                            );
                                         })
                                 })
                                 .child(render_connection_status_tag(
                                     connection_tests.get(&target.id),
                                     language,
                                 ))
-                                .child({
-                                    let plan_handle = self.state.clone();
-                                    let plan_target = target.clone();
-                                    Button::new("plan_sync")
-                                        .primary()
-                                        .label(tr(
-                                            language,
-                                            "Plan Dry Run",
-                                            "生成试运行计划",
-                                            "產生試運行計畫",
-                                        ))
-                                        .icon(Icon::new(IconName::LayoutDashboard).small())
-                                        .on_click(move |_, _, cx| {
-                                            {
-                                                let handle = plan_handle.clone();
-                                                let target_name = plan_target.name.clone();
-                                                handle.update(cx, |state, cx| {
-                                                    let mut touched = false;
-                                                    for session in state
-                                                        .sessions
-                                                        .iter_mut()
-                                                        .filter(|session| {
-                                                            session.target_id == plan_target.id
-                                                        })
-                                                    {
-                                                        session.status = SyncStatus::Planning;
-                                                        session.last_run = Some(SystemTime::now());
-                                                        touched = true;
-                                                    }
-                                                    if !touched {
-                                                        let id = state.next_session_id();
-                                                        state.sessions.push(SyncSession {
-                                                            id,
-                                                            target_id: plan_target.id,
-                                                            status: SyncStatus::Planning,
-                                                            last_run: Some(SystemTime::now()),
-                                                            pending_actions: 0,
-                                                        });
-                                                    }
-                                                    state.set_task_progress(
-                                                        plan_target.id,
-                                                        TaskProgress::new(
-                                                            TaskKind::Planning,
-                                                            0,
-                                                            plan_target.rules.len().max(1),
-                                                        ),
+                                .when_some(
+                                    match connection_tests.get(&target.id) {
+                                        Some(ConnectionTestState::Failure(reason)) => {
+                                            Some(reason.clone())
+                                        }
+                                        _ => None,
+                                    },
+                                    |row, reason| {
+                                        let handle = self.state.clone();
+                                        let target_id = target.id;
+                                        row.child(
+                                            Button::new("copy_connection_error")
+                                                .ghost()
+                                                .small()
+                                                .label(tr(
+                                                    language,
+                                                    "Copy details",
+                                                    "复制详情",
+                                                    "複製詳情",
+                                                ))
+                                                .on_click(move |_, _, cx| {
+                                                    copy_diagnostics(
+                                                        &handle,
+                                                        Some(target_id),
+                                                        Some(&reason),
+                                                        cx,
                                                     );
-                                                    state.log_event(
-                                                        LogLevel::Info,
-                                                        format!(
-                                                            "Planning sync for {}",
-                                                            target_name
-                                                        ),
-                                                    );
-                                                    cx.notify();
-                                                });
-                                            }
+                                                }),
+                                        )
+                                    },
+                                )
+                                .when(connection_failed, |row| {
+                                    let reconnect_handle = self.state.clone();
+                                    let reconnect_target = target.clone();
+                                    let reconnecting = matches!(
+                                        connection_tests.get(&target.id),
+                                        Some(ConnectionTestState::InProgress)
+                                    );
+                                    row.child(
+                                        Button::new(("reconnect_target", target.id))
+                                            .warning()
+                                            .small()
+                                            .label(tr(language, "Reconnect", "重新连接", "重新連線"))
+                                            .disabled(reconnecting)
+                                            .on_click(move |_, window, cx| {
+                                                let handle = reconnect_handle.clone();
+                                                with_session_secret(
+                                                    &reconnect_handle,
+                                                    reconnect_target.clone(),
+                                                    language,
+                                                    window,
+                                                    cx,
+                                                    move |target, _, cx| {
+                                                        reconnect(&handle, target, language, cx);
+                                                    },
+                                                );
+                                            }),
+                                    )
+                                })
+                                .when_some(clock_skew, |row, skew| {
+                                    row.child(render_clock_skew_tag(
+                                        skew,
+                                        target.correct_clock_skew,
+                                        language,
+                                    ))
+                                })
+                                .when(!target.remote_commands.due().is_empty(), |row| {
+                                    row.child(
+                                        Tag::warning().small().rounded_full().child(format!(
+                                            "{} {}",
+                                            tr(language, "Remote:", "远程：", "遠端："),
+                                            target.remote_commands.due().join(" ; ")
+                                        )),
+                                    )
+                                })
+                                .when(target.post_sync_hook.due(0), |row| {
+                                    row.child(
+                                        Tag::warning().small().rounded_full().child(format!(
+                                            "{} {}",
+                                            tr(language, "Hook:", "钩子：", "鉤子："),
+                                            target.post_sync_hook.command.trim()
+                                        )),
+                                    )
+                                })
+                                .child({
+                                    let preflight_handle = self.state.clone();
+                                    let preflight_target = target.clone();
+                                    Button::new("local_preflight")
+                                        .ghost()
+                                        .small()
+                                        .label(tr(
+                                            language,
+                                            "Local Check",
+                                            "本地检查",
+                                            "本地檢查",
+                                        ))
+                                        .icon(Icon::new(IconName::Folder).small())
+                                        .on_click(move |_, _, cx| {
+                                            run_local_preflight(
+                                                &preflight_handle,
+                                                preflight_target.clone(),
+                                                cx,
+                                            );
+                                        })
+                                })
+                                .child({
+                                    let plan_handle = self.state.clone();
+                                    let plan_target = target.clone();
+                                    Button::new("plan_sync")
+                                        .primary()
+                                        .label(tr(
+                                            language,
+                                            "Plan Dry Run",
+                                            "生成试运行计划",
+                                            "產生試運行計畫",
+                                        ))
+                                        .icon(Icon::new(IconName::LayoutDashboard).small())
+                                        .on_click(move |_, window, cx| {
+                                            let handle = plan_handle.clone();
+                                            with_session_secret(
+                                                &plan_handle,
+                                                plan_target.clone(),
+                                                language,
+                                                window,
+                                                cx,
+                                                move |plan_target, _, cx| {
+                                                    let plan_handle = handle.clone();
+                                                    {
+                                                        let handle = plan_handle.clone();
+                                                        let target_name = plan_target.name.clone();
+                                                        handle.update(cx, |state, cx| {
+                                                            let mut touched = false;
+                                                            for session in state
+                                                                .sessions
+                                                                .iter_mut()
+                                                                .filter(|session| {
+                                                                    session.target_id == plan_target.id
+                                                                })
+                                                            {
+                                                                session.status = SyncStatus::Planning;
+                                                                session.last_run = Some(SystemTime::now());
+                                                                touched = true;
+                                                            }
+                                                            if !touched {
+                                                                let id = state.next_session_id();
+                                                                state.sessions.push(SyncSession {
+                                                                    id,
+                                                                    target_id: plan_target.id,
+                                                                    status: SyncStatus::Planning,
+                                                                    last_run: Some(SystemTime::now()),
+                                                                    pending_actions: 0,
+                                                                });
+                                                            }
+                                                            state.set_task_progress(
+                                                                plan_target.id,
+                                                                TaskProgress::new(
+                                                                    TaskKind::Planning,
+                                                                    0,
+                                                                    plan_target.rules.len().max(1),
+                                                                ),
+                                                            );
+                                                            state.log_target_event(
+                                                                plan_target.id,
+                                                                LogLevel::Info,
+                                                                format!(
+                                                                    "Planning sync for {}",
+                                                                    target_name
+                                                                ),
+                                                            );
+                                                            cx.notify();
+                                                        });
+                                                    }
 
-        let async_handle = plan_handle.clone();
-        cx.spawn({
-            let snapshot = plan_target.clone();
-            async move |cx| {
-                let target_name = snapshot.name.clone();
-                let receiver = task_queue::submit_plan(snapshot.clone());
-                while let Ok(event) = receiver.recv().await {
-                    match event {
-                        TaskEvent::Progress { completed, total } => {
-                            let _ = async_handle.update(cx, |state, cx| {
-                                state.set_task_progress(
-                                    snapshot.id,
-                                    TaskProgress::new(TaskKind::Planning, completed, total),
-                                );
-                                cx.notify();
-                            });
-                        }
-                        TaskEvent::Finished(result) => {
-                            let _ = async_handle.update(cx, |state, cx| {
-                                state.clear_task_progress(snapshot.id);
-                                cx.notify();
-                            });
-                            match result {
-                                Ok(result) => {
-                                    let pending: usize = result
-                                        .jobs
-                                        .iter()
-                                        .map(|job| job.actions.len())
-                                        .sum();
+                let async_handle = plan_handle.clone();
+                cx.spawn({
+                    let snapshot = plan_target.clone();
+                    async move |cx| {
+                        let target_name = snapshot.name.clone();
+                        let mut receiver = task_queue::submit_plan(snapshot.clone());
+                        while let Some(event) = receiver.recv().await {
+                            match event {
+                                TaskEvent::Progress {
+                                    completed,
+                                    total,
+                                    discovered,
+                                    connecting,
+                                    ..
+                                } => {
                                     let _ = async_handle.update(cx, |state, cx| {
-                                        state.apply_planned_jobs(snapshot.id, result);
-                                        state.log_event(
-                                            LogLevel::Info,
-                                            format!(
-                                                "Dry run ready for {target_name} ({pending} actions)"
-                                            ),
+                                        state.set_task_progress(
+                                            snapshot.id,
+                                            TaskProgress::new(TaskKind::Planning, completed, total)
+                                                .with_discovered(discovered)
+                                                .with_connecting(connecting),
                                         );
                                         cx.notify();
                                     });
                                 }
-                                Err(err) => {
+                                TaskEvent::Finished(result) => {
                                     let _ = async_handle.update(cx, |state, cx| {
-                                        state.log_event(
-                                            LogLevel::Error,
-                                            format!(
-                                                "Planning failed for {target_name}: {err}"
-                                            ),
-                                        );
+                                        state.clear_task_progress(snapshot.id);
                                         cx.notify();
                                     });
+                                    match result {
+                                        Ok(result) => {
+                                            let pending: usize = result
+                                                .jobs
+                                                .iter()
+                                                .map(|job| job.actions.len())
+                                                .sum();
+                                            let _ = async_handle.update(cx, |state, cx| {
+                                                state.apply_planned_jobs(snapshot.id, result);
+                                                state.log_target_event(
+                                                    snapshot.id,
+                                                    LogLevel::Info,
+                                                    format!(
+                                                        "Dry run ready for {target_name} ({} actions)",
+                                                        format_count(pending)
+                                                    ),
+                                                );
+                                                cx.notify();
+                                            });
+                                        }
+                                        Err(err) => {
+                                            let _ = async_handle.update(cx, |state, cx| {
+                                                state.log_target_event(
+                                                    snapshot.id,
+                                                    LogLevel::Error,
+                                                    format!(
+                                                        "Planning failed for {target_name}: {err}"
+                                                    ),
+                                                );
+                                                cx.notify();
+                                            });
+                                        }
+                                    }
+                                    break;
                                 }
                             }
-                            break;
                         }
+                        Ok::<_, Error>(())
                     }
-                }
-                Ok::<_, Error>(())
-            }
-        })
-        .detach();
+                })
+                .detach();
+                                                },
+                                            );
                                         })
                                })
+                                .child({
+                                    let full_handle = self.state.clone();
+                                    let full_target = target.clone();
+                                    Button::new("plan_full_sync")
+                                        .ghost()
+                                        .label(tr(
+                                            language,
+                                            "Force Full Sync",
+                                            "强制完整同步",
+                                            "強制完整同步",
+                                        ))
+                                        .icon(Icon::new(IconName::TriangleAlert).small())
+                                        .on_click(move |_, window, cx| {
+                                            let handle = full_handle.clone();
+                                            with_session_secret(
+                                                &full_handle,
+                                                full_target.clone(),
+                                                language,
+                                                window,
+                                                cx,
+                                                move |target, _, cx| {
+                                                    let message = format!(
+                                                        "Planning forced full sync for {}",
+                                                        target.name
+                                                    );
+                                                    schedule_plan_for_target(
+                                                        &handle,
+                                                        target,
+                                                        true,
+                                                        Some(message),
+                                                        cx,
+                                                    );
+                                                },
+                                            );
+                                        })
+                                })
+                                .child({
+                                    let simulate_handle = self.state.clone();
+                                    let simulate_target = target.clone();
+                                    Button::new("simulate_execution")
+                                        .ghost()
+                                        .label(tr(
+                                            language,
+                                            "Simulate Execution",
+                                            "模拟执行",
+                                            "模擬執行",
+                                        ))
+                                        .icon(Icon::new(IconName::Eye).small())
+                                        .on_click(move |_, window, cx| {
+                                            let handle = simulate_handle.clone();
+                                            with_session_secret(
+                                                &simulate_handle,
+                                                simulate_target.clone(),
+                                                language,
+                                                window,
+                                                cx,
+                                                move |target, _, cx| {
+                                                    start_simulation(&handle, target, cx);
+                                                },
+                                            );
+                                        })
+                                })
                                 .child({
                                     let execute_handle = self.state.clone();
                                     let execute_target = target.clone();
@@ -806,100 +1730,55 @@ impl Render for AppView {
                                         .label(tr(language, "Execute Sync", "执行同步", "執行同步"))
                                         .icon(Icon::new(IconName::Check).small())
                                         .on_click(move |_, window, cx| {
-                                            let snapshot = execute_handle.update(cx, |state, cx| {
-                                                let jobs: Vec<_> = state
-                                                    .jobs
-                                                    .iter()
-                                                    .filter(|job| job.target_id == execute_target.id)
-                                                    .cloned()
-                                                    .collect();
-                                                if jobs.is_empty() {
-                                                    state.log_event(
-                                                        LogLevel::Info,
-                                                        format!(
-                                                            "Nothing to sync for {}",
-                                                            execute_target.name
-                                                        ),
-                                                    );
-                                                    cx.notify();
-                                                    return None;
-                                                }
-
-                                                for session in state
-                                                    .sessions
-                                                    .iter_mut()
-                                                    .filter(|session| session.target_id == execute_target.id)
-                                                {
-                                                    session.status = SyncStatus::Running { progress: 0.0 };
-                                                    session.last_run = Some(SystemTime::now());
-                                                }
-                                                state.log_event(
-                                                    LogLevel::Info,
-                                                    format!("Executing sync for {}", execute_target.name),
-                                                );
-                                                cx.notify();
-                                                Some((jobs, state.settings.clone()))
-                                            });
-
-                                            let Some((jobs, settings)) = snapshot else {
-                                                return;
-                                            };
-                                            let (delete_local, delete_remote) = destructive_counts(&jobs);
-                                            if settings.confirm_destructive
-                                                && (delete_local + delete_remote > 0)
-                                            {
-                                                let handle = execute_handle.clone();
-                                                let target_snapshot = execute_target.clone();
-                                                window.open_modal(cx, move |modal, _, _| {
-                                                    let message = format!(
-                                                        "{}\n• {} {}\n• {} {}",
-                                                        tr(
-                                                            language,
-                                                            "Destructive changes detected. Proceed?",
-                                                            "检测到破坏性变更，是否继续？",
-                                                            "偵測到破壞性變更，是否繼續？",
-                                                        ),
-                                                        delete_local,
-                                                        tr(language, "local deletions", "本地删除", "本地刪除"),
-                                                        delete_remote,
-                                                        tr(language, "remote deletions", "远程删除", "遠端刪除"),
-                                                    );
-                                                    modal
-                                                        .confirm()
-                                                        .title(tr(
-                                                            language,
-                                                            "Confirm Destructive Sync",
-                                                            "确认破坏性同步",
-                                                            "確認破壞性同步",
-                                                        ))
-                                                    .child(div().p_4().child(message))
-                                                    .on_ok({
-                                                        let jobs_clone = jobs.clone();
-                                                        let settings_clone = settings.clone();
-                                                        let handle_inner = handle.clone();
-                                                        let target_inner = target_snapshot.clone();
-                                                        move |_, _, cx| {
-                                                            run_execute_jobs(
-                                                                cx,
-                                                                &handle_inner,
-                                                                target_inner.clone(),
-                                                                jobs_clone.clone(),
-                                                                settings_clone.clone(),
-                                                            );
-                                                                true
-                                                            }
-                                                        })
-                                                        .on_cancel(|_, _, _| true)
-                                                });
-                                            } else {
-                                                run_execute_jobs(
-                                                    cx,
+                                            if execute_target.enabled {
+                                                request_execute(
                                                     &execute_handle,
                                                     execute_target.clone(),
-                                                    jobs,
-                                                    settings,
+                                                    language,
+                                                    window,
+                                                    cx,
                                                 );
+                                                return;
                                             }
+
+                                            let handle = execute_handle.clone();
+                                            let target_snapshot = execute_target.clone();
+                                            window.open_modal(cx, move |modal, _, _| {
+                                                modal
+                                                    .confirm()
+                                                    .title(tr(
+                                                        language,
+                                                        "Target Disabled",
+                                                        "目标已停用",
+                                                        "目標已停用",
+                                                    ))
+                                                    .child(div().p_4().child(tr(
+                                                        language,
+                                                        "This target is disabled. Run a sync for it anyway?",
+                                                        "该目标已停用，仍要执行同步吗？",
+                                                        "此目標已停用，仍要執行同步嗎？",
+                                                    )))
+                                                    .on_ok({
+                                                        let handle = handle.clone();
+                                                        let target_snapshot = target_snapshot.clone();
+                                                        move |_, window, cx| {
+                                                            let handle = handle.clone();
+                                                            let target_snapshot =
+                                                                target_snapshot.clone();
+                                                            window.defer(cx, move |window, cx| {
+                                                                request_execute(
+                                                                    &handle,
+                                                                    target_snapshot,
+                                                                    language,
+                                                                    window,
+                                                                    cx,
+                                                                );
+                                                            });
+                                                            true
+                                                        }
+                                                    })
+                                                    .on_cancel(|_, _, _| true)
+                                            });
                                         })
                                 })
                                 .child(
@@ -916,8 +1795,7 @@ impl Render for AppView {
                                             let handle = edit_handle.clone();
                                             move |_, _, cx| {
                                                 handle.update(cx, |state, cx| {
-                                                    state.target_form = Some(TargetFormMode::Edit(target_id));
-                                                    state.active_view = ActiveView::TargetSettings;
+                                                    state.open_target_form(TargetFormMode::Edit(target_id));
                                                     cx.notify();
                                                 });
                                             }
@@ -982,7 +1860,9 @@ impl Render for AppView {
                                                                 handle.update(cx, |state, cx| {
                                                                     state.remote_targets.retain(|t| t.id != target_id);
                                                                     state.connection_tests.remove(&target_id);
+                                                                    state.forget_rule_tests(target_id);
                                                                     state.drop_jobs_for_target(target_id);
+                                                                    pool::forget(target_id);
                                                                     if state.active_target == Some(target_id) {
                                                                         state.active_target = state
                                                                             .remote_targets
@@ -996,10 +1876,7 @@ impl Render for AppView {
                                                                         state.target_form = None;
                                                                         state.active_view = ActiveView::Dashboard;
                                                                     }
-                                                                    save_state(
-                                                                        &state.settings,
-                                                                        &state.remote_targets,
-                                                                    );
+                                                                    persist_state(state);
                                                                     cx.notify();
                                                                 });
                                                                 true
@@ -1029,8 +1906,7 @@ impl Render for AppView {
                                 let handle = self.state.clone();
                                 move |_, _, cx| {
                                     handle.update(cx, |state, cx| {
-                                        state.target_form = Some(TargetFormMode::Create);
-                                        state.active_view = ActiveView::TargetSettings;
+                                        state.open_target_form(TargetFormMode::Create);
                                         cx.notify();
                                     });
                                 }
@@ -1049,23 +1925,108 @@ impl Render for AppView {
                     "尚無同步任務。",
                 ))
         } else {
-            sessions
+            sorted_sessions
                 .iter()
                 .fold(div().v_flex().gap_3(), |builder, session| {
                     builder.child(render_session_card(session, &remote_targets, language, cx))
                 })
         };
 
+        let session_sort_handle = self.state.clone();
+        let sync_all_handle = self.state.clone();
+        let sync_all = self.state.read(cx).sync_all.clone();
         let session_section = GroupBox::new()
             .title(tr(language, "Sync Sessions", "同步任务", "同步任務"))
             .fill()
+            .child(
+                div()
+                    .h_flex()
+                    .justify_between()
+                    .items_center()
+                    .gap_2()
+                    .child(match sync_all {
+                        Some(progress) => div()
+                            .text_sm()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!(
+                                "{} {}/{} · {}",
+                                tr(language, "Syncing all:", "全部同步：", "全部同步："),
+                                progress.completed,
+                                progress.total,
+                                progress.current
+                            )),
+                        None => div().child(
+                            Button::new("sync_all")
+                                .success()
+                                .small()
+                                .icon(Icon::new(IconName::Check).small())
+                                .label(tr(
+                                    language,
+                                    "Sync All Targets",
+                                    "同步全部目标",
+                                    "同步全部目標",
+                                ))
+                                .disabled(!remote_targets.iter().any(|target| target.enabled))
+                                .on_click(move |_, _, cx| {
+                                    run_sync_all(&sync_all_handle, cx);
+                                }),
+                        ),
+                    })
+                    .child(
+                        Button::new("session_sort")
+                            .ghost()
+                            .small()
+                            .icon(Icon::new(IconName::ChevronsUpDown).small())
+                            .label(session_sort_label(settings.session_sort, language))
+                            .on_click(move |_, _, cx| {
+                                session_sort_handle.update(cx, |state, cx| {
+                                    state.settings.session_sort =
+                                        state.settings.session_sort.next();
+                                    persist_state(state);
+                                    cx.notify();
+                                });
+                            }),
+                    ),
+            )
             .child(session_cards);
 
+        let history = self.state.read(cx).history.clone();
+        let history_section = GroupBox::new()
+            .title(tr(language, "History", "历史记录", "歷史記錄"))
+            .fill()
+            .child(if history.is_empty() {
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(tr(
+                        language,
+                        "No finished syncs yet.",
+                        "暂无已完成的同步。",
+                        "尚無已完成的同步。",
+                    ))
+            } else {
+                history
+                    .iter()
+                    .fold(div().v_flex().gap_2(), |builder, record| {
+                        builder.child(render_history_entry(record, language, cx))
+                    })
+            });
+
+        let log_filtered = settings.log_problems_only || settings.log_active_target_only;
         let log_entries = if logs.is_empty() {
             div()
                 .text_sm()
                 .text_color(cx.theme().muted_foreground)
-                .child(tr(language, "No activity yet.", "暂无活动。", "尚無活動。"))
+                .child(if log_filtered {
+                    tr(
+                        language,
+                        "No matching activity.",
+                        "没有符合条件的活动。",
+                        "沒有符合條件的活動。",
+                    )
+                } else {
+                    tr(language, "No activity yet.", "暂无活动。", "尚無活動。")
+                })
         } else {
             logs.iter()
                 .rev()
@@ -1114,20 +2075,95 @@ impl Render for AppView {
                 })
         };
 
+        let problems_handle = self.state.clone();
+        let scope_handle = self.state.clone();
+        let log_filters = div()
+            .h_flex()
+            .justify_end()
+            .gap_2()
+            .child({
+                let button = Button::new("log_problems_only")
+                    .small()
+                    .icon(Icon::new(IconName::TriangleAlert).small())
+                    .label(tr(
+                        language,
+                        "Warnings & errors",
+                        "仅警告和错误",
+                        "僅警告與錯誤",
+                    ))
+                    .on_click(move |_, _, cx| {
+                        problems_handle.update(cx, |state, cx| {
+                            state.settings.log_problems_only = !state.settings.log_problems_only;
+                            persist_state(state);
+                            cx.notify();
+                        });
+                    });
+                if settings.log_problems_only {
+                    button.primary()
+                } else {
+                    button.ghost()
+                }
+            })
+            .child({
+                let button = Button::new("log_active_target_only")
+                    .small()
+                    .icon(Icon::new(IconName::Globe).small())
+                    .label(tr(language, "Selected target", "仅当前目标", "僅目前目標"))
+                    .on_click(move |_, _, cx| {
+                        scope_handle.update(cx, |state, cx| {
+                            state.settings.log_active_target_only =
+                                !state.settings.log_active_target_only;
+                            persist_state(state);
+                            cx.notify();
+                        });
+                    });
+                if settings.log_active_target_only {
+                    button.primary()
+                } else {
+                    button.ghost()
+                }
+            });
+
         let log_section = GroupBox::new()
             .title(tr(language, "Recent Activity", "最近活动", "最近活動"))
             .fill()
+            .child(log_filters)
             .child(log_entries);
 
+        let overviews = self.state.read(cx).target_overviews();
         let dashboard_stack = div()
             .v_flex()
             .gap_4()
             .p_6()
-            .child(target_section)
+            .map(|this| {
+                if active_target_id.is_none() && !overviews.is_empty() {
+                    this.child(render_targets_overview(
+                        &self.state,
+                        &overviews,
+                        &remote_targets,
+                        language,
+                        cx,
+                    ))
+                } else {
+                    this.child(target_section)
+                }
+            })
             .child(session_section)
+            .child(history_section)
             .child(log_section);
 
-        let settings_stack = render_settings_panel(&self.state, &settings, language, cx);
+        let profile_input = self
+            .profile_input
+            .get_or_insert_with(|| {
+                cx.new(|cx| {
+                    let mut input = InputState::new(window, cx);
+                    input.set_placeholder("work", window, cx);
+                    input
+                })
+            })
+            .clone();
+        let settings_stack =
+            render_settings_panel(&self.state, &settings, &profile_input, language, cx);
 
         let target_settings_box = if let Some(mode) = target_form_mode {
             let preset = match mode {
@@ -1170,6 +2206,8 @@ impl Render for AppView {
         let target_settings_stack = div().v_flex().gap_4().p_6().child(target_settings_box);
 
         let main_column = match active_view {
+            // The first-run setup stays up until it is finished or skipped.
+            _ if self.state.read(cx).setup.is_some() => target_settings_stack,
             ActiveView::Dashboard => dashboard_stack,
             ActiveView::Settings => settings_stack,
             ActiveView::TargetSettings => target_settings_stack,
@@ -1211,17 +2249,68 @@ fn render_target_form_panel(
     form.update(cx, |form_view, cx| {
         form_view.ensure_mode(window, cx, mode, preset_ref);
     });
+    let setup = state_handle.read(cx).setup;
 
     let form_state = form.read(cx);
     let name_input = form_state.name.clone();
     let host_input = form_state.host.clone();
     let username_input = form_state.username.clone();
     let base_path_input = form_state.base_path.clone();
+    let local_base_input = form_state.local_base.clone();
     let password_input = form_state.password.clone();
     let private_key_input = form_state.private_key.clone();
     let passphrase_input = form_state.passphrase.clone();
     let auth_choice = form_state.auth_choice;
+    let use_fallback_auth = form_state.use_fallback_auth;
+    let remember_credentials = form_state.remember_credentials;
+    let use_ssh_config = form_state.use_ssh_config;
+    let correct_clock_skew = form_state.correct_clock_skew;
+    let stat_fallback = form_state.stat_fallback;
+    let remote_read_only = form_state.remote_read_only;
+    let monitor_health = form_state.monitor_health;
     let rule_inputs = form_state.rules.clone();
+    let host_key_input = form_state.host_key_algorithms.clone();
+    let kex_input = form_state.kex_algorithms.clone();
+    let allow_legacy = form_state.allow_legacy_algorithms;
+    let max_connections = form_state.max_connections;
+    let socket_buffer_kib = form_state.socket_buffer_kib;
+    let source_input = form_state.source_address.clone();
+    let source_text = source_input.read(cx).text().to_string().trim().to_string();
+    let source_invalid = !source_text.is_empty() && source_text.parse::<IpAddr>().is_err();
+    let tags_input = form_state.tags.clone();
+    let hook_input = form_state.post_sync_command.clone();
+    let hook_enabled = form_state.post_sync_enabled;
+    let hook_on_failure = form_state.post_sync_on_failure;
+    let hook_command = hook_input.read(cx).text().to_string().trim().to_string();
+    let command_inputs = form_state.remote_commands.clone();
+    let commands_enabled = form_state.remote_commands_enabled;
+    let remote_commands: Vec<String> = command_inputs
+        .iter()
+        .map(|input| input.read(cx).text().to_string().trim().to_string())
+        .filter(|command| !command.is_empty())
+        .collect();
+    let target_color = form_state.color;
+    // Saved targets keep their secret in the keychain, even before a restart flags it stored.
+    let stored_credential = match mode {
+        TargetFormMode::Edit(id) => preset_ref
+            .filter(|target| target.remember_credentials)
+            .and_then(|target| {
+                target.auth_methods().find_map(|auth| match auth {
+                    AuthMethod::Password { secret, stored } if *stored || !secret.is_empty() => {
+                        Some(SecretSlot::Password(id))
+                    }
+                    AuthMethod::SshKey {
+                        passphrase,
+                        passphrase_stored,
+                        ..
+                    } if *passphrase_stored || passphrase.is_some() => {
+                        Some(SecretSlot::KeyPassphrase(id))
+                    }
+                    _ => None,
+                })
+            }),
+        TargetFormMode::Create => None,
+    };
 
     let name_value = current_input_value(&name_input, cx);
     let host_value = current_input_value(&host_input, cx);
@@ -1237,11 +2326,16 @@ fn render_target_form_panel(
             !local_value.trim().is_empty() && !remote_value.trim().is_empty()
         });
 
-    let username_ready = !username_value.trim().is_empty();
-    let auth_ready = match auth_choice {
-        AuthChoice::Password => username_ready && !password_value.trim().is_empty(),
-        AuthChoice::SshKey => username_ready && !private_key_value.trim().is_empty(),
-    };
+    // The SSH config can supply the user and IdentityFile.
+    let username_ready = use_ssh_config || !username_value.trim().is_empty();
+    let password_ready = !password_value.trim().is_empty();
+    let key_ready = use_ssh_config || !private_key_value.trim().is_empty();
+    let auth_ready = username_ready
+        && match auth_choice {
+            _ if use_fallback_auth => password_ready && key_ready,
+            AuthChoice::Password => password_ready,
+            AuthChoice::SshKey => key_ready,
+        };
 
     let ready_to_submit = !name_value.trim().is_empty()
         && !host_value.trim().is_empty()
@@ -1249,11 +2343,17 @@ fn render_target_form_panel(
         && rules_ready
         && auth_ready;
 
+    let remote_browse_ready =
+        (!host_value.trim().is_empty() && auth_ready) || matches!(mode, TargetFormMode::Edit(_));
+
     let rules_list = rule_inputs.iter().enumerate().fold(
         div().v_flex().gap_3(),
         |builder, (index, rule_input)| {
             let local_input = rule_input.local.clone();
             let remote_input = rule_input.remote.clone();
+            let include_input = rule_input.include.clone();
+            let ignore_input = rule_input.ignore.clone();
+            let skip_names_input = rule_input.skip_names.clone();
             let remove_button = if rule_inputs.len() > 1 {
                 Some(
                     Button::new(("remove_rule", index))
@@ -1308,58 +2408,570 @@ fn render_target_form_panel(
                 }))
             });
 
-            builder.child(
-                div()
-                    .v_flex()
-                    .gap_2()
-                    .p_3()
-                    .rounded(cx.theme().radius)
-                    .bg(cx.theme().muted.opacity(0.1))
-                    .child(
-                        div()
-                            .h_flex()
-                            .gap_2()
-                            .items_center()
-                            .child(
-                                div()
-                                    .flex_1()
-                                    .child(
-                                        div()
-                                            .text_sm()
-                                            .text_color(cx.theme().muted_foreground)
-                                            .child(tr(
-                                                language,
-                                                "Local Path",
-                                                "本地路径",
-                                                "本地路徑",
-                                            )),
-                                    )
-                                    .child(TextInput::new(&local_input).small()),
-                            )
-                            .child(
-                                div()
-                                    .flex_1()
-                                    .child(
-                                        div()
-                                            .text_sm()
-                                            .text_color(cx.theme().muted_foreground)
-                                            .child(tr(
-                                                language,
-                                                "Remote Path",
-                                                "远程路径",
-                                                "遠端路徑",
-                                            )),
-                                    )
-                                    .child(TextInput::new(&remote_input).small()),
-                            )
-                            .child(remove_button.unwrap_or_else(|| {
-                                Button::new(("noop_rule_remove", index))
-                                    .ghost()
+            let overwrite_selector = OverwritePolicy::ALL.into_iter().fold(
+                div().h_flex().gap_2().items_center().child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(tr(language, "Overwrite:", "覆盖：", "覆寫：")),
+                ),
+                |policy_builder, policy| {
+                    let button_id = match policy {
+                        OverwritePolicy::NewerOnly => ("rule_overwrite_newer", index),
+                        OverwritePolicy::AlwaysOverwrite => ("rule_overwrite_always", index),
+                        OverwritePolicy::SizeOrTimeDiffers => ("rule_overwrite_differs", index),
+                    };
+                    let button = Button::new(button_id)
+                        .small()
+                        .label(overwrite_policy_label(policy, language));
+                    let button = if policy == rule_input.overwrite_policy {
+                        button.primary()
+                    } else {
+                        button.ghost()
+                    };
+                    policy_builder.child(button.on_click({
+                        let handle = form.clone();
+                        move |_, _, cx| {
+                            handle.update(cx, |form, cx| {
+                                if let Some(rule) = form.rules.get_mut(index) {
+                                    rule.overwrite_policy = policy;
+                                    cx.notify();
+                                }
+                            });
+                        }
+                    }))
+                },
+            );
+
+            let compare_selector = ComparePolicy::ALL.into_iter().fold(
+                div().h_flex().gap_2().items_center().child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(tr(language, "Compare:", "比较：", "比較：")),
+                ),
+                |policy_builder, policy| {
+                    let button_id = match policy {
+                        ComparePolicy::ModifiedTime => ("rule_compare_time", index),
+                        ComparePolicy::SizeOnly => ("rule_compare_size", index),
+                        ComparePolicy::Checksum => ("rule_compare_checksum", index),
+                    };
+                    let button = Button::new(button_id)
+                        .small()
+                        .label(compare_policy_label(policy, language))
+                        .tooltip(compare_policy_hint(policy, language));
+                    let button = if policy == rule_input.compare_policy {
+                        button.primary()
+                    } else {
+                        button.ghost()
+                    };
+                    policy_builder.child(button.on_click({
+                        let handle = form.clone();
+                        move |_, _, cx| {
+                            handle.update(cx, |form, cx| {
+                                if let Some(rule) = form.rules.get_mut(index) {
+                                    rule.compare_policy = policy;
+                                    cx.notify();
+                                }
+                            });
+                        }
+                    }))
+                },
+            );
+
+            let collision_selector = CollisionPolicy::ALL.into_iter().fold(
+                div().h_flex().gap_2().items_center().child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(tr(language, "Name collisions:", "同名冲突：", "同名衝突：")),
+                ),
+                |policy_builder, policy| {
+                    let button_id = match policy {
+                        CollisionPolicy::Error => ("rule_collision_error", index),
+                        CollisionPolicy::Rename => ("rule_collision_rename", index),
+                        CollisionPolicy::Overwrite => ("rule_collision_overwrite", index),
+                    };
+                    let button = Button::new(button_id)
+                        .small()
+                        .label(collision_policy_label(policy, language));
+                    let button = if policy == rule_input.collision_policy {
+                        button.primary()
+                    } else {
+                        button.ghost()
+                    };
+                    policy_builder.child(button.on_click({
+                        let handle = form.clone();
+                        move |_, _, cx| {
+                            handle.update(cx, |form, cx| {
+                                if let Some(rule) = form.rules.get_mut(index) {
+                                    rule.collision_policy = policy;
+                                    cx.notify();
+                                }
+                            });
+                        }
+                    }))
+                },
+            );
+
+            let line_endings_selector = LineEndings::ALL.into_iter().fold(
+                div().h_flex().gap_2().items_center().child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(tr(language, "Text line endings:", "文本换行符：", "文字換行符：")),
+                ),
+                |endings_builder, endings| {
+                    let button_id = match endings {
+                        LineEndings::Keep => ("rule_line_endings_keep", index),
+                        LineEndings::Lf => ("rule_line_endings_lf", index),
+                        LineEndings::Crlf => ("rule_line_endings_crlf", index),
+                    };
+                    let button = Button::new(button_id)
+                        .small()
+                        .label(line_endings_label(endings, language));
+                    let button = if endings == rule_input.line_endings {
+                        button.primary()
+                    } else {
+                        button.ghost()
+                    };
+                    endings_builder.child(button.on_click({
+                        let handle = form.clone();
+                        move |_, _, cx| {
+                            handle.update(cx, |form, cx| {
+                                if let Some(rule) = form.rules.get_mut(index) {
+                                    rule.line_endings = endings;
+                                    cx.notify();
+                                }
+                            });
+                        }
+                    }))
+                },
+            );
+
+            let depth_stepper = rule_limit_stepper(
+                ElementId::named_usize("rule_max_depth", index),
+                tr(language, "Max depth:", "最大深度：", "最大深度："),
+                rule_input.max_depth,
+                &MAX_DEPTH_STEPS,
+                |count| count.to_string(),
+                {
+                    let handle = form.clone();
+                    move |max_depth, cx| {
+                        handle.update(cx, |form, cx| {
+                            if let Some(rule) = form.rules.get_mut(index) {
+                                rule.max_depth = max_depth;
+                                cx.notify();
+                            }
+                        });
+                    }
+                },
+                cx,
+            );
+            let entries_stepper = rule_limit_stepper(
+                ElementId::named_usize("rule_max_entries", index),
+                tr(language, "Max files:", "最大文件数：", "最大檔案數："),
+                rule_input.max_entries,
+                &MAX_ENTRIES_STEPS,
+                format_count,
+                {
+                    let handle = form.clone();
+                    move |max_entries, cx| {
+                        handle.update(cx, |form, cx| {
+                            if let Some(rule) = form.rules.get_mut(index) {
+                                rule.max_entries = max_entries;
+                                cx.notify();
+                            }
+                        });
+                    }
+                },
+                cx,
+            );
+
+            let conflict_selector = ConflictPolicy::ALL.into_iter().fold(
+                div().h_flex().gap_2().items_center().child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(tr(language, "Conflicts:", "冲突：", "衝突：")),
+                ),
+                |policy_builder, policy| {
+                    let button_id = match policy {
+                        ConflictPolicy::Skip => ("rule_conflict_skip", index),
+                        ConflictPolicy::KeepLocal => ("rule_conflict_local", index),
+                        ConflictPolicy::KeepRemote => ("rule_conflict_remote", index),
+                    };
+                    let button = Button::new(button_id)
+                        .small()
+                        .label(conflict_policy_label(policy, language));
+                    let button = if policy == rule_input.conflict_policy {
+                        button.primary()
+                    } else {
+                        button.ghost()
+                    };
+                    policy_builder.child(button.on_click({
+                        let handle = form.clone();
+                        move |_, _, cx| {
+                            handle.update(cx, |form, cx| {
+                                if let Some(rule) = form.rules.get_mut(index) {
+                                    rule.conflict_policy = policy;
+                                    cx.notify();
+                                }
+                            });
+                        }
+                    }))
+                },
+            );
+
+            builder.child(
+                div()
+                    .v_flex()
+                    .gap_2()
+                    .p_3()
+                    .rounded(cx.theme().radius)
+                    .bg(cx.theme().muted.opacity(0.1))
+                    .child(
+                        div()
+                            .h_flex()
+                            .gap_2()
+                            .items_center()
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(cx.theme().muted_foreground)
+                                            .child(tr(
+                                                language,
+                                                "Local Path",
+                                                "本地路径",
+                                                "本地路徑",
+                                            )),
+                                    )
+                                    .child(path_input(
+                                        &local_input,
+                                        browse_button(
+                                            ("browse_local", index),
+                                            local_input.clone(),
+                                            true,
+                                            language,
+                                        ),
+                                    )),
+                            )
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(cx.theme().muted_foreground)
+                                            .child(tr(
+                                                language,
+                                                "Remote Path",
+                                                "远程路径",
+                                                "遠端路徑",
+                                            )),
+                                    )
+                                    .child(path_input(
+                                        &remote_input,
+                                        remote_browse_button(
+                                            index,
+                                            form.clone(),
+                                            preset.clone(),
+                                            remote_input.clone(),
+                                            remote_browse_ready,
+                                            language,
+                                        ),
+                                    )),
+                            )
+                            .child(remove_button.unwrap_or_else(|| {
+                                Button::new(("noop_rule_remove", index))
+                                    .ghost()
                                     .icon(Icon::new(IconName::Delete).small())
                                     .disabled(true)
                             })),
                     )
-                    .child(direction_selector),
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(tr(
+                                language,
+                                "Both paths may use $NAME, ${NAME} or %NAME%, expanded from this computer's environment, not the server's, even in the remote path. A remote ~ is the folder the session starts in.",
+                                "两个路径都可以使用 $NAME、${NAME} 或 %NAME%，按本机而非服务器的环境变量展开，远程路径也是如此。远程路径中的 ~ 是会话的起始目录。",
+                                "兩個路徑都可以使用 $NAME、${NAME} 或 %NAME%，依本機而非伺服器的環境變數展開，遠端路徑亦然。遠端路徑中的 ~ 是工作階段的起始資料夾。",
+                            )),
+                    )
+                    .child(
+                        div()
+                            .h_flex()
+                            .gap_2()
+                            .items_center()
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(cx.theme().muted_foreground)
+                                            .child(tr(
+                                                language,
+                                                "Only these files (globs, optional)",
+                                                "仅同步这些文件（通配符，可选）",
+                                                "僅同步這些檔案（萬用字元，選填）",
+                                            )),
+                                    )
+                                    .child(TextInput::new(&include_input).small()),
+                            )
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(cx.theme().muted_foreground)
+                                            .child(tr(
+                                                language,
+                                                "Ignore (globs, wins over the include list)",
+                                                "忽略（通配符，优先于仅同步列表）",
+                                                "忽略（萬用字元，優先於僅同步清單）",
+                                            )),
+                                    )
+                                    .child(TextInput::new(&ignore_input).small()),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .h_flex()
+                            .gap_2()
+                            .items_center()
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .text_color(cx.theme().muted_foreground)
+                                            .child(tr(
+                                                language,
+                                                "Always skip these file names",
+                                                "始终跳过这些文件名",
+                                                "一律略過這些檔名",
+                                            )),
+                                    )
+                                    .child(TextInput::new(&skip_names_input).small()),
+                            )
+                            .child(
+                                Switch::new(("rule_skip_empty", index))
+                                    .checked(rule_input.skip_empty_files)
+                                    .label(tr(
+                                        language,
+                                        "Skip empty files",
+                                        "跳过空文件",
+                                        "略過空檔案",
+                                    ))
+                                    .on_click({
+                                        let handle = form.clone();
+                                        move |next, _, cx| {
+                                            let skip_empty_files = *next;
+                                            handle.update(cx, |form, cx| {
+                                                if let Some(rule) = form.rules.get_mut(index) {
+                                                    rule.skip_empty_files = skip_empty_files;
+                                                    cx.notify();
+                                                }
+                                            });
+                                        }
+                                    }),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .h_flex()
+                            .gap_4()
+                            .items_center()
+                            .flex_wrap()
+                            .child(direction_selector)
+                            .child(
+                                Switch::new(("rule_hidden", index))
+                                    .checked(rule_input.include_hidden)
+                                    .label(tr(
+                                        language,
+                                        "Include hidden files",
+                                        "包含隐藏文件",
+                                        "包含隱藏檔案",
+                                    ))
+                                    .on_click({
+                                        let handle = form.clone();
+                                        move |next, _, cx| {
+                                            let include_hidden = *next;
+                                            handle.update(cx, |form, cx| {
+                                                if let Some(rule) = form.rules.get_mut(index) {
+                                                    rule.include_hidden = include_hidden;
+                                                    cx.notify();
+                                                }
+                                            });
+                                        }
+                                    }),
+                            )
+                            .child(
+                                Switch::new(("rule_symlinks", index))
+                                    .checked(rule_input.follow_symlinks)
+                                    .label(tr(
+                                        language,
+                                        "Follow symlinks",
+                                        "跟随符号链接",
+                                        "跟隨符號連結",
+                                    ))
+                                    .on_click({
+                                        let handle = form.clone();
+                                        move |next, _, cx| {
+                                            let follow_symlinks = *next;
+                                            handle.update(cx, |form, cx| {
+                                                if let Some(rule) = form.rules.get_mut(index) {
+                                                    rule.follow_symlinks = follow_symlinks;
+                                                    cx.notify();
+                                                }
+                                            });
+                                        }
+                                    }),
+                            )
+                            .child(
+                                Switch::new(("rule_flatten", index))
+                                    .checked(rule_input.flatten)
+                                    .disabled(rule_input.direction != SyncDirection::Push)
+                                    .label(tr(
+                                        language,
+                                        "Flatten into remote folder",
+                                        "平铺到远程目录",
+                                        "平鋪到遠端資料夾",
+                                    ))
+                                    .on_click({
+                                        let handle = form.clone();
+                                        move |next, _, cx| {
+                                            let flatten = *next;
+                                            handle.update(cx, |form, cx| {
+                                                if let Some(rule) = form.rules.get_mut(index) {
+                                                    rule.flatten = flatten;
+                                                    cx.notify();
+                                                }
+                                            });
+                                        }
+                                    }),
+                            )
+                            .child(
+                                Switch::new(("rule_hard_links", index))
+                                    .checked(rule_input.hard_links)
+                                    .disabled(rule_input.direction != SyncDirection::Push)
+                                    .label(tr(
+                                        language,
+                                        "Link hard-linked files on the server",
+                                        "在服务器上重建硬链接",
+                                        "在伺服器上重建硬連結",
+                                    ))
+                                    .on_click({
+                                        let handle = form.clone();
+                                        move |next, _, cx| {
+                                            let hard_links = *next;
+                                            handle.update(cx, |form, cx| {
+                                                if let Some(rule) = form.rules.get_mut(index) {
+                                                    rule.hard_links = hard_links;
+                                                    cx.notify();
+                                                }
+                                            });
+                                        }
+                                    }),
+                            )
+                            .child(
+                                Switch::new(("rule_preserve_ownership", index))
+                                    .checked(rule_input.preserve_ownership)
+                                    .disabled(rule_input.direction == SyncDirection::Pull)
+                                    .label(tr(
+                                        language,
+                                        "Keep owner, group and xattrs",
+                                        "保留所有者、组和扩展属性",
+                                        "保留擁有者、群組和延伸屬性",
+                                    ))
+                                    .on_click({
+                                        let handle = form.clone();
+                                        move |next, _, cx| {
+                                            let preserve_ownership = *next;
+                                            handle.update(cx, |form, cx| {
+                                                if let Some(rule) = form.rules.get_mut(index) {
+                                                    rule.preserve_ownership = preserve_ownership;
+                                                    cx.notify();
+                                                }
+                                            });
+                                        }
+                                    }),
+                            )
+                            .child(
+                                Switch::new(("rule_remote_read_only", index))
+                                    .checked(rule_input.remote_read_only)
+                                    .label(tr(language, "Read-only remote", "远程只读", "遠端唯讀"))
+                                    .on_click({
+                                        let handle = form.clone();
+                                        move |next, _, cx| {
+                                            let read_only = *next;
+                                            handle.update(cx, |form, cx| {
+                                                if let Some(rule) = form.rules.get_mut(index) {
+                                                    rule.remote_read_only = read_only;
+                                                    cx.notify();
+                                                }
+                                            });
+                                        }
+                                    }),
+                            )
+                            .when(rule_input.direction == SyncDirection::Push, |this| {
+                                this.child(overwrite_selector)
+                            })
+                            .child(compare_selector)
+                            .child(collision_selector)
+                            .child(
+                                Switch::new(("rule_case_insensitive", index))
+                                    .checked(rule_input.case_insensitive)
+                                    .label(tr(
+                                        language,
+                                        "Destination ignores case",
+                                        "目标不区分大小写",
+                                        "目標不區分大小寫",
+                                    ))
+                                    .on_click({
+                                        let handle = form.clone();
+                                        move |next, _, cx| {
+                                            let case_insensitive = *next;
+                                            handle.update(cx, |form, cx| {
+                                                if let Some(rule) = form.rules.get_mut(index) {
+                                                    rule.case_insensitive = case_insensitive;
+                                                    cx.notify();
+                                                }
+                                            });
+                                        }
+                                    }),
+                            )
+                            .child(line_endings_selector)
+                            .when(
+                                rule_input.line_endings.clashes_with(
+                                    rule_input.direction,
+                                    rule_input.compare_policy,
+                                    rule_input.overwrite_policy,
+                                ),
+                                |this| {
+                                    this.child(Tag::warning().small().rounded_full().child(tr(
+                                        language,
+                                        "Rewriting line endings changes file sizes: compare by time or checksum, and don't overwrite on size differences",
+                                        "改写换行符会改变文件大小：请按时间或校验和比较，且不要按大小差异覆盖",
+                                        "改寫換行符會改變檔案大小：請依時間或校驗和比較，且不要依大小差異覆寫",
+                                    )))
+                                },
+                            )
+                            .child(depth_stepper)
+                            .child(entries_stepper)
+                            .when(
+                                rule_input.direction == SyncDirection::Bidirectional,
+                                |this| this.child(conflict_selector),
+                            ),
+                    ),
             )
         },
     );
@@ -1403,7 +3015,486 @@ fn render_target_form_panel(
             }))
         });
 
-    let auth_fields = match auth_choice {
+    let color_selector = TargetColor::ALL.into_iter().enumerate().fold(
+        div().h_flex().gap_2().items_center(),
+        |builder, (idx, color)| {
+            let mut button = Button::new(("target_color", idx))
+                .small()
+                .label(target_color_label(color, language));
+            if color == target_color {
+                button = button.primary();
+            } else {
+                button = button.ghost();
+            }
+            builder.child(
+                div()
+                    .h_flex()
+                    .gap_1()
+                    .items_center()
+                    .when_some(color.rgb(), |this, color| {
+                        this.child(div().size_2().rounded_full().bg(rgb(color)))
+                    })
+                    .child(button.on_click({
+                        let handle = form.clone();
+                        move |_, _, cx| {
+                            handle.update(cx, |form, cx| {
+                                form.color = color;
+                                cx.notify();
+                            });
+                        }
+                    })),
+            )
+        },
+    );
+
+    let clock_skew_switch = Switch::new("correct_clock_skew")
+        .checked(correct_clock_skew)
+        .on_click({
+            let handle = form.clone();
+            move |next, _, cx| {
+                let correct = *next;
+                handle.update(cx, |form, cx| {
+                    form.correct_clock_skew = correct;
+                    cx.notify();
+                });
+            }
+        });
+    let stat_fallback_switch = Switch::new("stat_fallback")
+        .checked(stat_fallback)
+        .on_click({
+            let handle = form.clone();
+            move |next, _, cx| {
+                let fallback = *next;
+                handle.update(cx, |form, cx| {
+                    form.stat_fallback = fallback;
+                    cx.notify();
+                });
+            }
+        });
+    let read_only_switch = Switch::new("remote_read_only")
+        .checked(remote_read_only)
+        .on_click({
+            let handle = form.clone();
+            move |next, _, cx| {
+                let read_only = *next;
+                handle.update(cx, |form, cx| {
+                    form.remote_read_only = read_only;
+                    cx.notify();
+                });
+            }
+        });
+    let monitor_health_switch = Switch::new("monitor_health")
+        .checked(monitor_health)
+        .on_click({
+            let handle = form.clone();
+            move |next, _, cx| {
+                let monitor = *next;
+                handle.update(cx, |form, cx| {
+                    form.monitor_health = monitor;
+                    cx.notify();
+                });
+            }
+        });
+    let hook_switch = Switch::new("post_sync_enabled")
+        .checked(hook_enabled)
+        .on_click({
+            let handle = form.clone();
+            move |next, _, cx| {
+                let enabled = *next;
+                handle.update(cx, |form, cx| {
+                    form.post_sync_enabled = enabled;
+                    cx.notify();
+                });
+            }
+        });
+    let hook_failure_switch = Switch::new("post_sync_on_failure")
+        .checked(hook_on_failure)
+        .on_click({
+            let handle = form.clone();
+            move |next, _, cx| {
+                let on_failure = *next;
+                handle.update(cx, |form, cx| {
+                    form.post_sync_on_failure = on_failure;
+                    cx.notify();
+                });
+            }
+        });
+    let hook_fields = div()
+        .v_flex()
+        .gap_3()
+        .child(settings_row(
+            tr(language, "Command", "命令", "命令"),
+            tr(
+                language,
+                "Runs in a local shell after a sync. SFTP_SYNC_TARGET, SFTP_SYNC_APPLIED and SFTP_SYNC_FAILED describe the run.",
+                "同步后在本地 shell 中运行。SFTP_SYNC_TARGET、SFTP_SYNC_APPLIED 和 SFTP_SYNC_FAILED 描述本次运行。",
+                "同步後在本機 shell 中執行。SFTP_SYNC_TARGET、SFTP_SYNC_APPLIED 與 SFTP_SYNC_FAILED 描述本次執行。",
+            ),
+            TextInput::new(&hook_input).small(),
+            cx,
+        ))
+        .child(settings_row(
+            tr(language, "Run hook", "运行钩子", "執行鉤子"),
+            tr(
+                language,
+                "The command never runs until this is on.",
+                "开启前命令永远不会运行。",
+                "開啟前命令永遠不會執行。",
+            ),
+            hook_switch,
+            cx,
+        ))
+        .child(settings_row(
+            tr(
+                language,
+                "Run after failed syncs",
+                "同步失败后也运行",
+                "同步失敗後也執行",
+            ),
+            tr(
+                language,
+                "By default the hook is skipped when any action failed.",
+                "默认情况下，任何操作失败时都会跳过钩子。",
+                "預設情況下，任何動作失敗時都會略過鉤子。",
+            ),
+            hook_failure_switch,
+            cx,
+        ))
+        .when(hook_enabled && !hook_command.is_empty(), |fields| {
+            fields.child(Tag::warning().small().rounded_full().child(format!(
+                "{} {hook_command}",
+                tr(
+                    language,
+                    "Runs on this computer after each sync:",
+                    "每次同步后在本机运行：",
+                    "每次同步後在本機執行：",
+                ),
+            )))
+        });
+    let command_rows = command_inputs.iter().enumerate().map(|(index, input)| {
+        div()
+            .h_flex()
+            .gap_2()
+            .items_center()
+            .child(div().flex_1().child(TextInput::new(input).small()))
+            .child(
+                Button::new(("remove_remote_command", index))
+                    .ghost()
+                    .icon(Icon::new(IconName::Delete).small())
+                    .on_click({
+                        let handle = form.clone();
+                        move |_, _, cx| {
+                            handle.update(cx, |form, cx| {
+                                if index < form.remote_commands.len() {
+                                    form.remote_commands.remove(index);
+                                    cx.notify();
+                                }
+                            });
+                        }
+                    }),
+            )
+    });
+    let commands_switch = Switch::new("remote_commands_enabled")
+        .checked(commands_enabled)
+        .on_click({
+            let handle = form.clone();
+            move |next, _, cx| {
+                let enabled = *next;
+                handle.update(cx, |form, cx| {
+                    form.remote_commands_enabled = enabled;
+                    cx.notify();
+                });
+            }
+        });
+    let command_fields = div()
+        .v_flex()
+        .gap_3()
+        .child(
+            div()
+                .text_xs()
+                .text_color(cx.theme().muted_foreground)
+                .child(tr(
+                    language,
+                    "Run on the server in order, over the sync's SSH session, after a sync without failures. A failing command fails the sync.",
+                    "在无失败的同步之后，通过同步的 SSH 会话在服务器上依次运行。命令失败会使同步失败。",
+                    "在無失敗的同步之後，透過同步的 SSH 工作階段在伺服器上依序執行。命令失敗會使同步失敗。",
+                )),
+        )
+        .child(div().v_flex().gap_2().children(command_rows))
+        .child(
+            Button::new("add_remote_command")
+                .ghost()
+                .icon(Icon::new(IconName::Plus).small())
+                .label(tr(language, "Add Command", "新增命令", "新增命令"))
+                .on_click({
+                    let handle = form.clone();
+                    move |_, window, cx| {
+                        handle.update(cx, |form, cx| {
+                            form.add_remote_command(window, cx, "");
+                            cx.notify();
+                        });
+                    }
+                }),
+        )
+        .child(settings_row(
+            tr(
+                language,
+                "Run remote commands",
+                "运行远程命令",
+                "執行遠端命令",
+            ),
+            tr(
+                language,
+                "The commands never run until this is on.",
+                "开启前命令永远不会运行。",
+                "開啟前命令永遠不會執行。",
+            ),
+            commands_switch,
+            cx,
+        ))
+        .when(commands_enabled && !remote_commands.is_empty(), |fields| {
+            fields.child(Tag::warning().small().rounded_full().child(format!(
+                "{} {}",
+                tr(
+                    language,
+                    "Runs on the server after each sync:",
+                    "每次同步后在服务器上运行：",
+                    "每次同步後在伺服器上執行：",
+                ),
+                remote_commands.join(" ; "),
+            )))
+        });
+    let legacy_switch = Switch::new("allow_legacy_algorithms")
+        .checked(allow_legacy)
+        .on_click({
+            let handle = form.clone();
+            move |next, _, cx| {
+                let allow = *next;
+                handle.update(cx, |form, cx| {
+                    form.allow_legacy_algorithms = allow;
+                    cx.notify();
+                });
+            }
+        });
+
+    let connections_controls = div()
+        .h_flex()
+        .gap_2()
+        .items_center()
+        .child(
+            Button::new("max_connections_decrease")
+                .ghost()
+                .icon(Icon::new(IconName::Minus).small())
+                .disabled(max_connections <= 1)
+                .on_click({
+                    let handle = form.clone();
+                    move |_, _, cx| {
+                        handle.update(cx, |form, cx| {
+                            form.max_connections = form.max_connections.saturating_sub(1).max(1);
+                            cx.notify();
+                        });
+                    }
+                }),
+        )
+        .child(
+            Tag::info()
+                .small()
+                .rounded_full()
+                .child(max_connections.to_string()),
+        )
+        .child(
+            Button::new("max_connections_increase")
+                .ghost()
+                .icon(Icon::new(IconName::Plus).small())
+                .disabled(max_connections >= MAX_CONNECTIONS_LIMIT)
+                .on_click({
+                    let handle = form.clone();
+                    move |_, _, cx| {
+                        handle.update(cx, |form, cx| {
+                            form.max_connections =
+                                (form.max_connections + 1).min(MAX_CONNECTIONS_LIMIT);
+                            cx.notify();
+                        });
+                    }
+                }),
+        );
+
+    let buffer_step = SOCKET_BUFFER_STEPS_KIB
+        .iter()
+        .position(|kib| *kib >= socket_buffer_kib)
+        .unwrap_or(SOCKET_BUFFER_STEPS_KIB.len() - 1);
+    let buffer_label = if socket_buffer_kib == 0 {
+        tr(language, "System", "系统默认", "系統預設").to_string()
+    } else {
+        format_bytes(socket_buffer_kib as u64 * 1024, language)
+    };
+    let buffer_controls = div()
+        .h_flex()
+        .gap_2()
+        .items_center()
+        .child(
+            Button::new("socket_buffer_decrease")
+                .ghost()
+                .icon(Icon::new(IconName::Minus).small())
+                .disabled(buffer_step == 0)
+                .on_click({
+                    let handle = form.clone();
+                    move |_, _, cx| {
+                        handle.update(cx, |form, cx| {
+                            form.socket_buffer_kib =
+                                SOCKET_BUFFER_STEPS_KIB[buffer_step.saturating_sub(1)];
+                            cx.notify();
+                        });
+                    }
+                }),
+        )
+        .child(Tag::info().small().rounded_full().child(buffer_label))
+        .child(
+            Button::new("socket_buffer_increase")
+                .ghost()
+                .icon(Icon::new(IconName::Plus).small())
+                .disabled(buffer_step + 1 >= SOCKET_BUFFER_STEPS_KIB.len())
+                .on_click({
+                    let handle = form.clone();
+                    move |_, _, cx| {
+                        handle.update(cx, |form, cx| {
+                            let next = (buffer_step + 1).min(SOCKET_BUFFER_STEPS_KIB.len() - 1);
+                            form.socket_buffer_kib = SOCKET_BUFFER_STEPS_KIB[next];
+                            cx.notify();
+                        });
+                    }
+                }),
+        );
+
+    let advanced_fields = div()
+        .v_flex()
+        .gap_3()
+        .child(settings_row(
+            tr(language, "Max connections", "最大连接数", "最大連線數"),
+            tr(
+                language,
+                "Connections opened to this host at once. Extra work waits its turn.",
+                "同时连接到此主机的数量，超出的任务会排队等待。",
+                "同時連線到此主機的數量，超出的工作會排隊等待。",
+            ),
+            connections_controls,
+            cx,
+        ))
+        .child(settings_row(
+            tr(language, "Source address", "源地址", "來源位址"),
+            tr(
+                language,
+                "Local IP to connect from, to pick the interface on multi-homed machines. Leave empty to let the system choose.",
+                "发起连接所用的本机 IP，用于在多网卡机器上选择网络接口。留空由系统选择。",
+                "發起連線所用的本機 IP，用於在多網卡機器上選擇網路介面。留空由系統選擇。",
+            ),
+            TextInput::new(&source_input).small(),
+            cx,
+        ))
+        .child(settings_row(
+            tr(language, "Socket buffers", "套接字缓冲区", "通訊端緩衝區"),
+            tr(
+                language,
+                "Send and receive buffer size of the connection. Larger buffers can speed up big transfers over fast, distant links; the system default suits most servers.",
+                "连接的发送与接收缓冲区大小。较大的缓冲区可加快高速远距离链路上的大文件传输；系统默认值适合大多数服务器。",
+                "連線的傳送與接收緩衝區大小。較大的緩衝區可加快高速遠距離連結上的大檔案傳輸；系統預設值適合大多數伺服器。",
+            ),
+            buffer_controls,
+            cx,
+        ))
+        .when(source_invalid, |fields| {
+            fields.child(Tag::warning().small().rounded_full().child(tr(
+                language,
+                "Source address must be an IPv4 or IPv6 address",
+                "源地址必须是 IPv4 或 IPv6 地址",
+                "來源位址必須是 IPv4 或 IPv6 位址",
+            )))
+        })
+        .child(settings_row(
+            tr(
+                language,
+                "Correct clock skew",
+                "校正时钟偏差",
+                "校正時鐘偏差",
+            ),
+            tr(
+                language,
+                "Measure the server clock when planning and shift remote modification times by the offset.",
+                "规划时测量服务器时钟，并按偏差调整远程修改时间。",
+                "規劃時測量伺服器時鐘，並依偏差調整遠端修改時間。",
+            ),
+            clock_skew_switch,
+            cx,
+        ))
+        .child(settings_row(
+            tr(
+                language,
+                "Stat fallback",
+                "逐个查询文件属性",
+                "逐一查詢檔案屬性",
+            ),
+            tr(
+                language,
+                "Look up files one at a time when the server lists them without size or modification time. Slower, but keeps them from comparing as empty.",
+                "服务器列出的文件缺少大小或修改时间时，逐个查询。较慢，但可避免它们被当作空文件比较。",
+                "伺服器列出的檔案缺少大小或修改時間時，逐一查詢。較慢，但可避免它們被當作空檔案比較。",
+            ),
+            stat_fallback_switch,
+            cx,
+        ))
+        .child(settings_row(
+            tr(
+                language,
+                "Host key algorithms",
+                "主机密钥算法",
+                "主機金鑰演算法",
+            ),
+            tr(
+                language,
+                "Comma-separated, most preferred first. Leave empty for secure defaults.",
+                "逗号分隔，优先级从高到低。留空使用安全默认值。",
+                "以逗號分隔，優先順序由高到低。留空使用安全預設值。",
+            ),
+            TextInput::new(&host_key_input).small(),
+            cx,
+        ))
+        .child(settings_row(
+            tr(language, "Key exchange algorithms", "密钥交换算法", "金鑰交換演算法"),
+            tr(
+                language,
+                "Comma-separated KEX names, e.g. curve25519-sha256.",
+                "逗号分隔的 KEX 名称，例如 curve25519-sha256。",
+                "以逗號分隔的 KEX 名稱，例如 curve25519-sha256。",
+            ),
+            TextInput::new(&kex_input).small(),
+            cx,
+        ))
+        .child(settings_row(
+            tr(
+                language,
+                "Allow legacy algorithms",
+                "允许旧版算法",
+                "允許舊版演算法",
+            ),
+            tr(
+                language,
+                "Fall back to ssh-rsa and SHA-1 key exchange for old servers.",
+                "为旧服务器回退到 ssh-rsa 和 SHA-1 密钥交换。",
+                "為舊伺服器回退到 ssh-rsa 與 SHA-1 金鑰交換。",
+            ),
+            legacy_switch,
+            cx,
+        ))
+        .when(allow_legacy, |fields| {
+            fields.child(Tag::warning().small().rounded_full().child(tr(
+                language,
+                "Legacy algorithms weaken connection security",
+                "旧版算法会降低连接安全性",
+                "舊版演算法會降低連線安全性",
+            )))
+        });
+
+    let auth_fields = match auth_choice {
         AuthChoice::Password => div()
             .v_flex()
             .gap_3()
@@ -1451,7 +3542,15 @@ fn render_target_form_panel(
                     "私钥文件的路径。",
                     "私鑰檔案路徑。",
                 ),
-                TextInput::new(&private_key_input).small(),
+                path_input(
+                    &private_key_input,
+                    browse_button(
+                        "browse_private_key",
+                        private_key_input.clone(),
+                        false,
+                        language,
+                    ),
+                ),
                 cx,
             ))
             .child(settings_row(
@@ -1471,206 +3570,352 @@ fn render_target_form_panel(
                 cx,
             )),
     };
-
-    let cancel_handle = state_handle.clone();
-    let cancel_button = Button::new("cancel_target_creation")
-        .ghost()
-        .label(tr(language, "Cancel", "取消", "取消"))
-        .on_click(move |_, _, cx| {
-            cancel_handle.update(cx, |state, cx| {
-                state.target_form = None;
-                state.active_view = ActiveView::Dashboard;
-                cx.notify();
-            });
+    let fallback_switch = Switch::new("use_fallback_auth")
+        .checked(use_fallback_auth)
+        .on_click({
+            let handle = form.clone();
+            move |next, _, cx| {
+                let enabled = *next;
+                handle.update(cx, |form, cx| {
+                    form.use_fallback_auth = enabled;
+                    cx.notify();
+                });
+            }
         });
-
-    let submit_handle = state_handle.clone();
-    let form_handle = form.clone();
-    let submit_button = Button::new("target_form_submit")
-        .primary()
-        .disabled(!ready_to_submit)
-        .label(match mode {
-            TargetFormMode::Create => tr(language, "Create Target", "创建目标", "建立目標"),
-            TargetFormMode::Edit(_) => tr(language, "Save Changes", "保存更改", "儲存變更"),
-        })
-        .on_click(move |_, _, cx| match mode {
-            TargetFormMode::Create => {
-                let next_id = submit_handle.read(cx).next_target_id();
-                if let Some(new_target) =
-                    form_handle.update(cx, |form, cx| form.build_target(next_id, cx))
-                {
-                    let plan_target = new_target.clone();
-                    submit_handle.update(cx, |state, cx| {
-                        state.remote_targets.push(new_target);
-                        state.active_target = state.remote_targets.last().map(|target| target.id);
-                        state.target_form = None;
-                        state.active_view = ActiveView::Dashboard;
-                        state.set_task_progress(
-                            plan_target.id,
-                            TaskProgress::new(
-                                TaskKind::Planning,
-                                0,
-                                plan_target.rules.len().max(1),
-                            ),
-                        );
-                        save_state(&state.settings, &state.remote_targets);
-                        cx.notify();
-                    });
-                    let async_handle = submit_handle.clone();
-                    cx.spawn({
-                        let plan_target = plan_target.clone();
-                        async move |cx| {
-                            let target_name = plan_target.name.clone();
-                            let receiver = task_queue::submit_plan(plan_target.clone());
-                            while let Ok(event) = receiver.recv().await {
-                                match event {
-                                    TaskEvent::Progress { completed, total } => {
-                                        let _ = async_handle.update(cx, |state, cx| {
-                                            state.set_task_progress(
-                                                plan_target.id,
-                                                TaskProgress::new(TaskKind::Planning, completed, total),
-                                            );
-                                            cx.notify();
-                                        });
-                                    }
-                                    TaskEvent::Finished(result) => {
-                                        let _ = async_handle.update(cx, |state, cx| {
-                                            state.clear_task_progress(plan_target.id);
-                                            cx.notify();
-                                        });
-                                        match result {
-                                            Ok(result) => {
-                                                let _ = async_handle.update(cx, |state, cx| {
-                                                    state.apply_planned_jobs(plan_target.id, result);
-                                                    let pending: usize = state
-                                                        .jobs
-                                                        .iter()
-                                                        .filter(|job| job.target_id == plan_target.id)
-                                                        .map(|job| job.pending_actions())
-                                                        .sum();
-                                                    state.log_event(
-                                                        LogLevel::Info,
-                                                        format!(
-                                                            "Sync plan ready for {target_name} ({pending} actions)"
-                                                        ),
-                                                    );
-                                                    cx.notify();
-                                                });
-                                            }
-                                            Err(err) => {
-                                                let _ = async_handle.update(cx, |state, cx| {
-                                                    state.log_event(
-                                                        LogLevel::Error,
-                                                        format!(
-                                                            "Failed to prepare sync plan for {target_name}: {err}"
-                                                        ),
-                                                    );
-                                                    cx.notify();
-                                                });
-                                            }
-                                        }
-                                        break;
-                                    }
-                                }
-                            }
-                            Ok::<_, Error>(())
-                        }
-                    })
-                    .detach();
-                }
+    let auth_fields = auth_fields.child(settings_row(
+        match auth_choice {
+            AuthChoice::Password => tr(
+                language,
+                "Fall back to SSH key",
+                "回退到密钥",
+                "回退到 SSH 金鑰",
+            ),
+            AuthChoice::SshKey => tr(
+                language,
+                "Fall back to password",
+                "回退到密码",
+                "回退到密碼",
+            ),
+        },
+        tr(
+            language,
+            "Tried only when the primary method is rejected.",
+            "仅在主要方式被拒绝时尝试。",
+            "僅在主要方式被拒絕時嘗試。",
+        ),
+        fallback_switch,
+        cx,
+    ));
+    let auth_fields = match (use_fallback_auth, auth_choice) {
+        (false, _) => auth_fields,
+        (true, AuthChoice::Password) => auth_fields
+            .child(settings_row(
+                tr(language, "Private Key Path", "私钥路径", "私鑰路徑"),
+                tr(
+                    language,
+                    "Path to the private key file.",
+                    "私钥文件的路径。",
+                    "私鑰檔案路徑。",
+                ),
+                path_input(
+                    &private_key_input,
+                    browse_button(
+                        "browse_private_key",
+                        private_key_input.clone(),
+                        false,
+                        language,
+                    ),
+                ),
+                cx,
+            ))
+            .child(settings_row(
+                tr(
+                    language,
+                    "Passphrase (optional)",
+                    "密钥口令（可选）",
+                    "金鑰密碼（可選）",
+                ),
+                tr(
+                    language,
+                    "Leave empty if the key has no passphrase.",
+                    "如果没有口令可留空。",
+                    "若沒有口令可留白。",
+                ),
+                TextInput::new(&passphrase_input).mask_toggle().small(),
+                cx,
+            )),
+        (true, AuthChoice::SshKey) => auth_fields.child(settings_row(
+            tr(language, "Password", "密码", "密碼"),
+            tr(
+                language,
+                "Stored securely in the system keychain.",
+                "安全存储在系统钥匙串中。",
+                "安全儲存在系統鑰匙圈中。",
+            ),
+            TextInput::new(&password_input).mask_toggle().small(),
+            cx,
+        )),
+    };
+    let ssh_config_switch = Switch::new("use_ssh_config")
+        .checked(use_ssh_config)
+        .on_click({
+            let handle = form.clone();
+            move |next, _, cx| {
+                let enabled = *next;
+                handle.update(cx, |form, cx| {
+                    form.use_ssh_config = enabled;
+                    cx.notify();
+                });
             }
-            TargetFormMode::Edit(target_id) => {
-                if let Some(updated) =
-                    form_handle.update(cx, |form, cx| form.build_target(target_id, cx))
-                {
-                    let plan_target = updated.clone();
-                    submit_handle.update(cx, |state, cx| {
-                        if let Some(existing) = state
-                            .remote_targets
-                            .iter_mut()
-                            .find(|target| target.id == target_id)
-                        {
-                            *existing = updated;
-                        }
-                        state.set_task_progress(
-                            plan_target.id,
-                            TaskProgress::new(
-                                TaskKind::Planning,
-                                0,
-                                plan_target.rules.len().max(1),
-                            ),
-                        );
-                        save_state(&state.settings, &state.remote_targets);
-                        state.target_form = None;
-                        state.active_view = ActiveView::Dashboard;
-                        cx.notify();
+        });
+    let remember_switch = Switch::new("remember_credentials")
+        .checked(remember_credentials)
+        .on_click({
+            let handle = form.clone();
+            move |next, _, cx| {
+                let remember = *next;
+                handle.update(cx, |form, cx| {
+                    form.remember_credentials = remember;
+                    cx.notify();
+                });
+            }
+        });
+    let auth_fields = auth_fields.child(settings_row(
+        tr(language, "Save credentials", "保存凭据", "儲存憑證"),
+        tr(
+            language,
+            "Keep secrets in the system keychain. When off, they are asked for once per session.",
+            "将密钥保存在系统钥匙串中。关闭后，每次启动会话时询问一次。",
+            "將密鑰儲存在系統鑰匙圈中。關閉後，每個工作階段詢問一次。",
+        ),
+        remember_switch,
+        cx,
+    ));
+    let auth_fields = match stored_credential {
+        Some(slot) => {
+            let clear_handle = state_handle.clone();
+            let clear_form = form.clone();
+            let clear_button = Button::new("clear_stored_credential")
+                .danger()
+                .small()
+                .label(tr(
+                    language,
+                    "Clear stored credential",
+                    "清除已存储的凭据",
+                    "清除已儲存的憑證",
+                ))
+                .on_click(move |_, window, cx| {
+                    let handle = clear_handle.clone();
+                    let form = clear_form.clone();
+                    window.open_modal(cx, move |modal, _, _| {
+                        modal
+                            .confirm()
+                            .title(tr(
+                                language,
+                                "Clear Stored Credential",
+                                "清除已存储的凭据",
+                                "清除已儲存的憑證",
+                            ))
+                            .child(div().p_4().child(tr(
+                                language,
+                                "Remove the saved secret for this target from the system keychain? You will need to enter it again before connecting.",
+                                "要从系统钥匙串中移除该目标已保存的密钥吗？连接前需要重新输入。",
+                                "要從系統鑰匙圈中移除此目標已儲存的密鑰嗎？連線前需要重新輸入。",
+                            )))
+                            .button_props(
+                                ModalButtonProps::default()
+                                    .ok_text(tr(language, "Clear", "清除", "清除"))
+                                    .ok_variant(ButtonVariant::Danger)
+                                    .cancel_text(tr(language, "Cancel", "取消", "取消")),
+                            )
+                            .on_ok({
+                                let handle = handle.clone();
+                                let form = form.clone();
+                                move |_, window, cx| {
+                                    clear_stored_credential(&handle, slot, cx);
+                                    form.update(cx, |form, cx| {
+                                        let input = match slot {
+                                            SecretSlot::Password(_) => form.password.clone(),
+                                            SecretSlot::KeyPassphrase(_) => form.passphrase.clone(),
+                                        };
+                                        form.set_value(&input, "", window, cx);
+                                    });
+                                    true
+                                }
+                            })
+                            .on_cancel(|_, _, _| true)
                     });
+                });
+            auth_fields.child(settings_row(
+                tr(
+                    language,
+                    "Stored credential",
+                    "已存储的凭据",
+                    "已儲存的憑證",
+                ),
+                tr(
+                    language,
+                    "Remove the saved secret, e.g. after a typo or when rotating credentials.",
+                    "移除已保存的密钥，例如输错或轮换凭据时。",
+                    "移除已儲存的密鑰，例如輸入錯誤或輪換憑證時。",
+                ),
+                clear_button,
+                cx,
+            ))
+        }
+        None => auth_fields,
+    };
 
-                    let async_handle = submit_handle.clone();
-                    cx.spawn({
-                        let plan_target = plan_target.clone();
-                        async move |cx| {
-                            let target_name = plan_target.name.clone();
-                            let receiver = task_queue::submit_plan(plan_target.clone());
-                            while let Ok(event) = receiver.recv().await {
-                                match event {
-                                    TaskEvent::Progress { completed, total } => {
-                                        let _ = async_handle.update(cx, |state, cx| {
-                                            state.set_task_progress(
-                                                plan_target.id,
-                                                TaskProgress::new(TaskKind::Planning, completed, total),
-                                            );
-                                            cx.notify();
-                                        });
-                                    }
-                                    TaskEvent::Finished(result) => {
-                                        let _ = async_handle.update(cx, |state, cx| {
-                                            state.clear_task_progress(plan_target.id);
-                                            cx.notify();
-                                        });
-                                        match result {
-                                            Ok(result) => {
-                                                let _ = async_handle.update(cx, |state, cx| {
-                                                    state.apply_planned_jobs(plan_target.id, result);
-                                                    let pending: usize = state
-                                                        .jobs
-                                                        .iter()
-                                                        .filter(|job| job.target_id == plan_target.id)
-                                                        .map(|job| job.pending_actions())
-                                                        .sum();
-                                                    state.log_event(
-                                                        LogLevel::Info,
-                                                        format!(
-                                                            "Sync plan ready for {target_name} ({pending} actions)"
-                                                        ),
-                                                    );
-                                                    cx.notify();
-                                                });
-                                            }
-                                            Err(err) => {
-                                                let _ = async_handle.update(cx, |state, cx| {
-                                                    state.log_event(
-                                                        LogLevel::Error,
-                                                        format!(
-                                                            "Failed to refresh sync plan for {target_name}: {err}"
-                                                        ),
-                                                    );
-                                                    cx.notify();
-                                                });
-                                            }
-                                        }
-                                        break;
-                                    }
-                                }
-                            }
-                            Ok::<_, Error>(())
-                        }
-                    })
-                    .detach();
+    let cancel_handle = state_handle.clone();
+    let cancel_button = Button::new("cancel_target_creation")
+        .ghost()
+        .label(tr(language, "Cancel", "取消", "取消"))
+        .on_click(move |_, _, cx| {
+            cancel_handle.update(cx, |state, cx| {
+                state.target_form = None;
+                state.active_view = ActiveView::Dashboard;
+                cx.notify();
+            });
+        });
+
+    let submit_handle = state_handle.clone();
+    let form_handle = form.clone();
+    let submit_button = Button::new("target_form_submit")
+        .primary()
+        .disabled(!ready_to_submit)
+        .label(match mode {
+            TargetFormMode::Create => tr(language, "Create Target", "创建目标", "建立目標"),
+            TargetFormMode::Edit(_) => tr(language, "Save Changes", "保存更改", "儲存變更"),
+        })
+        .on_click(move |_, window, cx| match mode {
+            TargetFormMode::Create => create_target(&submit_handle, &form_handle, false, cx),
+            TargetFormMode::Edit(target_id) => {
+                let Some(updated) =
+                    form_handle.update(cx, |form, cx| form.build_target(target_id, cx))
+                else {
+                    return;
+                };
+                let changes = submit_handle
+                    .read(cx)
+                    .remote_targets
+                    .iter()
+                    .find(|target| target.id == target_id)
+                    .map(|existing| existing.scope_changes(&updated))
+                    .unwrap_or_default();
+                if changes.is_empty() {
+                    save_edited_target(&submit_handle, updated, cx);
+                } else {
+                    confirm_scope_changes(&submit_handle, updated, changes, language, window, cx);
                 }
             }
         });
 
+    let name_row = settings_row(
+        tr(language, "Name", "名称", "名稱"),
+        tr(
+            language,
+            "Friendly label shown in the sidebar.",
+            "显示在侧边栏中的名称。",
+            "顯示在側邊欄中的名稱。",
+        ),
+        TextInput::new(&name_input).small(),
+        cx,
+    );
+    // Reads ~/.ssh/config when it applies, so what is shown is what connects.
+    let endpoint = (!host_value.trim().is_empty())
+        .then(|| connection::effective_endpoint(host_value.trim(), use_ssh_config));
+    let default_port = matches!(endpoint, Some((_, PortOrigin::Default)));
+    let host_control = div()
+        .h_flex()
+        .gap_2()
+        .items_center()
+        .child(TextInput::new(&host_input).small())
+        .when_some(endpoint, |row, (address, _)| {
+            row.child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(format!("→ {address}")),
+            )
+        });
+    let host_row = div()
+        .v_flex()
+        .gap_1()
+        .child(settings_row(
+            tr(language, "Host", "主机", "主機"),
+            tr(
+                language,
+                "hostname:port for the remote server.",
+                "远程服务器的主机名和端口。",
+                "遠端伺服器的主機與連接埠。",
+            ),
+            host_control,
+            cx,
+        ))
+        .when(default_port, |row| {
+            row.child(Tag::warning().small().rounded_full().child(tr(
+                language,
+                "No port given, so the default SSH port 22 is used. Add :port if the server listens elsewhere.",
+                "未指定端口，将使用默认 SSH 端口 22。如果服务器监听其他端口，请添加 :端口。",
+                "未指定連接埠，將使用預設 SSH 連接埠 22。如果伺服器監聽其他連接埠，請加上 :連接埠。",
+            )))
+        });
+    let ssh_config_row = settings_row(
+        tr(language, "Use SSH config", "使用 SSH 配置", "使用 SSH 設定"),
+        tr(
+            language,
+            "Look the host up in ~/.ssh/config for HostName, Port, User, IdentityFile and ProxyJump. Values entered here win.",
+            "在 ~/.ssh/config 中查找主机的 HostName、Port、User、IdentityFile 和 ProxyJump，此处填写的值优先。",
+            "在 ~/.ssh/config 中查找主機的 HostName、Port、User、IdentityFile 與 ProxyJump，此處填寫的值優先。",
+        ),
+        ssh_config_switch,
+        cx,
+    );
+    let base_path_row = settings_row(
+        tr(language, "Remote base path", "远程根路径", "遠端根路徑"),
+        tr(
+            language,
+            "Root directory on the remote machine.",
+            "远程主机上的根目录。",
+            "遠端主機上的根目錄。",
+        ),
+        TextInput::new(&base_path_input).small(),
+        cx,
+    );
+
+    if let Some(step) = setup.filter(|_| mode == TargetFormMode::Create) {
+        let server_ready = !name_value.trim().is_empty()
+            && !host_value.trim().is_empty()
+            && !base_path_value.trim().is_empty();
+        return render_setup_wizard(
+            step,
+            SetupPages {
+                server: div()
+                    .v_flex()
+                    .gap_3()
+                    .child(name_row)
+                    .child(host_row)
+                    .child(ssh_config_row)
+                    .child(base_path_row),
+                authentication: div()
+                    .v_flex()
+                    .gap_3()
+                    .child(auth_selector)
+                    .child(auth_fields),
+                rule: div().v_flex().gap_3().child(rules_list),
+            },
+            [server_ready, server_ready && auth_ready, ready_to_submit],
+            form,
+            state_handle,
+            language,
+            cx,
+        );
+    }
+
+    let drop_highlight = cx.theme().muted.opacity(0.3);
+    let drop_form = form.clone();
+    let drop_handle = state_handle.clone();
+
     GroupBox::new()
         .title(match mode {
             TargetFormMode::Create => tr(language, "New Target", "新增目标", "新增目標"),
@@ -1681,37 +3926,80 @@ fn render_target_form_panel(
             div()
                 .v_flex()
                 .gap_3()
+                .drag_over::<ExternalPaths>(move |style, _, _, _| style.bg(drop_highlight))
+                .on_drop(move |paths: &ExternalPaths, window, cx| {
+                    let rejected = drop_form.update(cx, |form, cx| {
+                        form.add_dropped_folders(paths.paths(), window, cx)
+                    });
+                    if rejected > 0 {
+                        drop_handle.update(cx, |state, cx| {
+                            state.log_event(
+                                LogLevel::Warn,
+                                format!(
+                                    "Ignored {rejected} dropped files; drop folders to use them as local paths"
+                                ),
+                            );
+                            cx.notify();
+                        });
+                    }
+                })
+                .child(name_row)
+                .child(host_row)
+                .child(ssh_config_row)
+                .child(base_path_row)
+                .child(settings_row(
+                    tr(language, "Local base folder", "本地根目录", "本機根資料夾"),
+                    tr(
+                        language,
+                        "Relative local rule paths resolve here. Empty uses your home folder.",
+                        "相对的本地规则路径基于此目录解析，留空则使用主目录。",
+                        "相對的本機規則路徑以此資料夾解析，留空則使用主資料夾。",
+                    ),
+                    TextInput::new(&local_base_input).small(),
+                    cx,
+                ))
                 .child(settings_row(
-                    tr(language, "Name", "名称", "名稱"),
+                    tr(language, "Read-only remote", "远程只读", "遠端唯讀"),
                     tr(
                         language,
-                        "Friendly label shown in the sidebar.",
-                        "显示在侧边栏中的名称。",
-                        "顯示在側邊欄中的名稱。",
+                        "Never upload to or delete on this server, whatever the rules or a forced sync plan.",
+                        "无论规则或强制同步如何规划，都不向此服务器上传或删除任何文件。",
+                        "無論規則或強制同步如何規劃，都不向此伺服器上傳或刪除任何檔案。",
                     ),
-                    TextInput::new(&name_input).small(),
+                    read_only_switch,
                     cx,
                 ))
                 .child(settings_row(
-                    tr(language, "Host", "主机", "主機"),
+                    tr(language, "Health check", "健康检查", "健康檢查"),
                     tr(
                         language,
-                        "hostname:port for the remote server.",
-                        "远程服务器的主机名和端口。",
-                        "遠端伺服器的主機與連接埠。",
+                        "Ping the server periodically over a kept-open session and show a green or red dot in the sidebar.",
+                        "通过保持打开的会话定期探测服务器，并在侧边栏显示绿色或红色圆点。",
+                        "透過保持開啟的工作階段定期探測伺服器，並在側邊欄顯示綠色或紅色圓點。",
                     ),
-                    TextInput::new(&host_input).small(),
+                    monitor_health_switch,
                     cx,
                 ))
                 .child(settings_row(
-                    tr(language, "Remote base path", "远程根路径", "遠端根路徑"),
+                    tr(language, "Color", "颜色", "顏色"),
                     tr(
                         language,
-                        "Root directory on the remote machine.",
-                        "远程主机上的根目录。",
-                        "遠端主機上的根目錄。",
+                        "Shown as a dot next to the target in the sidebar.",
+                        "在侧边栏中以圆点显示。",
+                        "在側邊欄中以圓點顯示。",
                     ),
-                    TextInput::new(&base_path_input).small(),
+                    color_selector,
+                    cx,
+                ))
+                .child(settings_row(
+                    tr(language, "Tags", "标签", "標籤"),
+                    tr(
+                        language,
+                        "Comma-separated, e.g. prod, staging. Click a tag to filter the sidebar.",
+                        "逗号分隔，例如 prod, staging。点击标签可筛选侧边栏。",
+                        "以逗號分隔，例如 prod, staging。點擊標籤可篩選側邊欄。",
+                    ),
+                    TextInput::new(&tags_input).small(),
                     cx,
                 ))
                 .child(
@@ -1728,6 +4016,24 @@ fn render_target_form_panel(
                         .child(auth_selector)
                         .child(auth_fields),
                 )
+                .child(
+                    GroupBox::new()
+                        .title(tr(language, "Post-sync hook", "同步后钩子", "同步後鉤子"))
+                        .fill()
+                        .child(hook_fields),
+                )
+                .child(
+                    GroupBox::new()
+                        .title(tr(language, "Remote commands", "远程命令", "遠端命令"))
+                        .fill()
+                        .child(command_fields),
+                )
+                .child(
+                    GroupBox::new()
+                        .title(tr(language, "Advanced", "高级", "進階"))
+                        .fill()
+                        .child(advanced_fields),
+                )
                 .child(
                     div()
                         .h_flex()
@@ -1739,885 +4045,5604 @@ fn render_target_form_panel(
         )
 }
 
-fn current_input_value(input: &Entity<InputState>, cx: &mut Context<AppView>) -> String {
-    input.read(cx).text().to_string()
+/// The target form split into the pages of the first-run setup.
+struct SetupPages {
+    server: Div,
+    authentication: Div,
+    rule: Div,
 }
 
-fn render_connection_status_tag(status: Option<&ConnectionTestState>, language: Language) -> Tag {
-    match status {
-        Some(ConnectionTestState::InProgress) => Tag::warning().small().rounded_full().child(tr(
+/// First-run setup: one page of the target form at a time, with a connection
+/// test before the rule page. Finishing saves the target in place of the
+/// samples; skipping keeps them and never shows the setup again.
+fn render_setup_wizard(
+    step: SetupStep,
+    pages: SetupPages,
+    ready: [bool; 3],
+    form: Entity<TargetFormView>,
+    state_handle: Entity<AppState>,
+    language: Language,
+    cx: &mut Context<AppView>,
+) -> GroupBox {
+    let (heading, page) = match step {
+        SetupStep::Server => (tr(language, "Server", "服务器", "伺服器"), pages.server),
+        SetupStep::Authentication => (
+            tr(language, "Authentication", "认证方式", "認證方式"),
+            pages.authentication,
+        ),
+        SetupStep::Rule => (
+            tr(language, "First sync rule", "第一条同步规则", "第一條同步規則"),
+            pages.rule,
+        ),
+    };
+    let hint = match step {
+        SetupStep::Server => tr(
             language,
-            "Testing...",
-            "测试中...",
-            "測試中...",
-        )),
-        Some(ConnectionTestState::Success(message)) => {
-            Tag::success().small().rounded_full().child(message.clone())
-        }
-        Some(ConnectionTestState::Failure(reason)) => {
-            Tag::danger().small().rounded_full().child(reason.clone())
-        }
-        None => Tag::secondary().small().rounded_full().child(tr(
+            "Which server to sync with. You can change everything later under Target Settings.",
+            "要同步的服务器。之后可以在“目标设置”中修改所有内容。",
+            "要同步的伺服器。之後可以在「目標設定」中修改所有內容。",
+        ),
+        SetupStep::Authentication => tr(
             language,
-            "Not tested",
-            "尚未测试",
-            "尚未測試",
-        )),
-    }
-}
-
+            "How to log in. Test the connection before moving on.",
+            "如何登录。继续之前请先测试连接。",
+            "如何登入。繼續之前請先測試連線。",
+        ),
+        SetupStep::Rule => tr(
+            language,
+            "Pick a local folder and the remote folder it syncs with.",
+            "选择一个本地文件夹及与之同步的远程文件夹。",
+            "選擇一個本機資料夾及與之同步的遠端資料夾。",
+        ),
+    };
+    let step_ready = ready[step.index()];
+
+    let page = if step == SetupStep::Authentication {
+        let test_id = state_handle.read(cx).next_target_id();
+        let status = state_handle.read(cx).connection_tests.get(&test_id).cloned();
+        let testing = matches!(status, Some(ConnectionTestState::InProgress));
+        let test_handle = state_handle.clone();
+        let test_form = form.clone();
+        page.child(
+            div()
+                .h_flex()
+                .gap_2()
+                .items_center()
+                .child(
+                    Button::new("setup_test_connection")
+                        .small()
+                        .label(tr(language, "Test Connection", "测试连接", "測試連線"))
+                        .disabled(!step_ready || testing)
+                        .on_click(move |_, _, cx| {
+                            let Some(target) = test_form.update(cx, |form, cx| {
+                                form.draft(cx).into_connection_target(test_id)
+                            }) else {
+                                return;
+                            };
+                            run_connection_test_with_retries(&test_handle, target, language, 1, cx);
+                        }),
+                )
+                .when_some(status, |row, status| {
+                    row.child(render_connection_status_tag(Some(&status), language))
+                }),
+        )
+    } else {
+        page
+    };
 
-fn render_session_card(
-    session: &SyncSession,
-    targets: &[RemoteTarget],
-    language: Language,
-    cx: &mut Context<AppView>,
-) -> impl IntoElement {
-    let target_name = targets
-        .iter()
-        .find(|target| target.id == session.target_id)
-        .map(|target| target.name.clone())
-        .unwrap_or_else(|| {
-            format!(
-                "{} {}",
-                tr(language, "Target", "目标", "目標"),
-                session.target_id
+    let skip_handle = state_handle.clone();
+    let back_handle = state_handle.clone();
+    let next_handle = state_handle.clone();
+    let buttons = div()
+        .h_flex()
+        .gap_2()
+        .items_center()
+        .child(
+            Button::new("setup_skip")
+                .ghost()
+                .label(tr(language, "Skip setup", "跳过设置", "略過設定"))
+                .on_click(move |_, _, cx| {
+                    skip_handle.update(cx, |state, cx| {
+                        state.setup = None;
+                        state.target_form = None;
+                        state.active_view = ActiveView::Dashboard;
+                        persist_state(state);
+                        cx.notify();
+                    });
+                }),
+        )
+        .child(div().flex_1())
+        .when_some(step.previous(), |buttons, previous| {
+            buttons.child(
+                Button::new("setup_back")
+                    .ghost()
+                    .label(tr(language, "Back", "上一步", "上一步"))
+                    .on_click(move |_, _, cx| {
+                        back_handle.update(cx, |state, cx| {
+                            state.setup = Some(previous);
+                            cx.notify();
+                        });
+                    }),
             )
+        })
+        .child(match step.next() {
+            Some(next) => Button::new("setup_next")
+                .primary()
+                .label(tr(language, "Next", "下一步", "下一步"))
+                .disabled(!step_ready)
+                .on_click(move |_, _, cx| {
+                    next_handle.update(cx, |state, cx| {
+                        state.setup = Some(next);
+                        cx.notify();
+                    });
+                }),
+            None => Button::new("setup_finish")
+                .primary()
+                .label(tr(language, "Finish", "完成", "完成"))
+                .disabled(!step_ready)
+                .on_click(move |_, _, cx| create_target(&next_handle, &form, true, cx)),
         });
 
-    let status_label = status_text(&session.status, language);
-    let badge = status_tag(&session.status).child(status_label.clone());
-
-    let progress_block = if let SyncStatus::Running { progress } = session.status {
-        Some(
+    GroupBox::new()
+        .title(tr(
+            language,
+            "Set up your first target",
+            "设置第一个目标",
+            "設定第一個目標",
+        ))
+        .fill()
+        .child(
             div()
                 .v_flex()
-                .gap_1()
+                .gap_3()
                 .child(
                     div()
-                        .text_sm()
-                        .text_color(cx.theme().muted_foreground)
-                        .child("Progress"),
+                        .v_flex()
+                        .gap_1()
+                        .child(div().font_semibold().child(format!(
+                            "{} {}/{} · {heading}",
+                            tr(language, "Step", "步骤", "步驟"),
+                            step.index() + 1,
+                            SetupStep::ALL.len()
+                        )))
+                        .child(
+                            div()
+                                .text_sm()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(hint),
+                        ),
                 )
-                .child(ProgressBar::new().value(progress.clamp(0.0, 1.0) * 100.0)),
+                .child(page)
+                .child(buttons),
         )
-    } else {
-        None
+}
+
+/// Saves the target built from the form, shows it on the dashboard and plans
+/// it. With `replace_targets`, it takes the place of every existing target,
+/// e.g. the samples shown before the first-run setup finished.
+fn create_target(
+    state_handle: &Entity<AppState>,
+    form: &Entity<TargetFormView>,
+    replace_targets: bool,
+    cx: &mut App,
+) {
+    let next_id = state_handle.read(cx).next_target_id();
+    let Some(new_target) = form.update(cx, |form, cx| form.build_target(next_id, cx)) else {
+        return;
     };
+    let plan_target = new_target.clone();
+    state_handle.update(cx, |state, cx| {
+        if replace_targets {
+            state.remote_targets.clear();
+        }
+        state.remote_targets.push(new_target);
+        state.active_target = state.remote_targets.last().map(|target| target.id);
+        state.target_form = None;
+        state.setup = None;
+        state.active_view = ActiveView::Dashboard;
+        state.set_task_progress(
+            plan_target.id,
+            TaskProgress::new(TaskKind::Planning, 0, plan_target.rules.len().max(1)),
+        );
+        persist_state(state);
+        cx.notify();
+    });
+    let async_handle = state_handle.clone();
+    cx.spawn({
+        let plan_target = plan_target.clone();
+        async move |cx| {
+            let target_name = plan_target.name.clone();
+            let mut receiver = task_queue::submit_plan(plan_target.clone());
+            while let Some(event) = receiver.recv().await {
+                match event {
+                    TaskEvent::Progress {
+                        completed,
+                        total,
+                        discovered,
+                        connecting,
+                        ..
+                    } => {
+                        let _ = async_handle.update(cx, |state, cx| {
+                            state.set_task_progress(
+                                plan_target.id,
+                                TaskProgress::new(TaskKind::Planning, completed, total)
+                                    .with_discovered(discovered)
+                                    .with_connecting(connecting),
+                            );
+                            cx.notify();
+                        });
+                    }
+                    TaskEvent::Finished(result) => {
+                        let _ = async_handle.update(cx, |state, cx| {
+                            state.clear_task_progress(plan_target.id);
+                            cx.notify();
+                        });
+                        match result {
+                            Ok(result) => {
+                                let _ = async_handle.update(cx, |state, cx| {
+                                    state.apply_planned_jobs(plan_target.id, result);
+                                    let pending: usize = state
+                                        .jobs
+                                        .iter()
+                                        .filter(|job| job.target_id == plan_target.id)
+                                        .map(|job| job.pending_actions())
+                                        .sum();
+                                    state.log_target_event(
+                                        plan_target.id,
+                                        LogLevel::Info,
+                                        format!(
+                                            "Sync plan ready for {target_name} ({} actions)",
+                                            format_count(pending)
+                                        ),
+                                    );
+                                    cx.notify();
+                                });
+                            }
+                            Err(err) => {
+                                let _ = async_handle.update(cx, |state, cx| {
+                                    state.log_target_event(
+                                        plan_target.id,
+                                        LogLevel::Error,
+                                        format!(
+                                            "Failed to prepare sync plan for {target_name}: {err}"
+                                        ),
+                                    );
+                                    cx.notify();
+                                });
+                            }
+                        }
+                        break;
+                    }
+                }
+            }
+            Ok::<_, Error>(())
+        }
+    })
+    .detach();
+}
 
+/// A text input with a picker button beside it.
+fn path_input(input: &Entity<InputState>, browse: Button) -> Div {
     div()
-        .v_flex()
+        .h_flex()
         .gap_2()
-        .p_4()
-        .rounded(cx.theme().radius)
-        .bg(cx.theme().list)
-        .child(
-            div()
-                .h_flex()
-                .justify_between()
-                .items_center()
-                .child(div().font_semibold().child(format!(
-                    "{} #{}",
-                    tr(language, "Session", "会话", "會話"),
-                    session.id
-                )))
-                .child(badge),
-        )
-        .child(
-            div()
-                .h_flex()
-                .gap_3()
-                .items_center()
-                .flex_wrap()
-                .child(Tag::info().small().rounded_full().child(format!(
-                    "{} {target_name}",
-                    tr(language, "Target:", "目标：", "目標：")
-                )))
-                .child(Tag::secondary().small().rounded_full().child(format!(
-                    "{} {}",
-                    tr(language, "Pending:", "待处理：", "待處理："),
-                    session.pending_actions
-                )))
-                .child(
-                    div()
-                        .text_sm()
-                        .text_color(cx.theme().muted_foreground)
-                        .child(
-                            session
-                                .last_run
-                                .map(|ts| {
-                                    format!(
-                                        "{} {}",
-                                        tr(language, "Last run", "上次运行", "上次執行"),
-                                        format_timestamp(ts, language)
-                                    )
-                                })
-                                .unwrap_or_else(|| {
-                                    tr(language, "Never executed", "尚未执行", "尚未執行").into()
-                                }),
-                        ),
-                ),
-        )
-        .when_some(progress_block, |this, block| this.child(block))
-        .when(
-            matches!(session.status, SyncStatus::Failed { .. }),
-            |this| {
-                if let SyncStatus::Failed { reason } = &session.status {
-                    this.child(
-                        div()
-                            .text_sm()
-                            .text_color(cx.theme().danger_foreground)
-                            .child(reason.clone()),
-                    )
-                } else {
-                    this
-                }
-            },
-        )
+        .items_center()
+        .child(div().flex_1().child(TextInput::new(input).small()))
+        .child(browse)
 }
 
-fn status_tag(status: &SyncStatus) -> Tag {
-    match status {
-        SyncStatus::Idle => Tag::secondary(),
-        SyncStatus::Planning => Tag::info(),
-        SyncStatus::AwaitingConfirmation => Tag::warning(),
-        SyncStatus::Running { .. } => Tag::primary(),
-        SyncStatus::Failed { .. } => Tag::danger(),
-        SyncStatus::Completed => Tag::success(),
-    }
-    .small()
-    .rounded_full()
+/// Fills `input` from the native folder (or file) dialog, so local paths are
+/// picked rather than typed.
+fn browse_button(
+    id: impl Into<ElementId>,
+    input: Entity<InputState>,
+    directories: bool,
+    language: Language,
+) -> Button {
+    Button::new(id)
+        .ghost()
+        .small()
+        .label(tr(language, "Browse…", "浏览…", "瀏覽…"))
+        .on_click(move |_, window, cx| {
+            let receiver = cx.prompt_for_paths(PathPromptOptions {
+                files: !directories,
+                directories,
+                multiple: false,
+                prompt: None,
+            });
+            let input = input.clone();
+            window
+                .spawn(cx, async move |cx| {
+                    let Ok(Ok(Some(paths))) = receiver.await else {
+                        return;
+                    };
+                    let Some(path) = paths.into_iter().next() else {
+                        return;
+                    };
+                    let _ = cx.update(|window, cx| {
+                        input.update(cx, |state, cx| {
+                            state.set_value(path.to_string_lossy().to_string(), window, cx);
+                        });
+                    });
+                })
+                .detach();
+        })
 }
 
-fn log_icon(level: LogLevel) -> IconName {
-    match level {
-        LogLevel::Info => IconName::Info,
-        LogLevel::Warn => IconName::TriangleAlert,
-        LogLevel::Error => IconName::CircleX,
+/// Remote folder picker state. The SFTP session stays open while the picker
+/// is shown and closes once the modal drops its handle.
+struct RemoteBrowser {
+    store: Option<Arc<SftpRemoteStore>>,
+    /// Resolved base path; folders under it fill a relative rule path.
+    root: PathBuf,
+    dir: PathBuf,
+    subdirs: Vec<String>,
+    error: Option<String>,
+    loading: bool,
+}
+
+impl RemoteBrowser {
+    fn show(&mut self, listing: Result<Vec<String>>) {
+        match listing {
+            Ok(subdirs) => {
+                self.subdirs = subdirs;
+                self.error = None;
+            }
+            Err(err) => {
+                self.subdirs.clear();
+                self.error = Some(format!("{err:#}"));
+            }
+        }
     }
 }
 
-fn render_settings_panel(
-    state: &Entity<AppState>,
-    settings: &AppSettings,
+/// Picks a rule's remote folder over SFTP, connecting with the form's current
+/// host and credentials, or the saved target's when those are incomplete.
+fn remote_browse_button(
+    index: usize,
+    form: Entity<TargetFormView>,
+    preset: Option<RemoteTarget>,
+    input: Entity<InputState>,
+    ready: bool,
     language: Language,
-    cx: &mut Context<AppView>,
-) -> Div {
-    let auto_handle = state.clone();
-    let auto_connect = Switch::new("auto_connect")
-        .checked(settings.auto_connect)
-        .on_click(move |next, _, cx| {
-            auto_handle.update(cx, |state, cx| {
-                state.settings.auto_connect = *next;
-                save_state(&state.settings, &state.remote_targets);
-                cx.notify();
-            });
-        });
+) -> Button {
+    Button::new(("browse_remote", index))
+        .ghost()
+        .small()
+        .label(tr(language, "Browse…", "浏览…", "瀏覽…"))
+        .disabled(!ready)
+        .on_click(move |_, window, cx| {
+            let target = form
+                .update(cx, |form, cx| form.connection_target(cx))
+                .or_else(|| preset.clone());
+            if let Some(target) = target {
+                open_remote_browser(target, input.clone(), language, window, cx);
+            }
+        })
+}
 
-    let watch_handle = state.clone();
-    let watch_changes = Switch::new("watch_changes")
-        .checked(settings.watch_local_changes)
-        .on_click(move |next, _, cx| {
-            watch_handle.update(cx, |state, cx| {
-                state.settings.watch_local_changes = *next;
-                save_state(&state.settings, &state.remote_targets);
-                cx.notify();
-            });
-        });
+fn open_remote_browser(
+    target: RemoteTarget,
+    input: Entity<InputState>,
+    language: Language,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let browser = cx.new(|_| RemoteBrowser {
+        store: None,
+        root: PathBuf::new(),
+        dir: PathBuf::new(),
+        subdirs: Vec::new(),
+        error: None,
+        loading: true,
+    });
 
-    let confirm_handle = state.clone();
-    let confirm_switch = Switch::new("confirm_destructive")
-        .checked(settings.confirm_destructive)
-        .on_click(move |next, _, cx| {
-            confirm_handle.update(cx, |state, cx| {
-                state.settings.confirm_destructive = *next;
-                save_state(&state.settings, &state.remote_targets);
-                cx.notify();
-            });
+    let handle = browser.clone();
+    let task = cx.background_spawn(async move {
+        let store = SftpRemoteStore::connect(&target)?;
+        let root = store.browse_root(&target);
+        let listing = store.list_dirs(&root);
+        Ok::<_, Error>((store, root, listing))
+    });
+    cx.spawn(async move |cx| {
+        let result = task.await;
+        let _ = handle.update(cx, |browser, cx| {
+            browser.loading = false;
+            match result {
+                Ok((store, root, listing)) => {
+                    browser.store = Some(Arc::new(store));
+                    browser.dir = root.clone();
+                    browser.root = root;
+                    browser.show(listing);
+                }
+                Err(err) => browser.error = Some(format!("{err:#}")),
+            }
+            cx.notify();
         });
+        Ok::<_, Error>(())
+    })
+    .detach();
 
-    let limit_handle = state.clone();
-    let limit_switch = Switch::new("limit_bandwidth")
-        .checked(settings.limit_bandwidth)
-        .on_click(move |next, _, cx| {
-            limit_handle.update(cx, |state, cx| {
-                state.settings.limit_bandwidth = *next;
-                save_state(&state.settings, &state.remote_targets);
-                cx.notify();
-            });
-        });
+    window.open_modal(cx, move |modal, _, cx| {
+        let state = browser.read(cx);
+        let connected = state.store.is_some();
+        let dir = state.dir.clone();
+        let parent = dir.parent().map(Path::to_path_buf);
 
-    let decrease_handle = state.clone();
-    let increase_handle = state.clone();
-    let bandwidth_controls = div()
+        let mut body = div().v_flex().gap_2().p_4().child(
+            div()
+                .h_flex()
+                .gap_2()
+                .items_center()
+                .child(
+                    Button::new("remote_dir_up")
+                        .ghost()
+                        .small()
+                        .label(tr(language, "Up", "上一级", "上一層"))
+                        .disabled(!connected || state.loading || parent.is_none())
+                        .on_click({
+                            let browser = browser.clone();
+                            move |_, _, cx| {
+                                if let Some(parent) = parent.clone() {
+                                    open_remote_dir(&browser, parent, cx);
+                                }
+                            }
+                        }),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .text_sm()
+                        .font_medium()
+                        .child(dir.display().to_string()),
+                ),
+        );
+
+        if state.loading {
+            body = body.child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(tr(language, "Loading…", "加载中…", "載入中…")),
+            );
+        } else if let Some(error) = &state.error {
+            body = body.child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().danger)
+                    .child(error.clone()),
+            );
+        } else if state.subdirs.is_empty() {
+            body = body.child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(tr(
+                        language,
+                        "No subfolders",
+                        "没有子文件夹",
+                        "沒有子資料夾",
+                    )),
+            );
+        }
+
+        let mut folders = div().v_flex().gap_1();
+        for (index, name) in state.subdirs.iter().enumerate() {
+            let child = dir.join(name);
+            folders = folders.child(
+                Button::new(("remote_dir", index))
+                    .ghost()
+                    .small()
+                    .icon(Icon::new(IconName::Folder).small())
+                    .label(name.clone())
+                    .disabled(state.loading)
+                    .on_click({
+                        let browser = browser.clone();
+                        move |_, _, cx| open_remote_dir(&browser, child.clone(), cx)
+                    }),
+            );
+        }
+        body = body.child(div().h(px(280.)).scrollable(Axis::Vertical).child(folders));
+
+        modal
+            .confirm()
+            .title(tr(
+                language,
+                "Choose Remote Folder",
+                "选择远程文件夹",
+                "選擇遠端資料夾",
+            ))
+            .child(body)
+            .button_props(
+                ModalButtonProps::default()
+                    .ok_text(tr(
+                        language,
+                        "Use This Folder",
+                        "使用此文件夹",
+                        "使用此資料夾",
+                    ))
+                    .cancel_text(tr(language, "Cancel", "取消", "取消")),
+            )
+            .on_ok({
+                let browser = browser.clone();
+                let input = input.clone();
+                move |_, window, cx| {
+                    let state = browser.read(cx);
+                    if state.store.is_none() {
+                        return true;
+                    }
+                    let value = sync::rule_remote_for(&state.dir, &state.root)
+                        .to_string_lossy()
+                        .to_string();
+                    input.update(cx, |input, cx| input.set_value(value, window, cx));
+                    true
+                }
+            })
+            .on_cancel(|_, _, _| true)
+    });
+}
+
+/// Lists `dir` in the background and moves the picker there. A folder that
+/// cannot be read shows its error in place, so the user can go back up.
+fn open_remote_dir(browser: &Entity<RemoteBrowser>, dir: PathBuf, cx: &mut App) {
+    let Some(store) = browser.read(cx).store.clone() else {
+        return;
+    };
+    browser.update(cx, |browser, cx| {
+        browser.loading = true;
+        cx.notify();
+    });
+
+    let handle = browser.clone();
+    let task = {
+        let dir = dir.clone();
+        cx.background_spawn(async move { store.list_dirs(&dir) })
+    };
+    cx.spawn(async move |cx| {
+        let listing = task.await;
+        let _ = handle.update(cx, |browser, cx| {
+            browser.loading = false;
+            browser.dir = dir;
+            browser.show(listing);
+            cx.notify();
+        });
+        Ok::<_, Error>(())
+    })
+    .detach();
+}
+
+fn current_input_value(input: &Entity<InputState>, cx: &mut Context<AppView>) -> String {
+    input.read(cx).text().to_string()
+}
+
+/// Non-zero counts of a plan, one line each, for the sidebar badge tooltip.
+fn plan_stats_lines(stats: &PlanStats, language: Language) -> Vec<String> {
+    [
+        (stats.uploads, tr(language, "Uploads", "上传", "上傳")),
+        (stats.downloads, tr(language, "Downloads", "下载", "下載")),
+        (
+            stats.deletes_remote,
+            tr(language, "Remote deletions", "远程删除", "遠端刪除"),
+        ),
+        (
+            stats.deletes_local,
+            tr(language, "Local deletions", "本地删除", "本地刪除"),
+        ),
+        (stats.conflicts, tr(language, "Conflicts", "冲突", "衝突")),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, label)| format!("{label}: {}", format_count(count)))
+    .collect()
+}
+
+struct PlanStatsTooltip {
+    lines: Vec<String>,
+}
+
+impl Render for PlanStatsTooltip {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .v_flex()
+            .gap_1()
+            .p_2()
+            .rounded(cx.theme().radius)
+            .border_1()
+            .border_color(cx.theme().border)
+            .bg(cx.theme().background)
+            .shadow_md()
+            .text_xs()
+            .children(self.lines.clone())
+    }
+}
+
+/// Green when the target answered its latest health check, red otherwise;
+/// the tooltip tells when it was checked and why it failed.
+fn render_health_dot(
+    target_id: TargetId,
+    health: TargetHealth,
+    language: Language,
+    cx: &App,
+) -> Stateful<Div> {
+    let color = if health.error.is_some() {
+        cx.theme().danger
+    } else {
+        cx.theme().success
+    };
+    let mut lines = vec![format!(
+        "{} {}",
+        if health.error.is_some() {
+            tr(language, "Unreachable", "无法连接", "無法連線")
+        } else {
+            tr(language, "Reachable", "可连接", "可連線")
+        },
+        format_timestamp(health.checked_at, language)
+    )];
+    lines.extend(health.error);
+    div()
+        .id(("health", target_id))
+        .size_2()
+        .rounded_full()
+        .bg(color)
+        .tooltip(move |_, cx| {
+            let lines = lines.clone();
+            cx.new(|_| PlanStatsTooltip { lines }).into()
+        })
+}
+
+fn render_clock_skew_tag(skew: i64, corrected: bool, language: Language) -> Tag {
+    let secs = skew.abs();
+    let label = match (skew > 0, language) {
+        (true, Language::English) => format!("Server clock {secs}s ahead"),
+        (false, Language::English) => format!("Server clock {secs}s behind"),
+        (true, Language::SimplifiedChinese) => format!("服务器时钟快 {secs} 秒"),
+        (false, Language::SimplifiedChinese) => format!("服务器时钟慢 {secs} 秒"),
+        (true, Language::TraditionalChinese) => format!("伺服器時鐘快 {secs} 秒"),
+        (false, Language::TraditionalChinese) => format!("伺服器時鐘慢 {secs} 秒"),
+    };
+    if corrected {
+        let note = tr(language, "corrected", "已校正", "已校正");
+        Tag::info()
+            .small()
+            .rounded_full()
+            .child(format!("{label} · {note}"))
+    } else {
+        Tag::warning().small().rounded_full().child(label)
+    }
+}
+
+/// Drops the target's pooled session and logs in again, showing how that
+/// went in its connection status. Monitored targets get their health
+/// updated too.
+fn reconnect(
+    state_handle: &Entity<AppState>,
+    target: RemoteTarget,
+    language: Language,
+    cx: &mut App,
+) {
+    pool::forget(target.id);
+    state_handle.update(cx, |state, cx| {
+        state
+            .connection_tests
+            .insert(target.id, ConnectionTestState::InProgress);
+        state.log_target_event(
+            target.id,
+            LogLevel::Info,
+            format!("Reconnecting to {}", target.name),
+        );
+        cx.notify();
+    });
+    let handle = state_handle.clone();
+    cx.spawn(async move |cx| {
+        let probe = target.clone();
+        let result = cx
+            .background_spawn(async move { health::check(&probe) })
+            .await;
+        let _ = handle.update(cx, |state, cx| {
+            let error = result.err().map(|err| format!("{err:#}"));
+            if target.monitor_health {
+                state.record_health(target.id, error.clone());
+            }
+            let status = match error {
+                None => {
+                    state.log_target_event(
+                        target.id,
+                        LogLevel::Info,
+                        format!("Reconnected to {}", target.name),
+                    );
+                    ConnectionTestState::Success(
+                        tr(language, "Reconnected", "已重新连接", "已重新連線").into(),
+                    )
+                }
+                Some(err) => {
+                    state.log_target_event(
+                        target.id,
+                        LogLevel::Error,
+                        format!("Reconnecting to {} failed: {err}", target.name),
+                    );
+                    ConnectionTestState::Failure(state.redact(&err))
+                }
+            };
+            state.connection_tests.insert(target.id, status);
+            cx.notify();
+        });
+        Ok::<_, Error>(())
+    })
+    .detach();
+}
+
+fn render_connection_status_tag(status: Option<&ConnectionTestState>, language: Language) -> Tag {
+    match status {
+        Some(ConnectionTestState::InProgress) => Tag::warning().small().rounded_full().child(tr(
+            language,
+            "Testing...",
+            "测试中...",
+            "測試中...",
+        )),
+        Some(ConnectionTestState::Success(message)) => {
+            Tag::success().small().rounded_full().child(message.clone())
+        }
+        Some(ConnectionTestState::Failure(reason)) => {
+            Tag::danger().small().rounded_full().child(reason.clone())
+        }
+        None => Tag::secondary().small().rounded_full().child(tr(
+            language,
+            "Not tested",
+            "尚未测试",
+            "尚未測試",
+        )),
+    }
+}
+
+/// Saves settings and targets. A secret the keychain refused only lives in
+/// memory, so its auth is marked unstored and a warning is logged once.
+fn persist_state(state: &mut AppState) {
+    state.remember_secrets();
+    for unsaved in save_state(&state.settings, &state.remote_targets) {
+        let Some(target) = state
+            .remote_targets
+            .iter_mut()
+            .find(|target| target.id == unsaved.target_id)
+        else {
+            continue;
+        };
+        for auth in std::iter::once(&mut target.auth).chain(target.fallback_auth.as_mut()) {
+            match (auth, unsaved.slot) {
+                (AuthMethod::Password { stored, .. }, SecretSlot::Password(_)) => *stored = false,
+                (
+                    AuthMethod::SshKey {
+                        passphrase_stored, ..
+                    },
+                    SecretSlot::KeyPassphrase(_),
+                ) => *passphrase_stored = false,
+                _ => {}
+            }
+        }
+        if target.credentials_pending {
+            continue;
+        }
+        target.credentials_pending = true;
+        let message = format!(
+            "Couldn't save credential for {} to the keychain; it will be asked for again after a restart: {}",
+            target.name, unsaved.error
+        );
+        state.log_target_event(unsaved.target_id, LogLevel::Warn, message);
+    }
+}
+
+/// Runs `proceed` with the target, first asking for its password when it is
+/// kept for this session only and has not been entered yet.
+fn with_session_secret(
+    state_handle: &Entity<AppState>,
+    target: RemoteTarget,
+    language: Language,
+    window: &mut Window,
+    cx: &mut App,
+    proceed: impl Fn(RemoteTarget, &mut Window, &mut App) + 'static,
+) {
+    if !target.needs_session_secret() {
+        proceed(target, window, cx);
+        return;
+    }
+    let input = cx.new(|cx| {
+        let mut input = InputState::new(window, cx);
+        input.set_masked(true, window, cx);
+        input
+    });
+    let handle = state_handle.clone();
+    let proceed = Rc::new(proceed);
+    window.open_modal(cx, move |modal, _, _| {
+        let handle = handle.clone();
+        let input = input.clone();
+        let proceed = proceed.clone();
+        let target_id = target.id;
+        modal
+            .confirm()
+            .title(tr(language, "Enter Password", "输入密码", "輸入密碼"))
+            .child(
+                div()
+                    .v_flex()
+                    .gap_2()
+                    .p_4()
+                    .child(format!(
+                        "{} {}@{}",
+                        tr(
+                            language,
+                            "This password is not saved. Enter it to connect to",
+                            "此密码不会保存。请输入密码以连接到",
+                            "此密碼不會儲存。請輸入密碼以連線到",
+                        ),
+                        target.username,
+                        target.host
+                    ))
+                    .child(TextInput::new(&input).mask_toggle().small()),
+            )
+            .button_props(
+                ModalButtonProps::default()
+                    .ok_text(tr(language, "Connect", "连接", "連線"))
+                    .cancel_text(tr(language, "Cancel", "取消", "取消")),
+            )
+            .on_ok(move |_, window, cx| {
+                let entered = input.read(cx).text().to_string();
+                if entered.is_empty() {
+                    return false;
+                }
+                let unlocked = handle.update(cx, |state, cx| {
+                    let target = state
+                        .remote_targets
+                        .iter_mut()
+                        .find(|target| target.id == target_id)?;
+                    if let AuthMethod::Password { secret, .. } = &mut target.auth {
+                        *secret = entered;
+                    }
+                    cx.notify();
+                    Some(target.clone())
+                });
+                if let Some(target) = unlocked {
+                    let proceed = proceed.clone();
+                    window.defer(cx, move |window, cx| proceed(target, window, cx));
+                }
+                true
+            })
+            .on_cancel(|_, _, _| true)
+    });
+}
+
+/// Deletes a target's keychain secret and marks its auth as needing credentials.
+fn clear_stored_credential(state_handle: &Entity<AppState>, slot: SecretSlot, cx: &mut App) {
+    let target_id = match slot {
+        SecretSlot::Password(id) | SecretSlot::KeyPassphrase(id) => id,
+    };
+    let result = secrets::delete(slot);
+    state_handle.update(cx, |state, cx| {
+        if let Err(err) = result {
+            state.log_target_event(
+                target_id,
+                LogLevel::Error,
+                format!("Failed to clear stored credential: {err:#}"),
+            );
+            cx.notify();
+            return;
+        }
+        let Some(target) = state
+            .remote_targets
+            .iter_mut()
+            .find(|target| target.id == target_id)
+        else {
+            return;
+        };
+        for auth in std::iter::once(&mut target.auth).chain(target.fallback_auth.as_mut()) {
+            match (auth, slot) {
+                (AuthMethod::Password { secret, stored }, SecretSlot::Password(_)) => {
+                    secret.clear();
+                    *stored = false;
+                }
+                (
+                    AuthMethod::SshKey {
+                        passphrase,
+                        passphrase_stored,
+                        ..
+                    },
+                    SecretSlot::KeyPassphrase(_),
+                ) => {
+                    *passphrase = None;
+                    *passphrase_stored = false;
+                }
+                _ => {}
+            }
+        }
+        target.credentials_pending = true;
+        let message = format!("Cleared stored credential for {}", target.name);
+        state.connection_tests.remove(&target_id);
+        state.forget_rule_tests(target_id);
+        persist_state(state);
+        state.log_target_event(target_id, LogLevel::Info, message);
+        cx.notify();
+    });
+}
+
+/// Copies a redacted diagnostics bundle (optionally with an error) to the clipboard.
+fn copy_diagnostics(
+    state_handle: &Entity<AppState>,
+    target_id: Option<TargetId>,
+    error: Option<&str>,
+    cx: &mut App,
+) {
+    let bundle = {
+        let state = state_handle.read(cx);
+        let target = target_id.and_then(|id| state.remote_targets.iter().find(|t| t.id == id));
+        diagnostics::build_bundle(state, target, error)
+    };
+    cx.write_to_clipboard(ClipboardItem::new_string(bundle));
+    state_handle.update(cx, |state, cx| {
+        state.log_event(LogLevel::Info, "Copied diagnostics to clipboard");
+        cx.notify();
+    });
+}
+
+/// Copies an `sftp://` link or `scp` command for a rule to the clipboard.
+/// "Copy link" and "Copy scp" buttons for one file of a rule.
+fn render_copy_file_links(
+    state_handle: &Entity<AppState>,
+    target: &RemoteTarget,
+    rule: &SyncRule,
+    rel_path: &Path,
+    id: ElementId,
+    language: Language,
+) -> Div {
+    let link_handle = state_handle.clone();
+    let scp_handle = state_handle.clone();
+    let sftp_link = sync::sftp_url(target, rule, Some(rel_path));
+    let scp_command = sync::scp_command(target, rule, Some(rel_path));
+    div()
         .h_flex()
-        .gap_2()
-        .items_center()
+        .gap_1()
         .child(
-            Button::new("bw_decrease")
+            Button::new(ElementId::NamedChild(Box::new(id.clone()), "copy_link".into()))
                 .ghost()
-                .icon(Icon::new(IconName::Minus).small())
-                .disabled(!settings.limit_bandwidth || settings.bandwidth_mbps <= 10)
-                .on_click(move |_, _, cx| {
-                    decrease_handle.update(cx, |state, cx| {
-                        if state.settings.bandwidth_mbps > 10 {
-                            state.settings.bandwidth_mbps -= 10;
-                            save_state(&state.settings, &state.remote_targets);
-                            cx.notify();
-                        }
-                    });
-                }),
-        )
-        .child(
-            Tag::info()
-                .small()
-                .rounded_full()
-                .child(format!("{} Mbps", settings.bandwidth_mbps)),
+                .xsmall()
+                .label(tr(language, "Copy link", "复制链接", "複製連結"))
+                .on_click(move |_, _, cx| copy_share_link(&link_handle, &sftp_link, cx)),
         )
         .child(
-            Button::new("bw_increase")
+            Button::new(ElementId::NamedChild(Box::new(id), "copy_scp".into()))
                 .ghost()
-                .icon(Icon::new(IconName::Plus).small())
-                .disabled(!settings.limit_bandwidth)
-                .on_click(move |_, _, cx| {
-                    increase_handle.update(cx, |state, cx| {
-                        state.settings.bandwidth_mbps += 10;
-                        save_state(&state.settings, &state.remote_targets);
-                        cx.notify();
-                    });
-                }),
-        );
+                .xsmall()
+                .label(tr(language, "Copy scp", "复制 scp", "複製 scp"))
+                .on_click(move |_, _, cx| copy_share_link(&scp_handle, &scp_command, cx)),
+        )
+}
 
-    let language_handle = state.clone();
-    let language_selector =
-        LANGUAGE_CHOICES
+fn copy_share_link(state_handle: &Entity<AppState>, text: &str, cx: &mut App) {
+    cx.write_to_clipboard(ClipboardItem::new_string(text.to_string()));
+    state_handle.update(cx, |state, cx| {
+        state.log_event(LogLevel::Info, format!("Copied {text} to clipboard"));
+        cx.notify();
+    });
+}
+
+/// Copies the target's current plan as an advisory shell script.
+fn copy_plan_script(state_handle: &Entity<AppState>, target: &RemoteTarget, cx: &mut App) {
+    let script = sync::plan_script(
+        target,
+        state_handle
+            .read(cx)
+            .jobs
             .iter()
-            .fold(div().h_flex().gap_2(), |builder, (choice, label)| {
-                let mut button = Button::new(language_button_id(*choice)).label(*label);
-                if *choice == settings.language {
-                    button = button.primary();
-                } else {
-                    button = button.ghost();
+            .filter(|job| job.target_id == target.id),
+    );
+    cx.write_to_clipboard(ClipboardItem::new_string(script));
+    state_handle.update(cx, |state, cx| {
+        state.log_target_event(
+            target.id,
+            LogLevel::Info,
+            format!(
+                "Copied sync plan for {} to clipboard as a shell script",
+                target.name
+            ),
+        );
+        cx.notify();
+    });
+}
+
+/// Totals across every target over a grid of per-target cards; a card opens
+/// its target.
+fn render_targets_overview(
+    state_handle: &Entity<AppState>,
+    overviews: &[TargetOverview],
+    targets: &[RemoteTarget],
+    language: Language,
+    cx: &mut Context<AppView>,
+) -> GroupBox {
+    let pending: usize = overviews.iter().map(|overview| overview.pending).sum();
+    let conflicted = overviews
+        .iter()
+        .filter(|overview| overview.conflicts > 0)
+        .count();
+    let checked = overviews
+        .iter()
+        .filter(|overview| overview.reachable.is_some())
+        .count();
+    let reachable = overviews
+        .iter()
+        .filter(|overview| overview.reachable == Some(true))
+        .count();
+    let total = |label: &'static str, value: String| {
+        div()
+            .v_flex()
+            .gap_1()
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(label),
+            )
+            .child(div().text_lg().font_semibold().child(value))
+    };
+    let totals = div()
+        .h_flex()
+        .gap_6()
+        .flex_wrap()
+        .child(total(
+            tr(language, "Pending actions", "待处理操作", "待處理操作"),
+            format_count(pending),
+        ))
+        .child(total(
+            tr(language, "Targets with conflicts", "有冲突的目标", "有衝突的目標"),
+            format_count(conflicted),
+        ))
+        .child(total(
+            tr(language, "Reachable", "可连接", "可連線"),
+            format!("{reachable}/{checked}"),
+        ));
+
+    let cards = overviews.iter().fold(div().grid().grid_cols(3).gap_3(), |grid, overview| {
+        let Some(target) = targets
+            .iter()
+            .find(|target| target.id == overview.target_id)
+            .cloned()
+        else {
+            return grid;
+        };
+        let reachability = match overview.reachable {
+            Some(true) => Tag::success().child(tr(language, "Reachable", "可连接", "可連線")),
+            Some(false) => Tag::danger().child(tr(language, "Unreachable", "无法连接", "無法連線")),
+            None => Tag::secondary().child(tr(language, "Not checked", "未检查", "未檢查")),
+        };
+        let last_sync = overview
+            .last_sync
+            .map(|ts| {
+                format!(
+                    "{} {}",
+                    tr(language, "Last sync", "上次同步", "上次同步"),
+                    format_timestamp(ts, language)
+                )
+            })
+            .unwrap_or_else(|| tr(language, "Never synced", "尚未同步", "尚未同步").into());
+        let handle = state_handle.clone();
+        grid.child(
+            div()
+                .id(("target_overview", overview.target_id))
+                .v_flex()
+                .gap_2()
+                .p_4()
+                .rounded(cx.theme().radius)
+                .bg(cx.theme().list)
+                .cursor_pointer()
+                .hover(|this| this.bg(cx.theme().list_hover))
+                .on_click(move |_, _, cx| select_target(&handle, target.clone(), cx))
+                .child(
+                    div()
+                        .h_flex()
+                        .justify_between()
+                        .items_center()
+                        .gap_2()
+                        .child(div().font_semibold().child(overview.name.clone()))
+                        .child(reachability.small().rounded_full()),
+                )
+                .child(
+                    div()
+                        .h_flex()
+                        .gap_2()
+                        .flex_wrap()
+                        .when(!overview.enabled, |this| {
+                            this.child(
+                                Tag::secondary()
+                                    .small()
+                                    .rounded_full()
+                                    .child(tr(language, "disabled", "已停用", "已停用")),
+                            )
+                        })
+                        .child(Tag::secondary().small().rounded_full().child(format!(
+                            "{} {}",
+                            tr(language, "Pending:", "待处理：", "待處理："),
+                            format_count(overview.pending)
+                        )))
+                        .when(overview.conflicts > 0, |this| {
+                            this.child(Tag::warning().small().rounded_full().child(format!(
+                                "{} {}",
+                                tr(language, "Conflicts:", "冲突：", "衝突："),
+                                format_count(overview.conflicts)
+                            )))
+                        }),
+                )
+                .child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(last_sync),
+                ),
+        )
+    });
+
+    GroupBox::new()
+        .title(tr(language, "All Targets", "全部目标", "全部目標"))
+        .fill()
+        .child(div().v_flex().gap_4().child(totals).child(cards))
+}
+
+fn render_session_card(
+    session: &SyncSession,
+    targets: &[RemoteTarget],
+    language: Language,
+    cx: &mut Context<AppView>,
+) -> impl IntoElement {
+    let target_name = targets
+        .iter()
+        .find(|target| target.id == session.target_id)
+        .map(|target| target.name.clone())
+        .unwrap_or_else(|| {
+            format!(
+                "{} {}",
+                tr(language, "Target", "目标", "目標"),
+                session.target_id
+            )
+        });
+
+    let status_label = status_text(&session.status, language);
+    let badge = status_tag(&session.status).child(status_label.clone());
+
+    let progress_block = if let SyncStatus::Running { progress } = session.status {
+        Some(
+            div()
+                .v_flex()
+                .gap_1()
+                .child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child("Progress"),
+                )
+                .child(ProgressBar::new().value(progress.clamp(0.0, 1.0) * 100.0)),
+        )
+    } else {
+        None
+    };
+
+    div()
+        .v_flex()
+        .gap_2()
+        .p_4()
+        .rounded(cx.theme().radius)
+        .bg(cx.theme().list)
+        .child(
+            div()
+                .h_flex()
+                .justify_between()
+                .items_center()
+                .child(div().font_semibold().child(format!(
+                    "{} #{}",
+                    tr(language, "Session", "会话", "會話"),
+                    session.id
+                )))
+                .child(badge),
+        )
+        .child(
+            div()
+                .h_flex()
+                .gap_3()
+                .items_center()
+                .flex_wrap()
+                .child(Tag::info().small().rounded_full().child(format!(
+                    "{} {target_name}",
+                    tr(language, "Target:", "目标：", "目標：")
+                )))
+                .child(Tag::secondary().small().rounded_full().child(format!(
+                    "{} {}",
+                    tr(language, "Pending:", "待处理：", "待處理："),
+                    format_count(session.pending_actions)
+                )))
+                .child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(
+                            session
+                                .last_run
+                                .map(|ts| {
+                                    format!(
+                                        "{} {}",
+                                        tr(language, "Last run", "上次运行", "上次執行"),
+                                        format_timestamp(ts, language)
+                                    )
+                                })
+                                .unwrap_or_else(|| {
+                                    tr(language, "Never executed", "尚未执行", "尚未執行").into()
+                                }),
+                        ),
+                ),
+        )
+        .when_some(progress_block, |this, block| this.child(block))
+        .when(
+            matches!(session.status, SyncStatus::Failed { .. }),
+            |this| {
+                if let SyncStatus::Failed { reason } = &session.status {
+                    this.child(
+                        div()
+                            .text_sm()
+                            .text_color(cx.theme().danger_foreground)
+                            .child(reason.clone()),
+                    )
+                } else {
+                    this
+                }
+            },
+        )
+}
+
+fn render_history_entry(record: &SessionRecord, language: Language, cx: &App) -> Div {
+    let badge = if record.succeeded() {
+        Tag::success()
+            .small()
+            .rounded_full()
+            .child(tr(language, "Completed", "已完成", "已完成"))
+    } else {
+        Tag::danger()
+            .small()
+            .rounded_full()
+            .child(tr(language, "Failed", "失败", "失敗"))
+    };
+    let counts = format!(
+        "{} {} · {} {} · {} {}",
+        format_count(record.applied),
+        tr(language, "applied", "已应用", "已套用"),
+        format_count(record.skipped),
+        tr(language, "conflicts", "冲突", "衝突"),
+        format_count(record.failed),
+        tr(language, "failed", "失败", "失敗"),
+    );
+
+    div()
+        .v_flex()
+        .gap_1()
+        .child(
+            div()
+                .h_flex()
+                .justify_between()
+                .items_center()
+                .gap_3()
+                .child(
+                    div()
+                        .h_flex()
+                        .gap_2()
+                        .items_center()
+                        .child(badge)
+                        .child(div().font_medium().child(record.target_name.clone()))
+                        .child(
+                            div()
+                                .text_sm()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(counts),
+                        ),
+                )
+                .child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(format_timestamp(record.finished_at, language)),
+                ),
+        )
+        .when_some(record.error.clone(), |this, error| {
+            this.child(div().text_sm().text_color(cx.theme().danger).child(error))
+        })
+}
+
+fn status_tag(status: &SyncStatus) -> Tag {
+    match status {
+        SyncStatus::Idle => Tag::secondary(),
+        SyncStatus::Planning => Tag::info(),
+        SyncStatus::AwaitingConfirmation => Tag::warning(),
+        SyncStatus::Running { .. } => Tag::primary(),
+        SyncStatus::Failed { .. } => Tag::danger(),
+        SyncStatus::Completed => Tag::success(),
+    }
+    .small()
+    .rounded_full()
+}
+
+fn log_icon(level: LogLevel) -> IconName {
+    match level {
+        LogLevel::Info => IconName::Info,
+        LogLevel::Warn => IconName::TriangleAlert,
+        LogLevel::Error => IconName::CircleX,
+    }
+}
+
+fn render_settings_panel(
+    state: &Entity<AppState>,
+    settings: &AppSettings,
+    profile_input: &Entity<InputState>,
+    language: Language,
+    cx: &mut Context<AppView>,
+) -> Div {
+    let auto_handle = state.clone();
+    let auto_connect = Switch::new("auto_connect")
+        .checked(settings.auto_connect)
+        .on_click(move |next, _, cx| {
+            auto_handle.update(cx, |state, cx| {
+                state.settings.auto_connect = *next;
+                persist_state(state);
+                cx.notify();
+            });
+        });
+
+    let watch_handle = state.clone();
+    let watch_changes = Switch::new("watch_changes")
+        .checked(settings.watch_local_changes)
+        .on_click(move |next, _, cx| {
+            watch_handle.update(cx, |state, cx| {
+                state.settings.watch_local_changes = *next;
+                persist_state(state);
+                cx.notify();
+            });
+        });
+
+    let backend_label = match watcher::active_backend() {
+        Some(WatchBackend::Native) => tr(
+            language,
+            "Native file events",
+            "系统原生文件事件",
+            "系統原生檔案事件",
+        )
+        .to_string(),
+        Some(WatchBackend::Polling(interval)) => format!(
+            "{} {} s",
+            tr(language, "Polling every", "轮询间隔", "輪詢間隔"),
+            interval.as_secs()
+        ),
+        None => tr(language, "Off", "关闭", "關閉").to_string(),
+    };
+    let poll_decrease_handle = state.clone();
+    let poll_increase_handle = state.clone();
+    let poll_controls = div()
+        .h_flex()
+        .gap_2()
+        .items_center()
+        .child(
+            Button::new("watch_poll_decrease")
+                .ghost()
+                .icon(Icon::new(IconName::Minus).small())
+                .disabled(settings.watch_poll_secs <= 1)
+                .on_click(move |_, _, cx| {
+                    poll_decrease_handle.update(cx, |state, cx| {
+                        state.settings.watch_poll_secs =
+                            state.settings.watch_poll_secs.saturating_sub(1).max(1);
+                        persist_state(state);
+                        cx.notify();
+                    });
+                }),
+        )
+        .child(
+            Tag::info()
+                .small()
+                .rounded_full()
+                .child(format!("{} s", settings.watch_poll_secs)),
+        )
+        .child(
+            Button::new("watch_poll_increase")
+                .ghost()
+                .icon(Icon::new(IconName::Plus).small())
+                .disabled(settings.watch_poll_secs >= 60)
+                .on_click(move |_, _, cx| {
+                    poll_increase_handle.update(cx, |state, cx| {
+                        state.settings.watch_poll_secs += 1;
+                        persist_state(state);
+                        cx.notify();
+                    });
+                }),
+        );
+
+    let confirm_handle = state.clone();
+    let confirm_switch = Switch::new("confirm_destructive")
+        .checked(settings.confirm_destructive)
+        .on_click(move |next, _, cx| {
+            confirm_handle.update(cx, |state, cx| {
+                state.settings.confirm_destructive = *next;
+                persist_state(state);
+                cx.notify();
+            });
+        });
+
+    let review_handle = state.clone();
+    let review_switch = Switch::new("review_deletions")
+        .checked(settings.review_deletions)
+        .on_click(move |next, _, cx| {
+            review_handle.update(cx, |state, cx| {
+                state.settings.review_deletions = *next;
+                persist_state(state);
+                cx.notify();
+            });
+        });
+
+    let backup_handle = state.clone();
+    let backup_switch = Switch::new("backup_conflicts")
+        .checked(settings.backup_conflicts)
+        .on_click(move |next, _, cx| {
+            backup_handle.update(cx, |state, cx| {
+                state.settings.backup_conflicts = *next;
+                persist_state(state);
+                cx.notify();
+            });
+        });
+
+    const MASS_DELETE_STEP: u8 = 10;
+    let mass_decrease_handle = state.clone();
+    let mass_increase_handle = state.clone();
+    let mass_delete_label = if settings.mass_delete_percent == 0 {
+        tr(language, "Off", "关闭", "關閉").to_string()
+    } else {
+        format!("{}%", settings.mass_delete_percent)
+    };
+    let mass_delete_controls = div()
+        .h_flex()
+        .gap_2()
+        .items_center()
+        .child(
+            Button::new("mass_delete_decrease")
+                .ghost()
+                .icon(Icon::new(IconName::Minus).small())
+                .disabled(settings.mass_delete_percent == 0)
+                .on_click(move |_, _, cx| {
+                    mass_decrease_handle.update(cx, |state, cx| {
+                        state.settings.mass_delete_percent = state
+                            .settings
+                            .mass_delete_percent
+                            .saturating_sub(MASS_DELETE_STEP);
+                        persist_state(state);
+                        cx.notify();
+                    });
+                }),
+        )
+        .child(Tag::info().small().rounded_full().child(mass_delete_label))
+        .child(
+            Button::new("mass_delete_increase")
+                .ghost()
+                .icon(Icon::new(IconName::Plus).small())
+                .disabled(settings.mass_delete_percent >= 100)
+                .on_click(move |_, _, cx| {
+                    mass_increase_handle.update(cx, |state, cx| {
+                        state.settings.mass_delete_percent =
+                            (state.settings.mass_delete_percent + MASS_DELETE_STEP).min(100);
+                        persist_state(state);
+                        cx.notify();
+                    });
+                }),
+        );
+
+    let limit_handle = state.clone();
+    let limit_switch = Switch::new("limit_bandwidth")
+        .checked(settings.limit_bandwidth)
+        .on_click(move |next, _, cx| {
+            limit_handle.update(cx, |state, cx| {
+                state.settings.limit_bandwidth = *next;
+                persist_state(state);
+                cx.notify();
+            });
+        });
+
+    let decrease_handle = state.clone();
+    let increase_handle = state.clone();
+    let bandwidth_controls = div()
+        .h_flex()
+        .gap_2()
+        .items_center()
+        .child(
+            Button::new("bw_decrease")
+                .ghost()
+                .icon(Icon::new(IconName::Minus).small())
+                .disabled(!settings.limit_bandwidth || settings.bandwidth_mbps <= 10)
+                .on_click(move |_, _, cx| {
+                    decrease_handle.update(cx, |state, cx| {
+                        if state.settings.bandwidth_mbps > 10 {
+                            state.settings.bandwidth_mbps -= 10;
+                            persist_state(state);
+                            cx.notify();
+                        }
+                    });
+                }),
+        )
+        .child(
+            Tag::info()
+                .small()
+                .rounded_full()
+                .child(format!("{} Mbps", settings.bandwidth_mbps)),
+        )
+        .child(
+            Button::new("bw_increase")
+                .ghost()
+                .icon(Icon::new(IconName::Plus).small())
+                .disabled(!settings.limit_bandwidth)
+                .on_click(move |_, _, cx| {
+                    increase_handle.update(cx, |state, cx| {
+                        state.settings.bandwidth_mbps += 10;
+                        persist_state(state);
+                        cx.notify();
+                    });
+                }),
+        );
+
+    let stability_handle = state.clone();
+    let stability_switch = Switch::new("stability_check")
+        .checked(settings.stability_check)
+        .on_click(move |next, _, cx| {
+            stability_handle.update(cx, |state, cx| {
+                state.settings.stability_check = *next;
+                persist_state(state);
+                cx.notify();
+            });
+        });
+
+    let window_decrease_handle = state.clone();
+    let window_increase_handle = state.clone();
+    let stability_controls = div()
+        .h_flex()
+        .gap_2()
+        .items_center()
+        .child(
+            Button::new("stability_decrease")
+                .ghost()
+                .icon(Icon::new(IconName::Minus).small())
+                .disabled(!settings.stability_check || settings.stability_window_ms <= 250)
+                .on_click(move |_, _, cx| {
+                    window_decrease_handle.update(cx, |state, cx| {
+                        if state.settings.stability_window_ms > 250 {
+                            state.settings.stability_window_ms -= 250;
+                            persist_state(state);
+                            cx.notify();
+                        }
+                    });
+                }),
+        )
+        .child(
+            Tag::info()
+                .small()
+                .rounded_full()
+                .child(format!("{} ms", settings.stability_window_ms)),
+        )
+        .child(
+            Button::new("stability_increase")
+                .ghost()
+                .icon(Icon::new(IconName::Plus).small())
+                .disabled(!settings.stability_check || settings.stability_window_ms >= 10_000)
+                .on_click(move |_, _, cx| {
+                    window_increase_handle.update(cx, |state, cx| {
+                        state.settings.stability_window_ms += 250;
+                        persist_state(state);
+                        cx.notify();
+                    });
+                }),
+        );
+
+    let language_handle = state.clone();
+    let language_selector =
+        LANGUAGE_CHOICES
+            .iter()
+            .fold(div().h_flex().gap_2(), |builder, (choice, label)| {
+                let mut button = Button::new(language_button_id(*choice)).label(*label);
+                if *choice == settings.language {
+                    button = button.primary();
+                } else {
+                    button = button.ghost();
+                }
+                builder.child(button.on_click({
+                    let handle = language_handle.clone();
+                    let selected = *choice;
+                    move |_, _, cx| {
+                        handle.update(cx, |state, cx| {
+                            state.settings.language = selected;
+                            persist_state(state);
+                            cx.notify();
+                        });
+                    }
+                }))
+            });
+
+    let general_box = GroupBox::new()
+        .title(tr(language, "General", "常规", "一般"))
+        .fill()
+        .child(
+            div()
+                .v_flex()
+                .gap_3()
+                .child(settings_row(
+                    tr(language, "Auto-connect", "自动连接", "自動連線"),
+                    tr(
+                        language,
+                        "Attach to the last used remote as soon as the app launches.",
+                        "启动应用后自动连接到上次使用的远程。",
+                        "啟動應用後自動連線到上次使用的遠端。",
+                    ),
+                    auto_connect,
+                    cx,
+                ))
+                .child(settings_row(
+                    tr(
+                        language,
+                        "Watch local changes",
+                        "监视本地更改",
+                        "監視本地變更",
+                    ),
+                    tr(
+                        language,
+                        "Monitor the local workspace and enqueue diffs automatically.",
+                        "监控本地工作区并自动加入差异。",
+                        "監控本地工作區並自動加入差異。",
+                    ),
+                    watch_changes,
+                    cx,
+                ))
+                .child(
+                    settings_row(
+                        tr(language, "Watcher backend", "监视方式", "監視方式"),
+                        tr(
+                            language,
+                            "Native events are used where available; polling is the fallback.",
+                            "优先使用系统原生事件，不可用时改为轮询。",
+                            "優先使用系統原生事件，無法使用時改為輪詢。",
+                        ),
+                        Tag::secondary().small().rounded_full().child(backend_label),
+                        cx,
+                    )
+                    .when(!settings.watch_local_changes, |row| row.opacity(0.5)),
+                )
+                .child(
+                    settings_row(
+                        tr(language, "Polling interval", "轮询间隔", "輪詢間隔"),
+                        tr(
+                            language,
+                            "How often the fallback rescans local folders.",
+                            "回退轮询时重新扫描本地目录的频率。",
+                            "回退輪詢時重新掃描本機資料夾的頻率。",
+                        ),
+                        poll_controls,
+                        cx,
+                    )
+                    .when(!settings.watch_local_changes, |row| row.opacity(0.5)),
+                )
+                .child(settings_row(
+                    tr(language, "Interface language", "界面语言", "介面語言"),
+                    tr(
+                        language,
+                        "Choose the display language.",
+                        "选择界面显示语言。",
+                        "選擇介面顯示語言。",
+                    ),
+                    language_selector,
+                    cx,
+                )),
+        );
+
+    let safety_box = GroupBox::new()
+        .title(tr(language, "Safety & Limits", "安全与限制", "安全與限制"))
+        .fill()
+        .child(
+            div()
+                .v_flex()
+                .gap_3()
+                .child(settings_row(
+                    tr(
+                        language,
+                        "Confirm destructive actions",
+                        "破坏性操作需确认",
+                        "破壞性操作需確認",
+                    ),
+                    tr(
+                        language,
+                        "Require explicit approval before deleting or overwriting remote files.",
+                        "删除或覆盖远程文件前需要确认。",
+                        "刪除或覆寫遠端檔案前需要確認。",
+                    ),
+                    confirm_switch,
+                    cx,
+                ))
+                .child(settings_row(
+                    tr(
+                        language,
+                        "Review deletions after sync",
+                        "同步后审核删除",
+                        "同步後審核刪除",
+                    ),
+                    tr(
+                        language,
+                        "Apply transfers right away but hold every deletion until you approve it on the target's dashboard.",
+                        "立即执行传输，但每项删除都会保留到你在目标面板中批准为止。",
+                        "立即執行傳輸，但每項刪除都會保留到你在目標面板中核准為止。",
+                    ),
+                    review_switch,
+                    cx,
+                ))
+                .child(settings_row(
+                    tr(
+                        language,
+                        "Back up resolved conflicts",
+                        "备份已解决的冲突",
+                        "備份已解決的衝突",
+                    ),
+                    tr(
+                        language,
+                        "Before a conflict policy overwrites a file, keep the old copy as name.conflict-<time>. Backups are never synced.",
+                        "冲突策略覆盖文件前，将旧副本保存为 name.conflict-<时间>。备份文件不会被同步。",
+                        "衝突策略覆寫檔案前，將舊副本保存為 name.conflict-<時間>。備份檔案不會被同步。",
+                    ),
+                    backup_switch,
+                    cx,
+                ))
+                .child(settings_row(
+                    tr(
+                        language,
+                        "Mass deletion guard",
+                        "大量删除保护",
+                        "大量刪除保護",
+                    ),
+                    tr(
+                        language,
+                        "Always ask again when a sync would delete this share of the destination.",
+                        "同步将删除目标端此比例的文件时始终再次确认。",
+                        "同步將刪除目標端此比例的檔案時一律再次確認。",
+                    ),
+                    mass_delete_controls,
+                    cx,
+                ))
+                .child(settings_row(
+                    tr(
+                        language,
+                        "Limit outbound bandwidth",
+                        "限制上传带宽",
+                        "限制上傳頻寬",
+                    ),
+                    tr(
+                        language,
+                        "Throttle transfer speed to keep headroom for other workloads.",
+                        "限制传输速度，为其他任务保留带宽。",
+                        "限制傳輸速度，為其他任務保留頻寬。",
+                    ),
+                    limit_switch,
+                    cx,
+                ))
+                .child(
+                    settings_row(
+                        tr(language, "Bandwidth cap", "带宽上限", "頻寬上限"),
+                        tr(
+                            language,
+                            "Applies when throttling is enabled.",
+                            "仅在启用限速时生效。",
+                            "僅在啟用限速時生效。",
+                        ),
+                        bandwidth_controls,
+                        cx,
+                    )
+                    .when(!settings.limit_bandwidth, |row| row.opacity(0.5)),
+                )
+                .child(settings_row(
+                    tr(
+                        language,
+                        "Skip files being written",
+                        "跳过正在写入的文件",
+                        "略過正在寫入的檔案",
+                    ),
+                    tr(
+                        language,
+                        "Defer uploads whose size or modification time is still changing.",
+                        "文件大小或修改时间仍在变化时，推迟到下次同步上传。",
+                        "檔案大小或修改時間仍在變化時，延後到下次同步上傳。",
+                    ),
+                    stability_switch,
+                    cx,
+                ))
+                .child(
+                    settings_row(
+                        tr(language, "Stability window", "稳定等待时间", "穩定等待時間"),
+                        tr(
+                            language,
+                            "How long a recently modified file must stay unchanged.",
+                            "最近修改的文件需保持不变的时长。",
+                            "最近修改的檔案需保持不變的時長。",
+                        ),
+                        stability_controls,
+                        cx,
+                    )
+                    .when(!settings.stability_check, |row| row.opacity(0.5)),
+                ),
+        );
+
+    let advanced_box = render_advanced_box(state, settings, language, cx);
+    let config_box = render_config_box(state, profile_input, language, cx);
+
+    div()
+        .v_flex()
+        .gap_4()
+        .p_6()
+        .child(
+            div()
+                .v_flex()
+                .gap_1()
+                .child(
+                    div()
+                        .text_2xl()
+                        .font_semibold()
+                        .child(tr(language, "Settings", "设置", "設定")),
+                )
+                .child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(tr(
+                            language,
+                            "Tune global behavior for every sync session.",
+                            "调整所有同步任务的全局行为。",
+                            "調整所有同步任務的全域行為。",
+                        )),
+                ),
+        )
+        .child(general_box)
+        .child(safety_box)
+        .child(advanced_box)
+        .child(config_box)
+}
+
+fn render_advanced_box(
+    state: &Entity<AppState>,
+    settings: &AppSettings,
+    language: Language,
+    cx: &mut Context<AppView>,
+) -> GroupBox {
+    const KEEPALIVE_STEP: u32 = 15;
+    const KEEPALIVE_MAX: u32 = 300;
+    const HEALTH_CHECK_MAX_MINS: u32 = 60;
+
+    let decrease_handle = state.clone();
+    let increase_handle = state.clone();
+    let keepalive_label = if settings.keepalive_secs == 0 {
+        tr(language, "Off", "关闭", "關閉").to_string()
+    } else {
+        format!("{} s", settings.keepalive_secs)
+    };
+    let keepalive_controls = div()
+        .h_flex()
+        .gap_2()
+        .items_center()
+        .child(
+            Button::new("keepalive_decrease")
+                .ghost()
+                .icon(Icon::new(IconName::Minus).small())
+                .disabled(settings.keepalive_secs == 0)
+                .on_click(move |_, _, cx| {
+                    decrease_handle.update(cx, |state, cx| {
+                        state.settings.keepalive_secs =
+                            state.settings.keepalive_secs.saturating_sub(KEEPALIVE_STEP);
+                        persist_state(state);
+                        cx.notify();
+                    });
+                }),
+        )
+        .child(Tag::info().small().rounded_full().child(keepalive_label))
+        .child(
+            Button::new("keepalive_increase")
+                .ghost()
+                .icon(Icon::new(IconName::Plus).small())
+                .disabled(settings.keepalive_secs >= KEEPALIVE_MAX)
+                .on_click(move |_, _, cx| {
+                    increase_handle.update(cx, |state, cx| {
+                        state.settings.keepalive_secs =
+                            (state.settings.keepalive_secs + KEEPALIVE_STEP).min(KEEPALIVE_MAX);
+                        persist_state(state);
+                        cx.notify();
+                    });
+                }),
+        );
+
+    let health_decrease_handle = state.clone();
+    let health_increase_handle = state.clone();
+    let health_controls = div()
+        .h_flex()
+        .gap_2()
+        .items_center()
+        .child(
+            Button::new("health_check_decrease")
+                .ghost()
+                .icon(Icon::new(IconName::Minus).small())
+                .disabled(settings.health_check_mins <= 1)
+                .on_click(move |_, _, cx| {
+                    health_decrease_handle.update(cx, |state, cx| {
+                        state.settings.health_check_mins =
+                            state.settings.health_check_mins.saturating_sub(1).max(1);
+                        persist_state(state);
+                        cx.notify();
+                    });
+                }),
+        )
+        .child(
+            Tag::info()
+                .small()
+                .rounded_full()
+                .child(format!("{} min", settings.health_check_mins)),
+        )
+        .child(
+            Button::new("health_check_increase")
+                .ghost()
+                .icon(Icon::new(IconName::Plus).small())
+                .disabled(settings.health_check_mins >= HEALTH_CHECK_MAX_MINS)
+                .on_click(move |_, _, cx| {
+                    health_increase_handle.update(cx, |state, cx| {
+                        state.settings.health_check_mins =
+                            (state.settings.health_check_mins + 1).min(HEALTH_CHECK_MAX_MINS);
+                        persist_state(state);
+                        cx.notify();
+                    });
+                }),
+        );
+
+    let choose_handle = state.clone();
+    let reset_handle = state.clone();
+    let staging_label = match &settings.staging_dir {
+        Some(dir) => dir.display().to_string(),
+        None => tr(
+            language,
+            "Next to destination",
+            "与目标文件相同目录",
+            "與目標檔案相同目錄",
+        )
+        .to_string(),
+    };
+    let staging_controls = div()
+        .h_flex()
+        .gap_2()
+        .items_center()
+        .child(Tag::secondary().small().rounded_full().child(staging_label))
+        .child(
+            Button::new("staging_dir_choose")
+                .ghost()
+                .small()
+                .label(tr(language, "Choose…", "选择…", "選擇…"))
+                .on_click(move |_, _, cx| {
+                    let receiver = cx.prompt_for_paths(PathPromptOptions {
+                        files: false,
+                        directories: true,
+                        multiple: false,
+                        prompt: None,
+                    });
+                    let handle = choose_handle.clone();
+                    cx.spawn(async move |cx| {
+                        let Ok(Ok(Some(paths))) = receiver.await else {
+                            return Ok::<_, Error>(());
+                        };
+                        let Some(dir) = paths.into_iter().next() else {
+                            return Ok(());
+                        };
+                        let _ = handle.update(cx, |state, cx| {
+                            state.settings.staging_dir = Some(dir);
+                            persist_state(state);
+                            cx.notify();
+                        });
+                        Ok(())
+                    })
+                    .detach();
+                }),
+        )
+        .child(
+            Button::new("staging_dir_reset")
+                .ghost()
+                .small()
+                .label(tr(language, "Reset", "重置", "重設"))
+                .disabled(settings.staging_dir.is_none())
+                .on_click(move |_, _, cx| {
+                    reset_handle.update(cx, |state, cx| {
+                        state.settings.staging_dir = None;
+                        persist_state(state);
+                        cx.notify();
+                    });
+                }),
+        );
+
+    let log_file_handle = state.clone();
+    let log_file_switch = Switch::new("log_to_file")
+        .checked(settings.log_to_file)
+        .on_click(move |next, _, cx| {
+            let enabled = *next;
+            log_file_handle.update(cx, |state, cx| {
+                state.settings.log_to_file = enabled;
+                persist_state(state);
+                if let Some(path) = log_file::current_path().filter(|_| enabled) {
+                    state.log_event(
+                        LogLevel::Info,
+                        format!("Writing the activity log to {}", path.display()),
+                    );
+                }
+                cx.notify();
+            });
+        });
+    let log_file_size = format_bytes(log_file::MAX_FILE_BYTES, language);
+    let log_file_hint = match language {
+        Language::English => format!(
+            "Keep the activity log in the config folder, up to {} files of {log_file_size}.",
+            log_file::MAX_FILES
+        ),
+        Language::SimplifiedChinese => format!(
+            "将活动日志保存到配置目录，最多保留 {} 个 {log_file_size} 的文件。",
+            log_file::MAX_FILES
+        ),
+        Language::TraditionalChinese => format!(
+            "將活動日誌保存到設定資料夾，最多保留 {} 個 {log_file_size} 的檔案。",
+            log_file::MAX_FILES
+        ),
+    };
+
+    GroupBox::new()
+        .title(tr(language, "Advanced", "高级", "進階"))
+        .fill()
+        .child(
+            div()
+                .v_flex()
+                .gap_3()
+                .child(settings_row(
+                    tr(language, "Write log files", "写入日志文件", "寫入日誌檔"),
+                    &log_file_hint,
+                    log_file_switch,
+                    cx,
+                ))
+                .child(settings_row(
+                    tr(language, "SSH keepalive", "SSH 保活", "SSH 保持連線"),
+                    tr(
+                        language,
+                        "Send keepalives on idle connections during a sync so firewalls don't drop them.",
+                        "同步期间在连接空闲时发送保活包，防止被防火墙断开。",
+                        "同步期間在連線閒置時傳送保活封包，避免被防火牆中斷。",
+                    ),
+                    keepalive_controls,
+                    cx,
+                ))
+                .child(settings_row(
+                    tr(language, "Health check interval", "健康检查间隔", "健康檢查間隔"),
+                    tr(
+                        language,
+                        "How often targets with health checks enabled are pinged.",
+                        "启用健康检查的目标多久探测一次。",
+                        "啟用健康檢查的目標多久探測一次。",
+                    ),
+                    health_controls,
+                    cx,
+                ))
+                .child(settings_row(
+                    tr(language, "Download staging folder", "下载暂存目录", "下載暫存目錄"),
+                    tr(
+                        language,
+                        "Downloads are written here first and moved into place when complete.",
+                        "下载先写入此处，完成后再移动到目标位置。",
+                        "下載先寫入此處，完成後再移動到目標位置。",
+                    ),
+                    staging_controls,
+                    cx,
+                )),
+        )
+}
+
+/// Swaps in the profile's settings and targets. The whole state is rebuilt,
+/// so the switcher is disabled while any task is running.
+fn switch_profile(state_handle: &Entity<AppState>, profile: &str, cx: &mut App) {
+    state_handle.update(cx, |state, cx| {
+        if let Err(err) = config::set_active_profile(profile) {
+            state.log_event(
+                LogLevel::Error,
+                format!("Failed to switch profile: {err:#}"),
+            );
+            cx.notify();
+            return;
+        }
+        let (settings, targets) = config::load_state();
+        *state = AppState::new(settings, targets);
+        persist_state(state);
+        state.log_event(LogLevel::Info, format!("Switched to profile {profile}"));
+        cx.notify();
+    });
+}
+
+fn render_config_box(
+    state: &Entity<AppState>,
+    profile_input: &Entity<InputState>,
+    language: Language,
+    cx: &mut Context<AppView>,
+) -> GroupBox {
+    let active_profile = config::active_profile();
+    let busy = {
+        let state = state.read(cx);
+        !state.task_progress.is_empty() || state.sync_all.is_some()
+    };
+    let profile_selector = config::list_profiles().into_iter().enumerate().fold(
+        div().h_flex().gap_2().flex_wrap(),
+        |builder, (index, profile)| {
+            let active = profile == active_profile;
+            let mut button = Button::new(("switch_profile", index))
+                .small()
+                .label(profile.clone())
+                .disabled(busy && !active);
+            if active {
+                button = button.primary();
+            } else {
+                button = button.ghost();
+            }
+            builder.child(button.on_click({
+                let handle = state.clone();
+                move |_, _, cx| {
+                    if !active {
+                        switch_profile(&handle, &profile, cx);
+                    }
+                }
+            }))
+        },
+    );
+    let create_handle = state.clone();
+    let create_input = profile_input.clone();
+    let create_button = Button::new("create_profile")
+        .ghost()
+        .small()
+        .label(tr(language, "Create", "创建", "建立"))
+        .disabled(busy)
+        .on_click(move |_, window, cx| {
+            let name = create_input.read(cx).text().to_string().trim().to_string();
+            if !config::is_valid_profile_name(&name) {
+                create_handle.update(cx, |state, cx| {
+                    state.log_event(
+                        LogLevel::Warn,
+                        "Profile names may only use letters, digits, - and _",
+                    );
+                    cx.notify();
+                });
+                return;
+            }
+            create_input.update(cx, |input, cx| input.set_value("", window, cx));
+            switch_profile(&create_handle, &name, cx);
+        });
+
+    let open_button = Button::new("open_config_dir")
+        .ghost()
+        .small()
+        .icon(Icon::new(IconName::Folder).small())
+        .label(tr(language, "Open Folder", "打开文件夹", "開啟資料夾"))
+        .on_click(|_, _, cx| {
+            if let Some(dir) = config::config_dir() {
+                let _ = std::fs::create_dir_all(&dir);
+                cx.open_with_system(&dir);
+            }
+        });
+
+    let export_handle = state.clone();
+    let export_button = Button::new("export_config")
+        .ghost()
+        .small()
+        .label(tr(language, "Export…", "导出…", "匯出…"))
+        .on_click(move |_, _, cx| {
+            let start_dir = dirs::home_dir().unwrap_or_default();
+            let receiver = cx.prompt_for_new_path(&start_dir, Some("sftp-sync-config.json"));
+            let handle = export_handle.clone();
+            cx.spawn(async move |cx| {
+                if let Ok(Ok(Some(path))) = receiver.await {
+                    let _ = handle.update(cx, |state, cx| {
+                        match config::export_state(&state.settings, &state.remote_targets, &path)
+                        {
+                            Ok(()) => state.log_event(
+                                LogLevel::Info,
+                                format!("Exported configuration to {}", path.display()),
+                            ),
+                            Err(err) => state.log_event(
+                                LogLevel::Error,
+                                format!("Failed to export configuration: {err:#}"),
+                            ),
+                        }
+                        cx.notify();
+                    });
+                }
+                Ok::<_, Error>(())
+            })
+            .detach();
+        });
+
+    let import_handle = state.clone();
+    let import_button = Button::new("import_config")
+        .ghost()
+        .small()
+        .label(tr(language, "Import…", "导入…", "匯入…"))
+        .on_click(move |_, _, cx| {
+            let receiver = cx.prompt_for_paths(PathPromptOptions {
+                files: true,
+                directories: false,
+                multiple: false,
+                prompt: None,
+            });
+            let handle = import_handle.clone();
+            cx.spawn(async move |cx| {
+                let Ok(Ok(Some(paths))) = receiver.await else {
+                    return Ok::<_, Error>(());
+                };
+                let Some(path) = paths.into_iter().next() else {
+                    return Ok(());
+                };
+                let _ = handle.update(cx, |state, cx| {
+                    let mut settings = state.settings.clone();
+                    let mut targets = state.remote_targets.clone();
+                    match config::import_state(&path, &mut settings, &mut targets) {
+                        Ok(summary) => {
+                            state.settings = settings;
+                            state.remote_targets = targets;
+                            if state.active_target.is_none() {
+                                state.active_target =
+                                    state.remote_targets.first().map(|target| target.id);
+                            }
+                            persist_state(state);
+                            state.log_event(
+                                LogLevel::Info,
+                                format!(
+                                    "Imported {} targets ({} already present); re-enter their credentials before syncing",
+                                    summary.added, summary.skipped
+                                ),
+                            );
+                        }
+                        Err(err) => state.log_event(
+                            LogLevel::Error,
+                            format!("Failed to import configuration: {err:#}"),
+                        ),
+                    }
+                    cx.notify();
+                });
+                Ok(())
+            })
+            .detach();
+        });
+
+    let diagnostics_handle = state.clone();
+    let diagnostics_button = Button::new("copy_diagnostics")
+        .ghost()
+        .small()
+        .label(tr(language, "Copy diagnostics", "复制诊断信息", "複製診斷資訊"))
+        .on_click(move |_, _, cx| {
+            let active = diagnostics_handle.read(cx).active_target;
+            copy_diagnostics(&diagnostics_handle, active, None, cx);
+        });
+
+    GroupBox::new()
+        .title(tr(language, "Configuration", "配置", "設定檔"))
+        .fill()
+        .child(
+            div()
+                .v_flex()
+                .gap_3()
+                .child(settings_row(
+                    tr(language, "Profile", "配置方案", "設定方案"),
+                    tr(
+                        language,
+                        "Separate sets of targets and settings, e.g. work and personal. Switching reloads everything.",
+                        "相互独立的目标和设置，例如工作和个人。切换后会重新加载全部内容。",
+                        "彼此獨立的目標與設定，例如工作與個人。切換後會重新載入全部內容。",
+                    ),
+                    div()
+                        .v_flex()
+                        .gap_2()
+                        .child(profile_selector)
+                        .child(
+                            div()
+                                .h_flex()
+                                .gap_2()
+                                .child(TextInput::new(profile_input).small())
+                                .child(create_button),
+                        ),
+                    cx,
+                ))
+                .child(settings_row(
+                    tr(language, "Config folder", "配置文件夹", "設定資料夾"),
+                    tr(
+                        language,
+                        "Where settings, targets and known hosts are stored.",
+                        "设置、目标和已知主机的存储位置。",
+                        "設定、目標與已知主機的儲存位置。",
+                    ),
+                    open_button,
+                    cx,
+                ))
+                .child(settings_row(
+                    tr(language, "Export / Import", "导出 / 导入", "匯出 / 匯入"),
+                    tr(
+                        language,
+                        "Move targets and settings to another machine. Passwords and passphrases are never exported.",
+                        "将目标和设置迁移到其他机器。密码和口令不会被导出。",
+                        "將目標與設定移轉到其他機器。密碼與口令不會被匯出。",
+                    ),
+                    div()
+                        .h_flex()
+                        .gap_2()
+                        .child(export_button)
+                        .child(import_button),
+                    cx,
+                ))
+                .child(settings_row(
+                    tr(language, "Diagnostics", "诊断", "診斷"),
+                    tr(
+                        language,
+                        "Copy app, system and selected-target details plus recent log lines for a bug report. Secrets are removed.",
+                        "复制应用、系统、当前目标信息及最近日志，用于反馈问题。密码等机密会被移除。",
+                        "複製應用、系統、目前目標資訊及最近日誌，用於回報問題。密碼等機密會被移除。",
+                    ),
+                    diagnostics_button,
+                    cx,
+                )),
+        )
+}
+
+fn settings_row(
+    title: &str,
+    description: &str,
+    control: impl IntoElement,
+    cx: &mut Context<AppView>,
+) -> Div {
+    let title_text = title.to_owned();
+    let description_text = description.to_owned();
+    let control = control.into_any_element();
+    div()
+        .h_flex()
+        .gap_4()
+        .items_center()
+        .justify_between()
+        .child(
+            div()
+                .v_flex()
+                .gap_1()
+                .child(div().font_semibold().child(title_text))
+                .child(
+                    div()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(description_text),
+                ),
+        )
+        .child(control)
+}
+
+/// Green, amber or red by how the rule's latest sync ended, with its time.
+fn rule_status_tag(status: &RuleStatus, language: Language) -> Tag {
+    let when = format_timestamp(status.finished_at, language);
+    if status.failed > 0 {
+        Tag::danger().small().rounded_full().child(format!(
+            "{} {} • {when}",
+            status.failed,
+            tr(language, "failed", "失败", "失敗"),
+        ))
+    } else if status.conflicts > 0 {
+        Tag::warning().small().rounded_full().child(format!(
+            "{} {} • {when}",
+            status.conflicts,
+            tr(language, "conflicts", "冲突", "衝突"),
+        ))
+    } else {
+        Tag::success().small().rounded_full().child(format!(
+            "{} • {when}",
+            tr(language, "synced", "已同步", "已同步"),
+        ))
+    }
+}
+
+fn format_timestamp(ts: SystemTime, language: Language) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const WEEK: u64 = 7 * DAY;
+    const MONTH: u64 = 30 * DAY;
+
+    let Ok(elapsed) = SystemTime::now().duration_since(ts) else {
+        return tr(language, "in the future", "未来", "未來").into();
+    };
+    let secs = elapsed.as_secs();
+    // Chinese counts take no plural form, so one template covers every count.
+    let (count, english, simplified, traditional) = match secs {
+        0..MINUTE => return tr(language, "just now", "刚刚", "剛剛").into(),
+        MINUTE..HOUR => (secs / MINUTE, "m", "分钟", "分鐘"),
+        HOUR..DAY => (secs / HOUR, "h", "小时", "小時"),
+        DAY..WEEK => (secs / DAY, "d", "天", "天"),
+        WEEK..MONTH => (secs / WEEK, "w", "周", "週"),
+        _ => return format_date(ts, language),
+    };
+    match language {
+        Language::English => format!("{count}{english} ago"),
+        Language::SimplifiedChinese => format!("{count} {simplified}前"),
+        Language::TraditionalChinese => format!("{count} {traditional}前"),
+    }
+}
+
+/// Calendar date (UTC) for timestamps too old for a relative label.
+fn format_date(ts: SystemTime, language: Language) -> String {
+    let days = ts
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86_400) as i64;
+    // Howard Hinnant's civil_from_days: days since 1970-01-01 to year, month, day.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    match language {
+        Language::English => format!("{year}-{month:02}-{day:02}"),
+        Language::SimplifiedChinese | Language::TraditionalChinese => {
+            format!("{year}年{month}月{day}日")
+        }
+    }
+}
+
+fn format_bytes(bytes: u64, language: Language) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let number = if unit == 0 || value >= 10.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.1}")
+    };
+    match language {
+        Language::English => format!("{number} {}", UNITS[unit]),
+        Language::SimplifiedChinese | Language::TraditionalChinese => {
+            format!("{number}{}", UNITS[unit])
+        }
+    }
+}
+
+/// Listing depth limits offered for a rule.
+const MAX_DEPTH_STEPS: [usize; 6] = [8, 16, 32, 64, 128, 256];
+/// Listing size limits offered for a rule.
+const MAX_ENTRIES_STEPS: [usize; 5] = [10_000, 100_000, 1_000_000, 5_000_000, 10_000_000];
+
+/// `label − value +` control moving a rule limit through `steps`. A value
+/// between steps, e.g. from a hand-edited config, moves to its neighbours.
+fn rule_limit_stepper(
+    id: ElementId,
+    label: &'static str,
+    value: usize,
+    steps: &'static [usize],
+    format: fn(usize) -> String,
+    on_change: impl Fn(usize, &mut App) + Clone + 'static,
+    cx: &mut Context<AppView>,
+) -> Div {
+    let lower = steps.iter().rev().find(|step| **step < value).copied();
+    let higher = steps.iter().find(|step| **step > value).copied();
+    let decrease = on_change.clone();
+    div()
+        .h_flex()
+        .gap_1()
+        .items_center()
+        .child(
+            div()
+                .text_sm()
+                .text_color(cx.theme().muted_foreground)
+                .child(label),
+        )
+        .child(
+            Button::new(ElementId::NamedChild(Box::new(id.clone()), "decrease".into()))
+                .ghost()
+                .small()
+                .icon(Icon::new(IconName::Minus).small())
+                .disabled(lower.is_none())
+                .on_click(move |_, _, cx| {
+                    if let Some(lower) = lower {
+                        decrease(lower, cx);
+                    }
+                }),
+        )
+        .child(Tag::info().small().rounded_full().child(format(value)))
+        .child(
+            Button::new(ElementId::NamedChild(Box::new(id), "increase".into()))
+                .ghost()
+                .small()
+                .icon(Icon::new(IconName::Plus).small())
+                .disabled(higher.is_none())
+                .on_click(move |_, _, cx| {
+                    if let Some(higher) = higher {
+                        on_change(higher, cx);
+                    }
+                }),
+        )
+}
+
+/// English noun for `count` things; the Chinese strings have no plural form.
+fn plural(count: usize, one: &'static str, many: &'static str) -> &'static str {
+    if count == 1 { one } else { many }
+}
+
+/// Groups digits in thousands with commas, which every supported language uses.
+fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+fn render_drift_summary(drift: DriftSummary, language: Language, cx: &mut Context<AppView>) -> Div {
+    let text = if drift.is_clean() {
+        tr(
+            language,
+            "No local changes since the last sync",
+            "自上次同步以来本地无变更",
+            "自上次同步以來本地無變更",
+        )
+        .to_string()
+    } else {
+        match language {
+            Language::English => format!(
+                "Since last sync: {} added · {} modified · {} removed locally",
+                drift.added, drift.modified, drift.removed
+            ),
+            Language::SimplifiedChinese => format!(
+                "自上次同步：本地新增 {} · 修改 {} · 删除 {}",
+                drift.added, drift.modified, drift.removed
+            ),
+            Language::TraditionalChinese => format!(
+                "自上次同步：本地新增 {} · 修改 {} · 刪除 {}",
+                drift.added, drift.modified, drift.removed
+            ),
+        }
+    };
+    div()
+        .text_sm()
+        .text_color(cx.theme().muted_foreground)
+        .child(text)
+}
+
+/// Recomputes local drift against the last-sync snapshot off the UI thread.
+/// Opens `target` on the dashboard and remembers it for the next start.
+fn select_target(state_handle: &Entity<AppState>, target: RemoteTarget, cx: &mut App) {
+    let target_id = target.id;
+    state_handle.update(cx, |state, cx| {
+        state.active_target = Some(target_id);
+        state.active_view = ActiveView::Dashboard;
+        if state.settings.last_target != Some(target_id) {
+            state.settings.last_target = Some(target_id);
+            persist_state(state);
+        }
+        cx.notify();
+    });
+    refresh_drift(state_handle, target, cx);
+}
+
+fn refresh_drift(state_handle: &Entity<AppState>, target: RemoteTarget, cx: &mut App) {
+    let handle = state_handle.clone();
+    let task = cx.background_spawn(async move {
+        let snapshot = config::load_snapshot(target.id)?;
+        let drift = sync::local_drift_for_target(&target, &snapshot).ok()?;
+        Some((target.id, drift))
+    });
+    cx.spawn(async move |cx| {
+        if let Some((target_id, drift)) = task.await {
+            let _ = handle.update(cx, |state, cx| {
+                state.drift.insert(target_id, drift);
+                cx.notify();
+            });
+        }
+        Ok::<_, Error>(())
+    })
+    .detach();
+}
+
+/// Checks one rule's remote folder in the background; the outcome shows on
+/// the rule's row and in the activity log.
+fn run_rule_test(
+    state_handle: &Entity<AppState>,
+    target: RemoteTarget,
+    index: usize,
+    language: Language,
+    cx: &mut App,
+) {
+    let Some(rule) = target.rules.get(index).cloned() else {
+        return;
+    };
+    let key = (target.id, index);
+    let label = rule.label();
+    state_handle.update(cx, |state, cx| {
+        state
+            .rule_tests
+            .insert(key, ConnectionTestState::InProgress);
+        cx.notify();
+    });
+
+    let handle = state_handle.clone();
+    let task = cx.background_spawn(async move { sync::check_rule_remote(&target, &rule) });
+    cx.spawn(async move |cx| {
+        let result = task.await;
+        let _ = handle.update(cx, |state, cx| {
+            let status = match result {
+                Ok(check) => {
+                    let (access, message) = if check.writable {
+                        (
+                            "readable and writable",
+                            tr(language, "Readable & writable", "可读写", "可讀寫"),
+                        )
+                    } else {
+                        ("readable", tr(language, "Readable", "可读", "可讀"))
+                    };
+                    state.log_target_event(
+                        key.0,
+                        LogLevel::Info,
+                        format!("Rule check {label}: {} is {access}", check.root.display()),
+                    );
+                    ConnectionTestState::Success(message.into())
+                }
+                Err(err) => {
+                    state.log_target_event(
+                        key.0,
+                        LogLevel::Error,
+                        format!("Rule check {label} failed: {err:#}"),
+                    );
+                    ConnectionTestState::Failure(state.redact(&format!("{err:#}")))
+                }
+            };
+            state.rule_tests.insert(key, status);
+            cx.notify();
+        });
+        Ok::<_, Error>(())
+    })
+    .detach();
+}
+
+/// Lists each rule's local side in the background and logs problems found,
+/// without opening an SSH session.
+fn run_local_preflight(state_handle: &Entity<AppState>, target: RemoteTarget, cx: &mut App) {
+    let handle = state_handle.clone();
+    let target_id = target.id;
+    let target_name = target.name.clone();
+    let task = cx.background_spawn(async move { sync::local_preflight(&target) });
+    cx.spawn(async move |cx| {
+        let reports = task.await;
+        let _ = handle.update(cx, |state, cx| {
+            let flagged = reports
+                .iter()
+                .filter(|report| !report.issues.is_empty())
+                .count();
+            for report in &reports {
+                if report.issues.is_empty() {
+                    state.log_target_event(
+                        target_id,
+                        LogLevel::Info,
+                        format!(
+                            "Local check {}: {} {}, {}",
+                            report.rule,
+                            format_count(report.files),
+                            plural(report.files, "file", "files"),
+                            format_bytes(report.bytes, Language::English)
+                        ),
+                    );
+                } else {
+                    let issues: Vec<String> =
+                        report.issues.iter().map(ToString::to_string).collect();
+                    state.log_target_event(
+                        target_id,
+                        LogLevel::Warn,
+                        format!("Local check {}: {}", report.rule, issues.join("; ")),
+                    );
+                }
+            }
+            let (level, message) = if flagged == 0 {
+                (
+                    LogLevel::Info,
+                    format!("Local check passed for {target_name}"),
+                )
+            } else {
+                (
+                    LogLevel::Warn,
+                    format!(
+                        "Local check for {target_name}: {flagged} of {} rules need attention",
+                        reports.len()
+                    ),
+                )
+            };
+            state.log_target_event(target_id, level, message);
+            cx.notify();
+        });
+        Ok::<_, Error>(())
+    })
+    .detach();
+}
+
+fn render_plan_summary(
+    totals: TransferTotals,
+    space: Option<SpaceCheck>,
+    collisions: usize,
+    identical: usize,
+    forced: bool,
+    language: Language,
+    cx: &mut Context<AppView>,
+) -> Div {
+    let headline = match language {
+        Language::English => format!(
+            "About to transfer {} {}, {}",
+            format_count(totals.files()),
+            plural(totals.files(), "file", "files"),
+            format_bytes(totals.bytes(), language)
+        ),
+        Language::SimplifiedChinese => format!(
+            "即将传输 {} 个文件，共 {}",
+            format_count(totals.files()),
+            format_bytes(totals.bytes(), language)
+        ),
+        Language::TraditionalChinese => format!(
+            "即將傳輸 {} 個檔案，共 {}",
+            format_count(totals.files()),
+            format_bytes(totals.bytes(), language)
+        ),
+    };
+    let direction_tag = |label: &str, files: usize, bytes: u64| {
+        Tag::secondary().small().rounded_full().child(format!(
+            "{label} {} • {}",
+            format_count(files),
+            format_bytes(bytes, language)
+        ))
+    };
+
+    div()
+        .v_flex()
+        .gap_2()
+        .child(
+            div()
+                .text_sm()
+                .text_color(cx.theme().muted_foreground)
+                .child(tr(language, "Plan preview", "计划预览", "計畫預覽")),
+        )
+        .child(div().font_medium().child(headline))
+        .child(
+            div()
+                .h_flex()
+                .gap_2()
+                .flex_wrap()
+                .when(forced, |this| {
+                    this.child(Tag::warning().small().rounded_full().child(tr(
+                        language,
+                        "Forced full sync: timestamps ignored",
+                        "强制完整同步：忽略时间戳",
+                        "強制完整同步：忽略時間戳",
+                    )))
+                })
+                .when(totals.upload_files > 0, |this| {
+                    this.child(direction_tag(
+                        direction_label(SyncDirection::Push, language),
+                        totals.upload_files,
+                        totals.upload_bytes,
+                    ))
+                })
+                .when(totals.download_files > 0, |this| {
+                    this.child(direction_tag(
+                        direction_label(SyncDirection::Pull, language),
+                        totals.download_files,
+                        totals.download_bytes,
+                    ))
+                })
+                .when(collisions > 0, |this| {
+                    this.child(Tag::warning().small().rounded_full().child(format!(
+                        "{} {}",
+                        format_count(collisions),
+                        tr(
+                            language,
+                            "files share a destination name",
+                            "个文件目标名称冲突",
+                            "個檔案目標名稱衝突",
+                        ),
+                    )))
+                })
+                .when(identical > 0, |this| {
+                    this.child(Tag::secondary().small().rounded_full().child(format!(
+                        "{} {}",
+                        format_count(identical),
+                        tr(
+                            language,
+                            "files skipped: same content",
+                            "个文件内容相同已跳过",
+                            "個檔案內容相同已略過",
+                        ),
+                    )))
+                }),
+        )
+        .when_some(space, |this, space| match space.available {
+            Some(available) if space.is_short() => {
+                this.child(Tag::danger().small().rounded_full().child(format!(
+                    "{} {} • {} {}",
+                    tr(
+                        language,
+                        "Not enough remote space:",
+                        "远程空间不足：",
+                        "遠端空間不足："
+                    ),
+                    format_bytes(space.required, language),
+                    format_bytes(available, language),
+                    tr(language, "free", "可用", "可用"),
+                )))
+            }
+            Some(_) => this,
+            None => this.child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(tr(
+                        language,
+                        "Remote free space is unavailable on this server.",
+                        "该服务器无法提供远程可用空间。",
+                        "此伺服器無法提供遠端可用空間。",
+                    )),
+            ),
+        })
+}
+
+/// Files listed in the plan preview per page; huge plans are revealed a page
+/// at a time so rendering them stays cheap. Execution is not affected.
+const PLAN_PREVIEW_ROWS: usize = 500;
+
+/// The planned files, by default only those with a pending action.
+fn render_plan_preview(
+    state_handle: &Entity<AppState>,
+    target: &RemoteTarget,
+    rules: &[SyncRule],
+    rows: &[(usize, PreviewRow)],
+    show_all: bool,
+    language: Language,
+    cx: &mut Context<AppView>,
+) -> Div {
+    let shown = state_handle
+        .read(cx)
+        .plan_rows_shown
+        .get(&target.id)
+        .copied()
+        .unwrap_or(PLAN_PREVIEW_ROWS)
+        .min(rows.len());
+    let handle = state_handle.clone();
+    let toggle = Switch::new("plan_show_all")
+        .checked(show_all)
+        .label(tr(
+            language,
+            "Show unchanged files",
+            "显示未变化的文件",
+            "顯示未變更的檔案",
+        ))
+        .on_click(move |next, _, cx| {
+            let show_all = *next;
+            handle.update(cx, |state, cx| {
+                state.plan_show_all = show_all;
+                state.plan_rows_shown.clear();
+                cx.notify();
+            });
+        });
+    let script_handle = state_handle.clone();
+    let script_target = target.clone();
+    let copy_script = Button::new(("copy_plan_script", target.id))
+        .ghost()
+        .small()
+        .label(tr(
+            language,
+            "Copy as Shell Script",
+            "复制为 Shell 脚本",
+            "複製為 Shell 指令碼",
+        ))
+        .on_click(move |_, _, cx| copy_plan_script(&script_handle, &script_target, cx));
+    let change_tag = |change: PreviewChange| {
+        let (tag, label) = match change {
+            PreviewChange::Upload => (Tag::primary(), tr(language, "upload", "上传", "上傳")),
+            PreviewChange::Download => (Tag::info(), tr(language, "download", "下载", "下載")),
+            PreviewChange::DeleteRemote => (
+                Tag::danger(),
+                tr(language, "delete remote", "删除远程", "刪除遠端"),
+            ),
+            PreviewChange::DeleteLocal => (
+                Tag::danger(),
+                tr(language, "delete local", "删除本地", "刪除本地"),
+            ),
+            PreviewChange::Conflict => (Tag::warning(), tr(language, "conflict", "冲突", "衝突")),
+            PreviewChange::Unchanged => (
+                Tag::secondary(),
+                tr(language, "unchanged", "未变化", "未變更"),
+            ),
+        };
+        tag.small().rounded_full().child(label)
+    };
+
+    div()
+        .v_flex()
+        .gap_1()
+        .child(
+            div()
+                .h_flex()
+                .justify_between()
+                .items_center()
+                .child(toggle)
+                .child(copy_script),
+        )
+        .children(rows.iter().take(shown).enumerate().map(|(index, (rule, row))| {
+            let rule = &rules[*rule];
+            div()
+                .h_flex()
+                .gap_2()
+                .items_center()
+                .child(change_tag(row.change))
+                .child(
+                    div()
+                        .flex_1()
+                        .text_xs()
+                        .child(rule.remote.join(&row.rel_path).display().to_string()),
+                )
+                .child(render_copy_file_links(
+                    state_handle,
+                    target,
+                    rule,
+                    &row.rel_path,
+                    ElementId::named_usize("plan_row", index),
+                    language,
+                ))
+        }))
+        .when(rows.len() > shown, |this| {
+            let handle = state_handle.clone();
+            let target_id = target.id;
+            let more = (rows.len() - shown).min(PLAN_PREVIEW_ROWS);
+            this.child(
+                div()
+                    .h_flex()
+                    .gap_2()
+                    .items_center()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!(
+                                "{} {} / {}",
+                                tr(language, "Showing", "显示", "顯示"),
+                                format_count(shown),
+                                format_count(rows.len())
+                            )),
+                    )
+                    .child(
+                        Button::new(("plan_show_more", target_id))
+                            .ghost()
+                            .xsmall()
+                            .label(format!(
+                                "{} {}",
+                                tr(language, "Show more", "显示更多", "顯示更多"),
+                                format_count(more)
+                            ))
+                            .on_click(move |_, _, cx| {
+                                handle.update(cx, |state, cx| {
+                                    state.plan_rows_shown.insert(target_id, shown + more);
+                                    cx.notify();
+                                });
+                            }),
+                    ),
+            )
+        })
+}
+
+fn render_task_progress(
+    progress: TaskProgress,
+    target_id: TargetId,
+    host: &str,
+    language: Language,
+) -> Div {
+    let label = match progress.kind {
+        TaskKind::Planning => tr(language, "Planning...", "规划中...", "規畫中..."),
+        TaskKind::Executing => tr(language, "Synchronizing...", "同步中...", "同步中..."),
+    };
+    let percent = progress.percent();
+    let planning = progress.kind == TaskKind::Planning;
+
+    div()
+        .w_full()
+        .mt_2()
+        .v_flex()
+        .gap_1()
+        .child(
+            div()
+                .h_flex()
+                .justify_between()
+                .items_center()
+                .child(div().text_sm().child(if progress.connecting {
+                    // The handshake has no steps to count yet.
+                    format!(
+                        "{} {host}...",
+                        tr(language, "Connecting to", "正在连接", "正在連線")
+                    )
+                } else {
+                    format!(
+                        "{} • {}/{}",
+                        label,
+                        format_count(progress.completed),
+                        format_count(progress.total)
+                    )
+                }))
+                .when(planning, |this| {
+                    this.child(
+                        Button::new(("cancel_plan", target_id))
+                            .ghost()
+                            .small()
+                            .icon(Icon::new(IconName::CircleX).small())
+                            .label(tr(language, "Cancel", "取消", "取消"))
+                            .on_click(move |_, _, _| {
+                                task_queue::cancel_plan(target_id);
+                            }),
+                    )
+                }),
+        )
+        .child(ProgressBar::new().value(percent))
+        .child(
+            div()
+                .h_flex()
+                .gap_2()
+                .text_xs()
+                .child(format!("{percent:.0}%"))
+                .when(planning && progress.discovered > 0, |this| {
+                    this.child(format!(
+                        "{} {}",
+                        format_count(progress.discovered),
+                        tr(language, "files found", "个文件已发现", "個檔案已發現")
+                    ))
+                }),
+        )
+}
+
+fn render_throughput(history: &ThroughputHistory, language: Language, cx: &App) -> Div {
+    const GRAPH_HEIGHT: f32 = 32.0;
+    const RATE_WINDOW_SECS: usize = 5;
+
+    let buckets = history.per_second(Instant::now());
+    let peak = buckets.iter().copied().max().unwrap_or(0).max(1);
+    let recent = &buckets[buckets.len().saturating_sub(RATE_WINDOW_SECS)..];
+    let rate = recent.iter().sum::<u64>() / recent.len().max(1) as u64;
+
+    let bars = buckets
+        .iter()
+        .fold(div().h_flex().items_end().gap(px(1.)), |graph, &bytes| {
+            let height = (bytes as f32 / peak as f32 * GRAPH_HEIGHT).max(1.);
+            graph.child(div().w(px(3.)).h(px(height)).bg(if bytes == 0 {
+                cx.theme().muted
+            } else {
+                cx.theme().primary
+            }))
+        });
+
+    div()
+        .mt_2()
+        .v_flex()
+        .gap_1()
+        .child(div().h(px(GRAPH_HEIGHT)).flex().items_end().child(bars))
+        .child(
+            div()
+                .text_xs()
+                .text_color(cx.theme().muted_foreground)
+                .child(format!(
+                    "{} {} • {}/s",
+                    format_bytes(history.total(), language),
+                    tr(language, "transferred", "已传输", "已傳輸"),
+                    format_bytes(rate, language)
+                )),
+        )
+}
+
+fn target_color_label(color: TargetColor, language: Language) -> &'static str {
+    match color {
+        TargetColor::None => tr(language, "None", "无", "無"),
+        TargetColor::Red => tr(language, "Red", "红", "紅"),
+        TargetColor::Orange => tr(language, "Orange", "橙", "橙"),
+        TargetColor::Green => tr(language, "Green", "绿", "綠"),
+        TargetColor::Blue => tr(language, "Blue", "蓝", "藍"),
+        TargetColor::Purple => tr(language, "Purple", "紫", "紫"),
+    }
+}
+
+fn target_sort_label(sort: TargetSort, language: Language) -> &'static str {
+    match sort {
+        TargetSort::Attention => tr(
+            language,
+            "Sort: needs attention",
+            "排序：需处理",
+            "排序：需處理",
+        ),
+        TargetSort::Name => tr(language, "Sort: name", "排序：名称", "排序：名稱"),
+        TargetSort::LastRun => tr(
+            language,
+            "Sort: last run",
+            "排序：最近运行",
+            "排序：最近執行",
+        ),
+        TargetSort::Pending => tr(language, "Sort: pending", "排序：待处理", "排序：待處理"),
+    }
+}
+
+fn session_sort_label(sort: SessionSort, language: Language) -> &'static str {
+    match sort {
+        SessionSort::Status => tr(language, "Sort: status", "排序：状态", "排序：狀態"),
+        SessionSort::Recent => tr(language, "Sort: recent", "排序：最近", "排序：最近"),
+    }
+}
+
+fn status_text(status: &SyncStatus, language: Language) -> String {
+    match status {
+        SyncStatus::Idle => tr(language, "Idle", "空闲", "閒置").into(),
+        SyncStatus::Planning => tr(
+            language,
+            "Planning sync plan",
+            "规划同步计划",
+            "規畫同步計畫",
+        )
+        .into(),
+        SyncStatus::AwaitingConfirmation => tr(
+            language,
+            "Awaiting user confirmation",
+            "等待用户确认",
+            "等待使用者確認",
+        )
+        .into(),
+        SyncStatus::Running { progress } => match language {
+            Language::English => format!(
+                "Running ({:.0}% complete)",
+                progress.clamp(0.0, 1.0) * 100.0
+            ),
+            Language::SimplifiedChinese => {
+                format!("运行中（完成 {:.0}%）", progress.clamp(0.0, 1.0) * 100.0)
+            }
+            Language::TraditionalChinese => {
+                format!("執行中（完成 {:.0}%）", progress.clamp(0.0, 1.0) * 100.0)
+            }
+        },
+        SyncStatus::Failed { reason } => match language {
+            Language::English => format!("Failed: {reason}"),
+            Language::SimplifiedChinese => format!("失败：{reason}"),
+            Language::TraditionalChinese => format!("失敗：{reason}"),
+        },
+        SyncStatus::Completed => tr(language, "Completed", "已完成", "已完成").into(),
+    }
+}
+
+fn overwrite_policy_label(policy: OverwritePolicy, language: Language) -> &'static str {
+    match policy {
+        OverwritePolicy::NewerOnly => tr(language, "newer only", "仅较新", "僅較新"),
+        OverwritePolicy::AlwaysOverwrite => tr(language, "always", "总是", "一律"),
+        OverwritePolicy::SizeOrTimeDiffers => tr(
+            language,
+            "size or time differs",
+            "大小或时间不同",
+            "大小或時間不同",
+        ),
+    }
+}
+
+fn compare_policy_label(policy: ComparePolicy, language: Language) -> &'static str {
+    match policy {
+        ComparePolicy::ModifiedTime => tr(language, "modified time", "修改时间", "修改時間"),
+        ComparePolicy::SizeOnly => tr(language, "size only", "仅大小", "僅大小"),
+        ComparePolicy::Checksum => tr(
+            language,
+            "checksum (skip identical)",
+            "校验和（跳过相同内容）",
+            "校驗和（略過相同內容）",
+        ),
+    }
+}
+
+fn compare_policy_hint(policy: ComparePolicy, language: Language) -> &'static str {
+    match policy {
+        ComparePolicy::ModifiedTime => tr(
+            language,
+            "A file changed when its modification time differs.",
+            "修改时间不同即视为已更改。",
+            "修改時間不同即視為已變更。",
+        ),
+        ComparePolicy::SizeOnly => tr(
+            language,
+            "A file changed when its size differs; times are ignored.",
+            "大小不同才视为已更改，忽略时间。",
+            "大小不同才視為已變更，忽略時間。",
+        ),
+        ComparePolicy::Checksum => tr(
+            language,
+            "Like modified time, but files with the same content are never transferred, \
+             even when their times differ. Hashing reads both copies while planning.",
+            "与修改时间相同，但内容相同的文件即使时间不同也不会传输。规划时会读取两端文件计算哈希。",
+            "與修改時間相同，但內容相同的檔案即使時間不同也不會傳輸。規劃時會讀取兩端檔案計算雜湊。",
+        ),
+    }
+}
+
+fn collision_policy_label(policy: CollisionPolicy, language: Language) -> &'static str {
+    match policy {
+        CollisionPolicy::Error => tr(language, "skip & report", "跳过并报告", "略過並回報"),
+        CollisionPolicy::Rename => tr(language, "rename", "重命名", "重新命名"),
+        CollisionPolicy::Overwrite => tr(language, "last wins", "后者覆盖", "後者覆寫"),
+    }
+}
+
+fn conflict_policy_label(policy: ConflictPolicy, language: Language) -> &'static str {
+    match policy {
+        ConflictPolicy::Skip => tr(language, "skip & report", "跳过并报告", "略過並回報"),
+        ConflictPolicy::KeepLocal => tr(language, "keep local", "保留本地", "保留本地"),
+        ConflictPolicy::KeepRemote => tr(language, "keep remote", "保留远程", "保留遠端"),
+    }
+}
+
+fn line_endings_label(endings: LineEndings, language: Language) -> &'static str {
+    match endings {
+        LineEndings::Keep => tr(language, "keep as is", "保持原样", "保持原樣"),
+        LineEndings::Lf => tr(language, "LF (Unix)", "LF（Unix）", "LF（Unix）"),
+        LineEndings::Crlf => tr(language, "CRLF (Windows)", "CRLF（Windows）", "CRLF（Windows）"),
+    }
+}
+
+fn direction_label(direction: SyncDirection, language: Language) -> &'static str {
+    match direction {
+        SyncDirection::Push => tr(language, "local → remote", "本地 → 远程", "本地 → 遠端"),
+        SyncDirection::Pull => tr(language, "remote → local", "远程 → 本地", "遠端 → 本地"),
+        SyncDirection::Bidirectional => tr(language, "two-way", "双向", "雙向"),
+    }
+}
+
+const LANGUAGE_CHOICES: &[(Language, &str)] = &[
+    (Language::English, "English"),
+    (Language::SimplifiedChinese, "简体中文"),
+    (Language::TraditionalChinese, "繁體中文"),
+];
+
+fn language_button_id(language: Language) -> &'static str {
+    match language {
+        Language::English => "lang_en",
+        Language::SimplifiedChinese => "lang_zh_hans",
+        Language::TraditionalChinese => "lang_zh_hant",
+    }
+}
+
+fn tr(
+    language: Language,
+    en: &'static str,
+    zh_hans: &'static str,
+    zh_hant: &'static str,
+) -> &'static str {
+    match language {
+        Language::English => en,
+        Language::SimplifiedChinese => zh_hans,
+        Language::TraditionalChinese => zh_hant,
+    }
+}
+
+fn plan_in_progress(state: &AppState, target_id: TargetId) -> bool {
+    state
+        .task_progress
+        .get(&target_id)
+        .map(|progress| matches!(progress.kind, TaskKind::Planning))
+        .unwrap_or(false)
+}
+
+fn schedule_plan_for_target(
+    state_handle: &Entity<AppState>,
+    target: RemoteTarget,
+    force: bool,
+    log_message: Option<String>,
+    cx: &mut App,
+) {
+    if state_handle
+        .read(cx)
+        .task_progress
+        .get(&target.id)
+        .map(|progress| matches!(progress.kind, TaskKind::Planning))
+        .unwrap_or(false)
+    {
+        return;
+    }
+
+    let total_rules = target.rules.len().max(1);
+    let target_name = target.name.clone();
+    let target_id = target.id;
+
+    state_handle.update(cx, |state, cx| {
+        if let Some(message) = log_message.clone() {
+            state.log_target_event(target_id, LogLevel::Info, message);
+        }
+
+        let mut touched = false;
+        for session in state
+            .sessions
+            .iter_mut()
+            .filter(|session| session.target_id == target_id)
+        {
+            session.status = SyncStatus::Planning;
+            session.last_run = Some(SystemTime::now());
+            touched = true;
+        }
+
+        if !touched {
+            let id = state.next_session_id();
+            state.sessions.push(SyncSession {
+                id,
+                target_id,
+                status: SyncStatus::Planning,
+                last_run: Some(SystemTime::now()),
+                pending_actions: 0,
+            });
+        }
+
+        state.set_task_progress(
+            target_id,
+            TaskProgress::new(TaskKind::Planning, 0, total_rules),
+        );
+        cx.notify();
+    });
+
+    let mut receiver = if force {
+        task_queue::submit_full_plan(target.clone())
+    } else {
+        task_queue::submit_plan(target.clone())
+    };
+    let handle = state_handle.clone();
+    {
+        let app: &mut App = cx;
+        app.spawn(async move |cx| {
+            while let Some(event) = receiver.recv().await {
+                match event {
+                    TaskEvent::Progress {
+                        completed,
+                        total,
+                        discovered,
+                        connecting,
+                        ..
+                    } => {
+                        let _ = handle.update(cx, |state, cx| {
+                            state.set_task_progress(
+                                target_id,
+                                TaskProgress::new(TaskKind::Planning, completed, total)
+                                    .with_discovered(discovered)
+                                    .with_connecting(connecting),
+                            );
+                            cx.notify();
+                        });
+                    }
+                    TaskEvent::Finished(result) => {
+                        let _ = handle.update(cx, |state, cx| {
+                            state.clear_task_progress(target_id);
+                            cx.notify();
+                        });
+                        match result {
+                            Ok(plan) => {
+                                let _ = handle.update(cx, |state, cx| {
+                                    state.apply_planned_jobs(target_id, plan);
+                                    let pending: usize = state
+                                        .jobs
+                                        .iter()
+                                        .filter(|job| job.target_id == target_id)
+                                        .map(|job| job.pending_actions())
+                                        .sum();
+                                    state.log_target_event(
+                                        target_id,
+                                        LogLevel::Info,
+                                        format!(
+                                            "Sync plan ready for {} ({} actions)",
+                                            target_name,
+                                            format_count(pending)
+                                        ),
+                                    );
+                                    cx.notify();
+                                });
+                            }
+                            Err(err) => {
+                                let _ = handle.update(cx, |state, cx| {
+                                    state.log_target_event(
+                                        target_id,
+                                        LogLevel::Error,
+                                        format!(
+                                            "Failed to prepare sync plan for {}: {err}",
+                                            target_name
+                                        ),
+                                    );
+                                    cx.notify();
+                                });
+                            }
+                        }
+                        break;
+                    }
+                }
+            }
+            Ok::<_, Error>(())
+        })
+        .detach();
+}
+
+}
+
+fn destructive_counts(jobs: &[SyncJob]) -> (usize, usize) {
+    let mut delete_local = 0;
+    let mut delete_remote = 0;
+    for job in jobs {
+        for action in &job.plan.actions {
+            match action {
+                SyncAction::DeleteLocal { .. } => delete_local += 1,
+                SyncAction::DeleteRemote { .. } => delete_remote += 1,
+                _ => {}
+            }
+        }
+    }
+    (delete_local, delete_remote)
+}
+
+/// Starts a sync for `target`, asking first when the plan is a forced full
+/// sync, the planned uploads exceed the remote free space, a rule's source is
+/// empty or the plan deletes an unusual share of files.
+fn request_execute(
+    state_handle: &Entity<AppState>,
+    target: RemoteTarget,
+    language: Language,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    if target.needs_session_secret() {
+        let handle = state_handle.clone();
+        with_session_secret(
+            state_handle,
+            target,
+            language,
+            window,
+            cx,
+            move |target, window, cx| {
+                request_execute(&handle, target, language, window, cx);
+            },
+        );
+        return;
+    }
+    let totals = {
+        let state = state_handle.read(cx);
+        state.forced_plans.contains(&target.id).then(|| {
+            TransferTotals::from_actions(
+                state
+                    .jobs
+                    .iter()
+                    .filter(|job| job.target_id == target.id)
+                    .flat_map(|job| job.plan.actions.iter()),
+            )
+        })
+    };
+    let Some(totals) = totals else {
+        guard_remote_space(state_handle, target, language, window, cx);
+        return;
+    };
+
+    let handle = state_handle.clone();
+    window.open_modal(cx, move |modal, _, _| {
+        let message = format!(
+            "{}\n{} {} • {}",
+            tr(
+                language,
+                "This forced full sync transfers every selected file, even unchanged ones, and overwrites the other side.",
+                "此次强制完整同步将传输所有选中的文件（包括未更改的文件），并覆盖另一端。",
+                "此次強制完整同步將傳輸所有選取的檔案（包括未變更的檔案），並覆寫另一端。",
+            ),
+            format_count(totals.files()),
+            tr(
+                language,
+                plural(totals.files(), "file", "files"),
+                "个文件",
+                "個檔案"
+            ),
+            format_bytes(totals.bytes(), language),
+        );
+        modal
+            .confirm()
+            .title(tr(
+                language,
+                "Force Full Sync",
+                "强制完整同步",
+                "強制完整同步",
+            ))
+            .child(div().p_4().child(message))
+            .button_props(
+                ModalButtonProps::default()
+                    .ok_text(tr(language, "Sync Everything", "全部同步", "全部同步"))
+                    .ok_variant(ButtonVariant::Danger)
+                    .cancel_text(tr(language, "Cancel", "取消", "取消")),
+            )
+            .on_ok({
+                let handle = handle.clone();
+                let target = target.clone();
+                move |_, window, cx| {
+                    let handle = handle.clone();
+                    let target = target.clone();
+                    window.defer(cx, move |window, cx| {
+                        guard_remote_space(&handle, target, language, window, cx);
+                    });
+                    true
+                }
+            })
+            .on_cancel(|_, _, _| true)
+    });
+}
+
+/// Asks before syncing when the planned uploads may not fit on the server.
+/// Writes an edited target back and re-plans it.
+fn save_edited_target(state_handle: &Entity<AppState>, updated: RemoteTarget, cx: &mut App) {
+    let target_id = updated.id;
+    let plan_target = updated.clone();
+    state_handle.update(cx, |state, cx| {
+        if let Some(existing) = state
+            .remote_targets
+            .iter_mut()
+            .find(|target| target.id == target_id)
+        {
+            let mut updated = RemoteTarget {
+                enabled: existing.enabled,
+                ..updated
+            };
+            updated.keep_rule_statuses(existing);
+            *existing = updated;
+        }
+        state.forget_rule_tests(target_id);
+        state.set_task_progress(
+            plan_target.id,
+            TaskProgress::new(TaskKind::Planning, 0, plan_target.rules.len().max(1)),
+        );
+        persist_state(state);
+        state.target_form = None;
+        state.active_view = ActiveView::Dashboard;
+        cx.notify();
+    });
+
+    let async_handle = state_handle.clone();
+    cx.spawn({
+        let plan_target = plan_target.clone();
+        async move |cx| {
+            let target_name = plan_target.name.clone();
+            let mut receiver = task_queue::submit_plan(plan_target.clone());
+            while let Some(event) = receiver.recv().await {
+                match event {
+                    TaskEvent::Progress {
+                        completed,
+                        total,
+                        discovered,
+                        connecting,
+                        ..
+                    } => {
+                        let _ = async_handle.update(cx, |state, cx| {
+                            state.set_task_progress(
+                                plan_target.id,
+                                TaskProgress::new(TaskKind::Planning, completed, total)
+                                    .with_discovered(discovered)
+                                    .with_connecting(connecting),
+                            );
+                            cx.notify();
+                        });
+                    }
+                    TaskEvent::Finished(result) => {
+                        let _ = async_handle.update(cx, |state, cx| {
+                            state.clear_task_progress(plan_target.id);
+                            cx.notify();
+                        });
+                        match result {
+                            Ok(result) => {
+                                let _ = async_handle.update(cx, |state, cx| {
+                                    state.apply_planned_jobs(plan_target.id, result);
+                                    let pending: usize = state
+                                        .jobs
+                                        .iter()
+                                        .filter(|job| job.target_id == plan_target.id)
+                                        .map(|job| job.pending_actions())
+                                        .sum();
+                                    state.log_target_event(
+                                        plan_target.id,
+                                        LogLevel::Info,
+                                        format!(
+                                            "Sync plan ready for {target_name} ({} actions)",
+                                            format_count(pending)
+                                        ),
+                                    );
+                                    cx.notify();
+                                });
+                            }
+                            Err(err) => {
+                                let _ = async_handle.update(cx, |state, cx| {
+                                    state.log_target_event(
+                                        plan_target.id,
+                                        LogLevel::Error,
+                                        format!(
+                                            "Failed to refresh sync plan for {target_name}: {err}"
+                                        ),
+                                    );
+                                    cx.notify();
+                                });
+                            }
+                        }
+                        break;
+                    }
+                }
+            }
+            Ok::<_, Error>(())
+        }
+    })
+    .detach();
+}
+
+/// Lists edits that drop or redirect synced files and saves only once confirmed.
+fn confirm_scope_changes(
+    state_handle: &Entity<AppState>,
+    updated: RemoteTarget,
+    changes: Vec<ScopeChange>,
+    language: Language,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let handle = state_handle.clone();
+    window.open_modal(cx, move |modal, _, _| {
+        let lines: Vec<String> = changes
+            .iter()
+            .map(|change| scope_change_text(change, language))
+            .collect();
+        modal
+            .confirm()
+            .title(tr(
+                language,
+                "Sync Scope Changed",
+                "同步范围已更改",
+                "同步範圍已變更",
+            ))
+            .child(
+                div()
+                    .p_4()
+                    .flex()
+                    .flex_col()
+                    .gap_1()
+                    .child(tr(
+                        language,
+                        "These edits change which files this target syncs. The next plan may upload, download or delete files it did not before.",
+                        "这些修改会改变此目标同步的文件。下一次计划可能会上传、下载或删除以前不涉及的文件。",
+                        "這些修改會改變此目標同步的檔案。下一次計畫可能會上傳、下載或刪除以前不涉及的檔案。",
+                    ))
+                    .children(lines.into_iter().map(|line| format!("• {line}"))),
+            )
+            .button_props(
+                ModalButtonProps::default()
+                    .ok_text(tr(language, "Save Changes", "保存更改", "儲存變更"))
+                    .ok_variant(ButtonVariant::Danger)
+                    .cancel_text(tr(language, "Cancel", "取消", "取消")),
+            )
+            .on_ok({
+                let handle = handle.clone();
+                let updated = updated.clone();
+                move |_, window, cx| {
+                    let handle = handle.clone();
+                    let updated = updated.clone();
+                    window.defer(cx, move |_, cx| save_edited_target(&handle, updated, cx));
+                    true
+                }
+            })
+            .on_cancel(|_, _, _| true)
+    });
+}
+
+fn scope_change_text(change: &ScopeChange, language: Language) -> String {
+    match change {
+        ScopeChange::BasePath { from, to } => format!(
+            "{}: {} → {}",
+            tr(language, "Remote base path", "远程根路径", "遠端根路徑"),
+            from.display(),
+            to.display()
+        ),
+        ScopeChange::LocalBase { from, to } => format!(
+            "{}: {} → {}",
+            tr(language, "Local base folder", "本地根目录", "本機根目錄"),
+            from.display(),
+            to.display()
+        ),
+        ScopeChange::RuleRemoved(rule) => format!(
+            "{}: {rule}",
+            tr(language, "Rule removed", "规则已删除", "規則已刪除")
+        ),
+        ScopeChange::RuleMoved { from, to } => format!(
+            "{}: {from} ⇒ {to}",
+            tr(language, "Rule moved", "规则已移动", "規則已移動")
+        ),
+        ScopeChange::Direction { rule, from, to } => format!(
+            "{}: {rule} ({} ⇒ {})",
+            tr(language, "Direction changed", "方向已更改", "方向已變更"),
+            direction_label(*from, language),
+            direction_label(*to, language)
+        ),
+    }
+}
+
+fn guard_remote_space(
+    state_handle: &Entity<AppState>,
+    target: RemoteTarget,
+    language: Language,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let short = state_handle
+        .read(cx)
+        .space_checks
+        .get(&target.id)
+        .copied()
+        .filter(SpaceCheck::is_short);
+    let Some(space) = short else {
+        guard_empty_source(state_handle, target, language, window, cx);
+        return;
+    };
+
+    let handle = state_handle.clone();
+    window.open_modal(cx, move |modal, _, _| {
+        let message = format!(
+            "{}\n{} {} • {} {}",
+            tr(
+                language,
+                "The planned uploads may not fit on the server. Sync anyway?",
+                "计划上传的内容可能超出服务器空间，仍要同步吗？",
+                "計畫上傳的內容可能超出伺服器空間，仍要同步嗎？",
+            ),
+            format_bytes(space.required, language),
+            tr(language, "needed", "需要", "需要"),
+            format_bytes(space.available.unwrap_or_default(), language),
+            tr(language, "free", "可用", "可用"),
+        );
+        modal
+            .confirm()
+            .title(tr(
+                language,
+                "Low Remote Space",
+                "远程空间不足",
+                "遠端空間不足",
+            ))
+            .child(div().p_4().child(message))
+            .on_ok({
+                let handle = handle.clone();
+                let target = target.clone();
+                move |_, window, cx| {
+                    let handle = handle.clone();
+                    let target = target.clone();
+                    window.defer(cx, move |window, cx| {
+                        guard_empty_source(&handle, target, language, window, cx);
+                    });
+                    true
+                }
+            })
+            .on_cancel(|_, _, _| true)
+    });
+}
+
+/// Asks, regardless of `confirm_destructive`, when a rule's source listed no
+/// files and its plan deletes the whole destination. Confirming also covers
+/// the mass deletion guard, which such a plan would trip as well.
+fn guard_empty_source(
+    state_handle: &Entity<AppState>,
+    target: RemoteTarget,
+    language: Language,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let empty_sources: usize = state_handle
+        .read(cx)
+        .jobs
+        .iter()
+        .filter(|job| job.target_id == target.id)
+        .map(|job| job.plan.stats.empty_sources)
+        .sum();
+    if empty_sources == 0 {
+        guard_mass_deletion(state_handle, target, language, window, cx);
+        return;
+    }
+
+    let handle = state_handle.clone();
+    window.open_modal(cx, move |modal, _, _| {
+        let message = format!(
+            "{}\n{} {}",
+            tr(
+                language,
+                "A source folder is empty, so this sync would delete every file on the other side. This usually means the folder is wrong or not mounted.",
+                "源文件夹为空，此次同步将删除另一端的所有文件。这通常意味着文件夹选错或未挂载。",
+                "來源資料夾為空，此次同步將刪除另一端的所有檔案。這通常表示資料夾選錯或未掛載。",
+            ),
+            format_count(empty_sources),
+            tr(language, "rules affected", "条规则受影响", "條規則受影響"),
+        );
+        modal
+            .confirm()
+            .title(tr(language, "Empty Source", "源为空", "來源為空"))
+            .child(div().p_4().child(message))
+            .button_props(
+                ModalButtonProps::default()
+                    .ok_text(tr(
+                        language,
+                        "Delete Anyway",
+                        "仍然删除",
+                        "仍然刪除",
+                    ))
+                    .ok_variant(ButtonVariant::Danger)
+                    .cancel_text(tr(language, "Cancel", "取消", "取消")),
+            )
+            .on_ok({
+                let handle = handle.clone();
+                let target = target.clone();
+                move |_, window, cx| {
+                    let handle = handle.clone();
+                    let target = target.clone();
+                    window.defer(cx, move |window, cx| {
+                        start_execute(&handle, target, language, window, cx);
+                    });
+                    true
+                }
+            })
+            .on_cancel(|_, _, _| true)
+    });
+}
+
+/// Asks again, regardless of `confirm_destructive`, when the plan would delete
+/// a large share of the destination, e.g. because the source came back empty.
+fn guard_mass_deletion(
+    state_handle: &Entity<AppState>,
+    target: RemoteTarget,
+    language: Language,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let found = {
+        let state = state_handle.read(cx);
+        sync::mass_deletion(
+            state.jobs.iter().filter(|job| job.target_id == target.id),
+            state.settings.mass_delete_count,
+            state.settings.mass_delete_percent,
+        )
+    };
+    let Some(found) = found else {
+        start_execute(state_handle, target, language, window, cx);
+        return;
+    };
+
+    let handle = state_handle.clone();
+    window.open_modal(cx, move |modal, _, _| {
+        let message = format!(
+            "{}\n{} / {} ({}%)",
+            tr(
+                language,
+                "This sync would delete a large part of the destination. If the source folder is empty or the wrong one, cancel now.",
+                "此次同步将删除目标端的大部分文件。如果源文件夹为空或选错，请立即取消。",
+                "此次同步將刪除目標端的大部分檔案。如果來源資料夾為空或選錯，請立即取消。",
+            ),
+            format_count(found.deletions),
+            format_count(found.destination_files),
+            found.percent(),
+        );
+        modal
+            .confirm()
+            .title(tr(
+                language,
+                "Mass Deletion",
+                "大量删除",
+                "大量刪除",
+            ))
+            .child(div().p_4().child(message))
+            .button_props(
+                ModalButtonProps::default()
+                    .ok_text(tr(
+                        language,
+                        "Delete Anyway",
+                        "仍然删除",
+                        "仍然刪除",
+                    ))
+                    .ok_variant(ButtonVariant::Danger)
+                    .cancel_text(tr(language, "Cancel", "取消", "取消")),
+            )
+            .on_ok({
+                let handle = handle.clone();
+                let target = target.clone();
+                move |_, window, cx| {
+                    let handle = handle.clone();
+                    let target = target.clone();
+                    window.defer(cx, move |window, cx| {
+                        start_execute(&handle, target, language, window, cx);
+                    });
+                    true
+                }
+            })
+            .on_cancel(|_, _, _| true)
+    });
+}
+
+fn render_bulk_direction(
+    state_handle: &Entity<AppState>,
+    target: &RemoteTarget,
+    language: Language,
+    cx: &App,
+) -> Div {
+    [
+        SyncDirection::Push,
+        SyncDirection::Pull,
+        SyncDirection::Bidirectional,
+    ]
+    .into_iter()
+    .fold(
+        div().h_flex().gap_1().items_center().child(
+            div()
+                .text_xs()
+                .text_color(cx.theme().muted_foreground)
+                .child(tr(language, "Set all:", "全部设为：", "全部設為：")),
+        ),
+        |row, direction| {
+            let button_id = match direction {
+                SyncDirection::Push => "bulk_dir_push",
+                SyncDirection::Pull => "bulk_dir_pull",
+                SyncDirection::Bidirectional => "bulk_dir_bidi",
+            };
+            let handle = state_handle.clone();
+            let target = target.clone();
+            row.child(
+                Button::new(button_id)
+                    .ghost()
+                    .small()
+                    .label(direction_label(direction, language))
+                    .disabled(target.rules.iter().all(|rule| rule.direction == direction))
+                    .on_click(move |_, window, cx| {
+                        confirm_bulk_direction(&handle, &target, direction, language, window, cx);
+                    }),
+            )
+        },
+    )
+}
+
+fn render_compare_targets(
+    state_handle: &Entity<AppState>,
+    target: &RemoteTarget,
+    remote_targets: &[RemoteTarget],
+    language: Language,
+    cx: &App,
+) -> Div {
+    remote_targets
+        .iter()
+        .filter(|other| other.id != target.id)
+        .fold(
+            div().h_flex().gap_1().items_center().flex_wrap().child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(tr(language, "Compare with:", "对比：", "比對：")),
+            ),
+            |row, other| {
+                let handle = state_handle.clone();
+                let left = target.clone();
+                let right = other.clone();
+                row.child(
+                    Button::new(("compare_target", other.id))
+                        .ghost()
+                        .small()
+                        .label(other.name.clone())
+                        .on_click(move |_, _, cx| {
+                            run_comparison(&handle, left.clone(), right.clone(), cx);
+                        }),
+                )
+            },
+        )
+}
+
+/// Lists both targets over SFTP off the UI thread; nothing is transferred.
+fn run_comparison(
+    state_handle: &Entity<AppState>,
+    left: RemoteTarget,
+    right: RemoteTarget,
+    cx: &mut App,
+) {
+    let handle = state_handle.clone();
+    let target_id = left.id;
+    let left_name = left.name.clone();
+    let right_name = right.name.clone();
+    handle.update(cx, |state, cx| {
+        state.log_target_event(
+            target_id,
+            LogLevel::Info,
+            format!("Comparing {left_name} with {right_name}"),
+        );
+        cx.notify();
+    });
+    let task = cx.background_spawn(async move { sync::compare_targets(&left, &right) });
+    cx.spawn(async move |cx| {
+        let result = task.await;
+        let _ = handle.update(cx, |state, cx| {
+            match result {
+                Ok(comparison) => {
+                    let (level, message) = if comparison.is_identical() {
+                        (
+                            LogLevel::Info,
+                            format!(
+                                "{left_name} and {right_name} match ({} {})",
+                                comparison.identical,
+                                plural(comparison.identical, "file", "files")
+                            ),
+                        )
+                    } else {
+                        (
+                            LogLevel::Warn,
+                            format!(
+                                "{left_name} vs {right_name}: {} only on {left_name}, {} only on {right_name}, {} differ",
+                                comparison.only_left.len(),
+                                comparison.only_right.len(),
+                                comparison.differing.len()
+                            ),
+                        )
+                    };
+                    state.log_target_event(target_id, level, message);
+                    state.comparisons.insert(
+                        target_id,
+                        ComparisonReport {
+                            other_name: right_name.clone(),
+                            comparison,
+                        },
+                    );
                 }
-                builder.child(button.on_click({
-                    let handle = language_handle.clone();
-                    let selected = *choice;
-                    move |_, _, cx| {
-                        handle.update(cx, |state, cx| {
-                            state.settings.language = selected;
-                            save_state(&state.settings, &state.remote_targets);
-                            cx.notify();
-                        });
-                    }
-                }))
-            });
+                Err(err) => state.log_target_event(
+                    target_id,
+                    LogLevel::Error,
+                    format!("Comparing {left_name} with {right_name} failed: {err:#}"),
+                ),
+            }
+            cx.notify();
+        });
+        Ok::<_, Error>(())
+    })
+    .detach();
+}
 
-    let general_box = GroupBox::new()
-        .title(tr(language, "General", "常规", "一般"))
-        .fill()
+/// Paths shown per comparison column before collapsing into a count.
+const COMPARISON_PREVIEW: usize = 12;
+
+fn render_interrupted(
+    state_handle: &Entity<AppState>,
+    target: &RemoteTarget,
+    journals: &[ExecutionJournal],
+    language: Language,
+    cx: &mut Context<AppView>,
+) -> Div {
+    let remaining: usize = journals
+        .iter()
+        .map(|journal| journal.remaining().len())
+        .sum();
+    let total: usize = journals.iter().map(|journal| journal.actions.len()).sum();
+    let rules: Vec<String> = journals
+        .iter()
+        .map(|journal| journal.rule.label())
+        .collect();
+    let resume_handle = state_handle.clone();
+    let resume_target = target.clone();
+    let discard_handle = state_handle.clone();
+    let target_id = target.id;
+    div()
+        .v_flex()
+        .gap_2()
+        .p_3()
+        .rounded(cx.theme().radius)
+        .bg(cx.theme().muted.opacity(0.15))
         .child(
             div()
-                .v_flex()
-                .gap_3()
-                .child(settings_row(
-                    tr(language, "Auto-connect", "自动连接", "自動連線"),
-                    tr(
-                        language,
-                        "Attach to the last used remote as soon as the app launches.",
-                        "启动应用后自动连接到上次使用的远程。",
-                        "啟動應用後自動連線到上次使用的遠端。",
-                    ),
-                    auto_connect,
-                    cx,
-                ))
-                .child(settings_row(
-                    tr(
-                        language,
-                        "Watch local changes",
-                        "监视本地更改",
-                        "監視本地變更",
-                    ),
+                .h_flex()
+                .justify_between()
+                .items_center()
+                .gap_2()
+                .child(div().font_medium().child(format!(
+                    "{} {} / {}",
                     tr(
                         language,
-                        "Monitor the local workspace and enqueue diffs automatically.",
-                        "监控本地工作区并自动加入差异。",
-                        "監控本地工作區並自動加入差異。",
+                        "Interrupted sync, actions left:",
+                        "同步被中断，剩余操作：",
+                        "同步被中斷，剩餘操作：",
                     ),
-                    watch_changes,
-                    cx,
-                ))
-                .child(settings_row(
-                    tr(language, "Interface language", "界面语言", "介面語言"),
-                    tr(
+                    format_count(remaining),
+                    format_count(total),
+                )))
+                .child(
+                    div()
+                        .h_flex()
+                        .gap_2()
+                        .child(
+                            Button::new("resume_interrupted")
+                                .primary()
+                                .small()
+                                .label(tr(language, "Resume", "继续", "繼續"))
+                                .on_click(move |_, window, cx| {
+                                    resume_interrupted(
+                                        &resume_handle,
+                                        resume_target.clone(),
+                                        language,
+                                        window,
+                                        cx,
+                                    );
+                                }),
+                        )
+                        .child(
+                            Button::new("discard_interrupted")
+                                .ghost()
+                                .small()
+                                .label(tr(language, "Discard", "丢弃", "捨棄"))
+                                .on_click(move |_, _, cx| {
+                                    discard_handle.update(cx, |state, cx| {
+                                        discard_interrupted(state, target_id);
+                                        cx.notify();
+                                    });
+                                }),
+                        ),
+                ),
+        )
+        .child(
+            div()
+                .text_xs()
+                .text_color(cx.theme().muted_foreground)
+                .child(rules.join(", ")),
+        )
+}
+
+/// Held deletions are listed up to this many; the bulk buttons cover the rest.
+const PENDING_DELETIONS_SHOWN: usize = 50;
+
+/// Deletions the last run held for review, each with its own approve and
+/// keep buttons plus both for the whole list.
+fn render_pending_deletions(
+    state_handle: &Entity<AppState>,
+    target: &RemoteTarget,
+    deletions: &[HeldDeletion],
+    language: Language,
+    cx: &mut Context<AppView>,
+) -> Div {
+    let approve_all_handle = state_handle.clone();
+    let approve_all_target = target.clone();
+    let keep_all_handle = state_handle.clone();
+    let target_id = target.id;
+    let rows = deletions.iter().take(PENDING_DELETIONS_SHOWN).enumerate().fold(
+        div().v_flex().gap_1(),
+        |list, (index, deletion)| {
+            let (side, rel_path) = match &deletion.action {
+                SyncAction::DeleteLocal { rel_path } => {
+                    (tr(language, "local", "本地", "本地"), rel_path)
+                }
+                SyncAction::DeleteRemote { rel_path } => {
+                    (tr(language, "remote", "远程", "遠端"), rel_path)
+                }
+                _ => return list,
+            };
+            let approve_handle = state_handle.clone();
+            let approve_target = target.clone();
+            let keep_handle = state_handle.clone();
+            list.child(
+                div()
+                    .h_flex()
+                    .gap_2()
+                    .items_center()
+                    .child(Tag::danger().small().rounded_full().child(side))
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_sm()
+                            .truncate()
+                            .child(rel_path.display().to_string()),
+                    )
+                    .child(render_copy_file_links(
+                        state_handle,
+                        target,
+                        &deletion.rule,
+                        rel_path,
+                        ElementId::named_usize("held_deletion", index),
                         language,
-                        "Choose the display language.",
-                        "选择界面显示语言。",
-                        "選擇介面顯示語言。",
+                    ))
+                    .child(
+                        Button::new(("approve_deletion", index))
+                            .danger()
+                            .xsmall()
+                            .label(tr(language, "Delete", "删除", "刪除"))
+                            .on_click(move |_, _, cx| {
+                                apply_reviewed_deletions(
+                                    &approve_handle,
+                                    approve_target.clone(),
+                                    Some(index),
+                                    cx,
+                                );
+                            }),
+                    )
+                    .child(
+                        Button::new(("keep_deletion", index))
+                            .ghost()
+                            .xsmall()
+                            .label(tr(language, "Keep", "保留", "保留"))
+                            .on_click(move |_, _, cx| {
+                                keep_handle.update(cx, |state, cx| {
+                                    keep_held_deletions(state, target_id, Some(index));
+                                    cx.notify();
+                                });
+                            }),
                     ),
-                    language_selector,
-                    cx,
-                )),
-        );
+            )
+        },
+    );
+    let hidden = deletions.len().saturating_sub(PENDING_DELETIONS_SHOWN);
 
-    let safety_box = GroupBox::new()
-        .title(tr(language, "Safety & Limits", "安全与限制", "安全與限制"))
-        .fill()
+    div()
+        .v_flex()
+        .gap_2()
+        .p_3()
+        .rounded(cx.theme().radius)
+        .bg(cx.theme().muted.opacity(0.15))
         .child(
             div()
-                .v_flex()
-                .gap_3()
-                .child(settings_row(
-                    tr(
-                        language,
-                        "Confirm destructive actions",
-                        "破坏性操作需确认",
-                        "破壞性操作需確認",
-                    ),
-                    tr(
-                        language,
-                        "Require explicit approval before deleting or overwriting remote files.",
-                        "删除或覆盖远程文件前需要确认。",
-                        "刪除或覆寫遠端檔案前需要確認。",
-                    ),
-                    confirm_switch,
-                    cx,
-                ))
-                .child(settings_row(
-                    tr(
-                        language,
-                        "Limit outbound bandwidth",
-                        "限制上传带宽",
-                        "限制上傳頻寬",
-                    ),
+                .h_flex()
+                .justify_between()
+                .items_center()
+                .gap_2()
+                .child(div().font_medium().child(format!(
+                    "{} {}",
                     tr(
                         language,
-                        "Throttle transfer speed to keep headroom for other workloads.",
-                        "限制传输速度，为其他任务保留带宽。",
-                        "限制傳輸速度，為其他任務保留頻寬。",
+                        "Deletions awaiting review:",
+                        "待审核的删除：",
+                        "待審核的刪除：",
                     ),
-                    limit_switch,
-                    cx,
-                ))
+                    format_count(deletions.len()),
+                )))
                 .child(
-                    settings_row(
-                        tr(language, "Bandwidth cap", "带宽上限", "頻寬上限"),
-                        tr(
-                            language,
-                            "Applies when throttling is enabled.",
-                            "仅在启用限速时生效。",
-                            "僅在啟用限速時生效。",
+                    div()
+                        .h_flex()
+                        .gap_2()
+                        .child(
+                            Button::new("approve_all_deletions")
+                                .danger()
+                                .small()
+                                .label(tr(language, "Delete All", "全部删除", "全部刪除"))
+                                .on_click(move |_, _, cx| {
+                                    apply_reviewed_deletions(
+                                        &approve_all_handle,
+                                        approve_all_target.clone(),
+                                        None,
+                                        cx,
+                                    );
+                                }),
+                        )
+                        .child(
+                            Button::new("keep_all_deletions")
+                                .ghost()
+                                .small()
+                                .label(tr(language, "Keep All", "全部保留", "全部保留"))
+                                .on_click(move |_, _, cx| {
+                                    keep_all_handle.update(cx, |state, cx| {
+                                        keep_held_deletions(state, target_id, None);
+                                        cx.notify();
+                                    });
+                                }),
                         ),
-                        bandwidth_controls,
-                        cx,
-                    )
-                    .when(!settings.limit_bandwidth, |row| row.opacity(0.5)),
                 ),
+        )
+        .child(rows)
+        .when(hidden > 0, |this| {
+            this.child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(format!(
+                        "{} {}",
+                        format_count(hidden),
+                        tr(language, "more not shown", "项未显示", "項未顯示"),
+                    )),
+            )
+        })
+}
+
+/// Takes the held deletion at `index`, or all of them, off the target's list.
+fn take_held_deletions(
+    state: &mut AppState,
+    target_id: TargetId,
+    index: Option<usize>,
+) -> Vec<HeldDeletion> {
+    let Some(pending) = state.pending_deletions.get_mut(&target_id) else {
+        return Vec::new();
+    };
+    let taken = match index {
+        Some(index) if index < pending.len() => vec![pending.remove(index)],
+        Some(_) => Vec::new(),
+        None => std::mem::take(pending),
+    };
+    if pending.is_empty() {
+        state.pending_deletions.remove(&target_id);
+    }
+    taken
+}
+
+/// Drops held deletions without applying them; the files stay, and the next
+/// run holds them again if they are still extraneous.
+fn keep_held_deletions(state: &mut AppState, target_id: TargetId, index: Option<usize>) {
+    let kept = take_held_deletions(state, target_id, index).len();
+    if kept > 0 {
+        state.log_target_event(
+            target_id,
+            LogLevel::Info,
+            format!("Kept {kept} files held for deletion"),
+        );
+    }
+}
+
+/// Applies the held deletion at `index`, or all of them, then re-plans the
+/// target so its plan matches what is left.
+fn apply_reviewed_deletions(
+    state_handle: &Entity<AppState>,
+    target: RemoteTarget,
+    index: Option<usize>,
+    cx: &mut App,
+) {
+    let (deletions, options) = state_handle.update(cx, |state, cx| {
+        let deletions = take_held_deletions(state, target.id, index);
+        cx.notify();
+        (deletions, ExecutionOptions::from_settings(&state.settings))
+    });
+    if deletions.is_empty() {
+        return;
+    }
+    let handle = state_handle.clone();
+    let task_target = target.clone();
+    let task = cx.background_spawn(async move {
+        sync::apply_held_deletions(&task_target, &deletions, &options)
+    });
+    cx.spawn(async move |cx| {
+        let result = task.await;
+        let _ = handle.update(cx, |state, cx| {
+            match &result {
+                Ok(summary) => {
+                    state.log_target_event(
+                        target.id,
+                        LogLevel::Info,
+                        format!(
+                            "Applied {} reviewed deletions for {}",
+                            summary.applied, target.name
+                        ),
+                    );
+                    if summary.refused > 0 {
+                        state.log_target_event(
+                            target.id,
+                            LogLevel::Warn,
+                            format!(
+                                "Refused {} remote deletions for {}: the remote is read-only",
+                                summary.refused, target.name
+                            ),
+                        );
+                    }
+                    for warning in &summary.stale_deletions {
+                        state.log_target_event(target.id, LogLevel::Warn, warning.clone());
+                    }
+                    for failure in &summary.failures {
+                        state.log_target_event(
+                            target.id,
+                            LogLevel::Error,
+                            format!("{}: {}", failure.rule, failure.reason),
+                        );
+                    }
+                }
+                Err(err) => state.log_target_event(
+                    target.id,
+                    LogLevel::Error,
+                    format!("Reviewed deletions for {} failed: {err:#}", target.name),
+                ),
+            }
+            cx.notify();
+        });
+        if let Ok(plan) = await_plan(&handle, &target, cx).await {
+            let _ = handle.update(cx, |state, cx| {
+                state.apply_planned_jobs(target.id, plan);
+                cx.notify();
+            });
+        }
+        Ok::<_, Error>(())
+    })
+    .detach();
+}
+
+/// Runs what is left of the target's interrupted jobs; actions the journal
+/// marks as applied are not repeated.
+fn resume_interrupted(
+    state_handle: &Entity<AppState>,
+    target: RemoteTarget,
+    language: Language,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let (jobs, settings) = state_handle.update(cx, |state, cx| {
+        let jobs: Vec<SyncJob> = state
+            .interrupted
+            .iter()
+            .filter(|journal| journal.target_id == target.id)
+            .cloned()
+            .map(ExecutionJournal::into_sync_job)
+            .collect();
+        let remaining: usize = jobs.iter().map(SyncJob::pending_actions).sum();
+        state.log_target_event(
+            target.id,
+            LogLevel::Info,
+            format!(
+                "Resuming interrupted sync for {} ({} actions left)",
+                target.name, remaining
+            ),
         );
+        cx.notify();
+        (jobs, state.settings.clone())
+    });
+    if jobs.is_empty() {
+        return;
+    }
+    confirm_and_execute(state_handle, target, jobs, settings, language, window, cx);
+}
+
+fn discard_interrupted(state: &mut AppState, target_id: TargetId) {
+    let journal_dir = config::journal_dir();
+    state.interrupted.retain(|journal| {
+        if journal.target_id != target_id {
+            return true;
+        }
+        if let Some(dir) = journal_dir.as_deref() {
+            ExecutionJournal::discard(dir, journal.session_id);
+        }
+        false
+    });
+    state.log_target_event(target_id, LogLevel::Info, "Discarded interrupted sync");
+}
 
+fn render_comparison(
+    state_handle: &Entity<AppState>,
+    target_id: TargetId,
+    report: ComparisonReport,
+    language: Language,
+    cx: &mut Context<AppView>,
+) -> Div {
+    let comparison = &report.comparison;
+    let column = |title: String, paths: &[PathBuf]| {
+        div()
+            .v_flex()
+            .flex_1()
+            .gap_1()
+            .min_w(px(160.))
+            .child(
+                div()
+                    .text_sm()
+                    .font_medium()
+                    .child(format!("{title} ({})", paths.len())),
+            )
+            .children(
+                paths
+                    .iter()
+                    .take(COMPARISON_PREVIEW)
+                    .map(|path| div().text_xs().child(path.display().to_string())),
+            )
+            .when(paths.len() > COMPARISON_PREVIEW, |this| {
+                this.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(format!("+{}", paths.len() - COMPARISON_PREVIEW)),
+                )
+            })
+    };
+    let close_handle = state_handle.clone();
     div()
         .v_flex()
-        .gap_4()
-        .p_6()
+        .gap_2()
+        .p_3()
+        .rounded(cx.theme().radius)
+        .bg(cx.theme().muted.opacity(0.15))
         .child(
             div()
-                .v_flex()
-                .gap_1()
-                .child(
-                    div()
-                        .text_2xl()
-                        .font_semibold()
-                        .child(tr(language, "Settings", "设置", "設定")),
-                )
+                .h_flex()
+                .justify_between()
+                .items_center()
+                .child(div().font_medium().child(format!(
+                    "{} {} · {} {}",
+                    tr(language, "Compared with", "对比目标", "比對目標"),
+                    report.other_name,
+                    comparison.identical,
+                    tr(language, "identical", "个相同", "個相同"),
+                )))
                 .child(
-                    div()
-                        .text_sm()
-                        .text_color(cx.theme().muted_foreground)
-                        .child(tr(
-                            language,
-                            "Tune global behavior for every sync session.",
-                            "调整所有同步任务的全局行为。",
-                            "調整所有同步任務的全域行為。",
-                        )),
+                    Button::new("close_comparison")
+                        .ghost()
+                        .small()
+                        .label(tr(language, "Close", "关闭", "關閉"))
+                        .on_click(move |_, _, cx| {
+                            close_handle.update(cx, |state, cx| {
+                                state.comparisons.remove(&target_id);
+                                cx.notify();
+                            });
+                        }),
                 ),
         )
-        .child(general_box)
-        .child(safety_box)
-}
-
-fn settings_row(
-    title: &str,
-    description: &str,
-    control: impl IntoElement,
-    cx: &mut Context<AppView>,
-) -> Div {
-    let title_text = title.to_owned();
-    let description_text = description.to_owned();
-    let control = control.into_any_element();
-    div()
-        .h_flex()
-        .gap_4()
-        .items_center()
-        .justify_between()
+        .when(!comparison.unmatched_rules.is_empty(), |this| {
+            let rules: Vec<String> = comparison
+                .unmatched_rules
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            this.child(Tag::warning().small().rounded_full().child(format!(
+                "{} {}",
+                tr(
+                    language,
+                    "Rules on one side only:",
+                    "仅一侧存在的规则：",
+                    "僅一側存在的規則：",
+                ),
+                rules.join(", ")
+            )))
+        })
         .child(
             div()
-                .v_flex()
-                .gap_1()
-                .child(div().font_semibold().child(title_text))
-                .child(
-                    div()
-                        .text_sm()
-                        .text_color(cx.theme().muted_foreground)
-                        .child(description_text),
-                ),
+                .h_flex()
+                .gap_4()
+                .items_start()
+                .flex_wrap()
+                .child(column(
+                    tr(language, "Only here", "仅本目标", "僅本目標").to_string(),
+                    &comparison.only_left,
+                ))
+                .child(column(
+                    format!(
+                        "{} {}",
+                        tr(language, "Only on", "仅存在于", "僅存在於"),
+                        report.other_name
+                    ),
+                    &comparison.only_right,
+                ))
+                .child(column(
+                    tr(language, "Differ", "不同", "不同").to_string(),
+                    &comparison.differing,
+                )),
         )
-        .child(control)
 }
 
-fn format_timestamp(ts: SystemTime, language: Language) -> String {
-    match SystemTime::now().duration_since(ts) {
-        Ok(elapsed) if elapsed < Duration::from_secs(60) => {
-            tr(language, "just now", "刚刚", "剛剛").into()
-        }
-        Ok(elapsed) if elapsed < Duration::from_secs(3600) => {
-            let minutes = elapsed.as_secs() / 60;
-            match language {
-                Language::English => format!("{minutes}m ago"),
-                Language::SimplifiedChinese => format!("{minutes} 分钟前"),
-                Language::TraditionalChinese => format!("{minutes} 分鐘前"),
-            }
+fn confirm_bulk_direction(
+    state_handle: &Entity<AppState>,
+    target: &RemoteTarget,
+    direction: SyncDirection,
+    language: Language,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let changes: Vec<String> = target
+        .rules
+        .iter()
+        .filter(|rule| rule.direction != direction)
+        .map(|rule| {
+            format!(
+                "{}: {} ⇒ {}",
+                rule.local.display(),
+                direction_label(rule.direction, language),
+                direction_label(direction, language)
+            )
+        })
+        .collect();
+    let handle = state_handle.clone();
+    let target_id = target.id;
+    let target_name = target.name.clone();
+    window.open_modal(cx, move |modal, _, _| {
+        modal
+            .confirm()
+            .title(tr(
+                language,
+                "Change Rule Directions",
+                "修改规则方向",
+                "修改規則方向",
+            ))
+            .child(
+                div()
+                    .p_4()
+                    .v_flex()
+                    .gap_1()
+                    .child(target_name.clone())
+                    .children(
+                        changes
+                            .iter()
+                            .map(|change| div().text_sm().child(change.clone())),
+                    ),
+            )
+            .button_props(
+                ModalButtonProps::default()
+                    .ok_text(tr(language, "Apply", "应用", "套用"))
+                    .cancel_text(tr(language, "Cancel", "取消", "取消")),
+            )
+            .on_ok({
+                let handle = handle.clone();
+                let target_name = target_name.clone();
+                move |_, _, cx| {
+                    handle.update(cx, |state, cx| {
+                        let changed = state.set_rule_directions(target_id, direction);
+                        persist_state(state);
+                        state.log_target_event(
+                            target_id,
+                            LogLevel::Info,
+                            format!(
+                                "Set {changed} rules of {target_name} to {}",
+                                direction_label(direction, Language::English)
+                            ),
+                        );
+                        cx.notify();
+                    });
+                    true
+                }
+            })
+            .on_cancel(|_, _, _| true)
+    });
+}
+
+fn start_execute(
+    state_handle: &Entity<AppState>,
+    target: RemoteTarget,
+    language: Language,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let snapshot = state_handle.update(cx, |state, cx| {
+        let jobs: Vec<_> = state
+            .jobs
+            .iter()
+            .filter(|job| job.target_id == target.id)
+            .cloned()
+            .collect();
+        if jobs.is_empty() {
+            state.log_target_event(
+                target.id,
+                LogLevel::Info,
+                format!("Nothing to sync for {}", target.name),
+            );
+            cx.notify();
+            return None;
         }
-        Ok(elapsed) => {
-            let hours = elapsed.as_secs() / 3600;
-            match language {
-                Language::English => format!("{hours}h ago"),
-                Language::SimplifiedChinese => format!("{hours} 小时前"),
-                Language::TraditionalChinese => format!("{hours} 小時前"),
-            }
+
+        for session in state
+            .sessions
+            .iter_mut()
+            .filter(|session| session.target_id == target.id)
+        {
+            session.status = SyncStatus::Running { progress: 0.0 };
+            session.last_run = Some(SystemTime::now());
         }
-        Err(_) => tr(language, "in the future", "未来", "未來").into(),
-    }
-}
+        state.log_target_event(
+            target.id,
+            LogLevel::Info,
+            format!("Executing sync for {}", target.name),
+        );
+        cx.notify();
+        Some((jobs, state.settings.clone()))
+    });
 
-fn render_task_progress(progress: TaskProgress, language: Language) -> Div {
-    let label = match progress.kind {
-        TaskKind::Planning => tr(language, "Planning...", "规划中...", "規畫中..."),
-        TaskKind::Executing => tr(language, "Synchronizing...", "同步中...", "同步中..."),
+    let Some((jobs, settings)) = snapshot else {
+        return;
     };
-    let percent = progress.percent();
-
-    div()
-        .w_full()
-        .mt_2()
-        .v_flex()
-        .gap_1()
-        .child(
-            div()
-                .text_sm()
-                .child(format!(
-                    "{} • {}/{}",
-                    label, progress.completed, progress.total
-                )),
-        )
-        .child(ProgressBar::new().value(percent))
-        .child(div().text_xs().child(format!("{percent:.0}%")))
+    confirm_and_execute(state_handle, target, jobs, settings, language, window, cx);
 }
 
-fn status_text(status: &SyncStatus, language: Language) -> String {
-    match status {
-        SyncStatus::Idle => tr(language, "Idle", "空闲", "閒置").into(),
-        SyncStatus::Planning => tr(
-            language,
-            "Planning sync plan",
-            "规划同步计划",
-            "規畫同步計畫",
-        )
-        .into(),
-        SyncStatus::AwaitingConfirmation => tr(
-            language,
-            "Awaiting user confirmation",
-            "等待用户确认",
-            "等待使用者確認",
-        )
-        .into(),
-        SyncStatus::Running { progress } => match language {
-            Language::English => format!(
-                "Running ({:.0}% complete)",
-                progress.clamp(0.0, 1.0) * 100.0
-            ),
-            Language::SimplifiedChinese => {
-                format!("运行中（完成 {:.0}%）", progress.clamp(0.0, 1.0) * 100.0)
-            }
-            Language::TraditionalChinese => {
-                format!("執行中（完成 {:.0}%）", progress.clamp(0.0, 1.0) * 100.0)
-            }
-        },
-        SyncStatus::Failed { reason } => match language {
-            Language::English => format!("Failed: {reason}"),
-            Language::SimplifiedChinese => format!("失败：{reason}"),
-            Language::TraditionalChinese => format!("失敗：{reason}"),
-        },
-        SyncStatus::Completed => tr(language, "Completed", "已完成", "已完成").into(),
+/// Runs `jobs`, asking first when they delete files and the settings want a
+/// confirmation for that.
+fn confirm_and_execute(
+    state_handle: &Entity<AppState>,
+    target: RemoteTarget,
+    jobs: Vec<SyncJob>,
+    settings: AppSettings,
+    language: Language,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let (delete_local, delete_remote) = destructive_counts(&jobs);
+    // Deletions held for review are confirmed one by one afterwards instead.
+    let deletes = delete_local + delete_remote > 0 && !settings.review_deletions;
+    if settings.confirm_destructive && deletes {
+        let handle = state_handle.clone();
+        window.open_modal(cx, move |modal, _, _| {
+            let message = format!(
+                "{}\n• {} {}\n• {} {}",
+                tr(
+                    language,
+                    "Destructive changes detected. Proceed?",
+                    "检测到破坏性变更，是否继续？",
+                    "偵測到破壞性變更，是否繼續？",
+                ),
+                delete_local,
+                tr(language, "local deletions", "本地删除", "本地刪除"),
+                delete_remote,
+                tr(language, "remote deletions", "远程删除", "遠端刪除"),
+            );
+            modal
+                .confirm()
+                .title(tr(
+                    language,
+                    "Confirm Destructive Sync",
+                    "确认破坏性同步",
+                    "確認破壞性同步",
+                ))
+                .child(div().p_4().child(message))
+                .on_ok({
+                    let jobs = jobs.clone();
+                    let settings = settings.clone();
+                    let handle = handle.clone();
+                    let target = target.clone();
+                    move |_, _, cx| {
+                        run_execute_jobs(
+                            cx,
+                            &handle,
+                            target.clone(),
+                            jobs.clone(),
+                            settings.clone(),
+                        );
+                        true
+                    }
+                })
+                .on_cancel(|_, _, _| true)
+        });
+    } else {
+        run_execute_jobs(cx, state_handle, target, jobs, settings);
     }
 }
 
-fn direction_label(direction: SyncDirection, language: Language) -> &'static str {
-    match direction {
-        SyncDirection::Push => tr(language, "local → remote", "本地 → 远程", "本地 → 遠端"),
-        SyncDirection::Pull => tr(language, "remote → local", "远程 → 本地", "遠端 → 本地"),
-        SyncDirection::Bidirectional => tr(language, "two-way", "双向", "雙向"),
+/// Totals across one "Sync All" run, logged as a single roll-up at the end.
+#[derive(Default)]
+struct SyncAllReport {
+    targets: usize,
+    files: usize,
+    failures: usize,
+    /// Plans that need a confirmation, e.g. deletions; run them from their panel.
+    held: Vec<String>,
+    failed: Vec<String>,
+}
+
+impl SyncAllReport {
+    fn roll_up(&self) -> (LogLevel, String) {
+        let mut message = format!(
+            "Sync all finished: {} {}, {} {}, {} {}",
+            self.targets,
+            plural(self.targets, "target", "targets"),
+            format_count(self.files),
+            plural(self.files, "file", "files"),
+            self.failures,
+            plural(self.failures, "failure", "failures")
+        );
+        if !self.held.is_empty() {
+            message.push_str(&format!(
+                "; awaiting confirmation: {}",
+                self.held.join(", ")
+            ));
+        }
+        if !self.failed.is_empty() {
+            message.push_str(&format!("; failed: {}", self.failed.join(", ")));
+        }
+        let level = if !self.failed.is_empty() || self.failures > 0 {
+            LogLevel::Error
+        } else if !self.held.is_empty() {
+            LogLevel::Warn
+        } else {
+            LogLevel::Info
+        };
+        (level, message)
     }
 }
 
-const LANGUAGE_CHOICES: &[(Language, &str)] = &[
-    (Language::English, "English"),
-    (Language::SimplifiedChinese, "简体中文"),
-    (Language::TraditionalChinese, "繁體中文"),
-];
+/// Plans and executes every enabled target in turn through the task queue.
+/// A failing target is reported and the run moves on to the next one.
+fn run_sync_all(state_handle: &Entity<AppState>, cx: &mut App) {
+    let targets: Vec<RemoteTarget> = state_handle
+        .read(cx)
+        .remote_targets
+        .iter()
+        .filter(|target| target.enabled)
+        .cloned()
+        .collect();
+    if targets.is_empty() {
+        return;
+    }
+    let handle = state_handle.clone();
+    handle.update(cx, |state, cx| {
+        state.sync_all = Some(SyncAllProgress {
+            completed: 0,
+            total: targets.len(),
+            current: targets[0].name.clone(),
+        });
+        state.log_event(
+            LogLevel::Info,
+            format!("Syncing all {} enabled targets", targets.len()),
+        );
+        cx.notify();
+    });
+    cx.spawn(async move |cx| {
+        let mut report = SyncAllReport::default();
+        for (index, target) in targets.iter().enumerate() {
+            // Abort All clears the run; stop before submitting more work.
+            let running = handle
+                .update(cx, |state, cx| {
+                    let Some(progress) = state.sync_all.as_mut() else {
+                        return false;
+                    };
+                    progress.completed = index;
+                    progress.current = target.name.clone();
+                    cx.notify();
+                    true
+                })
+                .unwrap_or(false);
+            if !running {
+                return Ok(());
+            }
+            report.targets += 1;
+
+            match await_plan(&handle, target, cx).await {
+                Ok(plan) => {
+                    let _ = handle.update(cx, |state, cx| {
+                        state.apply_planned_jobs(target.id, plan);
+                        cx.notify();
+                    });
+                }
+                Err(err) => {
+                    let _ = handle.update(cx, |state, cx| {
+                        state.log_target_event(
+                            target.id,
+                            LogLevel::Error,
+                            format!("Planning failed for {}: {err}", target.name),
+                        );
+                        cx.notify();
+                    });
+                    report.failed.push(target.name.clone());
+                    continue;
+                }
+            }
+
+            let (jobs, held, settings) = handle.read_with(cx, |state, _| {
+                let jobs: Vec<SyncJob> = state
+                    .jobs
+                    .iter()
+                    .filter(|job| job.target_id == target.id)
+                    .cloned()
+                    .collect();
+                let (delete_local, delete_remote) = destructive_counts(&jobs);
+                let held = (state.settings.confirm_destructive
+                    && !state.settings.review_deletions
+                    && delete_local + delete_remote > 0)
+                    || state
+                        .space_checks
+                        .get(&target.id)
+                        .is_some_and(SpaceCheck::is_short)
+                    || jobs.iter().any(|job| job.plan.stats.empty_sources > 0)
+                    || sync::mass_deletion(
+                        jobs.iter(),
+                        state.settings.mass_delete_count,
+                        state.settings.mass_delete_percent,
+                    )
+                    .is_some();
+                (jobs, held, state.settings.clone())
+            })?;
+            if jobs.iter().map(SyncJob::pending_actions).sum::<usize>() == 0 {
+                continue;
+            }
+            if held {
+                let _ = handle.update(cx, |state, cx| {
+                    state.log_target_event(
+                        target.id,
+                        LogLevel::Warn,
+                        format!(
+                            "Sync all skipped {}: its plan needs confirmation, run it from its panel",
+                            target.name
+                        ),
+                    );
+                    cx.notify();
+                });
+                report.held.push(target.name.clone());
+                continue;
+            }
 
-fn language_button_id(language: Language) -> &'static str {
-    match language {
-        Language::English => "lang_en",
-        Language::SimplifiedChinese => "lang_zh_hans",
-        Language::TraditionalChinese => "lang_zh_hant",
-    }
+            let _ = handle.update(cx, |state, cx| {
+                for session in state
+                    .sessions
+                    .iter_mut()
+                    .filter(|session| session.target_id == target.id)
+                {
+                    session.status = SyncStatus::Running { progress: 0.0 };
+                    session.last_run = Some(SystemTime::now());
+                }
+                state.log_target_event(
+                    target.id,
+                    LogLevel::Info,
+                    format!("Executing sync for {}", target.name),
+                );
+                cx.notify();
+            });
+            let exec_receiver =
+                cx.update(|app| begin_execution(app, &handle, target, jobs, settings))?;
+            match drive_execution(&handle, target.clone(), exec_receiver, cx).await {
+                Some(summary) => {
+                    report.files += summary.applied;
+                    report.failures += summary.failures.len();
+                }
+                None => report.failed.push(target.name.clone()),
+            }
+        }
+
+        let _ = handle.update(cx, |state, cx| {
+            state.sync_all = None;
+            let (level, message) = report.roll_up();
+            state.log_event(level, message);
+            cx.notify();
+        });
+        Ok::<_, Error>(())
+    })
+    .detach();
 }
 
-fn tr(
-    language: Language,
-    en: &'static str,
-    zh_hans: &'static str,
-    zh_hant: &'static str,
-) -> &'static str {
-    match language {
-        Language::English => en,
-        Language::SimplifiedChinese => zh_hans,
-        Language::TraditionalChinese => zh_hant,
+/// Plans `target` through the task queue, mirroring progress into the state.
+async fn await_plan(
+    handle: &Entity<AppState>,
+    target: &RemoteTarget,
+    cx: &mut AsyncApp,
+) -> Result<PlanJobsResult> {
+    let _ = handle.update(cx, |state, cx| {
+        state.set_task_progress(
+            target.id,
+            TaskProgress::new(TaskKind::Planning, 0, target.rules.len().max(1)),
+        );
+        cx.notify();
+    });
+    let mut receiver = task_queue::submit_plan(target.clone());
+    let mut result = Err(anyhow!("planning for {} was dropped", target.name));
+    while let Some(event) = receiver.recv().await {
+        match event {
+            TaskEvent::Progress {
+                completed,
+                total,
+                discovered,
+                connecting,
+                ..
+            } => {
+                let _ = handle.update(cx, |state, cx| {
+                    state.set_task_progress(
+                        target.id,
+                        TaskProgress::new(TaskKind::Planning, completed, total)
+                            .with_discovered(discovered)
+                            .with_connecting(connecting),
+                    );
+                    cx.notify();
+                });
+            }
+            TaskEvent::Finished(finished) => {
+                result = finished;
+                break;
+            }
+        }
     }
+    let _ = handle.update(cx, |state, cx| {
+        state.clear_task_progress(target.id);
+        cx.notify();
+    });
+    result
 }
 
-fn plan_in_progress(state: &AppState, target_id: TargetId) -> bool {
-    state
-        .task_progress
-        .get(&target_id)
-        .map(|progress| matches!(progress.kind, TaskKind::Planning))
-        .unwrap_or(false)
+/// Dry-runs the target's current plan and logs each action it would apply.
+/// Nothing is transferred or deleted, and sessions and history are left as
+/// they were.
+fn start_simulation(state_handle: &Entity<AppState>, target: RemoteTarget, cx: &mut App) {
+    let snapshot = state_handle.update(cx, |state, cx| {
+        let jobs: Vec<_> = state
+            .jobs
+            .iter()
+            .filter(|job| job.target_id == target.id)
+            .cloned()
+            .collect();
+        if jobs.is_empty() {
+            state.log_target_event(
+                target.id,
+                LogLevel::Info,
+                format!("Nothing to simulate for {}", target.name),
+            );
+            cx.notify();
+            return None;
+        }
+        let total_actions = jobs.iter().map(SyncJob::pending_actions).sum();
+        state.set_task_progress(
+            target.id,
+            TaskProgress::new(TaskKind::Executing, 0, total_actions),
+        );
+        state.log_target_event(
+            target.id,
+            LogLevel::Info,
+            format!("Simulating sync for {}", target.name),
+        );
+        cx.notify();
+        Some((jobs, state.settings.clone()))
+    });
+    let Some((jobs, settings)) = snapshot else {
+        return;
+    };
+    let mut receiver = task_queue::submit_simulation(target.clone(), jobs, settings);
+    let handle = state_handle.clone();
+    cx.spawn(async move |cx| {
+        let mut result = Err(anyhow!("simulation for {} was dropped", target.name));
+        while let Some(event) = receiver.recv().await {
+            match event {
+                TaskEvent::Progress {
+                    completed,
+                    total,
+                    connecting,
+                    ..
+                } => {
+                    let _ = handle.update(cx, |state, cx| {
+                        state.set_task_progress(
+                            target.id,
+                            TaskProgress::new(TaskKind::Executing, completed, total)
+                                .with_connecting(connecting),
+                        );
+                        cx.notify();
+                    });
+                }
+                TaskEvent::Finished(finished) => {
+                    result = finished;
+                    break;
+                }
+            }
+        }
+        let _ = handle.update(cx, |state, cx| {
+            state.clear_task_progress(target.id);
+            match result {
+                Ok(summary) => {
+                    let multi_rule = summary.rules.len() > 1;
+                    for log in &summary.would_apply {
+                        let message = if multi_rule {
+                            format!("Would {} ({})", log.action, log.rule)
+                        } else {
+                            format!("Would {}", log.action)
+                        };
+                        state.log_target_event(target.id, LogLevel::Info, message);
+                    }
+                    state.log_target_event(
+                        target.id,
+                        LogLevel::Info,
+                        format!(
+                            "Simulated sync for {}: {} would apply, {} conflicts skipped, {} deferred, {} refused, {} held for review",
+                            target.name,
+                            format_count(summary.would_apply.len()),
+                            summary.skipped,
+                            summary.deferred,
+                            summary.refused,
+                            summary.held_deletions.len()
+                        ),
+                    );
+                }
+                Err(err) => state.log_target_event(
+                    target.id,
+                    LogLevel::Error,
+                    format!("Simulation failed for {}: {err}", target.name),
+                ),
+            }
+            cx.notify();
+        });
+        Ok::<_, Error>(())
+    })
+    .detach();
 }
 
-fn schedule_plan_for_target(
+fn run_execute_jobs(
+    app: &mut App,
     state_handle: &Entity<AppState>,
     target: RemoteTarget,
-    log_message: Option<String>,
-    cx: &mut Context<AppView>,
+    jobs: Vec<SyncJob>,
+    settings: AppSettings,
 ) {
-    if state_handle
-        .read(cx)
-        .task_progress
-        .get(&target.id)
-        .map(|progress| matches!(progress.kind, TaskKind::Planning))
-        .unwrap_or(false)
-    {
-        return;
-    }
+    let exec_receiver = begin_execution(app, state_handle, &target, jobs, settings);
+    let handle = state_handle.clone();
+    app.spawn(async move |cx| {
+        drive_execution(&handle, target, exec_receiver, cx).await;
+        Ok::<_, Error>(())
+    })
+    .detach();
+}
 
-    let total_rules = target.rules.len().max(1);
-    let target_name = target.name.clone();
+/// Submits the jobs and starts showing their progress; `drive_execution`
+/// follows them to the end.
+fn begin_execution(
+    app: &mut App,
+    state_handle: &Entity<AppState>,
+    target: &RemoteTarget,
+    jobs: Vec<SyncJob>,
+    settings: AppSettings,
+) -> TaskReceiver<ExecutionSummary> {
+    let total_actions = jobs.iter().map(SyncJob::pending_actions).sum();
+    let exec_receiver = task_queue::submit_execute(target.clone(), jobs, settings);
     let target_id = target.id;
-
-    state_handle.update(cx, |state, cx| {
-        if let Some(message) = log_message.clone() {
-            state.log_event(LogLevel::Info, message);
+    state_handle.update(app, |state, _| {
+        // The executor drops the target's old journals when it starts.
+        state
+            .interrupted
+            .retain(|journal| journal.target_id != target_id);
+        state.set_task_progress(
+            target_id,
+            TaskProgress::new(TaskKind::Executing, 0, total_actions),
+        );
+        state.record_throughput(target_id, 0);
+    });
+    // Redraw every second while running so stalls show up in the graph.
+    let ticker_handle = state_handle.clone();
+    app.spawn(async move |cx| {
+        loop {
+            cx.background_executor().timer(Duration::from_secs(1)).await;
+            let running = ticker_handle
+                .update(cx, |state, cx| {
+                    let running = state.throughput.contains_key(&target_id);
+                    if running {
+                        cx.notify();
+                    }
+                    running
+                })
+                .unwrap_or(false);
+            if !running {
+                break;
+            }
         }
+        Ok::<_, Error>(())
+    })
+    .detach();
+    exec_receiver
+}
 
-        let mut touched = false;
-        for session in state
-            .sessions
-            .iter_mut()
-            .filter(|session| session.target_id == target_id)
-        {
-            session.status = SyncStatus::Planning;
-            session.last_run = Some(SystemTime::now());
-            touched = true;
+/// Logs a remote command's exit code and output.
+fn log_remote_command(
+    state: &mut AppState,
+    target: &RemoteTarget,
+    outcome: &Result<RemoteCommandOutcome>,
+) {
+    let outcome = match outcome {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            state.log_target_event(
+                target.id,
+                LogLevel::Error,
+                format!("Remote command on {} failed: {err:#}", target.name),
+            );
+            return;
         }
+    };
+    let level = if outcome.exit_code == 0 {
+        LogLevel::Info
+    } else {
+        LogLevel::Error
+    };
+    state.log_target_event(
+        target.id,
+        level,
+        format!(
+            "Remote command `{}` on {} exited with code {}",
+            outcome.command, target.name, outcome.exit_code
+        ),
+    );
+    if !outcome.stdout.is_empty() {
+        state.log_target_event(
+            target.id,
+            LogLevel::Info,
+            format!("Command output: {}", outcome.stdout),
+        );
+    }
+    if !outcome.stderr.is_empty() {
+        state.log_target_event(
+            target.id,
+            level,
+            format!("Command errors: {}", outcome.stderr),
+        );
+    }
+}
 
-        if !touched {
-            let id = state.next_session_id();
-            state.sessions.push(SyncSession {
-                id,
-                target_id,
-                status: SyncStatus::Planning,
-                last_run: Some(SystemTime::now()),
-                pending_actions: 0,
-            });
+/// Logs how the target's post-sync hook ended, with whatever it printed.
+fn log_hook_outcome(state: &mut AppState, target: &RemoteTarget, hook: &Result<HookOutcome>) {
+    let outcome = match hook {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            state.log_target_event(
+                target.id,
+                LogLevel::Error,
+                format!("Post-sync hook for {} failed: {err:#}", target.name),
+            );
+            return;
         }
-
-        state.set_task_progress(
-            target_id,
-            TaskProgress::new(TaskKind::Planning, 0, total_rules),
+    };
+    let (level, status) = match outcome.exit_code {
+        _ if outcome.timed_out => (LogLevel::Error, "timed out and was stopped".to_string()),
+        Some(0) => (LogLevel::Info, "finished".to_string()),
+        Some(code) => (LogLevel::Error, format!("exited with code {code}")),
+        None => (LogLevel::Error, "was killed".to_string()),
+    };
+    state.log_target_event(
+        target.id,
+        level,
+        format!("Post-sync hook for {} {status}", target.name),
+    );
+    if !outcome.stdout.is_empty() {
+        state.log_target_event(
+            target.id,
+            LogLevel::Info,
+            format!("Hook output: {}", outcome.stdout),
         );
-        cx.notify();
-    });
+    }
+    if !outcome.stderr.is_empty() {
+        state.log_target_event(
+            target.id,
+            LogLevel::Warn,
+            format!("Hook errors: {}", outcome.stderr),
+        );
+    }
+}
 
-    let receiver = task_queue::submit_plan(target.clone());
-    let handle = state_handle.clone();
-    {
-        let app: &mut App = cx;
-        app.spawn(async move |cx| {
-            while let Ok(event) = receiver.recv().await {
-                match event {
-                    TaskEvent::Progress { completed, total } => {
-                        let _ = handle.update(cx, |state, cx| {
-                            state.set_task_progress(
-                                target_id,
-                                TaskProgress::new(TaskKind::Planning, completed, total),
+/// Logs the outcome of an execution, updates its sessions and re-plans the
+/// target. Returns the summary unless the run failed outright.
+async fn drive_execution(
+    handle: &Entity<AppState>,
+    target_snapshot: RemoteTarget,
+    mut exec_receiver: TaskReceiver<ExecutionSummary>,
+    cx: &mut AsyncApp,
+) -> Option<ExecutionSummary> {
+    loop {
+        match exec_receiver.recv().await {
+            Some(TaskEvent::Progress {
+                completed,
+                total,
+                bytes,
+                connecting,
+                ..
+            }) => {
+                let _ = handle.update(cx, |state, cx| {
+                    state.set_task_progress(
+                        target_snapshot.id,
+                        TaskProgress::new(TaskKind::Executing, completed, total)
+                            .with_connecting(connecting),
+                    );
+                    state.record_throughput(target_snapshot.id, bytes);
+                    cx.notify();
+                });
+                continue;
+            }
+            Some(TaskEvent::Finished(Ok(summary))) => {
+                let clean_run =
+                    summary.failures.is_empty() && summary.deferred == 0 && summary.refused == 0;
+                let _ = handle.update(cx, |state, cx| {
+                    state.record_history(SessionRecord::from_summary(&target_snapshot, &summary));
+                    state.record_rule_statuses(target_snapshot.id, &summary.rules);
+                    persist_state(state);
+                    // Each run's held deletions replace the previous run's.
+                    if summary.held_deletions.is_empty() {
+                        state.pending_deletions.remove(&target_snapshot.id);
+                    } else {
+                        state.log_target_event(
+                            target_snapshot.id,
+                            LogLevel::Warn,
+                            format!(
+                                "Held {} deletions for review on {}",
+                                summary.held_deletions.len(),
+                                target_snapshot.name
+                            ),
+                        );
+                        state
+                            .pending_deletions
+                            .insert(target_snapshot.id, summary.held_deletions.clone());
+                    }
+                    if summary.deferred > 0 {
+                        state.log_target_event(
+                            target_snapshot.id,
+                            LogLevel::Warn,
+                            format!(
+                                "Deferred {} uploads for {} (files still being written or in use)",
+                                summary.deferred, target_snapshot.name
+                            ),
+                        );
+                    }
+                    if summary.refused > 0 {
+                        state.log_target_event(
+                            target_snapshot.id,
+                            LogLevel::Warn,
+                            format!(
+                                "Refused {} uploads and remote deletions for {}: the remote is read-only",
+                                summary.refused, target_snapshot.name
+                            ),
+                        );
+                    }
+                    if let Some(first) = summary.ownership_warnings.first() {
+                        state.log_target_event(
+                            target_snapshot.id,
+                            LogLevel::Warn,
+                            format!(
+                                "Could not keep the owner or attributes of {} uploads for {}; first: {first}",
+                                summary.ownership_warnings.len(),
+                                target_snapshot.name
+                            ),
+                        );
+                    }
+                    // Single-rule targets keep the one-line summary.
+                    let multi_rule = summary.rules.len() > 1;
+                    if multi_rule {
+                        for rule in &summary.rules {
+                            let level = if rule.failed > 0 {
+                                LogLevel::Error
+                            } else {
+                                LogLevel::Info
+                            };
+                            state.log_target_event(
+                                target_snapshot.id,
+                                level,
+                                format!(
+                                    "[{}] {}: {} applied, {} conflicts, {} failed",
+                                    target_snapshot.name,
+                                    rule.rule,
+                                    rule.applied,
+                                    rule.skipped,
+                                    rule.failed
+                                ),
                             );
-                            cx.notify();
-                        });
+                        }
                     }
-                    TaskEvent::Finished(result) => {
-                        let _ = handle.update(cx, |state, cx| {
-                            state.clear_task_progress(target_id);
-                            cx.notify();
-                        });
-                        match result {
-                            Ok(plan) => {
-                                let _ = handle.update(cx, |state, cx| {
-                                    state.apply_planned_jobs(target_id, plan);
-                                    let pending: usize = state
-                                        .jobs
-                                        .iter()
-                                        .filter(|job| job.target_id == target_id)
-                                        .map(|job| job.pending_actions())
-                                        .sum();
-                                    state.log_event(
-                                        LogLevel::Info,
-                                        format!(
-                                            "Sync plan ready for {} ({} actions)",
-                                            target_name, pending
-                                        ),
-                                    );
-                                    cx.notify();
-                                });
-                            }
-                            Err(err) => {
-                                let _ = handle.update(cx, |state, cx| {
-                                    state.log_event(
-                                        LogLevel::Error,
-                                        format!(
-                                            "Failed to prepare sync plan for {}: {err}",
-                                            target_name
-                                        ),
-                                    );
-                                    cx.notify();
-                                });
-                            }
+                    if summary.failures.is_empty() {
+                        state.log_target_event(
+                            target_snapshot.id,
+                            LogLevel::Info,
+                            format!(
+                                "Sync completed for {} ({} actions, {} conflicts)",
+                                target_snapshot.name,
+                                format_count(summary.applied),
+                                format_count(summary.skipped)
+                            ),
+                        );
+                        for session in state
+                            .sessions
+                            .iter_mut()
+                            .filter(|session| session.target_id == target_snapshot.id)
+                        {
+                            session.status = SyncStatus::Completed;
+                            session.last_run = Some(SystemTime::now());
+                        }
+                    } else {
+                        let failure_count = summary.failures.len();
+                        let first_error = summary
+                            .failures
+                            .first()
+                            .map(|failure| {
+                                if multi_rule {
+                                    format!("{}: {}", failure.rule, failure.reason)
+                                } else {
+                                    failure.reason.clone()
+                                }
+                            })
+                            .unwrap_or_else(|| "Unknown failure".into());
+                        state.log_target_event(
+                            target_snapshot.id,
+                            LogLevel::Error,
+                            format!(
+                                "Sync finished with {failure_count} failures for {}",
+                                target_snapshot.name
+                            ),
+                        );
+                        for group in summary.failure_groups() {
+                            let message = if group.count == 1 {
+                                format!("{}: {}", group.first.rule, group.first.reason)
+                            } else {
+                                format!("{} files failed: {}", group.count, group.cause)
+                            };
+                            state.log_target_event(target_snapshot.id, LogLevel::Error, message);
+                        }
+                        for session in state
+                            .sessions
+                            .iter_mut()
+                            .filter(|session| session.target_id == target_snapshot.id)
+                        {
+                            session.status = SyncStatus::Failed {
+                                reason: first_error.clone(),
+                            };
+                            session.last_run = Some(SystemTime::now());
                         }
-                        break;
+                    }
+                    for outcome in &summary.remote_commands {
+                        log_remote_command(state, &target_snapshot, outcome);
+                    }
+                    if let Some(reason) = summary.remote_command_failure() {
+                        for session in state
+                            .sessions
+                            .iter_mut()
+                            .filter(|session| session.target_id == target_snapshot.id)
+                        {
+                            session.status = SyncStatus::Failed {
+                                reason: reason.clone(),
+                            };
+                        }
+                    }
+                    if let Some(hook) = &summary.hook {
+                        log_hook_outcome(state, &target_snapshot, hook);
+                    }
+                    cx.notify();
+                });
+
+                let mut follow_receiver = task_queue::submit_plan(target_snapshot.clone());
+                loop {
+                    match follow_receiver.recv().await {
+                        Some(TaskEvent::Progress {
+                            completed,
+                            total,
+                            discovered,
+                            connecting,
+                            ..
+                        }) => {
+                            let _ = handle.update(cx, |state, cx| {
+                                state.set_task_progress(
+                                    target_snapshot.id,
+                                    TaskProgress::new(TaskKind::Planning, completed, total)
+                                        .with_discovered(discovered)
+                                        .with_connecting(connecting),
+                                );
+                                cx.notify();
+                            });
+                            continue;
+                        }
+                        Some(TaskEvent::Finished(Ok(plan))) => {
+                            let _ = handle.update(cx, |state, cx| {
+                                state.apply_planned_jobs(target_snapshot.id, plan);
+                                state.clear_task_progress(target_snapshot.id);
+                                if clean_run {
+                                    record_sync_snapshot(state, &target_snapshot);
+                                }
+                                cx.notify();
+                            });
+                            break;
+                        }
+                        Some(TaskEvent::Finished(Err(err))) => {
+                            let _ = handle.update(cx, |state, cx| {
+                                state.clear_task_progress(target_snapshot.id);
+                                state.log_target_event(
+                                    target_snapshot.id,
+                                    LogLevel::Warn,
+                                    format!(
+                                        "Failed to refresh plan after sync for {}: {err}",
+                                        target_snapshot.name
+                                    ),
+                                );
+                                cx.notify();
+                            });
+                            break;
+                        }
+                        None => break,
                     }
                 }
+                return Some(summary);
             }
-            Ok::<_, Error>(())
-        })
-        .detach();
-}
-
+            Some(TaskEvent::Finished(Err(err))) => {
+                let message = err.to_string();
+                let _ = handle.update(cx, |state, cx| {
+                    state.clear_task_progress(target_snapshot.id);
+                    state.record_history(SessionRecord::aborted(&target_snapshot, message.clone()));
+                    state.log_target_event(
+                        target_snapshot.id,
+                        LogLevel::Error,
+                        format!("Sync failed for {}: {}", target_snapshot.name, message),
+                    );
+                    for session in state
+                        .sessions
+                        .iter_mut()
+                        .filter(|session| session.target_id == target_snapshot.id)
+                    {
+                        session.status = SyncStatus::Failed {
+                            reason: message.clone(),
+                        };
+                        session.last_run = Some(SystemTime::now());
+                    }
+                    cx.notify();
+                });
+                break;
+            }
+            None => break,
+        }
+    }
+    None
 }
 
-fn destructive_counts(jobs: &[SyncJob]) -> (usize, usize) {
-    let mut delete_local = 0;
-    let mut delete_remote = 0;
-    for job in jobs {
-        for action in &job.plan.actions {
-            match action {
-                SyncAction::DeleteLocal { .. } => delete_local += 1,
-                SyncAction::DeleteRemote { .. } => delete_remote += 1,
-                _ => {}
-            }
+/// Stores the post-sync local index as the baseline for drift previews.
+fn record_sync_snapshot(state: &mut AppState, target: &RemoteTarget) {
+    let snapshot =
+        TargetSnapshot::from_jobs(state.jobs.iter().filter(|job| job.target_id == target.id));
+    match config::save_snapshot(target.id, &snapshot) {
+        Ok(()) => {
+            state.drift.insert(target.id, DriftSummary::default());
         }
+        Err(err) => state.log_target_event(
+            target.id,
+            LogLevel::Warn,
+            format!("Failed to save sync snapshot for {}: {err:#}", target.name),
+        ),
     }
-    (delete_local, delete_remote)
 }
 
-fn run_execute_jobs(
-    app: &mut App,
+/// How often a reattached task's progress is read back from the task queue.
+const REATTACH_POLL: Duration = Duration::from_millis(500);
+
+/// Shows tasks that were already running when this state was built, e.g. a
+/// sync started before the window was closed and reopened. There is no
+/// receiver for them, so progress is polled, and each target is re-planned
+/// once its task ends.
+fn reattach_running_tasks(
     state_handle: &Entity<AppState>,
-    target: RemoteTarget,
-    jobs: Vec<SyncJob>,
-    settings: AppSettings,
+    targets: &[RemoteTarget],
+    running: HashMap<TargetId, ActiveTask>,
+    cx: &mut App,
 ) {
-    let exec_receiver = task_queue::submit_execute(target.clone(), jobs, settings.clone());
-    let handle = state_handle.clone();
-    app.spawn({
-        let target_snapshot = target.clone();
-        async move |cx| {
-            loop {
-                match exec_receiver.recv().await {
-                    Ok(TaskEvent::Progress { completed, total }) => {
-                        let _ = handle.update(cx, |state, cx| {
-                            state.set_task_progress(
-                                target_snapshot.id,
-                                TaskProgress::new(TaskKind::Executing, completed, total),
-                            );
-                            cx.notify();
-                        });
-                        continue;
-                    }
-                    Ok(TaskEvent::Finished(Ok(summary))) => {
-                        let _ = handle.update(cx, |state, cx| {
-                            if summary.failures.is_empty() {
-                                state.log_event(
-                                    LogLevel::Info,
-                                    format!(
-                                        "Sync completed for {} ({} actions, {} conflicts)",
-                                        target_snapshot.name, summary.applied, summary.skipped
-                                    ),
-                                );
-                                for session in state
-                                    .sessions
-                                    .iter_mut()
-                                    .filter(|session| session.target_id == target_snapshot.id)
-                                {
-                                    session.status = SyncStatus::Completed;
-                                    session.last_run = Some(SystemTime::now());
-                                }
-                            } else {
-                                let failure_count = summary.failures.len();
-                                let first_error = summary
-                                    .failures
-                                    .first()
-                                    .map(|(_, reason)| reason.clone())
-                                    .unwrap_or_else(|| "Unknown failure".into());
-                                state.log_event(
-                                    LogLevel::Error,
-                                    format!(
-                                        "Sync finished with {failure_count} failures for {}: {first_error}",
-                                        target_snapshot.name
-                                    ),
-                                );
-                                for session in state
-                                    .sessions
-                                    .iter_mut()
-                                    .filter(|session| session.target_id == target_snapshot.id)
-                                {
-                                    session.status =
-                                        SyncStatus::Failed { reason: first_error.clone() };
-                                    session.last_run = Some(SystemTime::now());
-                                }
-                            }
-                            cx.notify();
-                        });
+    let mut waiting: Vec<RemoteTarget> = targets
+        .iter()
+        .filter(|target| running.contains_key(&target.id))
+        .cloned()
+        .collect();
+    if waiting.is_empty() {
+        return;
+    }
+    state_handle.update(cx, |state, cx| {
+        for target in &waiting {
+            let task = running[&target.id];
+            let message = match task.kind {
+                TaskKind::Planning => {
+                    format!("A plan for {} is already being prepared", target.name)
+                }
+                TaskKind::Executing => format!("A sync of {} is still running", target.name),
+            };
+            state.log_target_event(target.id, LogLevel::Info, message);
+            state.set_task_progress(target.id, active_task_progress(task));
+        }
+        cx.notify();
+    });
 
-                        let follow_receiver =
-                            task_queue::submit_plan(target_snapshot.clone());
-                        loop {
-                            match follow_receiver.recv().await {
-                                Ok(TaskEvent::Progress { completed, total }) => {
-                                    let _ = handle.update(cx, |state, cx| {
-                                        state.set_task_progress(
-                                            target_snapshot.id,
-                                            TaskProgress::new(
-                                                TaskKind::Planning,
-                                                completed,
-                                                total,
-                                            ),
-                                        );
-                                        cx.notify();
-                                    });
-                                    continue;
-                                }
-                                Ok(TaskEvent::Finished(Ok(plan))) => {
-                                    let _ = handle.update(cx, |state, cx| {
-                                        state.apply_planned_jobs(target_snapshot.id, plan);
-                                        state.clear_task_progress(target_snapshot.id);
-                                        cx.notify();
-                                    });
-                                    break;
-                                }
-                                Ok(TaskEvent::Finished(Err(err))) => {
-                                    let _ = handle.update(cx, |state, cx| {
-                                        state.clear_task_progress(target_snapshot.id);
-                                        state.log_event(
-                                            LogLevel::Warn,
-                                            format!(
-                                                "Failed to refresh plan after sync for {}: {err}",
-                                                target_snapshot.name
-                                            ),
-                                        );
-                                        cx.notify();
-                                    });
-                                    break;
-                                }
-                                Err(recv_err) => {
-                                    let _ = handle.update(cx, |state, cx| {
-                                        state.clear_task_progress(target_snapshot.id);
-                                        state.log_event(
-                                            LogLevel::Warn,
-                                            format!(
-                                                "Failed to refresh plan after sync for {}: {recv_err}",
-                                                target_snapshot.name
-                                            ),
-                                        );
-                                        cx.notify();
-                                    });
-                                    break;
-                                }
-                            }
-                        }
-                        break;
-                    }
-                    Ok(TaskEvent::Finished(Err(err))) => {
-                        let message = err.to_string();
-                        let _ = handle.update(cx, |state, cx| {
-                            state.log_event(
-                                LogLevel::Error,
-                                format!("Sync failed for {}: {}", target_snapshot.name, message),
-                            );
-                            for session in state
-                                .sessions
-                                .iter_mut()
-                                .filter(|session| session.target_id == target_snapshot.id)
-                            {
-                                session.status =
-                                    SyncStatus::Failed { reason: message.clone() };
-                                session.last_run = Some(SystemTime::now());
-                            }
-                            cx.notify();
-                        });
-                        break;
-                    }
-                    Err(recv_err) => {
-                        let message = format!("task cancelled: {recv_err}");
-                        let _ = handle.update(cx, |state, cx| {
-                            state.log_event(
-                                LogLevel::Error,
-                                format!("Sync failed for {}: {}", target_snapshot.name, message),
-                            );
-                            for session in state
-                                .sessions
-                                .iter_mut()
-                                .filter(|session| session.target_id == target_snapshot.id)
-                            {
-                                session.status =
-                                    SyncStatus::Failed { reason: message.clone() };
-                                session.last_run = Some(SystemTime::now());
-                            }
-                            cx.notify();
-                        });
-                        break;
-                    }
+    let handle = state_handle.clone();
+    cx.spawn(async move |cx| {
+        while !waiting.is_empty() {
+            cx.background_executor().timer(REATTACH_POLL).await;
+            let running = task_queue::active_tasks();
+            let (still_running, finished): (Vec<_>, Vec<_>) = waiting
+                .into_iter()
+                .partition(|target| running.contains_key(&target.id));
+            let _ = handle.update(cx, |state, cx| {
+                for target in &still_running {
+                    state.set_task_progress(target.id, active_task_progress(running[&target.id]));
+                }
+                for target in &finished {
+                    state.clear_task_progress(target.id);
                 }
+                cx.notify();
+            });
+            for target in finished {
+                let message = format!("Refreshing sync plan for {}", target.name);
+                AppView::schedule_plan_for_target_async(&handle, target, Some(message), cx);
             }
-
-            Ok::<_, Error>(())
+            waiting = still_running;
         }
     })
     .detach();
 }
 
-fn run_connection_test(
+fn active_task_progress(task: ActiveTask) -> TaskProgress {
+    TaskProgress::new(task.kind, task.completed, task.total).with_connecting(task.connecting)
+}
+
+const AUTO_CONNECT_ATTEMPTS: u32 = 3;
+const AUTO_CONNECT_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Tests the connection off the UI thread, retrying with a growing delay.
+/// Retried runs are logged so the startup outcome shows up in the activity list.
+fn run_connection_test_with_retries(
     state_handle: &Entity<AppState>,
     target: RemoteTarget,
     language: Language,
-    cx: &mut Context<AppView>,
+    attempts: u32,
+    cx: &mut App,
 ) {
     let target_id = target.id;
     state_handle.update(cx, |state, cx| {
@@ -2631,14 +9656,53 @@ fn run_connection_test(
     {
         let app: &mut App = cx;
         app.spawn(async move |cx| {
-            let result = connection::test_connection(&target);
-            let status = match result {
-                Ok(_) => ConnectionTestState::Success(
-                    tr(language, "Connection OK", "连接成功", "連線成功").into(),
-                ),
-                Err(err) => ConnectionTestState::Failure(err.to_string()),
+            let mut attempt = 1;
+            let result = loop {
+                let probe = target.clone();
+                let result = cx
+                    .background_spawn(async move { connection::test_connection(&probe) })
+                    .await;
+                if result.is_ok() || attempt >= attempts {
+                    break result;
+                }
+                cx.background_executor()
+                    .timer(AUTO_CONNECT_RETRY_DELAY * attempt)
+                    .await;
+                attempt += 1;
             };
             let _ = handle.update(cx, |state, cx| {
+                let status = match result {
+                    Ok(check) => {
+                        if attempts > 1 {
+                            state.log_target_event(
+                                target.id,
+                                LogLevel::Info,
+                                format!("Auto-connected to {} using {}", target.name, check.method),
+                            );
+                        }
+                        if let Some(skew) = check.clock_skew {
+                            state.record_clock_skew(target.id, skew);
+                        }
+                        ConnectionTestState::Success(format!(
+                            "{} ({})",
+                            tr(language, "Connection OK", "连接成功", "連線成功"),
+                            check.method
+                        ))
+                    }
+                    Err(err) => {
+                        if attempts > 1 {
+                            state.log_target_event(
+                                target.id,
+                                LogLevel::Error,
+                                format!(
+                                    "Auto-connect to {} failed after {attempt} attempts: {err:#}",
+                                    target.name
+                                ),
+                            );
+                        }
+                        ConnectionTestState::Failure(state.redact(&format!("{err:#}")))
+                    }
+                };
                 state.connection_tests.insert(target_id, status);
                 cx.notify();
             });
@@ -2652,6 +9716,24 @@ struct RuleInputs {
     local: Entity<InputState>,
     remote: Entity<InputState>,
     direction: SyncDirection,
+    include_hidden: bool,
+    follow_symlinks: bool,
+    max_depth: usize,
+    max_entries: usize,
+    flatten: bool,
+    hard_links: bool,
+    preserve_ownership: bool,
+    remote_read_only: bool,
+    overwrite_policy: OverwritePolicy,
+    compare_policy: ComparePolicy,
+    collision_policy: CollisionPolicy,
+    case_insensitive: bool,
+    conflict_policy: ConflictPolicy,
+    include: Entity<InputState>,
+    ignore: Entity<InputState>,
+    skip_empty_files: bool,
+    skip_names: Entity<InputState>,
+    line_endings: LineEndings,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -2660,16 +9742,47 @@ enum AuthChoice {
     SshKey,
 }
 
+impl AuthChoice {
+    fn other(self) -> Self {
+        match self {
+            AuthChoice::Password => AuthChoice::SshKey,
+            AuthChoice::SshKey => AuthChoice::Password,
+        }
+    }
+}
+
 struct TargetFormView {
     name: Entity<InputState>,
     host: Entity<InputState>,
     username: Entity<InputState>,
     base_path: Entity<InputState>,
+    local_base: Entity<InputState>,
     password: Entity<InputState>,
     private_key: Entity<InputState>,
     passphrase: Entity<InputState>,
     auth_choice: AuthChoice,
+    /// Also configure the other auth kind, tried when the primary is rejected.
+    use_fallback_auth: bool,
+    remember_credentials: bool,
+    use_ssh_config: bool,
+    correct_clock_skew: bool,
+    stat_fallback: bool,
+    remote_read_only: bool,
+    monitor_health: bool,
     rules: Vec<RuleInputs>,
+    host_key_algorithms: Entity<InputState>,
+    kex_algorithms: Entity<InputState>,
+    allow_legacy_algorithms: bool,
+    max_connections: usize,
+    socket_buffer_kib: u32,
+    source_address: Entity<InputState>,
+    color: TargetColor,
+    tags: Entity<InputState>,
+    post_sync_command: Entity<InputState>,
+    post_sync_enabled: bool,
+    post_sync_on_failure: bool,
+    remote_commands: Vec<Entity<InputState>>,
+    remote_commands_enabled: bool,
     loaded_from: Option<TargetId>,
 }
 
@@ -2680,11 +9793,32 @@ impl TargetFormView {
             host: Self::spawn_input(window, cx, "prod.example.com:22", false),
             username: Self::spawn_input(window, cx, "deploy", false),
             base_path: Self::spawn_input(window, cx, "/srv/www", false),
+            local_base: Self::spawn_input(window, cx, "~/projects", false),
             password: Self::spawn_input(window, cx, "••••••", true),
             private_key: Self::spawn_input(window, cx, "~/.ssh/id_ed25519", false),
             passphrase: Self::spawn_input(window, cx, "••••••", true),
             auth_choice: AuthChoice::Password,
+            use_fallback_auth: false,
+            remember_credentials: true,
+            use_ssh_config: false,
+            correct_clock_skew: false,
+            stat_fallback: true,
+            remote_read_only: false,
+            monitor_health: false,
             rules: Vec::new(),
+            host_key_algorithms: Self::spawn_input(window, cx, "ssh-ed25519,rsa-sha2-512", false),
+            kex_algorithms: Self::spawn_input(window, cx, "curve25519-sha256", false),
+            allow_legacy_algorithms: false,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            socket_buffer_kib: 0,
+            source_address: Self::spawn_input(window, cx, "192.168.1.20", false),
+            color: TargetColor::None,
+            tags: Self::spawn_input(window, cx, "prod, web", false),
+            post_sync_command: Self::spawn_input(window, cx, "notify-send \"Sync done\"", false),
+            post_sync_enabled: false,
+            post_sync_on_failure: false,
+            remote_commands: Vec::new(),
+            remote_commands_enabled: false,
             loaded_from: None,
         };
         view.add_rule(window, cx, "./apps/web", "/web", SyncDirection::Push);
@@ -2701,13 +9835,82 @@ impl TargetFormView {
     ) {
         let local = Self::spawn_input(window, cx, local_placeholder, false);
         let remote = Self::spawn_input(window, cx, remote_placeholder, false);
+        let include = Self::spawn_input(window, cx, "*.rs, *.toml", false);
+        let ignore = Self::spawn_input(window, cx, "target, node_modules", false);
+        let skip_names = Self::spawn_input(window, cx, ".DS_Store, Thumbs.db", false);
+        self.set_value(&skip_names, &DEFAULT_SKIP_NAMES.join(", "), window, cx);
         self.rules.push(RuleInputs {
             local,
             remote,
             direction,
+            include_hidden: true,
+            follow_symlinks: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            flatten: false,
+            hard_links: false,
+            preserve_ownership: false,
+            remote_read_only: false,
+            overwrite_policy: OverwritePolicy::default(),
+            compare_policy: ComparePolicy::default(),
+            collision_policy: CollisionPolicy::default(),
+            case_insensitive: false,
+            conflict_policy: ConflictPolicy::default(),
+            include,
+            ignore,
+            skip_empty_files: false,
+            skip_names,
+            line_endings: LineEndings::default(),
         });
     }
 
+    /// Uses folders dropped onto the form as local rule paths, filling empty
+    /// rows before adding new ones, and names the target after the first
+    /// folder when the name is blank. Returns how many paths were not folders.
+    fn add_dropped_folders(
+        &mut self,
+        paths: &[PathBuf],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> usize {
+        let mut rejected = 0;
+        for path in paths {
+            if !path.is_dir() {
+                rejected += 1;
+                continue;
+            }
+            let empty_row = self
+                .rules
+                .iter()
+                .find(|rule| self.read(&rule.local, cx).trim().is_empty())
+                .map(|rule| rule.local.clone());
+            let local = match empty_row {
+                Some(local) => local,
+                None => {
+                    self.add_rule(window, cx, "./apps/web", "/web", SyncDirection::Push);
+                    let Some(rule) = self.rules.last() else {
+                        continue;
+                    };
+                    rule.local.clone()
+                }
+            };
+            self.set_value(&local, &path.to_string_lossy(), window, cx);
+            if self.read(&self.name, cx).trim().is_empty()
+                && let Some(folder) = path.file_name()
+            {
+                self.set_value(&self.name, &folder.to_string_lossy(), window, cx);
+            }
+        }
+        cx.notify();
+        rejected
+    }
+
+    fn add_remote_command(&mut self, window: &mut Window, cx: &mut Context<Self>, value: &str) {
+        let input = Self::spawn_input(window, cx, "sudo systemctl reload nginx", false);
+        self.set_value(&input, value, window, cx);
+        self.remote_commands.push(input);
+    }
+
     fn spawn_input(
         window: &mut Window,
         cx: &mut Context<Self>,
@@ -2756,12 +9959,33 @@ impl TargetFormView {
         self.set_value(&self.host, "", window, cx);
         self.set_value(&self.username, "", window, cx);
         self.set_value(&self.base_path, "", window, cx);
+        self.set_value(&self.local_base, "", window, cx);
         self.set_value(&self.password, "", window, cx);
         self.set_value(&self.private_key, "", window, cx);
         self.set_value(&self.passphrase, "", window, cx);
         self.auth_choice = AuthChoice::Password;
+        self.use_fallback_auth = false;
+        self.remember_credentials = true;
+        self.use_ssh_config = false;
+        self.correct_clock_skew = false;
+        self.stat_fallback = true;
+        self.remote_read_only = false;
+        self.monitor_health = false;
         self.rules.clear();
         self.add_rule(window, cx, "./apps/web", "/web", SyncDirection::Push);
+        self.set_value(&self.host_key_algorithms, "", window, cx);
+        self.set_value(&self.kex_algorithms, "", window, cx);
+        self.allow_legacy_algorithms = false;
+        self.max_connections = DEFAULT_MAX_CONNECTIONS;
+        self.socket_buffer_kib = 0;
+        self.set_value(&self.source_address, "", window, cx);
+        self.color = TargetColor::None;
+        self.set_value(&self.tags, "", window, cx);
+        self.set_value(&self.post_sync_command, "", window, cx);
+        self.post_sync_enabled = false;
+        self.post_sync_on_failure = false;
+        self.remote_commands.clear();
+        self.remote_commands_enabled = false;
         self.loaded_from = None;
     }
 
@@ -2775,6 +9999,12 @@ impl TargetFormView {
             window,
             cx,
         );
+        self.set_value(
+            &self.local_base,
+            target.local_base.to_str().unwrap_or_default(),
+            window,
+            cx,
+        );
 
         self.rules.clear();
         for rule in &target.rules {
@@ -2785,39 +10015,114 @@ impl TargetFormView {
                 rule.remote.to_str().unwrap_or_default(),
                 rule.direction,
             );
-        }
-        if self.rules.is_empty() {
-            self.add_rule(window, cx, "./apps/web", "/web", SyncDirection::Push);
-        }
-
-        match &target.auth {
-            AuthMethod::Password { secret, .. } => {
-                self.auth_choice = AuthChoice::Password;
-                self.set_value(&self.password, secret, window, cx);
-                self.set_value(&self.private_key, "", window, cx);
-                self.set_value(&self.passphrase, "", window, cx);
+            if let Some(inputs) = self.rules.last_mut() {
+                inputs.include_hidden = rule.include_hidden;
+                inputs.follow_symlinks = rule.follow_symlinks;
+                inputs.max_depth = rule.max_depth;
+                inputs.max_entries = rule.max_entries;
+                inputs.flatten = rule.flatten;
+                inputs.hard_links = rule.hard_links;
+                inputs.preserve_ownership = rule.preserve_ownership;
+                inputs.remote_read_only = rule.remote_read_only;
+                inputs.overwrite_policy = rule.overwrite_policy;
+                inputs.compare_policy = rule.compare_policy;
+                inputs.collision_policy = rule.collision_policy;
+                inputs.case_insensitive = rule.case_insensitive;
+                inputs.conflict_policy = rule.conflict_policy;
+                inputs.skip_empty_files = rule.skip_empty_files;
+                inputs.line_endings = rule.line_endings;
             }
-            AuthMethod::SshKey {
-                private_key,
-                passphrase,
-                ..
-            } => {
-                self.auth_choice = AuthChoice::SshKey;
+            if let Some(inputs) = self.rules.last().cloned() {
                 self.set_value(
-                    &self.private_key,
-                    private_key.to_str().unwrap_or_default(),
+                    &inputs.local,
+                    rule.local.to_str().unwrap_or_default(),
                     window,
                     cx,
                 );
                 self.set_value(
-                    &self.passphrase,
-                    passphrase.as_deref().unwrap_or_default(),
+                    &inputs.remote,
+                    rule.remote.to_str().unwrap_or_default(),
                     window,
                     cx,
                 );
-                self.set_value(&self.password, "", window, cx);
+                self.set_value(&inputs.include, &rule.include.join(", "), window, cx);
+                self.set_value(&inputs.ignore, &rule.ignore.join(", "), window, cx);
+                self.set_value(&inputs.skip_names, &rule.skip_names.join(", "), window, cx);
+            }
+        }
+        if self.rules.is_empty() {
+            self.add_rule(window, cx, "./apps/web", "/web", SyncDirection::Push);
+        }
+
+        self.set_value(&self.password, "", window, cx);
+        self.set_value(&self.private_key, "", window, cx);
+        self.set_value(&self.passphrase, "", window, cx);
+        self.auth_choice = match target.auth {
+            AuthMethod::Password { .. } => AuthChoice::Password,
+            AuthMethod::SshKey { .. } => AuthChoice::SshKey,
+        };
+        self.use_fallback_auth = target.fallback_auth.is_some();
+        self.remember_credentials = target.remember_credentials;
+        self.use_ssh_config = target.use_ssh_config;
+        self.correct_clock_skew = target.correct_clock_skew;
+        self.stat_fallback = target.stat_fallback;
+        self.remote_read_only = target.remote_read_only;
+        self.monitor_health = target.monitor_health;
+        for auth in target.auth_methods() {
+            match auth {
+                AuthMethod::Password { secret, .. } => {
+                    self.set_value(&self.password, secret, window, cx);
+                }
+                AuthMethod::SshKey {
+                    private_key,
+                    passphrase,
+                    ..
+                } => {
+                    self.set_value(
+                        &self.private_key,
+                        private_key.to_str().unwrap_or_default(),
+                        window,
+                        cx,
+                    );
+                    self.set_value(
+                        &self.passphrase,
+                        passphrase.as_deref().unwrap_or_default(),
+                        window,
+                        cx,
+                    );
+                }
             }
         }
+        self.set_value(
+            &self.host_key_algorithms,
+            &target.algorithms.host_key,
+            window,
+            cx,
+        );
+        self.set_value(&self.kex_algorithms, &target.algorithms.kex, window, cx);
+        self.allow_legacy_algorithms = target.algorithms.allow_legacy;
+        self.max_connections = target.max_connections;
+        self.socket_buffer_kib = target.socket_buffer_kib;
+        let source_address = target
+            .source_address
+            .map(|address| address.to_string())
+            .unwrap_or_default();
+        self.set_value(&self.source_address, &source_address, window, cx);
+        self.color = target.color;
+        self.set_value(&self.tags, &target.tags.join(", "), window, cx);
+        self.set_value(
+            &self.post_sync_command,
+            &target.post_sync_hook.command,
+            window,
+            cx,
+        );
+        self.post_sync_enabled = target.post_sync_hook.enabled;
+        self.post_sync_on_failure = target.post_sync_hook.run_on_failure;
+        self.remote_commands.clear();
+        for command in &target.remote_commands.commands {
+            self.add_remote_command(window, cx, command);
+        }
+        self.remote_commands_enabled = target.remote_commands.enabled;
         self.loaded_from = Some(target.id);
     }
 
@@ -2829,12 +10134,23 @@ impl TargetFormView {
         cx: &mut Context<Self>,
     ) {
         let text = value.to_string();
-        let _ = input.update(cx, |state, cx| {
+        input.update(cx, |state, cx| {
             state.set_value(text.clone(), window, cx);
         });
     }
 
     fn build_target(&self, next_id: TargetId, cx: &mut Context<Self>) -> Option<RemoteTarget> {
+        self.draft(cx).into_remote_target(next_id)
+    }
+
+    /// The target as far as needed to open a session, for browsing the
+    /// remote side before the rules are complete.
+    fn connection_target(&self, cx: &mut Context<Self>) -> Option<RemoteTarget> {
+        self.draft(cx)
+            .into_connection_target(self.loaded_from.unwrap_or_default())
+    }
+
+    fn draft(&self, cx: &mut Context<Self>) -> TargetDraft {
         let rules = self
             .rules
             .iter()
@@ -2842,21 +10158,70 @@ impl TargetFormView {
                 local: self.read(&inputs.local, cx),
                 remote: self.read(&inputs.remote, cx),
                 direction: inputs.direction,
+                include_hidden: inputs.include_hidden,
+                follow_symlinks: inputs.follow_symlinks,
+                max_depth: inputs.max_depth,
+                max_entries: inputs.max_entries,
+                flatten: inputs.flatten,
+                hard_links: inputs.hard_links,
+                preserve_ownership: inputs.preserve_ownership,
+                remote_read_only: inputs.remote_read_only,
+                overwrite_policy: inputs.overwrite_policy,
+                compare_policy: inputs.compare_policy,
+                collision_policy: inputs.collision_policy,
+                case_insensitive: inputs.case_insensitive,
+                conflict_policy: inputs.conflict_policy,
+                include: parse_patterns(&self.read(&inputs.include, cx)),
+                ignore: parse_patterns(&self.read(&inputs.ignore, cx)),
+                skip_empty_files: inputs.skip_empty_files,
+                skip_names: parse_patterns(&self.read(&inputs.skip_names, cx)),
+                line_endings: inputs.line_endings,
             })
             .collect();
 
-        let draft = TargetDraft {
+        TargetDraft {
             name: self.read(&self.name, cx),
             host: self.read(&self.host, cx),
             username: self.read(&self.username, cx),
             base_path: self.read(&self.base_path, cx),
+            local_base: self.read(&self.local_base, cx),
             password: self.read(&self.password, cx),
             private_key: self.read(&self.private_key, cx),
             passphrase: self.read(&self.passphrase, cx),
             auth_choice: self.auth_choice,
+            use_fallback_auth: self.use_fallback_auth,
+            remember_credentials: self.remember_credentials,
+            use_ssh_config: self.use_ssh_config,
+            correct_clock_skew: self.correct_clock_skew,
+            stat_fallback: self.stat_fallback,
+            remote_read_only: self.remote_read_only,
+            monitor_health: self.monitor_health,
             rules,
-        };
-        draft.into_remote_target(next_id)
+            algorithms: SshAlgorithms {
+                host_key: self.read(&self.host_key_algorithms, cx).trim().to_string(),
+                kex: self.read(&self.kex_algorithms, cx).trim().to_string(),
+                allow_legacy: self.allow_legacy_algorithms,
+            },
+            max_connections: self.max_connections,
+            socket_buffer_kib: self.socket_buffer_kib,
+            source_address: self.read(&self.source_address, cx).trim().to_string(),
+            color: self.color,
+            tags: parse_tags(&self.read(&self.tags, cx)),
+            post_sync_hook: PostSyncHook {
+                command: self.read(&self.post_sync_command, cx).trim().to_string(),
+                enabled: self.post_sync_enabled,
+                run_on_failure: self.post_sync_on_failure,
+            },
+            remote_commands: RemoteCommands {
+                commands: self
+                    .remote_commands
+                    .iter()
+                    .map(|input| self.read(input, cx).trim().to_string())
+                    .filter(|command| !command.is_empty())
+                    .collect(),
+                enabled: self.remote_commands_enabled,
+            },
+        }
     }
 
     fn read(&self, input: &Entity<InputState>, cx: &mut Context<Self>) -> String {
@@ -2869,26 +10234,61 @@ struct TargetDraft {
     host: String,
     username: String,
     base_path: String,
+    local_base: String,
     password: String,
     private_key: String,
     passphrase: String,
     auth_choice: AuthChoice,
+    use_fallback_auth: bool,
+    remember_credentials: bool,
+    use_ssh_config: bool,
+    correct_clock_skew: bool,
+    stat_fallback: bool,
+    remote_read_only: bool,
+    monitor_health: bool,
     rules: Vec<RuleDraft>,
+    algorithms: SshAlgorithms,
+    max_connections: usize,
+    socket_buffer_kib: u32,
+    source_address: String,
+    color: TargetColor,
+    tags: Vec<String>,
+    post_sync_hook: PostSyncHook,
+    remote_commands: RemoteCommands,
 }
 
 struct RuleDraft {
     local: String,
     remote: String,
     direction: SyncDirection,
+    include_hidden: bool,
+    follow_symlinks: bool,
+    max_depth: usize,
+    max_entries: usize,
+    flatten: bool,
+    hard_links: bool,
+    preserve_ownership: bool,
+    remote_read_only: bool,
+    overwrite_policy: OverwritePolicy,
+    compare_policy: ComparePolicy,
+    collision_policy: CollisionPolicy,
+    case_insensitive: bool,
+    conflict_policy: ConflictPolicy,
+    include: Vec<String>,
+    ignore: Vec<String>,
+    skip_empty_files: bool,
+    skip_names: Vec<String>,
+    line_endings: LineEndings,
 }
 
 impl TargetDraft {
     fn is_valid(&self) -> bool {
         let base_valid = !self.name.trim().is_empty()
             && !self.host.trim().is_empty()
-            && !self.username.trim().is_empty()
+            && (self.use_ssh_config || !self.username.trim().is_empty())
             && !self.base_path.trim().is_empty()
-            && !self.rules.is_empty();
+            && !self.rules.is_empty()
+            && self.source_address_valid();
         if !base_valid {
             return false;
         }
@@ -2901,23 +10301,37 @@ impl TargetDraft {
             if local.is_empty() || remote.is_empty() {
                 return false;
             }
+            if rule
+                .line_endings
+                .clashes_with(rule.direction, rule.compare_policy, rule.overwrite_policy)
+            {
+                return false;
+            }
             if !local_paths.insert(local.to_string()) || !remote_paths.insert(remote.to_string()) {
                 return false;
             }
         }
 
-        match self.auth_choice {
-            AuthChoice::Password => !self.password.trim().is_empty(),
-            AuthChoice::SshKey => !self.private_key.trim().is_empty(),
-        }
+        self.credentials_ready()
     }
 
-    fn into_remote_target(self, id: TargetId) -> Option<RemoteTarget> {
-        if !self.is_valid() {
-            return None;
+    fn source_address_valid(&self) -> bool {
+        self.source_address.is_empty() || self.source_address.parse::<IpAddr>().is_ok()
+    }
+
+    fn credentials_ready(&self) -> bool {
+        let password_ready = !self.password.trim().is_empty();
+        // The SSH config can supply the IdentityFile.
+        let key_ready = self.use_ssh_config || !self.private_key.trim().is_empty();
+        match self.auth_choice {
+            _ if self.use_fallback_auth => password_ready && key_ready,
+            AuthChoice::Password => password_ready,
+            AuthChoice::SshKey => key_ready,
         }
+    }
 
-        let auth = match self.auth_choice {
+    fn auth_method(&self, choice: AuthChoice) -> AuthMethod {
+        match choice {
             AuthChoice::Password => AuthMethod::Password {
                 secret: self.password.trim().to_string(),
                 stored: false,
@@ -2934,26 +10348,87 @@ impl TargetDraft {
                 },
                 passphrase_stored: false,
             },
-        };
+        }
+    }
+
+    fn into_remote_target(self, id: TargetId) -> Option<RemoteTarget> {
+        if !self.is_valid() {
+            return None;
+        }
+        Some(self.build(id))
+    }
+
+    fn into_connection_target(mut self, id: TargetId) -> Option<RemoteTarget> {
+        if self.host.trim().is_empty()
+            || (!self.use_ssh_config && self.username.trim().is_empty())
+            || !self.credentials_ready()
+            || !self.source_address_valid()
+        {
+            return None;
+        }
+        self.rules.clear();
+        Some(self.build(id))
+    }
+
+    fn build(self, id: TargetId) -> RemoteTarget {
+        let auth = self.auth_method(self.auth_choice);
+        let fallback_auth = self
+            .use_fallback_auth
+            .then(|| self.auth_method(self.auth_choice.other()));
 
         let rules = self
             .rules
             .into_iter()
             .map(|rule| SyncRule {
-                local: PathBuf::from(rule.local.trim()),
-                remote: PathBuf::from(rule.remote.trim()),
-                direction: rule.direction,
+                include_hidden: rule.include_hidden,
+                follow_symlinks: rule.follow_symlinks,
+                max_depth: rule.max_depth,
+                max_entries: rule.max_entries,
+                flatten: rule.flatten && rule.direction == SyncDirection::Push,
+                hard_links: rule.hard_links && rule.direction == SyncDirection::Push,
+                preserve_ownership: rule.preserve_ownership
+                    && rule.direction != SyncDirection::Pull,
+                remote_read_only: rule.remote_read_only,
+                overwrite_policy: rule.overwrite_policy,
+                compare_policy: rule.compare_policy,
+                collision_policy: rule.collision_policy,
+                case_insensitive: rule.case_insensitive,
+                conflict_policy: rule.conflict_policy,
+                include: rule.include,
+                ignore: rule.ignore,
+                skip_empty_files: rule.skip_empty_files,
+                skip_names: rule.skip_names,
+                line_endings: rule.line_endings,
+                ..SyncRule::new(rule.local.trim(), rule.remote.trim(), rule.direction)
             })
             .collect();
 
-        Some(RemoteTarget {
+        RemoteTarget {
             id,
             name: self.name.trim().to_string(),
             host: self.host.trim().to_string(),
             username: self.username.trim().to_string(),
             base_path: PathBuf::from(self.base_path.trim()),
+            local_base: PathBuf::from(self.local_base.trim()),
             rules,
             auth,
-        })
+            fallback_auth,
+            enabled: true,
+            credentials_pending: false,
+            remember_credentials: self.remember_credentials,
+            use_ssh_config: self.use_ssh_config,
+            correct_clock_skew: self.correct_clock_skew,
+            stat_fallback: self.stat_fallback,
+            remote_read_only: self.remote_read_only,
+            monitor_health: self.monitor_health,
+            algorithms: self.algorithms,
+            color: self.color,
+            tags: self.tags,
+            max_connections: self.max_connections,
+            socket_buffer_kib: self.socket_buffer_kib,
+            source_address: self.source_address.parse().ok(),
+            post_sync_hook: self.post_sync_hook,
+            remote_commands: self.remote_commands,
+        }
     }
 }
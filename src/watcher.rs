@@ -1,16 +1,18 @@
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
     sync::{mpsc, Arc},
     thread,
+    time::{Duration, Instant},
 };
 
 use anyhow::{Context, Result};
 use async_channel::Sender;
-use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use notify::{Config, Event, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher, WatcherKind};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 
-use crate::model::TargetId;
+use crate::{model::TargetId, redact};
 
 #[derive(Clone)]
 pub struct WatchTarget {
@@ -23,22 +25,52 @@ pub struct WatchEvent {
     pub target_id: TargetId,
 }
 
+/// How the running watcher learns about changes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WatchBackend {
+    /// inotify, FSEvents or ReadDirectoryChangesW.
+    Native,
+    /// Rescans the roots every interval; used when native events fail, e.g.
+    /// on network mounts or when the inotify watch limit is reached.
+    Polling(Duration),
+}
+
 enum Command {
     Configure {
         enabled: bool,
         targets: Vec<WatchTarget>,
+        poll_interval: Duration,
     },
-    #[allow(dead_code)]
     Shutdown,
 }
 
 struct ActiveWatcher {
-    _watcher: RecommendedWatcher,
+    _watcher: Box<dyn Watcher + Send>,
     #[allow(dead_code)]
     roots: Arc<Vec<(PathBuf, TargetId)>>,
 }
 
+/// Events for paths the app changed itself this recently are dropped, so a
+/// download does not plan its target again. Polling adds its interval.
+const OWN_WRITE_WINDOW: Duration = Duration::from_secs(3);
+/// Older own writes are forgotten; longer than any sensible poll interval.
+const OWN_WRITE_RETENTION: Duration = Duration::from_secs(600);
+/// Suffix of the staging files downloads are written to before the rename.
+const STAGING_SUFFIX: &str = ".sftp-sync.part";
+
 static COMMAND_TX: Lazy<Mutex<Option<mpsc::Sender<Command>>>> = Lazy::new(|| Mutex::new(None));
+static ACTIVE_BACKEND: Lazy<Mutex<Option<WatchBackend>>> = Lazy::new(|| Mutex::new(None));
+static OWN_WRITES: Lazy<Mutex<OwnWrites>> = Lazy::new(|| {
+    Mutex::new(OwnWrites {
+        paths: HashMap::new(),
+        pruned: Instant::now(),
+    })
+});
+
+struct OwnWrites {
+    paths: HashMap<PathBuf, Instant>,
+    pruned: Instant,
+}
 
 pub fn ensure_service(event_tx: Sender<WatchEvent>) {
     let mut guard = COMMAND_TX.lock();
@@ -50,13 +82,50 @@ pub fn ensure_service(event_tx: Sender<WatchEvent>) {
     }
 }
 
-pub fn configure(enabled: bool, targets: Vec<WatchTarget>) {
+pub fn configure(enabled: bool, targets: Vec<WatchTarget>, poll_interval: Duration) {
     if let Some(tx) = COMMAND_TX.lock().as_ref() {
-        let _ = tx.send(Command::Configure { enabled, targets });
+        let _ = tx.send(Command::Configure {
+            enabled,
+            targets,
+            poll_interval,
+        });
     }
 }
 
-#[allow(dead_code)]
+/// Backend of the watcher currently running, or `None` while watching is off.
+pub fn active_backend() -> Option<WatchBackend> {
+    *ACTIVE_BACKEND.lock()
+}
+
+/// Records that a sync just wrote, created or removed `path`. Its parent is
+/// recorded too, since some backends report the folder as modified.
+pub fn note_own_write(path: &Path) {
+    let now = Instant::now();
+    let mut writes = OWN_WRITES.lock();
+    if now.duration_since(writes.pruned) >= OWN_WRITE_RETENTION {
+        writes
+            .paths
+            .retain(|_, at| now.duration_since(*at) < OWN_WRITE_RETENTION);
+        writes.pruned = now;
+    }
+    writes.paths.insert(path.to_path_buf(), now);
+    if let Some(parent) = path.parent() {
+        writes.paths.insert(parent.to_path_buf(), now);
+    }
+}
+
+fn is_own_write(path: &Path, window: Duration) -> bool {
+    if path.to_string_lossy().ends_with(STAGING_SUFFIX) {
+        return true;
+    }
+    OWN_WRITES
+        .lock()
+        .paths
+        .get(path)
+        .is_some_and(|at| at.elapsed() <= window)
+}
+
+/// Stops the watch thread; `ensure_service` starts a fresh one.
 pub fn shutdown() {
     if let Some(tx) = COMMAND_TX.lock().take() {
         let _ = tx.send(Command::Shutdown);
@@ -69,26 +138,37 @@ fn run_watch_loop(cmd_rx: mpsc::Receiver<Command>, event_tx: Sender<WatchEvent>)
         match cmd {
             Command::Shutdown => {
                 _active = None;
+                *ACTIVE_BACKEND.lock() = None;
                 break;
             }
-            Command::Configure { enabled, targets } => {
+            Command::Configure {
+                enabled,
+                targets,
+                poll_interval,
+            } => {
+                // Drop the old watcher first so a native retry gets its inotify watches back.
+                _active = None;
+                *ACTIVE_BACKEND.lock() = None;
                 if !enabled || targets.is_empty() {
-                    _active = None;
                     continue;
                 }
-                match build_watcher(targets, event_tx.clone()) {
-                    Ok(watcher) => _active = Some(watcher),
-                    Err(err) => {
-                        eprintln!("watcher configuration failed: {err:?}");
-                        _active = None;
+                match build_watcher(targets, event_tx.clone(), poll_interval) {
+                    Ok((watcher, backend)) => {
+                        _active = Some(watcher);
+                        *ACTIVE_BACKEND.lock() = Some(backend);
                     }
+                    Err(err) => redact::eprint(format!("watcher configuration failed: {err:?}")),
                 };
             }
         }
     }
 }
 
-fn build_watcher(targets: Vec<WatchTarget>, event_tx: Sender<WatchEvent>) -> Result<ActiveWatcher> {
+fn build_watcher(
+    targets: Vec<WatchTarget>,
+    event_tx: Sender<WatchEvent>,
+    poll_interval: Duration,
+) -> Result<(ActiveWatcher, WatchBackend)> {
     let roots: Vec<(PathBuf, TargetId)> = targets
         .into_iter()
         .flat_map(|target| {
@@ -100,24 +180,67 @@ fn build_watcher(targets: Vec<WatchTarget>, event_tx: Sender<WatchEvent>) -> Res
         .collect();
 
     let roots_arc = Arc::new(roots);
-    let callback_roots = roots_arc.clone();
-    let watcher_event_tx = event_tx.clone();
-
-    let mut watcher = RecommendedWatcher::new(
+    let handler = |window: Duration| {
+        let callback_roots = roots_arc.clone();
+        let watcher_event_tx = event_tx.clone();
         move |res: Result<Event, notify::Error>| {
             if let Ok(event) = res {
-                if let Some(first_path) = event.paths.first() {
-                    if let Some(target_id) = match_target(callback_roots.clone(), first_path) {
-                        let _ = watcher_event_tx.try_send(WatchEvent { target_id });
-                    }
+                // The echo of the app's own downloads and deletions is not a local change.
+                let changed = event.paths.iter().find(|path| !is_own_write(path, window));
+                if let Some(changed) = changed
+                    && let Some(target_id) = match_target(callback_roots.clone(), changed)
+                {
+                    let _ = watcher_event_tx.try_send(WatchEvent { target_id });
                 }
             }
+        }
+    };
+
+    let native = if RecommendedWatcher::kind() == WatcherKind::PollWatcher {
+        None
+    } else {
+        match watch_roots(
+            RecommendedWatcher::new(handler(OWN_WRITE_WINDOW), Config::default()),
+            &roots_arc,
+        ) {
+            Ok(watcher) => Some(watcher),
+            Err(err) => {
+                redact::eprint(format!(
+                    "native file events unavailable, falling back to polling: {err:?}"
+                ));
+                None
+            }
+        }
+    };
+    let (watcher, backend) = match native {
+        Some(watcher) => (watcher, WatchBackend::Native),
+        None => (
+            watch_roots(
+                PollWatcher::new(
+                    handler(OWN_WRITE_WINDOW + poll_interval),
+                    Config::default().with_poll_interval(poll_interval),
+                ),
+                &roots_arc,
+            )?,
+            WatchBackend::Polling(poll_interval),
+        ),
+    };
+
+    Ok((
+        ActiveWatcher {
+            _watcher: watcher,
+            roots: roots_arc,
         },
-        Config::default().with_poll_interval(std::time::Duration::from_secs(2)),
-    )
-    .context("failed to start filesystem watcher")?;
+        backend,
+    ))
+}
 
-    for (root, _) in roots_arc.iter() {
+fn watch_roots<W: Watcher + Send + 'static>(
+    watcher: notify::Result<W>,
+    roots: &[(PathBuf, TargetId)],
+) -> Result<Box<dyn Watcher + Send>> {
+    let mut watcher = watcher.context("failed to start filesystem watcher")?;
+    for (root, _) in roots {
         if !root.exists() {
             continue;
         }
@@ -125,11 +248,7 @@ fn build_watcher(targets: Vec<WatchTarget>, event_tx: Sender<WatchEvent>) -> Res
             .watch(root, RecursiveMode::Recursive)
             .with_context(|| format!("failed to watch {}", root.display()))?;
     }
-
-    Ok(ActiveWatcher {
-        _watcher: watcher,
-        roots: roots_arc,
-    })
+    Ok(Box::new(watcher))
 }
 
 fn match_target(roots: Arc<Vec<(PathBuf, TargetId)>>, path: &Path) -> Option<TargetId> {